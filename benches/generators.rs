@@ -0,0 +1,185 @@
+//! Compares the height controllers on a configurable workload: how fast
+//! insertions and lookups are with each of them, and what shape the list ends
+//! up with.
+//!
+//! Run with `cargo bench --bench generators -- [options]`:
+//!
+//!  * `--len N`: number of keys to insert, 100000 by default.
+//!  * `--order ORDER`: order in which keys are inserted, `random` (default),
+//!    `sequential` or `reverse`.
+//!  * `--lookups N`: number of lookups of existing keys, in random order,
+//!    `len` by default.
+//!  * `--max-height N`: maximum height of every controller, 16 by default.
+//!
+//! To try a controller of your own, add it to `controllers`.
+extern crate skiplist;
+
+use skiplist::*;
+
+use std::collections::hash_map::DefaultHasher;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+struct Workload {
+    len_: usize,
+    order_: String,
+    lookups_: Option<usize>,
+    max_height_: usize,
+}
+
+fn parse_workload() -> Workload {
+    let mut workload = Workload {
+        len_: 100_000,
+        order_: "random".to_string(),
+        lookups_: None,
+        max_height_: 16,
+    };
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        // Passed by `cargo bench` to every target.
+        if arg == "--bench" {
+            continue;
+        }
+
+        let value = args.next().unwrap_or_else(|| panic!("missing value for {}", arg));
+        let invalid = || panic!("invalid value for {}: {}", arg, value);
+        match arg.as_str() {
+            "--len" => workload.len_ = value.parse().unwrap_or_else(|_| invalid()),
+            "--order" => workload.order_ = value.clone(),
+            "--lookups" => workload.lookups_ = Some(value.parse().unwrap_or_else(|_| invalid())),
+            "--max-height" => workload.max_height_ = value.parse().unwrap_or_else(|_| invalid()),
+            _ => panic!("unknown option {}", arg),
+        }
+    }
+
+    workload
+}
+
+/// Shuffles `keys` with a fixed seed, so that every controller sees the same
+/// keys.
+fn shuffle(keys: &mut [u64], seed: u64) {
+    let mut entropy = SeededEntropy::new(seed);
+    for i in (1..keys.len()).rev() {
+        keys.swap(i, (entropy.next_u64() % (i as u64 + 1)) as usize);
+    }
+}
+
+fn keys(workload: &Workload) -> Vec<u64> {
+    let mut keys: Vec<u64> = (0..workload.len_ as u64).collect();
+    match workload.order_.as_str() {
+        "sequential" => {}
+        "reverse" => keys.reverse(),
+        "random" => shuffle(&mut keys, 0x5eed),
+        other => panic!("unknown order {}", other),
+    }
+
+    keys
+}
+
+/// Example of a custom controller: gives the `n`-th inserted key the number
+/// of trailing zeros of `n` as its height, which builds a perfectly balanced
+/// list when keys are inserted in order, and a poor one otherwise.
+#[derive(Clone)]
+struct CounterGenerator {
+    max_height_: usize,
+    count_: u64,
+}
+
+impl HeightControl<u64> for CounterGenerator {
+    fn max_height(&self) -> usize {
+        self.max_height_
+    }
+
+    fn get_height(&mut self, _key: &u64) -> usize {
+        self.count_ += 1;
+        std::cmp::min(self.count_.trailing_zeros() as usize, self.max_height_ - 1)
+    }
+}
+
+fn controllers(max_height: usize) -> Vec<(&'static str, Box<HeightControl<u64>>)> {
+    let controllers: Vec<(&'static str, Box<HeightControl<u64>>)> = vec![
+        ("geometric(0.5)", Box::new(GeometricalGenerator::new(max_height, 0.5))),
+        ("geometric(0.25)", Box::new(GeometricalGenerator::new(max_height, 0.25))),
+        ("two-pow", Box::new(TwoPowGenerator::new(max_height))),
+        ("adaptive(0.5)", Box::new(AdaptiveGenerator::new(max_height, 0.5))),
+        ("hash-coin", Box::new(HashCoinGenerator::new(max_height, DefaultHasher::new()))),
+        ("counter", Box::new(CounterGenerator { max_height_: max_height, count_: 0 })),
+    ];
+
+    controllers
+}
+
+/// Counts the comparisons done by searches.
+struct Comparisons(Arc<AtomicUsize>);
+
+impl Metrics for Comparisons {
+    fn comparisons(&self, count: usize) {
+        self.0.fetch_add(count, Ordering::Relaxed);
+    }
+}
+
+fn per_second(count: usize, elapsed: Duration) -> f64 {
+    count as f64 / elapsed.as_secs_f64() / 1e6
+}
+
+fn main() {
+    let workload = parse_workload();
+    let keys = keys(&workload);
+    let mut lookups = keys.clone();
+    shuffle(&mut lookups, 0x100c);
+    lookups.truncate(workload.lookups_.unwrap_or(keys.len()));
+
+    println!(
+        "{} keys inserted in {} order, {} lookups, maximum height {}\n",
+        keys.len(), workload.order_, lookups.len(), workload.max_height_
+    );
+    println!(
+        "{:<16} {:>12} {:>12} {:>12} {:>12} {:>8}",
+        "controller", "insert Mop/s", "get Mop/s", "cmp/get", "avg height", "height"
+    );
+
+    let mut histograms = Vec::new();
+    for (name, controller) in controllers(workload.max_height_) {
+        let mut map = SkipListMap::new(controller);
+        let start = Instant::now();
+        for &key in &keys {
+            map.insert(key, key);
+        }
+        let inserting = start.elapsed();
+
+        let start = Instant::now();
+        let mut found = 0;
+        for key in &lookups {
+            found += map.get(key).is_some() as usize;
+        }
+        let getting = start.elapsed();
+        assert_eq!(found, lookups.len());
+
+        // Counted in a separate pass, so that the hooks are not timed.
+        let comparisons = Arc::new(AtomicUsize::new(0));
+        map.set_metrics(Box::new(Comparisons(comparisons.clone())));
+        for key in &lookups {
+            map.get(key);
+        }
+
+        let stats = map.stats();
+        println!(
+            "{:<16} {:>12.2} {:>12.2} {:>12.1} {:>12.2} {:>8}",
+            name,
+            per_second(keys.len(), inserting),
+            per_second(lookups.len(), getting),
+            comparisons.load(Ordering::Relaxed) as f64 / lookups.len().max(1) as f64,
+            stats.average_height,
+            stats.max_height
+        );
+        histograms.push((name, stats.level_counts));
+    }
+
+    println!("\nnodes per level, from level 0 up:");
+    for (name, counts) in histograms {
+        let counts: Vec<String> = counts.iter().map(|count| count.to_string()).collect();
+        println!("{:<16} {}", name, counts.join(" "));
+    }
+}
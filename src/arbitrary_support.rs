@@ -0,0 +1,68 @@
+//! `arbitrary::Arbitrary` implementations, enabled by the `arbitrary` feature,
+//! so that fuzz targets can take Skip Lists and height controllers as inputs.
+//!
+//! Lists draw the heights of their nodes from the fuzzer's input too, rather
+//! than from the operating system, so that a crashing input reproduces the
+//! same towers every time it is run.
+use map::SkipListMap;
+use entropy::SeededEntropy;
+use height_control::{AdaptiveGenerator, GeometricalGenerator, HashCoinGenerator, TwoPowGenerator};
+
+use std;
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+/// Picks a probability strictly between 0 and 1.
+fn probability(u: &mut Unstructured) -> Result<f64> {
+    Ok((u.int_in_range(1..=255u8)? as f64) / 256.0)
+}
+
+impl<'a> Arbitrary<'a> for GeometricalGenerator {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<GeometricalGenerator> {
+        let max_height = u.int_in_range(1..=30)?;
+        Ok(GeometricalGenerator::new(max_height, probability(u)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for AdaptiveGenerator {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<AdaptiveGenerator> {
+        let max_height = u.int_in_range(1..=30)?;
+        Ok(AdaptiveGenerator::new(max_height, probability(u)?))
+    }
+}
+
+impl<'a, K: 'static> Arbitrary<'a> for TwoPowGenerator<K> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<TwoPowGenerator<K>> {
+        Ok(TwoPowGenerator::new(u.int_in_range(1..=32)?))
+    }
+}
+
+impl<'a, K, H> Arbitrary<'a> for HashCoinGenerator<K, H>
+where
+    K: 'static + std::hash::Hash,
+    H: 'static + std::hash::Hasher + Clone + Default + Send,
+{
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<HashCoinGenerator<K, H>> {
+        Ok(HashCoinGenerator::new(u.int_in_range(1..=30)?, H::default()))
+    }
+}
+
+impl<'a, K, V> Arbitrary<'a> for SkipListMap<K, V>
+where
+    K: 'static + Ord + Arbitrary<'a>,
+    V: Arbitrary<'a>,
+{
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<SkipListMap<K, V>> {
+        let max_height = u.int_in_range(1..=30)?;
+        let upgrade_probability = probability(u)?;
+        let entropy = SeededEntropy::new(u64::arbitrary(u)?);
+        let controller = GeometricalGenerator::with_entropy(max_height, upgrade_probability, entropy);
+
+        let mut list = SkipListMap::new(Box::new(controller));
+        for entry in u.arbitrary_iter::<(K, V)>()? {
+            let (key, value) = entry?;
+            list.insert(key, value);
+        }
+
+        Ok(list)
+    }
+}
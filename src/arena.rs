@@ -0,0 +1,313 @@
+//! Skip List with a struct-of-arrays layout.
+//!
+//! `ArenaSkipList` keeps its entries in an arena and refers to them by index.
+//! Instead of every node owning its tower, the links of each level live in an
+//! array of their own, so that a search walking a level reads `u32`s out of a
+//! single contiguous array, rather than following pointers to towers
+//! scattered around the heap. Keys are kept apart from the values for the
+//! same reason: a search only ever touches the links and the keys.
+//!
+//! The array of a level holds a link for every slot up to the last one that
+//! reaches that level, so links cost 4 bytes per slot and level in use.
+//! Removed slots are kept in a free list, and reused by later insertions.
+use height_control::{HeightContext, HeightControl};
+
+use std;
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+
+/// Slot used as the null link.
+const NIL: u32 = u32::MAX;
+
+/// Slot of the head, which holds neither a key nor a value.
+const HEAD: u32 = 0;
+
+/// `SkipListMap` storing its links level by level. See the `arena` module.
+pub struct ArenaSkipList<K, V> {
+    keys_: Vec<Option<K>>,
+    values_: Vec<Option<V>>,
+    // `levels_[level][slot]` is the slot following `slot` at `level`.
+    levels_: Vec<Vec<u32>>,
+    free_: Vec<u32>,
+    length_: usize,
+    // Highest level reached by any node since the last `clear`.
+    height_: usize,
+    controller_: Box<HeightControl<K>>,
+}
+
+impl<K, V> ArenaSkipList<K, V> {
+    /// Builds an empty list.
+    ///
+    /// # Arguments
+    ///
+    ///  * `controller`: generates heights for inserted nodes. A node of height
+    ///    `h` is linked at levels `0..=h`.
+    pub fn new(controller: Box<HeightControl<K>>) -> ArenaSkipList<K, V> {
+        let levels = controller.max_height() + 1;
+        ArenaSkipList {
+            keys_: vec![None],
+            values_: vec![None],
+            levels_: vec![vec![NIL]; levels],
+            free_: Vec::new(),
+            length_: 0,
+            height_: 0,
+            controller_: controller,
+        }
+    }
+
+    /// Returns the number of elements stored in the structure.
+    pub fn len(&self) -> usize {
+        self.length_
+    }
+
+    /// Returns `true` if there are no elements stored within the structure.
+    pub fn is_empty(&self) -> bool {
+        self.length_ == 0
+    }
+
+    /// Returns the number of slots in the arena, including the free ones and
+    /// the head. It never shrinks, except through `clear`.
+    pub fn slots(&self) -> usize {
+        self.keys_.len()
+    }
+
+    /// Makes room in the arena for at least `additional` more entries, on top
+    /// of the free slots, so that a burst of insertions doesn't grow it piece
+    /// by piece. Only the links of level 0 are reserved: the other levels are
+    /// reached by a fraction of the slots, and grow as tall nodes come.
+    pub fn reserve(&mut self, additional: usize) {
+        let needed = additional.saturating_sub(self.free_.len());
+        self.keys_.reserve(needed);
+        self.values_.reserve(needed);
+        let links = &mut self.levels_[0];
+        links.reserve((self.keys_.len() + needed).saturating_sub(links.len()));
+    }
+
+    /// Removes all elements, and releases the arena.
+    pub fn clear(&mut self) {
+        self.keys_.truncate(1);
+        self.values_.truncate(1);
+        for links in &mut self.levels_ {
+            links.truncate(1);
+            links[0] = NIL;
+        }
+
+        self.free_.clear();
+        self.length_ = 0;
+        self.height_ = 0;
+    }
+
+    /// Iterates over the entries, in key order.
+    pub fn iter(&self) -> ArenaIter<'_, K, V> {
+        ArenaIter {
+            list_: self,
+            current_: self.next(HEAD, 0),
+        }
+    }
+
+    fn next(&self, slot: u32, level: usize) -> u32 {
+        self.levels_[level][slot as usize]
+    }
+
+    fn set_next(&mut self, slot: u32, level: usize, next: u32) {
+        let links = &mut self.levels_[level];
+        if links.len() <= slot as usize {
+            links.resize(slot as usize + 1, NIL);
+        }
+
+        links[slot as usize] = next;
+    }
+
+    fn key(&self, slot: u32) -> &K {
+        self.keys_[slot as usize].as_ref().unwrap()
+    }
+
+    fn value(&self, slot: u32) -> &V {
+        self.values_[slot as usize].as_ref().unwrap()
+    }
+
+    /// Stores the entry in a free slot, or in a new one. Its links are left
+    /// for the caller to set.
+    fn allocate(&mut self, key: K, value: V) -> u32 {
+        if let Some(slot) = self.free_.pop() {
+            self.keys_[slot as usize] = Some(key);
+            self.values_[slot as usize] = Some(value);
+            return slot;
+        }
+
+        let slot = self.keys_.len();
+        assert!(slot < NIL as usize, "arena is full");
+        self.keys_.push(Some(key));
+        self.values_.push(Some(value));
+        slot as u32
+    }
+}
+
+impl<K: Ord, V> ArenaSkipList<K, V> {
+    /// Returns the shape of the list, as seen by its height controller.
+    fn context(&self) -> HeightContext {
+        HeightContext {
+            len: self.length_,
+            height: self.height_,
+        }
+    }
+
+    /// Finds, for every level, the last slot with a key less than `key`.
+    fn find_updates<Q>(&self, key: &Q) -> Vec<u32>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut updates = vec![HEAD; self.levels_.len()];
+        let mut current = HEAD;
+        for level in (0..=self.height_).rev() {
+            loop {
+                let next = self.next(current, level);
+                if next == NIL || self.key(next).borrow() >= key {
+                    break;
+                }
+                current = next;
+            }
+
+            updates[level] = current;
+        }
+
+        updates
+    }
+
+    /// Returns the slot holding `key`, if it exists.
+    fn find<Q>(&self, key: &Q) -> Option<u32>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut current = HEAD;
+        for level in (0..=self.height_).rev() {
+            loop {
+                let next = self.next(current, level);
+                if next == NIL {
+                    break;
+                }
+
+                match self.key(next).borrow().cmp(key) {
+                    Ordering::Less => current = next,
+                    Ordering::Equal => return Some(next),
+                    Ordering::Greater => break,
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Inserts `value` under `key`, returning the value it replaced, if any.
+    ///
+    /// # Panics
+    ///
+    /// If the arena already holds `u32::MAX - 1` slots.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let updates = self.find_updates(&key);
+        let candidate = self.next(updates[0], 0);
+        if candidate != NIL && *self.key(candidate) == key {
+            return self.values_[candidate as usize].replace(value);
+        }
+
+        let max_height = self.levels_.len() - 1;
+        let height = std::cmp::min(self.controller_.get_height_in(&key, &self.context()), max_height);
+        let node = self.allocate(key, value);
+        for (level, &update) in updates.iter().enumerate().take(height + 1) {
+            let next = self.next(update, level);
+            self.set_next(node, level, next);
+            self.set_next(update, level, node);
+        }
+
+        self.height_ = std::cmp::max(self.height_, height);
+        self.length_ += 1;
+        None
+    }
+
+    /// Returns a const reference to the element with key `key`, if it exists.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).map(|slot| self.value(slot))
+    }
+
+    /// Returns a mutable reference to the element with key `key`, if it
+    /// exists.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let slot = self.find(key)?;
+        self.values_[slot as usize].as_mut()
+    }
+
+    /// Returns true if `key` is in the list.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).is_some()
+    }
+
+    /// Removes the element with key `key`, returning its value if it existed.
+    /// Its slot is reused by a later insertion.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let updates = self.find_updates(key);
+        let target = self.next(updates[0], 0);
+        if target == NIL || self.key(target).borrow() != key {
+            return None;
+        }
+
+        // A node missing from a level is missing from all the ones above.
+        for (level, &update) in updates.iter().enumerate().take(self.height_ + 1) {
+            if self.next(update, level) != target {
+                break;
+            }
+
+            let next = self.next(target, level);
+            self.set_next(update, level, next);
+        }
+
+        self.keys_[target as usize] = None;
+        self.free_.push(target);
+        self.length_ -= 1;
+        self.values_[target as usize].take()
+    }
+}
+
+impl<K: std::fmt::Debug, V: std::fmt::Debug> std::fmt::Debug for ArenaSkipList<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Iterator over the entries of an `ArenaSkipList`, in key order.
+pub struct ArenaIter<'a, K: 'a, V: 'a> {
+    list_: &'a ArenaSkipList<K, V>,
+    current_: u32,
+}
+
+impl<'a, K, V> Iterator for ArenaIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.current_ == NIL {
+            return None;
+        }
+
+        let list = self.list_;
+        let slot = self.current_;
+        self.current_ = list.next(slot, 0);
+        Some((list.key(slot), list.value(slot)))
+    }
+}
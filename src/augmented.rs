@@ -0,0 +1,431 @@
+use height_control::HeightControl;
+use entropy::Entropy;
+use index_list::{IndexIter, IndexList, Spans, HEAD, NIL};
+
+use std;
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+use std::ops::{Add, Bound, RangeBounds};
+
+/// Summary kept under every link of an `AugmentedSkipListMap`, over the
+/// entries the link skips. It must form a monoid: `combine` is associative,
+/// and `empty` is its identity. It need not be commutative, since entries are
+/// always combined in key order.
+pub trait Aggregate<K, V> {
+    type Summary: Clone;
+
+    /// Returns the summary of no entries.
+    fn empty() -> Self::Summary;
+
+    /// Returns the summary of a single entry.
+    fn single(key: &K, value: &V) -> Self::Summary;
+
+    /// Returns the summary of the entries summarized by `left`, followed by
+    /// the ones summarized by `right`.
+    fn combine(left: &Self::Summary, right: &Self::Summary) -> Self::Summary;
+}
+
+/// Counts entries.
+pub struct Count;
+
+impl<K, V> Aggregate<K, V> for Count {
+    type Summary = usize;
+
+    fn empty() -> usize {
+        0
+    }
+
+    fn single(_key: &K, _value: &V) -> usize {
+        1
+    }
+
+    fn combine(left: &usize, right: &usize) -> usize {
+        left + right
+    }
+}
+
+/// Adds values up.
+pub struct Sum;
+
+impl<K, V: Clone + Default + Add<Output = V>> Aggregate<K, V> for Sum {
+    type Summary = V;
+
+    fn empty() -> V {
+        V::default()
+    }
+
+    fn single(_key: &K, value: &V) -> V {
+        value.clone()
+    }
+
+    fn combine(left: &V, right: &V) -> V {
+        left.clone() + right.clone()
+    }
+}
+
+/// Finds the smallest value, if there is any.
+pub struct Min;
+
+impl<K, V: Clone + Ord> Aggregate<K, V> for Min {
+    type Summary = Option<V>;
+
+    fn empty() -> Option<V> {
+        None
+    }
+
+    fn single(_key: &K, value: &V) -> Option<V> {
+        Some(value.clone())
+    }
+
+    fn combine(left: &Option<V>, right: &Option<V>) -> Option<V> {
+        match (left, right) {
+            (Some(left), Some(right)) => Some(std::cmp::min(left, right).clone()),
+            (left, None) => left.clone(),
+            (None, right) => right.clone(),
+        }
+    }
+}
+
+/// Finds the largest value, if there is any.
+pub struct Max;
+
+impl<K, V: Clone + Ord> Aggregate<K, V> for Max {
+    type Summary = Option<V>;
+
+    fn empty() -> Option<V> {
+        None
+    }
+
+    fn single(_key: &K, value: &V) -> Option<V> {
+        Some(value.clone())
+    }
+
+    fn combine(left: &Option<V>, right: &Option<V>) -> Option<V> {
+        std::cmp::max(left, right).clone()
+    }
+}
+
+/// Values that carry a weight, for the `Weight` aggregate.
+pub trait Weighted {
+    fn weight(&self) -> u64;
+}
+
+impl Weighted for u64 {
+    fn weight(&self) -> u64 {
+        *self
+    }
+}
+
+/// A value paired with its weight.
+impl<T> Weighted for (u64, T) {
+    fn weight(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Adds the weights of the values up. Enables searching and sampling entries
+/// by weight, see `AugmentedSkipListMap::find_by_cumulative_weight`.
+pub struct Weight;
+
+impl<K, V: Weighted> Aggregate<K, V> for Weight {
+    type Summary = u64;
+
+    fn empty() -> u64 {
+        0
+    }
+
+    fn single(_key: &K, value: &V) -> u64 {
+        value.weight()
+    }
+
+    fn combine(left: &u64, right: &u64) -> u64 {
+        left + right
+    }
+}
+
+/// Keeps the summaries of `A` under the links of an `IndexList`.
+struct Summaries<A>(PhantomData<A>);
+
+impl<K, V, A: Aggregate<K, V>> Spans<(K, V)> for Summaries<A> {
+    type Span = A::Summary;
+
+    fn single(entry: &(K, V)) -> A::Summary {
+        A::single(&entry.0, &entry.1)
+    }
+
+    fn combine(left: &A::Summary, right: &A::Summary) -> A::Summary {
+        A::combine(left, right)
+    }
+}
+
+/// Map that keeps a summary of the entries under every link, as defined by
+/// `A`, and uses them to summarize any range of keys in O(log n) on average.
+///
+/// Summaries are rebuilt along the search path on every change, so values can
+/// only be changed through `insert`.
+pub struct AugmentedSkipListMap<K, V, A: Aggregate<K, V>> {
+    list_: IndexList<(K, V), A::Summary>,
+    controller_: Box<HeightControl<K>>,
+}
+
+impl<K, V, A: Aggregate<K, V>> AugmentedSkipListMap<K, V, A> {
+    pub fn new(controller: Box<HeightControl<K>>) -> AugmentedSkipListMap<K, V, A> {
+        AugmentedSkipListMap {
+            list_: IndexList::new(controller.max_height(), A::empty),
+            controller_: controller,
+        }
+    }
+
+    /// Returns the number of elements stored in the structure.
+    pub fn len(&self) -> usize {
+        self.list_.len()
+    }
+
+    /// Returns `true` if there are no elements stored within the structure.
+    pub fn is_empty(&self) -> bool {
+        self.list_.len() == 0
+    }
+
+    /// Removes all elements.
+    pub fn clear(&mut self) {
+        self.list_.clear();
+    }
+
+    /// Iterates over the entries, in key order.
+    pub fn iter(&self) -> AugmentedIter<'_, K, V, A> {
+        AugmentedIter {
+            entries_: self.list_.entries(),
+        }
+    }
+}
+
+impl<K, V> AugmentedSkipListMap<K, V, Count> {
+    /// Returns the value held by `node`, as returned by `insert_node`.
+    pub(crate) fn node_value(&self, node: usize) -> &V {
+        &self.list_.entry(node).1
+    }
+
+    /// Returns the value held by `node`. Counts don't depend on the values,
+    /// so changing it leaves them up to date.
+    pub(crate) fn node_value_mut(&mut self, node: usize) -> &mut V {
+        &mut self.list_.entry_mut(node).1
+    }
+}
+
+impl<K: Ord, V, A: Aggregate<K, V>> AugmentedSkipListMap<K, V, A> {
+    /// Finds the last node before `key` at every level.
+    fn search<Q>(&self, key: &Q) -> Vec<usize>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.list_.find_updates_by(|entry| entry.0.borrow() < key)
+    }
+
+    /// Returns the node with key `key`, if it exists, along with the last
+    /// node before it at every level.
+    fn find<Q>(&self, key: &Q) -> (Option<usize>, Vec<usize>)
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let updates = self.search(key);
+        let node = match self.list_.next(updates[0], 0) {
+            NIL => None,
+            next if self.list_.entry(next).0.borrow() == key => Some(next),
+            _ => None,
+        };
+
+        (node, updates)
+    }
+
+    /// Inserts `value` under `key`, returning the value it replaced, if any.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.insert_node(key, value).1
+    }
+
+    /// Same as `insert`, but also returns the node that holds the entry,
+    /// which stays the same for as long as the entry is in the map.
+    pub(crate) fn insert_node(&mut self, key: K, value: V) -> (usize, Option<V>) {
+        let (node, updates) = self.find(&key);
+        if let Some(node) = node {
+            let replaced = std::mem::replace(&mut self.list_.entry_mut(node).1, value);
+            self.list_.update_spans::<Summaries<A>>(&updates, Some(node));
+            return (node, Some(replaced));
+        }
+
+        let height = self.controller_.get_height(&key);
+        let node = self.list_.link(&updates, (key, value), height);
+        self.list_.update_spans::<Summaries<A>>(&updates, Some(node));
+        (node, None)
+    }
+
+    /// Returns a const reference to the element with key `key`, if it exists.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).0.map(|node| &self.list_.entry(node).1)
+    }
+
+    /// Returns true if `key` is in the map.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).0.is_some()
+    }
+
+    /// Removes the element with key `key`, returning its value if it existed.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (target, updates) = self.find(key);
+        let (_, value) = self.list_.unlink(&updates, target?);
+        self.list_.update_spans::<Summaries<A>>(&updates, None);
+        Some(value)
+    }
+
+    /// Returns the summary of every entry within `range`.
+    ///
+    /// # Remarks
+    ///
+    /// From the first entry in the range, this takes the longest link that
+    /// stays within the range at every step, and combines the summary under
+    /// it.
+    pub fn range_aggregate<T, R>(&self, range: R) -> A::Summary
+    where
+        K: Borrow<T>,
+        R: RangeBounds<T>,
+        T: Ord + ?Sized,
+    {
+        let within_end = |node: usize| {
+            node != NIL &&
+                match range.end_bound() {
+                    Bound::Included(end) => self.list_.entry(node).0.borrow() <= end,
+                    Bound::Excluded(end) => self.list_.entry(node).0.borrow() < end,
+                    Bound::Unbounded => true,
+                }
+        };
+
+        let mut current = match range.start_bound() {
+            Bound::Included(start) => self.list_.next(self.search(start)[0], 0),
+            Bound::Excluded(start) => {
+                let next = self.list_.next(self.search(start)[0], 0);
+                if next != NIL && self.list_.entry(next).0.borrow() == start {
+                    self.list_.next(next, 0)
+                } else {
+                    next
+                }
+            }
+            Bound::Unbounded => self.list_.next(HEAD, 0),
+        };
+
+        let mut summary = A::empty();
+        while within_end(current) {
+            let node = self.list_.node(current);
+
+            // The span up to the next node at some level is within the range
+            // if that next node is.
+            let level = (1..node.levels())
+                .rev()
+                .find(|&level| within_end(node.next(level)))
+                .unwrap_or(0);
+            summary = A::combine(&summary, node.span(level));
+            current = node.next(level);
+        }
+
+        summary
+    }
+}
+
+impl<K, V: Weighted> AugmentedSkipListMap<K, V, Weight> {
+    /// Returns the sum of the weights of all the entries.
+    pub fn total_weight(&self) -> u64 {
+        // The spans of the top level cover the whole list.
+        let top = self.list_.levels() - 1;
+        let mut total = 0;
+        let mut current = HEAD;
+        loop {
+            total += self.list_.node(current).span(top);
+            current = self.list_.next(current, top);
+            if current == NIL {
+                return total;
+            }
+        }
+    }
+
+    /// Returns the entry whose weight covers `weight`, when the weights of
+    /// the entries are laid end to end in key order: the first entry such
+    /// that the weights up to it, included, add up to more than `weight`.
+    /// Returns `None` if `weight` is not below the total weight.
+    ///
+    /// # Remarks
+    ///
+    /// Entries of weight 0 are never returned. Takes O(log n) on average,
+    /// skipping whole spans while their weight fits under `weight`.
+    pub fn find_by_cumulative_weight(&self, weight: u64) -> Option<(&K, &V)> {
+        // `before` is the weight of the entries before `current`.
+        let mut before = 0;
+        let mut current = HEAD;
+        for level in (0..self.list_.levels()).rev() {
+            loop {
+                let node = self.list_.node(current);
+                let next = node.next(level);
+                if next == NIL || before + node.span(level) > weight {
+                    break;
+                }
+
+                before += node.span(level);
+                current = next;
+            }
+        }
+
+        if current == HEAD || before + self.list_.node(current).span(0) <= weight {
+            return None;
+        }
+
+        let (ref key, ref value) = *self.list_.entry(current);
+        Some((key, value))
+    }
+
+    /// Picks an entry at random, each with a probability proportional to its
+    /// weight. Returns `None` if the total weight is 0.
+    pub fn sample<E: Entropy>(&self, entropy: &mut E) -> Option<(&K, &V)> {
+        let total = self.total_weight();
+        if total == 0 {
+            return None;
+        }
+
+        // Scales the random bits down to `0..total` without the bias of `%`.
+        let weight = ((entropy.next_u64() as u128 * total as u128) >> 64) as u64;
+        self.find_by_cumulative_weight(weight)
+    }
+}
+
+impl<K, V, A: Aggregate<K, V>> std::fmt::Debug for AugmentedSkipListMap<K, V, A>
+where
+    K: std::fmt::Debug,
+    V: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Iterator over the entries of an `AugmentedSkipListMap`, in key order.
+pub struct AugmentedIter<'a, K: 'a, V: 'a, A: 'a + Aggregate<K, V>> {
+    entries_: IndexIter<'a, (K, V), A::Summary>,
+}
+
+impl<'a, K: 'a, V: 'a, A: 'a + Aggregate<K, V>> Iterator for AugmentedIter<'a, K, V, A> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entries_.next().map(|(key, value)| (key, value))
+    }
+}
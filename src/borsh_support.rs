@@ -0,0 +1,47 @@
+//! Serialization through `borsh`, enabled by the `borsh` feature.
+//!
+//! A `SkipListMap` is encoded like a `BTreeMap`: the number of entries as a
+//! `u32`, followed by the entries in increasing key order. Decoding appends
+//! the entries in O(1) each, and rejects encodings whose keys are not strictly
+//! increasing, so that every map has a single valid encoding.
+use map::SkipListMap;
+use build::UnsortedError;
+
+use std::convert::TryFrom;
+
+use borsh::io::{self, Read, Write};
+use borsh::{BorshDeserialize, BorshSerialize};
+
+impl<K: BorshSerialize, V: BorshSerialize> BorshSerialize for SkipListMap<K, V> {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let len = u32::try_from(self.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "too many entries"))?;
+        len.serialize(writer)?;
+        for (key, value) in self.iter() {
+            key.serialize(writer)?;
+            value.serialize(writer)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<K, V> BorshDeserialize for SkipListMap<K, V>
+where
+    K: 'static + Ord + BorshDeserialize,
+    V: BorshDeserialize,
+{
+    fn deserialize_reader<R: Read>(reader: &mut R) -> io::Result<SkipListMap<K, V>> {
+        let len = u32::deserialize_reader(reader)? as usize;
+        let mut list = SkipListMap::builder().expected_len(len).build();
+        for position in 0..len {
+            let key = K::deserialize_reader(reader)?;
+            let value = V::deserialize_reader(reader)?;
+            if list.push_back(key, value).is_err() {
+                return Err(UnsortedError { position }.into());
+            }
+        }
+
+        Ok(list)
+    }
+}
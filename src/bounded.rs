@@ -0,0 +1,184 @@
+use map::SkipListMap;
+use height_control::HeightControl;
+use iter::{Iter, Range};
+use error::Error;
+
+use std;
+use std::borrow::Borrow;
+use std::ops::RangeBounds;
+
+/// Which end of a full `BoundedSkipListMap` entries are evicted from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Evict {
+    /// Evicts the smallest key, keeping the largest ones, e.g. for the top
+    /// scores of a leaderboard.
+    Smallest,
+    /// Evicts the largest key, keeping the smallest ones.
+    Largest,
+}
+
+/// `SkipListMap` that holds at most `capacity` entries. Inserting a new key
+/// into a full map evicts the entry at the end chosen by `Evict`, which may be
+/// the one just inserted.
+pub struct BoundedSkipListMap<K, V> {
+    map_: SkipListMap<K, V>,
+    capacity_: usize,
+    evict_: Evict,
+}
+
+impl<K, V> BoundedSkipListMap<K, V> {
+    pub fn new(
+        capacity: usize,
+        evict: Evict,
+        controller: Box<HeightControl<K>>,
+    ) -> BoundedSkipListMap<K, V> {
+        BoundedSkipListMap {
+            map_: SkipListMap::new(controller),
+            capacity_: capacity,
+            evict_: evict,
+        }
+    }
+
+    /// Returns the maximum number of entries.
+    pub fn capacity(&self) -> usize {
+        self.capacity_
+    }
+
+    /// Returns the end entries are evicted from.
+    pub fn evict(&self) -> Evict {
+        self.evict_
+    }
+
+    /// Removes all elements.
+    pub fn clear(&mut self) {
+        self.map_.clear()
+    }
+
+    /// Returns the number of elements stored in the structure.
+    pub fn len(&self) -> usize {
+        self.map_.len()
+    }
+
+    /// Returns `true` if there are no elements stored within the structure.
+    pub fn is_empty(&self) -> bool {
+        self.map_.is_empty()
+    }
+
+    /// Returns `true` if inserting a new key would evict an entry.
+    pub fn is_full(&self) -> bool {
+        self.map_.len() >= self.capacity_
+    }
+
+    /// Iterates over the entries, in key order.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        self.map_.iter()
+    }
+
+    /// Returns the underlying map.
+    pub fn as_map(&self) -> &SkipListMap<K, V> {
+        &self.map_
+    }
+
+    /// Consumes the map, returning the underlying one.
+    pub fn into_map(self) -> SkipListMap<K, V> {
+        self.map_
+    }
+}
+
+impl<K: Ord, V> BoundedSkipListMap<K, V> {
+    /// Inserts `value` under `key`. Returns the value it replaced, if any,
+    /// and the entry evicted to make room for it, if any.
+    pub fn insert(&mut self, key: K, value: V) -> (Option<V>, Option<(K, V)>) {
+        let replaced = self.map_.insert(key, value);
+        if self.map_.len() <= self.capacity_ {
+            return (replaced, None);
+        }
+
+        let evicted = match self.evict_ {
+            Evict::Smallest => self.map_.pop_first(),
+            Evict::Largest => self.map_.pop_last(),
+        };
+
+        (replaced, evicted)
+    }
+
+    /// Inserts `value` under `key`, and returns the value it replaced, if
+    /// any. Fails with `Error::CapacityExceeded` instead of evicting an entry
+    /// if `key` is new and the map is full.
+    pub fn try_insert(&mut self, key: K, value: V) -> Result<Option<V>, Error> {
+        if self.is_full() && !self.map_.contains_key(&key) {
+            return Err(Error::CapacityExceeded);
+        }
+
+        Ok(self.map_.insert(key, value))
+    }
+
+    /// Changes the maximum number of entries, and returns the entries evicted
+    /// to fit in it, in key order.
+    pub fn set_capacity(&mut self, capacity: usize) -> Vec<(K, V)> {
+        self.capacity_ = capacity;
+        let excess = self.map_.len().saturating_sub(capacity);
+        match self.evict_ {
+            Evict::Smallest => self.map_.pop_first_n(excess),
+            Evict::Largest => self.map_.pop_last_n(excess),
+        }
+    }
+
+    /// Returns a const reference to the element with key `key`, if it exists.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map_.get(key)
+    }
+
+    /// Returns a mutable reference to the element with key `key`, if it
+    /// exists.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map_.get_mut(key)
+    }
+
+    /// Returns true if `key` is in the map.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map_.contains_key(key)
+    }
+
+    /// Removes the element with key `key`, returning its value if it existed.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map_.remove(key)
+    }
+
+    /// Returns the entry with the smallest key, if any.
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.map_.first()
+    }
+
+    /// Iterates over the entries within `range`, in key order.
+    pub fn range<T, R>(&self, range: R) -> Range<'_, K, V>
+    where
+        K: Borrow<T>,
+        R: RangeBounds<T>,
+        T: Ord + ?Sized,
+    {
+        self.map_.range(range)
+    }
+}
+
+impl<K: std::fmt::Debug, V: std::fmt::Debug> std::fmt::Debug for BoundedSkipListMap<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
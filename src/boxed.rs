@@ -0,0 +1,146 @@
+use map::SkipListMap;
+use height_control::HeightControl;
+use iter::{Iter, Range};
+
+use std;
+use std::borrow::Borrow;
+use std::ops::RangeBounds;
+
+/// `SkipListMap` that stores its values behind a `Box`.
+///
+/// Values are stored inline in the nodes, so large ones spread the keys that
+/// searches compare over many cache lines. Boxing them keeps nodes small, at
+/// the cost of one allocation per entry, and of an indirection when reading a
+/// value. Values are still handed in and out as `V`, `&V`, and `&mut V`.
+pub struct BoxedSkipListMap<K, V> {
+    map_: SkipListMap<K, Box<V>>,
+}
+
+impl<K, V> BoxedSkipListMap<K, V> {
+    pub fn new(controller: Box<HeightControl<K>>) -> BoxedSkipListMap<K, V> {
+        BoxedSkipListMap {
+            map_: SkipListMap::new(controller),
+        }
+    }
+
+    /// Removes all elements.
+    pub fn clear(&mut self) {
+        self.map_.clear()
+    }
+
+    /// Returns the number of elements stored in the structure.
+    pub fn len(&self) -> usize {
+        self.map_.len()
+    }
+
+    /// Returns `true` if there are no elements stored within the structure.
+    pub fn is_empty(&self) -> bool {
+        self.map_.is_empty()
+    }
+
+    /// Iterates over the entries, in key order.
+    pub fn iter(&self) -> BoxedIter<Iter<'_, K, Box<V>>> {
+        BoxedIter(self.map_.iter())
+    }
+
+    /// Returns the underlying map, with boxed values.
+    pub fn as_map(&self) -> &SkipListMap<K, Box<V>> {
+        &self.map_
+    }
+
+    /// Consumes the map, returning the underlying one.
+    pub fn into_map(self) -> SkipListMap<K, Box<V>> {
+        self.map_
+    }
+}
+
+impl<K: Ord, V> BoxedSkipListMap<K, V> {
+    /// Inserts `value` under `key`, returning the value it replaced, if any.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.map_.insert(key, Box::new(value)).map(|value| *value)
+    }
+
+    /// Returns a const reference to the element with key `key`, if it exists.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map_.get(key).map(|value| &**value)
+    }
+
+    /// Returns a mutable reference to the element with key `key`, if it
+    /// exists.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map_.get_mut(key).map(|value| &mut **value)
+    }
+
+    /// Returns true if `key` is in the map.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map_.contains_key(key)
+    }
+
+    /// Removes the element with key `key`, returning its value if it existed.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map_.remove(key).map(|value| *value)
+    }
+
+    /// Returns the entry with the smallest key, if any.
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.map_.first().map(|(key, value)| (key, &**value))
+    }
+
+    /// Iterates over the entries within `range`, in key order.
+    pub fn range<T, R>(&self, range: R) -> BoxedIter<Range<'_, K, Box<V>>>
+    where
+        K: Borrow<T>,
+        R: RangeBounds<T>,
+        T: Ord + ?Sized,
+    {
+        BoxedIter(self.map_.range(range))
+    }
+}
+
+impl<K: 'static + std::hash::Hash, V> Default for BoxedSkipListMap<K, V> {
+    fn default() -> BoxedSkipListMap<K, V> {
+        BoxedSkipListMap {
+            map_: Default::default(),
+        }
+    }
+}
+
+impl<K: std::fmt::Debug, V: std::fmt::Debug> std::fmt::Debug for BoxedSkipListMap<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Iterator over the entries of a `BoxedSkipListMap`, in key order.
+pub struct BoxedIter<I>(I);
+
+impl<'a, K: 'a, V: 'a, I> Iterator for BoxedIter<I>
+where
+    I: Iterator<Item = (&'a K, &'a Box<V>)>,
+{
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(key, value)| (key, &**value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
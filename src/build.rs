@@ -0,0 +1,94 @@
+use map::SkipListMap;
+use height_control::HeightControl;
+
+use std;
+use std::io;
+
+/// Error returned by `SkipListMap::build_from_sorted` when the entries are not
+/// in strictly increasing key order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnsortedError {
+    /// Index of the first entry whose key is not greater than the previous
+    /// one.
+    pub position: usize,
+}
+
+impl std::fmt::Display for UnsortedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "entry {} is out of order", self.position)
+    }
+}
+
+impl std::error::Error for UnsortedError {}
+
+impl From<UnsortedError> for io::Error {
+    fn from(error: UnsortedError) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, error)
+    }
+}
+
+impl<K: Ord, V> SkipListMap<K, V> {
+    /// Builds a list from entries arriving in strictly increasing key order.
+    /// Every entry is appended as soon as it arrives, in O(1), so the source
+    /// never needs to be buffered.
+    ///
+    /// # Arguments
+    ///
+    ///  * `entries`: source of the entries. It may fail, e.g. when reading a
+    ///    file, in which case the error is returned as is. Sorted runs can be
+    ///    passed straight through `RunReader`; infallible iterators can be
+    ///    wrapped with `map(Ok::<_, UnsortedError>)`.
+    ///  * `controller`: generates heights for the nodes.
+    ///
+    /// # Remarks
+    ///
+    /// Fails with an `UnsortedError`, converted into `E`, as soon as a key is
+    /// not greater than the previous one.
+    pub fn build_from_sorted<I, E>(
+        entries: I,
+        controller: Box<HeightControl<K>>,
+    ) -> Result<SkipListMap<K, V>, E>
+    where
+        I: IntoIterator<Item = Result<(K, V), E>>,
+        E: From<UnsortedError>,
+    {
+        let mut list = SkipListMap::new(controller);
+        let mut fingers = list.empty_fingers();
+
+        for (position, entry) in entries.into_iter().enumerate() {
+            let (key, value) = entry?;
+
+            // The last node linked at level 0 is the greatest one so far.
+            let last = unsafe { fingers[0].as_ref() };
+            if !std::ptr::eq(last, list.head()) && *last.key::<K>() >= key {
+                return Err(UnsortedError { position }.into());
+            }
+
+            let height = list.generate_height(&key);
+            list.push_back_unchecked(&mut fingers, key, value, height);
+        }
+
+        Ok(list)
+    }
+
+    /// Appends a batch of entries whose keys are strictly increasing, and all
+    /// greater than the keys already in the list, in a single pass through
+    /// the tail. This is the bulk version of `push_back`.
+    ///
+    /// # Remarks
+    ///
+    /// Fails with an `UnsortedError` as soon as a key is out of order. The
+    /// entries before it are kept in the list, and the rest are dropped.
+    pub fn extend_from_sorted<I>(&mut self, entries: I) -> Result<(), UnsortedError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        for (position, (key, value)) in entries.into_iter().enumerate() {
+            if self.push_back(key, value).is_err() {
+                return Err(UnsortedError { position });
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,137 @@
+use map::SkipListMap;
+use height_control::{HeightControl, TwoPowGenerator};
+use metrics::Metrics;
+use pool::NodePool;
+
+use std;
+
+/// Configures and builds a `SkipListMap`, as returned by
+/// `SkipListMap::builder`. Every option is optional.
+///
+/// Orderings other than the `Ord` of the keys are expressed through the key
+/// type, e.g. with `Descending`, rather than configured here.
+pub struct SkipListMapBuilder<K, V> {
+    controller_: Option<Box<HeightControl<K>>>,
+    expected_len_: Option<usize>,
+    pool_: Option<NodePool<K, V>>,
+    metrics_: Option<Box<Metrics>>,
+}
+
+impl<K, V> SkipListMapBuilder<K, V> {
+    /// Builds a builder with every option left to its default.
+    pub fn new() -> SkipListMapBuilder<K, V> {
+        SkipListMapBuilder {
+            controller_: None,
+            expected_len_: None,
+            pool_: None,
+            metrics_: None,
+        }
+    }
+
+    /// Generates node heights with `controller`. Takes precedence over the
+    /// height chosen from `expected_len`.
+    pub fn height_control(mut self, controller: Box<HeightControl<K>>) -> Self {
+        self.controller_ = Some(controller);
+        self
+    }
+
+    /// Hints how many elements the map will hold. Unless a controller is
+    /// given, heights are generated up to about `log2(expected_len)`, instead
+    /// of the 16 levels used by `Default`. If a node pool is given, it is
+    /// filled with up to `expected_len` free nodes; otherwise, nothing is
+    /// allocated ahead of time, since nodes are allocated one at a time, and
+    /// searches only need buffers as tall as the list.
+    pub fn expected_len(mut self, expected_len: usize) -> Self {
+        self.expected_len_ = Some(expected_len);
+        self
+    }
+
+    /// Takes nodes from `pool`, and gives them back to it. See
+    /// `SkipListMap::set_node_pool`.
+    pub fn node_pool(mut self, pool: NodePool<K, V>) -> Self {
+        self.pool_ = Some(pool);
+        self
+    }
+
+    /// Reports everything the map does to `metrics`. See
+    /// `SkipListMap::set_metrics`.
+    pub fn metrics(mut self, metrics: Box<Metrics>) -> Self {
+        self.metrics_ = Some(metrics);
+        self
+    }
+
+    /// Builds an empty map with the given options.
+    pub fn build(self) -> SkipListMap<K, V>
+    where
+        K: 'static,
+    {
+        let controller = match self.controller_ {
+            Some(controller) => controller,
+            None => {
+                let max_height = self.expected_len_.map_or(16, height_for);
+                Box::new(TwoPowGenerator::new(max_height))
+            }
+        };
+
+        let mut map = SkipListMap::new(controller);
+        if let Some(pool) = self.pool_ {
+            if let Some(expected_len) = self.expected_len_ {
+                pool.reserve(expected_len);
+            }
+            map.set_node_pool(pool);
+        }
+        if let Some(metrics) = self.metrics_ {
+            map.set_metrics(metrics);
+        }
+
+        map
+    }
+}
+
+impl<K, V> Default for SkipListMapBuilder<K, V> {
+    fn default() -> Self {
+        SkipListMapBuilder::new()
+    }
+}
+
+impl<K, V> std::fmt::Debug for SkipListMapBuilder<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("SkipListMapBuilder")
+            .field("height_control", &self.controller_.is_some())
+            .field("expected_len", &self.expected_len_)
+            .field("node_pool", &self.pool_)
+            .field("metrics", &self.metrics_.is_some())
+            .finish()
+    }
+}
+
+/// Maximum height for a `TwoPowGenerator` that holds `expected_len` elements:
+/// `log2(expected_len)` levels, rounded up. Lengths past the largest power of
+/// two get one level per bit.
+fn height_for(expected_len: usize) -> usize {
+    let levels = expected_len
+        .checked_next_power_of_two()
+        .map_or(usize::BITS as usize, |power| power.trailing_zeros() as usize);
+    std::cmp::max(levels, 1)
+}
+
+impl<K, V> SkipListMap<K, V> {
+    /// Returns a builder, to configure a map before building it.
+    pub fn builder() -> SkipListMapBuilder<K, V> {
+        SkipListMapBuilder::new()
+    }
+
+    /// Builds an empty map sized for about `expected_len` elements, so that
+    /// it doesn't need to be given a `HeightControl`. Heights are generated
+    /// up to about `log2(expected_len)`; see `SkipListMapBuilder` for further
+    /// options.
+    ///
+    /// Only the height is sized: without a node pool, which can only be given
+    /// through the builder, nothing is allocated ahead of time.
+    pub fn with_capacity(expected_len: usize) -> SkipListMap<K, V>
+    where
+        K: 'static,
+    {
+        SkipListMap::builder().expected_len(expected_len).build()
+    }
+}
@@ -0,0 +1,157 @@
+use observed::Observer;
+use changeset::Change;
+
+use std;
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Error returned by `ChangeCapture::changes_since` when some of the changes
+/// asked for were already dropped from the buffer, so the follower has to
+/// start over from a full copy of the map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TruncatedError {
+    /// Sequence number of the oldest change still in the buffer, or of the
+    /// next one when the buffer is empty.
+    pub oldest: u64,
+}
+
+impl std::fmt::Display for TruncatedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "changes before {} are no longer buffered", self.oldest)
+    }
+}
+
+impl std::error::Error for TruncatedError {}
+
+/// Change, along with its sequence number.
+pub type SequencedChange<K, V> = (u64, Change<K, V>);
+
+type Sink<K, V> = Box<FnMut(u64, &Change<K, V>) + Send>;
+
+struct CaptureState<K, V> {
+    // Sequence numbers are consecutive, from front to back.
+    buffer_: VecDeque<SequencedChange<K, V>>,
+    capacity_: usize,
+    // Sequence number of the last change, 0 before the first one.
+    last_: u64,
+    sinks_: Vec<Sink<K, V>>,
+}
+
+/// Change data capture for an `ObservedSkipListMap`: every change made to the
+/// map is given a sequence number, starting from 1, and kept in a bounded
+/// buffer and handed to every subscribed sink, e.g. to replicate the map to
+/// followers without sending them full snapshots.
+///
+/// Followers apply the changes in order, e.g. through
+/// `SkipListMap::apply_changeset`, and catch up after falling behind through
+/// `changes_since`.
+///
+/// Clones of a capture share the same buffer and sinks, so one of them is
+/// registered through `ObservedSkipListMap::observe`, and the others are kept
+/// to read from it.
+///
+/// # Remarks
+///
+/// Observers are called before each change is applied, so a change may be
+/// captured, and handed to the sinks, even though applying it then fails,
+/// e.g. because the height controller or the allocator panics. Followers then
+/// diverge from the map, and should start over from a full copy of it after
+/// such a panic. Sinks are called with the buffer locked, so they must not
+/// call back into the capture.
+pub struct ChangeCapture<K, V> {
+    state_: Arc<Mutex<CaptureState<K, V>>>,
+}
+
+impl<K, V> ChangeCapture<K, V> {
+    /// Builds a capture that buffers the last `capacity` changes. With a
+    /// capacity of 0, changes are only handed to the sinks.
+    pub fn new(capacity: usize) -> ChangeCapture<K, V> {
+        ChangeCapture {
+            state_: Arc::new(Mutex::new(CaptureState {
+                buffer_: VecDeque::new(),
+                capacity_: capacity,
+                last_: 0,
+                sinks_: Vec::new(),
+            })),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, CaptureState<K, V>> {
+        // Changes are buffered before sinks run, so a panicking sink leaves
+        // the buffer consistent, if over capacity until the next change.
+        self.state_.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Hands every change from now on to `sink`, along with its sequence
+    /// number, after the sinks subscribed before it.
+    pub fn subscribe<F>(&self, sink: F)
+    where
+        F: 'static + FnMut(u64, &Change<K, V>) + Send,
+    {
+        self.state().sinks_.push(Box::new(sink));
+    }
+
+    /// Returns the sequence number of the last change captured, or 0 if there
+    /// was none.
+    pub fn last_sequence(&self) -> u64 {
+        self.state().last_
+    }
+
+    /// Gives `change` the next sequence number, buffers it and hands it to
+    /// the sinks.
+    fn capture(&self, change: Change<K, V>) {
+        let mut state = self.state();
+        let state = &mut *state;
+        state.last_ += 1;
+        state.buffer_.push_back((state.last_, change));
+
+        let (sequence, ref change) = *state.buffer_.back().unwrap();
+        for sink in &mut state.sinks_ {
+            sink(sequence, change);
+        }
+
+        while state.buffer_.len() > state.capacity_ {
+            state.buffer_.pop_front();
+        }
+    }
+}
+
+impl<K: Clone, V: Clone> ChangeCapture<K, V> {
+    /// Returns the changes after `sequence`, in order, for a follower that
+    /// has applied every change up to it. Fails with a `TruncatedError` if
+    /// some of them are no longer buffered.
+    pub fn changes_since(&self, sequence: u64) -> Result<Vec<SequencedChange<K, V>>, TruncatedError> {
+        let state = self.state();
+        let oldest = state.buffer_.front().map_or(state.last_ + 1, |&(oldest, _)| oldest);
+        let next = sequence.saturating_add(1);
+        if next < oldest {
+            return Err(TruncatedError { oldest });
+        }
+
+        let skipped = usize::try_from(next - oldest).unwrap_or(usize::MAX);
+        Ok(state.buffer_.iter().skip(skipped).cloned().collect())
+    }
+}
+
+impl<K, V> Clone for ChangeCapture<K, V> {
+    fn clone(&self) -> ChangeCapture<K, V> {
+        ChangeCapture {
+            state_: self.state_.clone(),
+        }
+    }
+}
+
+impl<K: Clone + Send, V: Clone + Send> Observer<K, V> for ChangeCapture<K, V> {
+    fn inserted(&mut self, key: &K, value: &V) {
+        self.capture(Change::Added(key.clone(), value.clone()));
+    }
+
+    fn updated(&mut self, key: &K, _old: &V, new: &V) {
+        self.capture(Change::Changed(key.clone(), new.clone()));
+    }
+
+    fn removed(&mut self, key: &K, _value: &V) {
+        self.capture(Change::Removed(key.clone()));
+    }
+}
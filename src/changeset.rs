@@ -0,0 +1,119 @@
+use map::SkipListMap;
+use error::Error;
+
+use std;
+use std::cmp::Ordering;
+
+/// Single difference between two maps, as produced by `SkipListMap::diff`
+/// and applied by `SkipListMap::apply_changeset`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change<K, V> {
+    /// `key` is only in the newer map, with the given value.
+    Added(K, V),
+    /// `key` is only in the older map.
+    Removed(K),
+    /// `key` is in both maps, and its value changed to the given one.
+    Changed(K, V),
+}
+
+impl<K, V> Change<K, V> {
+    /// Returns the key the change is about.
+    pub fn key(&self) -> &K {
+        match *self {
+            Change::Added(ref key, _) | Change::Removed(ref key) | Change::Changed(ref key, _) => {
+                key
+            }
+        }
+    }
+}
+
+impl<K: Ord + Clone, V: PartialEq + Clone> SkipListMap<K, V> {
+    /// Returns the changes that turn `self` into `other`, in key order. Both
+    /// maps are walked once, side by side.
+    pub fn diff(&self, other: &SkipListMap<K, V>) -> Vec<Change<K, V>> {
+        let mut changes = Vec::new();
+        let mut mine = self.iter().peekable();
+        let mut theirs = other.iter().peekable();
+        loop {
+            let order = match (mine.peek(), theirs.peek()) {
+                (Some(&(old, _)), Some(&(new, _))) => old.cmp(new),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => return changes,
+            };
+
+            match order {
+                Ordering::Less => {
+                    let (key, _) = mine.next().unwrap();
+                    changes.push(Change::Removed(key.clone()));
+                }
+                Ordering::Greater => {
+                    let (key, value) = theirs.next().unwrap();
+                    changes.push(Change::Added(key.clone(), value.clone()));
+                }
+                Ordering::Equal => {
+                    let (_, old) = mine.next().unwrap();
+                    let (key, new) = theirs.next().unwrap();
+                    if old != new {
+                        changes.push(Change::Changed(key.clone(), new.clone()));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<K: Ord, V> SkipListMap<K, V> {
+    /// Applies `changes`, e.g. as produced by `diff` on another replica, so
+    /// that replicas converge by exchanging changes instead of whole maps.
+    ///
+    /// Changes in increasing key order are applied in a single walk through
+    /// the list: each one is looked up starting from where the previous one
+    /// was. Changes out of order are still applied, but start over from the
+    /// head.
+    ///
+    /// # Remarks
+    ///
+    /// Fails with `Error::KeyExists` on an `Added` key that is already in the
+    /// map, and with `Error::KeyMissing` on a `Removed` or `Changed` key that
+    /// is not. The changes before the failing one stay applied, and the ones
+    /// after it are dropped.
+    pub fn apply_changeset<I>(&mut self, changes: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = Change<K, V>>,
+    {
+        let mut fingers = vec![self.head_; self.max_height()];
+        for change in changes {
+            let after_fingers = fingers[0] == self.head_ ||
+                unsafe { fingers[0].as_ref().key::<K>() } < change.key();
+            if !after_fingers {
+                fingers = vec![self.head_; self.max_height()];
+            }
+            self.advance_fingers(&mut fingers, change.key());
+
+            let found = unsafe { fingers[0].as_ref().link(0) }
+                .filter(|node| unsafe { node.as_ref().key::<K>() } == change.key());
+            match (change, found) {
+                (Change::Added(key, value), None) => unsafe {
+                    let height = self.generate_height(&key);
+                    let node = self.link_new_node(&fingers, key, value, height);
+                    let levels = std::cmp::max(node.as_ref().height(), 1);
+                    for finger in fingers.iter_mut().take(levels) {
+                        *finger = node;
+                    }
+                },
+                (Change::Removed(_), Some(node)) => unsafe {
+                    self.unlink_node(&fingers, node);
+                    drop(self.recycle_node(node));
+                },
+                (Change::Changed(_, value), Some(node)) => unsafe {
+                    drop((*node.as_ptr()).replace_value(value));
+                },
+                (Change::Added(..), Some(_)) => return Err(Error::KeyExists),
+                (_, None) => return Err(Error::KeyMissing),
+            }
+        }
+
+        Ok(())
+    }
+}
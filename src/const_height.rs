@@ -0,0 +1,340 @@
+//! Skip List whose maximum height is a compile-time constant.
+//!
+//! `ConstHeightSkipListMap<K, V, H>` keeps the links of its head in an array
+//! of `H` entries, and searches record the path they walked in another one,
+//! on the stack, where `SkipListMap` allocates a vector per search. Each node
+//! is a single allocation holding its key, its value and exactly as many
+//! links as its tower has levels, instead of pointing to a separate vector of
+//! links.
+//!
+//! The price is that `H` can't be chosen at runtime: heights given out by the
+//! controller are capped at `H - 1`.
+use height_control::{HeightContext, HeightControl};
+
+use std;
+use std::alloc::{self, Layout};
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::marker::PhantomData;
+use std::ptr::{self, NonNull};
+
+type Link<K, V> = Option<NonNull<Node<K, V>>>;
+
+/// A node of height `height_` is followed by `height_ + 1` links.
+#[repr(C)]
+struct Node<K, V> {
+    key_: K,
+    value_: V,
+    height_: usize,
+    forward_: [Link<K, V>; 0],
+}
+
+impl<K, V> Node<K, V> {
+    fn layout(height: usize) -> Layout {
+        let links = std::mem::offset_of!(Node<K, V>, forward_);
+        let size = links + (height + 1) * std::mem::size_of::<Link<K, V>>();
+        Layout::from_size_align(size, std::mem::align_of::<Node<K, V>>())
+            .unwrap()
+            .pad_to_align()
+    }
+
+    /// Allocates a node with an unlinked tower.
+    fn allocate(key: K, value: V, height: usize) -> NonNull<Node<K, V>> {
+        let layout = Self::layout(height);
+        unsafe {
+            let node = match NonNull::new(alloc::alloc(layout) as *mut Node<K, V>) {
+                Some(node) => node,
+                None => alloc::handle_alloc_error(layout),
+            };
+
+            ptr::write(ptr::addr_of_mut!((*node.as_ptr()).key_), key);
+            ptr::write(ptr::addr_of_mut!((*node.as_ptr()).value_), value);
+            ptr::write(ptr::addr_of_mut!((*node.as_ptr()).height_), height);
+            for level in 0..=height {
+                ptr::write(Self::tower(node).add(level), None);
+            }
+
+            node
+        }
+    }
+
+    /// Moves the key and value out of `node`, and frees it.
+    unsafe fn free(node: NonNull<Node<K, V>>) -> (K, V) {
+        let node = node.as_ptr();
+        let entry = (ptr::read(&(*node).key_), ptr::read(&(*node).value_));
+        alloc::dealloc(node as *mut u8, Self::layout((*node).height_));
+        entry
+    }
+
+    /// Returns the first link of the tower of `node`.
+    unsafe fn tower(node: NonNull<Node<K, V>>) -> *mut Link<K, V> {
+        ptr::addr_of_mut!((*node.as_ptr()).forward_) as *mut Link<K, V>
+    }
+}
+
+/// `SkipListMap` with towers of at most `H` levels, fixed at compile time.
+/// See the `const_height` module.
+pub struct ConstHeightSkipListMap<K, V, const H: usize = 16> {
+    head_: [Link<K, V>; H],
+    length_: usize,
+    // Highest level reached by any node since the last `clear`.
+    height_: usize,
+    controller_: Box<HeightControl<K>>,
+    marker_: PhantomData<(K, V)>,
+}
+
+// The list uniquely owns all of its nodes, and the controller is required to
+// be `Send` by `HeightControl`.
+unsafe impl<K: Send, V: Send, const H: usize> Send for ConstHeightSkipListMap<K, V, H> {}
+
+impl<K, V, const H: usize> ConstHeightSkipListMap<K, V, H> {
+    /// Builds an empty list.
+    ///
+    /// # Arguments
+    ///
+    ///  * `controller`: generates heights for inserted nodes. A node of height
+    ///    `h` is linked at levels `0..=h`, and heights are capped at `H - 1`.
+    ///
+    /// # Panics
+    ///
+    /// If `H` is 0.
+    pub fn new(controller: Box<HeightControl<K>>) -> ConstHeightSkipListMap<K, V, H> {
+        assert!(H > 0, "towers must have at least one level");
+        ConstHeightSkipListMap {
+            head_: [None; H],
+            length_: 0,
+            height_: 0,
+            controller_: controller,
+            marker_: PhantomData,
+        }
+    }
+
+    /// Returns the number of elements stored in the structure.
+    pub fn len(&self) -> usize {
+        self.length_
+    }
+
+    /// Returns `true` if there are no elements stored within the structure.
+    pub fn is_empty(&self) -> bool {
+        self.length_ == 0
+    }
+
+    /// Removes all elements.
+    ///
+    /// # Remarks
+    ///
+    /// The list is emptied before any element is dropped, so if a destructor
+    /// panics, the elements after it are leaked, but the list stays usable.
+    pub fn clear(&mut self) {
+        let mut current = self.head_[0];
+        self.head_ = [None; H];
+        self.length_ = 0;
+        self.height_ = 0;
+
+        while let Some(node) = current {
+            unsafe {
+                current = *Node::tower(node);
+                drop(Node::free(node));
+            }
+        }
+    }
+
+    /// Iterates over the entries, in key order.
+    pub fn iter(&self) -> ConstHeightIter<'_, K, V> {
+        ConstHeightIter {
+            current_: self.head_[0],
+            marker_: PhantomData,
+        }
+    }
+
+    /// Returns the entry with the smallest key, if any.
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.iter().next()
+    }
+
+    /// Removes the entry with the smallest key, and returns it.
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        let first = self.head_[0]?;
+        unsafe {
+            // The first node is the first one on every level it is linked at.
+            let tower = Node::tower(first);
+            for level in 0..=(*first.as_ptr()).height_ {
+                self.head_[level] = *tower.add(level);
+            }
+
+            self.length_ -= 1;
+            Some(Node::free(first))
+        }
+    }
+}
+
+impl<K: Ord, V, const H: usize> ConstHeightSkipListMap<K, V, H> {
+    /// Returns the shape of the list, as seen by its height controller.
+    fn context(&self) -> HeightContext {
+        HeightContext {
+            len: self.length_,
+            height: self.height_,
+        }
+    }
+
+    /// Finds, for every level, the tower of the last node with a key less
+    /// than `key`. The head counts as a node with the smallest key.
+    fn find_updates<Q>(&mut self, key: &Q) -> [*mut Link<K, V>; H]
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let head = self.head_.as_mut_ptr();
+        let mut updates = [head; H];
+        let mut tower = head;
+        for level in (0..=self.height_).rev() {
+            unsafe {
+                while let Some(next) = *tower.add(level) {
+                    if (*next.as_ptr()).key_.borrow() >= key {
+                        break;
+                    }
+                    tower = Node::tower(next);
+                }
+            }
+
+            updates[level] = tower;
+        }
+
+        updates
+    }
+
+    /// Returns the node holding `key`, if it exists.
+    fn find<Q>(&self, key: &Q) -> Link<K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut tower = self.head_.as_ptr();
+        for level in (0..=self.height_).rev() {
+            unsafe {
+                while let Some(next) = *tower.add(level) {
+                    match (*next.as_ptr()).key_.borrow().cmp(key) {
+                        Ordering::Less => tower = Node::tower(next),
+                        Ordering::Equal => return Some(next),
+                        Ordering::Greater => break,
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Inserts `value` under `key`, returning the value it replaced, if any.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let updates = self.find_updates(&key);
+        unsafe {
+            if let Some(next) = *updates[0] {
+                if (*next.as_ptr()).key_ == key {
+                    return Some(std::mem::replace(&mut (*next.as_ptr()).value_, value));
+                }
+            }
+
+            let height = std::cmp::min(self.controller_.get_height_in(&key, &self.context()), H - 1);
+            let node = Node::allocate(key, value, height);
+            let tower = Node::tower(node);
+            for (level, &update) in updates.iter().enumerate().take(height + 1) {
+                *tower.add(level) = *update.add(level);
+                *update.add(level) = Some(node);
+            }
+
+            self.height_ = std::cmp::max(self.height_, height);
+        }
+
+        self.length_ += 1;
+        None
+    }
+
+    /// Returns a const reference to the element with key `key`, if it exists.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).map(|node| unsafe { &(*node.as_ptr()).value_ })
+    }
+
+    /// Returns a mutable reference to the element with key `key`, if it
+    /// exists.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).map(|node| unsafe { &mut (*node.as_ptr()).value_ })
+    }
+
+    /// Returns true if `key` is in the list.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).is_some()
+    }
+
+    /// Removes the element with key `key`, returning its value if it existed.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let updates = self.find_updates(key);
+        unsafe {
+            let target = (*updates[0])?;
+            if (*target.as_ptr()).key_.borrow() != key {
+                return None;
+            }
+
+            let tower = Node::tower(target);
+            let height = (*target.as_ptr()).height_;
+            for (level, &update) in updates.iter().enumerate().take(height + 1) {
+                *update.add(level) = *tower.add(level);
+            }
+
+            self.length_ -= 1;
+            let (_, value) = Node::free(target);
+            Some(value)
+        }
+    }
+}
+
+impl<K, V, const H: usize> Drop for ConstHeightSkipListMap<K, V, H> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<K, V, const H: usize> std::fmt::Debug for ConstHeightSkipListMap<K, V, H>
+where
+    K: std::fmt::Debug,
+    V: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Iterator over the entries of a `ConstHeightSkipListMap`, in key order.
+pub struct ConstHeightIter<'a, K: 'a, V: 'a> {
+    current_: Link<K, V>,
+    marker_: PhantomData<&'a (K, V)>,
+}
+
+impl<'a, K, V> Iterator for ConstHeightIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.current_?;
+        unsafe {
+            self.current_ = *Node::tower(node);
+            let node = &*node.as_ptr();
+            Some((&node.key_, &node.value_))
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use map::SkipListMap;
+use height_control::HeightControl;
+use iter::{Iter, Range};
+
+use std;
+use std::borrow::Borrow;
+use std::ops::RangeBounds;
+use std::sync::Arc;
+
+/// `SkipListMap` whose values are shared through `Arc`, and copied on write.
+///
+/// Cloning the map only clones keys and `Arc` handles, so a working copy of a
+/// large map can be taken cheaply, and changed speculatively. A value is only
+/// cloned the first time it is mutated through a map that shares it.
+pub struct CowSkipListMap<K, V> {
+    map_: SkipListMap<K, Arc<V>>,
+}
+
+impl<K, V> CowSkipListMap<K, V> {
+    pub fn new(controller: Box<HeightControl<K>>) -> CowSkipListMap<K, V> {
+        CowSkipListMap {
+            map_: SkipListMap::new(controller),
+        }
+    }
+
+    /// Removes all elements.
+    pub fn clear(&mut self) {
+        self.map_.clear()
+    }
+
+    /// Returns the number of elements stored in the structure.
+    pub fn len(&self) -> usize {
+        self.map_.len()
+    }
+
+    /// Returns `true` if there are no elements stored within the structure.
+    pub fn is_empty(&self) -> bool {
+        self.map_.is_empty()
+    }
+
+    /// Iterates over the entries, in key order.
+    pub fn iter(&self) -> CowIter<Iter<'_, K, Arc<V>>> {
+        CowIter(self.map_.iter())
+    }
+
+    /// Returns the underlying map, with shared values.
+    pub fn as_map(&self) -> &SkipListMap<K, Arc<V>> {
+        &self.map_
+    }
+
+    /// Consumes the map, returning the underlying one.
+    pub fn into_map(self) -> SkipListMap<K, Arc<V>> {
+        self.map_
+    }
+}
+
+impl<K: Ord, V> CowSkipListMap<K, V> {
+    /// Returns a const reference to the element with key `key`, if it exists.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map_.get(key).map(|value| &**value)
+    }
+
+    /// Returns the shared handle to the element with key `key`, if it exists.
+    pub fn get_shared<Q>(&self, key: &Q) -> Option<&Arc<V>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map_.get(key)
+    }
+
+    /// Returns true if `key` is in the map.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map_.contains_key(key)
+    }
+
+    /// Returns the entry with the smallest key, if any.
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.map_.first().map(|(key, value)| (key, &**value))
+    }
+
+    /// Iterates over the entries within `range`, in key order.
+    pub fn range<T, R>(&self, range: R) -> CowIter<Range<'_, K, Arc<V>>>
+    where
+        K: Borrow<T>,
+        R: RangeBounds<T>,
+        T: Ord + ?Sized,
+    {
+        CowIter(self.map_.range(range))
+    }
+}
+
+impl<K: Ord, V: Clone> CowSkipListMap<K, V> {
+    /// Inserts `value` under `key`, returning the value it replaced, if any.
+    /// The replaced value is cloned if another map still shares it.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.map_.insert(key, Arc::new(value)).map(Arc::unwrap_or_clone)
+    }
+
+    /// Returns a mutable reference to the element with key `key`, if it
+    /// exists. The value is cloned first if another map still shares it.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map_.get_mut(key).map(Arc::make_mut)
+    }
+
+    /// Removes the element with key `key`, returning its value if it existed.
+    /// The value is cloned if another map still shares it.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map_.remove(key).map(Arc::unwrap_or_clone)
+    }
+}
+
+impl<K: Ord + Clone, V> Clone for CowSkipListMap<K, V> {
+    fn clone(&self) -> CowSkipListMap<K, V> {
+        CowSkipListMap {
+            map_: self.map_.clone(),
+        }
+    }
+}
+
+impl<K: 'static + std::hash::Hash, V> Default for CowSkipListMap<K, V> {
+    fn default() -> CowSkipListMap<K, V> {
+        CowSkipListMap {
+            map_: Default::default(),
+        }
+    }
+}
+
+impl<K: std::fmt::Debug, V: std::fmt::Debug> std::fmt::Debug for CowSkipListMap<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Iterator over the entries of a `CowSkipListMap`, in key order.
+pub struct CowIter<I>(I);
+
+impl<'a, K: 'a, V: 'a, I> Iterator for CowIter<I>
+where
+    I: Iterator<Item = (&'a K, &'a Arc<V>)>,
+{
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(key, value)| (key, &**value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
@@ -0,0 +1,85 @@
+use map::SkipListMap;
+use iter::Range;
+
+use std::ops::Bound;
+
+/// Walks a `SkipListMap` in key order, remembering its position by the last
+/// key it returned rather than by pointing into the list, so that the list can
+/// be mutated freely between steps, e.g. to serve paginated scans that
+/// interleave with writes.
+///
+/// Every step seeks back into the list in O(log n). Steps see the list as it
+/// is at that point: entries inserted after the position are returned, and
+/// the ones before it never are, even if the key at the position was removed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ResumableCursor<K> {
+    // `None` before the first step.
+    last_: Option<K>,
+}
+
+impl<K> ResumableCursor<K> {
+    /// Builds a cursor positioned before the first entry.
+    pub fn new() -> ResumableCursor<K> {
+        ResumableCursor { last_: None }
+    }
+
+    /// Builds a cursor that resumes right after `key`, e.g. from the position
+    /// of a previous cursor handed out as a page token.
+    pub fn after(key: K) -> ResumableCursor<K> {
+        ResumableCursor { last_: Some(key) }
+    }
+
+    /// Returns the last key returned, if any.
+    pub fn position(&self) -> Option<&K> {
+        self.last_.as_ref()
+    }
+
+    /// Returns the last key returned, if any, consuming the cursor.
+    pub fn into_position(self) -> Option<K> {
+        self.last_
+    }
+}
+
+impl<K: Ord + Clone> ResumableCursor<K> {
+    /// Returns the entry with the smallest key after the position, and moves
+    /// the cursor to it. Returns `None`, and stays put, when there is none.
+    pub fn next<'a, V>(&mut self, list: &'a SkipListMap<K, V>) -> Option<(&'a K, &'a V)> {
+        let entry = self.remaining(list).next();
+        if let Some((key, _)) = entry {
+            self.last_ = Some(key.clone());
+        }
+
+        entry
+    }
+
+    /// Returns up to `n` entries after the position, in key order, and moves
+    /// the cursor to the last of them. Seeks once for the whole page.
+    pub fn next_page<'a, V>(
+        &mut self,
+        list: &'a SkipListMap<K, V>,
+        n: usize,
+    ) -> Vec<(&'a K, &'a V)> {
+        let page: Vec<(&'a K, &'a V)> = self.remaining(list).take(n).collect();
+        if let Some(&(key, _)) = page.last() {
+            self.last_ = Some(key.clone());
+        }
+
+        page
+    }
+
+    /// Iterates over the entries after the position.
+    fn remaining<'a, V>(&self, list: &'a SkipListMap<K, V>) -> Range<'a, K, V> {
+        let start = match self.last_ {
+            Some(ref key) => Bound::Excluded(key),
+            None => Bound::Unbounded,
+        };
+
+        list.range::<K, _>((start, Bound::Unbounded))
+    }
+}
+
+impl<K> Default for ResumableCursor<K> {
+    fn default() -> ResumableCursor<K> {
+        ResumableCursor::new()
+    }
+}
@@ -0,0 +1,172 @@
+use map::SkipListMap;
+use height_control::HeightControl;
+use iter::{Iter, Range};
+
+use std;
+use std::cmp::Ordering;
+use std::ops::{Bound, RangeBounds};
+
+/// Key wrapper that reverses the order of `K`, so that a `SkipListMap` keyed by
+/// `Descending<K>` keeps its greatest keys first.
+///
+/// Most of the time, `DescendingSkipListMap` is more convenient, since it
+/// takes and returns plain keys.
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Descending<K: ?Sized>(pub K);
+
+impl<K: ?Sized> Descending<K> {
+    /// Views a reference to a key as a reference to a `Descending` key, so that
+    /// lookups don't need to own the key.
+    pub fn from_ref(key: &K) -> &Descending<K> {
+        // `Descending` is a transparent wrapper around `K`.
+        unsafe { &*(key as *const K as *const Descending<K>) }
+    }
+}
+
+impl<K: ?Sized + PartialOrd> PartialOrd for Descending<K> {
+    fn partial_cmp(&self, other: &Descending<K>) -> Option<Ordering> {
+        other.0.partial_cmp(&self.0)
+    }
+}
+
+impl<K: ?Sized + Ord> Ord for Descending<K> {
+    fn cmp(&self, other: &Descending<K>) -> Ordering {
+        other.0.cmp(&self.0)
+    }
+}
+
+fn descending_bound<K>(bound: Bound<&K>) -> Bound<&Descending<K>> {
+    match bound {
+        Bound::Included(key) => Bound::Included(Descending::from_ref(key)),
+        Bound::Excluded(key) => Bound::Excluded(Descending::from_ref(key)),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+/// `SkipListMap` that keeps its greatest keys first. Iteration, `first`, and
+/// `range` all go from the greatest key to the smallest one.
+pub struct DescendingSkipListMap<K, V> {
+    map_: SkipListMap<Descending<K>, V>,
+}
+
+impl<K, V> DescendingSkipListMap<K, V> {
+    pub fn new(controller: Box<HeightControl<Descending<K>>>) -> DescendingSkipListMap<K, V> {
+        DescendingSkipListMap {
+            map_: SkipListMap::new(controller),
+        }
+    }
+
+    /// Removes all elements.
+    pub fn clear(&mut self) {
+        self.map_.clear()
+    }
+
+    /// Returns the number of elements stored in the structure.
+    pub fn len(&self) -> usize {
+        self.map_.len()
+    }
+
+    /// Returns `true` if there are no elements stored within the structure.
+    pub fn is_empty(&self) -> bool {
+        self.map_.is_empty()
+    }
+
+    /// Iterates over the entries, from the greatest key to the smallest one.
+    pub fn iter(&self) -> DescendingIter<Iter<'_, Descending<K>, V>> {
+        DescendingIter(self.map_.iter())
+    }
+
+    /// Returns the underlying map, keyed by `Descending<K>`.
+    pub fn as_map(&self) -> &SkipListMap<Descending<K>, V> {
+        &self.map_
+    }
+
+    /// Consumes the map, returning the underlying one.
+    pub fn into_map(self) -> SkipListMap<Descending<K>, V> {
+        self.map_
+    }
+}
+
+impl<K: Ord, V> DescendingSkipListMap<K, V> {
+    /// Inserts `value` under `key`, returning the value it replaced, if any.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.map_.insert(Descending(key), value)
+    }
+
+    /// Returns a const reference to the element with key `key`, if it exists.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.map_.get(Descending::from_ref(key))
+    }
+
+    /// Returns a mutable reference to the element with key `key`, if it
+    /// exists.
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.map_.get_mut(Descending::from_ref(key))
+    }
+
+    /// Returns true if `key` is in the map.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.map_.contains_key(Descending::from_ref(key))
+    }
+
+    /// Removes the element with key `key`, returning its value if it existed.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.map_.remove(Descending::from_ref(key))
+    }
+
+    /// Returns the entry with the greatest key, if any.
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.map_.first().map(|(key, value)| (&key.0, value))
+    }
+
+    /// Iterates over the entries within `range`, from the greatest key to the
+    /// smallest one.
+    ///
+    /// # Arguments
+    ///
+    ///  * `range`: bounds on the keys, written as for any other map. For
+    ///    example, `20..=45` yields the keys from 45 down to 20.
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> DescendingIter<Range<'_, Descending<K>, V>> {
+        // The upper bound is where the iteration starts.
+        let bounds = (
+            descending_bound(range.end_bound()),
+            descending_bound(range.start_bound()),
+        );
+
+        DescendingIter(self.map_.range(bounds))
+    }
+}
+
+impl<K: 'static + Ord + std::hash::Hash, V> Default for DescendingSkipListMap<K, V> {
+    fn default() -> DescendingSkipListMap<K, V> {
+        DescendingSkipListMap {
+            map_: Default::default(),
+        }
+    }
+}
+
+impl<K: std::fmt::Debug, V: std::fmt::Debug> std::fmt::Debug for DescendingSkipListMap<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Iterator over the entries of a `DescendingSkipListMap`, from the greatest
+/// key to the smallest one.
+pub struct DescendingIter<I>(I);
+
+impl<'a, K: 'a, V: 'a, I> Iterator for DescendingIter<I>
+where
+    I: Iterator<Item = (&'a Descending<K>, &'a V)>,
+{
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(key, value)| (&key.0, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
@@ -0,0 +1,111 @@
+use map::SkipListMap;
+use node::Node;
+use pool::NodePool;
+
+use std;
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+
+/// Entry taken out of a `SkipListMap` by `detach`, still in the node it was
+/// allocated in. It can be attached to any map with the same key and value
+/// types, e.g. to move entries between shards, without moving or cloning the
+/// key and value, or allocating anything.
+///
+/// Dropping it frees the node, along with its key and value, or gives it back
+/// to the node pool of the map it was detached from, if any.
+pub struct DetachedNode<K, V> {
+    node_: NonNull<Node<K, V>>,
+    pool_: Option<NodePool<K, V>>,
+    marker_: PhantomData<Box<(K, V)>>,
+}
+
+// The node is uniquely owned by the handle.
+unsafe impl<K: Send, V: Send> Send for DetachedNode<K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for DetachedNode<K, V> {}
+
+impl<K, V> DetachedNode<K, V> {
+    /// Returns the key of the entry.
+    pub fn key(&self) -> &K {
+        unsafe { self.node_.as_ref().key() }
+    }
+
+    /// Returns the value of the entry.
+    pub fn value(&self) -> &V {
+        unsafe { self.node_.as_ref().value() }
+    }
+
+    /// Returns a mutable reference to the value of the entry.
+    pub fn value_mut(&mut self) -> &mut V {
+        unsafe { Node::key_value_mut_ptr(self.node_).1 }
+    }
+
+    /// Frees the node, or gives it back to its pool, returning its key and
+    /// value.
+    pub fn into_entry(mut self) -> (K, V) {
+        let node = self.node_;
+        let pool = self.pool_.take();
+        std::mem::forget(self);
+        match pool {
+            Some(pool) => pool.recycle(node),
+            None => SkipListMap::take_node(node),
+        }
+    }
+}
+
+impl<K, V> Drop for DetachedNode<K, V> {
+    fn drop(&mut self) {
+        match self.pool_ {
+            Some(ref pool) => drop(pool.recycle(self.node_)),
+            None => drop(SkipListMap::take_node(self.node_)),
+        }
+    }
+}
+
+impl<K: std::fmt::Debug, V: std::fmt::Debug> std::fmt::Debug for DetachedNode<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_tuple("DetachedNode").field(self.key()).field(self.value()).finish()
+    }
+}
+
+impl<K: Ord, V> SkipListMap<K, V> {
+    /// Unlinks the entry with key `key`, if it exists, and returns it still
+    /// in its node, to be attached to another map with `attach`.
+    pub fn detach<Q>(&mut self, key: &Q) -> Option<DetachedNode<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.unlink(key).map(|node| DetachedNode {
+            node_: node,
+            pool_: self.pool_.clone(),
+            marker_: PhantomData,
+        })
+    }
+
+    /// Links a node detached from this or another map into this one, reusing
+    /// its allocation. If its key is already in the map, the node is handed
+    /// back untouched.
+    ///
+    /// # Remarks
+    ///
+    /// The node keeps its tower height, unless it is too tall for this map,
+    /// in which case it gets a new height from the map's controller.
+    pub fn attach(&mut self, mut node: DetachedNode<K, V>) -> Result<(), DetachedNode<K, V>> {
+        let (lower_bound, updates) = self.find_lower_bound_with_updates(node.key());
+        if let Some(next) = unsafe { lower_bound.as_ref().next(0) } {
+            if next.key::<K>() == node.key() {
+                return Err(node);
+            }
+        }
+
+        let pointer = node.node_;
+        drop(node.pool_.take());
+        std::mem::forget(node);
+        unsafe {
+            self.relink_node(&updates, pointer);
+        }
+
+        Ok(())
+    }
+}
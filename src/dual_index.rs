@@ -0,0 +1,130 @@
+use augmented::{AugmentedIter, AugmentedSkipListMap, Count};
+use height_control::HeightControl;
+
+use std;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::Bound;
+
+/// Map from ids to values that is ordered by a score attached to every id,
+/// such as a leaderboard. Ids are looked up through a hash index, and entries
+/// are kept sorted by score, then by id, in an `AugmentedSkipListMap` that
+/// counts them, so ranks take O(log n). Lookups by id go straight to the node
+/// of the entry, in O(1).
+pub struct DualIndexMap<I, S, V> {
+    // Score of every id, along with the node of its entry in `ordered_`.
+    scores_: HashMap<I, (S, usize)>,
+    ordered_: AugmentedSkipListMap<(S, I), V, Count>,
+}
+
+impl<I, S, V> DualIndexMap<I, S, V>
+where
+    I: Hash + Ord + Clone,
+    S: Ord + Clone,
+{
+    pub fn new(controller: Box<HeightControl<(S, I)>>) -> DualIndexMap<I, S, V> {
+        DualIndexMap {
+            scores_: HashMap::new(),
+            ordered_: AugmentedSkipListMap::new(controller),
+        }
+    }
+
+    /// Returns the number of ids stored in the structure.
+    pub fn len(&self) -> usize {
+        self.scores_.len()
+    }
+
+    /// Returns `true` if there are no ids stored within the structure.
+    pub fn is_empty(&self) -> bool {
+        self.scores_.is_empty()
+    }
+
+    /// Removes all ids.
+    pub fn clear(&mut self) {
+        self.scores_.clear();
+        self.ordered_.clear();
+    }
+
+    /// Iterates over the entries, ordered by score, then by id.
+    pub fn iter(&self) -> DualIndexIter<'_, I, S, V> {
+        DualIndexIter(self.ordered_.iter())
+    }
+
+    /// Inserts `value` under `id` with `score`, returning the score and value
+    /// it replaced, if any.
+    pub fn insert(&mut self, id: I, score: S, value: V) -> Option<(S, V)> {
+        let replaced = self.remove(&id);
+        let (node, _) = self.ordered_.insert_node((score.clone(), id.clone()), value);
+        self.scores_.insert(id, (score, node));
+        replaced
+    }
+
+    /// Returns the score of `id`, if it exists.
+    pub fn score(&self, id: &I) -> Option<&S> {
+        self.scores_.get(id).map(|(score, _)| score)
+    }
+
+    /// Returns the score and value of `id`, if it exists.
+    pub fn get(&self, id: &I) -> Option<(&S, &V)> {
+        let &(ref score, node) = self.scores_.get(id)?;
+        Some((score, self.ordered_.node_value(node)))
+    }
+
+    /// Returns the score of `id` and a mutable reference to its value, if it
+    /// exists. The score can only be changed through `update_score`, which
+    /// moves the entry to its new place.
+    pub fn get_mut(&mut self, id: &I) -> Option<(&S, &mut V)> {
+        let &(ref score, node) = self.scores_.get(id)?;
+        Some((score, self.ordered_.node_value_mut(node)))
+    }
+
+    /// Returns true if `id` is in the map.
+    pub fn contains_id(&self, id: &I) -> bool {
+        self.scores_.contains_key(id)
+    }
+
+    /// Changes the score of `id`, and returns its previous one, if `id`
+    /// exists.
+    pub fn update_score(&mut self, id: &I, score: S) -> Option<S> {
+        let (previous, value) = self.remove(id)?;
+        self.insert(id.clone(), score, value);
+        Some(previous)
+    }
+
+    /// Removes `id`, returning its score and value if it existed.
+    pub fn remove(&mut self, id: &I) -> Option<(S, V)> {
+        let (score, _) = self.scores_.remove(id)?;
+        let value = self.ordered_.remove(&(score.clone(), id.clone()))?;
+        Some((score, value))
+    }
+
+    /// Returns the number of entries ordered before `id`, if it exists.
+    pub fn rank_of(&self, id: &I) -> Option<usize> {
+        let (score, _) = self.scores_.get(id)?;
+        let key = (score.clone(), id.clone());
+        Some(self.ordered_.range_aggregate((Bound::Unbounded, Bound::Excluded(&key))))
+    }
+}
+
+impl<I, S, V> std::fmt::Debug for DualIndexMap<I, S, V>
+where
+    I: std::fmt::Debug,
+    S: std::fmt::Debug,
+    V: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_list().entries(DualIndexIter(self.ordered_.iter())).finish()
+    }
+}
+
+/// Iterator over the entries of a `DualIndexMap`, ordered by score, then by
+/// id.
+pub struct DualIndexIter<'a, I: 'a, S: 'a, V: 'a>(AugmentedIter<'a, (S, I), V, Count>);
+
+impl<'a, I: 'a, S: 'a, V: 'a> Iterator for DualIndexIter<'a, I, S, V> {
+    type Item = (&'a I, &'a S, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|((score, id), value)| (id, score, value))
+    }
+}
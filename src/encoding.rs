@@ -0,0 +1,192 @@
+use std;
+use std::io::{self, Read, Write};
+
+/// Binary encoding for keys and values, used by the formats the Skip List can
+/// be written to (see `SkipListMap::write_to`).
+///
+/// Numbers are written in little endian, and variable length types are
+/// prefixed by their length as a `u64`.
+pub trait Encode: Sized {
+    /// Writes `self` into `writer`.
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()>;
+
+    /// Reads a value written by `encode` from `reader`.
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Self>;
+}
+
+macro_rules! encode_number {
+    ($($number:ty),*) => {
+        $(
+            impl Encode for $number {
+                fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+                    writer.write_all(&self.to_le_bytes())
+                }
+
+                fn decode<R: Read>(reader: &mut R) -> io::Result<$number> {
+                    let mut bytes = [0; std::mem::size_of::<$number>()];
+                    reader.read_exact(&mut bytes)?;
+                    Ok(<$number>::from_le_bytes(bytes))
+                }
+            }
+        )*
+    };
+}
+
+encode_number!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
+
+// Sizes are always written with 64 bits, so that files can be moved between
+// platforms.
+impl Encode for usize {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        (*self as u64).encode(writer)
+    }
+
+    fn decode<R: Read>(reader: &mut R) -> io::Result<usize> {
+        let value = u64::decode(reader)?;
+        if value > usize::MAX as u64 {
+            return Err(invalid_data("size does not fit in usize"));
+        }
+
+        Ok(value as usize)
+    }
+}
+
+impl Encode for isize {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        (*self as i64).encode(writer)
+    }
+
+    fn decode<R: Read>(reader: &mut R) -> io::Result<isize> {
+        let value = i64::decode(reader)?;
+        if value > isize::MAX as i64 || value < isize::MIN as i64 {
+            return Err(invalid_data("size does not fit in isize"));
+        }
+
+        Ok(value as isize)
+    }
+}
+
+impl Encode for bool {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        (*self as u8).encode(writer)
+    }
+
+    fn decode<R: Read>(reader: &mut R) -> io::Result<bool> {
+        match u8::decode(reader)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(invalid_data("invalid boolean")),
+        }
+    }
+}
+
+impl Encode for String {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.len().encode(writer)?;
+        writer.write_all(self.as_bytes())
+    }
+
+    fn decode<R: Read>(reader: &mut R) -> io::Result<String> {
+        let length = usize::decode(reader)?;
+        let mut bytes = Vec::new();
+        reader.take(length as u64).read_to_end(&mut bytes)?;
+        if bytes.len() != length {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        String::from_utf8(bytes).map_err(|_| invalid_data("invalid UTF-8"))
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.len().encode(writer)?;
+        for element in self {
+            element.encode(writer)?;
+        }
+
+        Ok(())
+    }
+
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Vec<T>> {
+        let length = usize::decode(reader)?;
+        // The length is not trusted for the allocation: a corrupted one would
+        // otherwise abort the process.
+        let mut elements = Vec::with_capacity(std::cmp::min(length, 1024));
+        for _ in 0..length {
+            elements.push(T::decode(reader)?);
+        }
+
+        Ok(elements)
+    }
+}
+
+impl<T: Encode> Encode for Option<T> {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        match *self {
+            None => false.encode(writer),
+            Some(ref value) => {
+                true.encode(writer)?;
+                value.encode(writer)
+            }
+        }
+    }
+
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Option<T>> {
+        if bool::decode(reader)? {
+            Ok(Some(T::decode(reader)?))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl<A: Encode, B: Encode> Encode for (A, B) {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.0.encode(writer)?;
+        self.1.encode(writer)
+    }
+
+    fn decode<R: Read>(reader: &mut R) -> io::Result<(A, B)> {
+        Ok((A::decode(reader)?, B::decode(reader)?))
+    }
+}
+
+/// Error for well-formed reads that produce invalid contents.
+pub(crate) fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip<T: Encode + PartialEq + std::fmt::Debug>(value: T) {
+        let mut bytes = Vec::new();
+        value.encode(&mut bytes).unwrap();
+        let mut reader = &bytes[..];
+        assert_eq!(T::decode(&mut reader).unwrap(), value);
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn round_trips() {
+        round_trip(0xdead_beefu32);
+        round_trip(-12i64);
+        round_trip(usize::MAX);
+        round_trip(1.5f64);
+        round_trip(true);
+        round_trip("skip list".to_string());
+        round_trip(vec![Some(1u8), None]);
+        round_trip((3u16, String::new()));
+    }
+
+    #[test]
+    fn truncated_string() {
+        let mut bytes = Vec::new();
+        "skip list".to_string().encode(&mut bytes).unwrap();
+        bytes.pop();
+        let error = String::decode(&mut &bytes[..]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
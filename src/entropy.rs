@@ -0,0 +1,155 @@
+extern crate rand;
+
+#[cfg(feature = "getrandom")]
+extern crate getrandom;
+
+/// Source of random bits for the randomized height controllers,
+/// `GeometricalGenerator` and `TwoPowGenerator`.
+///
+/// By default they use `rand`'s thread-local generator, which needs operating
+/// system support that is not available on every target: on
+/// `wasm32-unknown-unknown` it is not backed by anything. Implementing this
+/// trait allows plugging in whatever the platform provides.
+///
+/// Sources are cloned along with the controllers that hold them. Clones should
+/// not produce the same sequence as the original, or the lists built from them
+/// will have correlated towers.
+pub trait Entropy: Clone + Send {
+    /// Returns 64 uniformly distributed random bits.
+    fn next_u64(&mut self) -> u64;
+
+    /// Returns a uniformly distributed value in the open interval (0, 1).
+    fn next_f64(&mut self) -> f64 {
+        // The 52 high bits plus the extra half still fit in the mantissa, and
+        // the half keeps the value away from both ends.
+        ((self.next_u64() >> 12) as f64 + 0.5) / (1u64 << 52) as f64
+    }
+}
+
+/// Uses `rand`'s thread-local generator.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThreadEntropy;
+
+impl Entropy for ThreadEntropy {
+    fn next_u64(&mut self) -> u64 {
+        rand::random()
+    }
+}
+
+/// xorshift64* generator, from: Sebastiano Vigna. 2016. "An experimental
+/// exploration of Marsaglia's xorshift generators, scrambled". Given the same
+/// seed, it always produces the same sequence, e.g. to reproduce the towers
+/// of a list, or to shuffle the keys of a benchmark.
+///
+/// Clones carry on with the same sequence as the original, so controllers
+/// cloned from one another build lists with the same towers.
+#[derive(Debug, Clone)]
+pub struct SeededEntropy {
+    state_: u64,
+}
+
+impl SeededEntropy {
+    /// Builds a new `SeededEntropy` that starts from `seed`.
+    pub fn new(seed: u64) -> SeededEntropy {
+        // Xorshift never leaves the all zeroes state.
+        SeededEntropy { state_: seed.max(1) }
+    }
+}
+
+impl Entropy for SeededEntropy {
+    fn next_u64(&mut self) -> u64 {
+        self.state_ ^= self.state_ >> 12;
+        self.state_ ^= self.state_ << 25;
+        self.state_ ^= self.state_ >> 27;
+        self.state_.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+}
+
+/// `SeededEntropy` seeded through `getrandom`, which supports browsers
+/// (through the `js` feature) and WASI runtimes. The operating system is only
+/// queried when building or cloning the source.
+#[cfg(feature = "getrandom")]
+#[derive(Debug)]
+pub struct GetrandomEntropy {
+    inner_: SeededEntropy,
+}
+
+#[cfg(feature = "getrandom")]
+impl GetrandomEntropy {
+    /// Builds a new `GetrandomEntropy`.
+    ///
+    /// # Remarks
+    ///
+    /// Panics if `getrandom` has no backend for the current target.
+    pub fn new() -> GetrandomEntropy {
+        let mut seed = [0u8; 8];
+        getrandom::getrandom(&mut seed).expect("getrandom failed to seed the height generator");
+        GetrandomEntropy { inner_: SeededEntropy::new(u64::from_le_bytes(seed)) }
+    }
+}
+
+#[cfg(feature = "getrandom")]
+impl Default for GetrandomEntropy {
+    fn default() -> GetrandomEntropy {
+        GetrandomEntropy::new()
+    }
+}
+
+#[cfg(feature = "getrandom")]
+impl Clone for GetrandomEntropy {
+    fn clone(&self) -> GetrandomEntropy {
+        GetrandomEntropy::new()
+    }
+}
+
+#[cfg(feature = "getrandom")]
+impl Entropy for GetrandomEntropy {
+    fn next_u64(&mut self) -> u64 {
+        self.inner_.next_u64()
+    }
+}
+
+/// Source used by the randomized controllers unless told otherwise. It is
+/// `GetrandomEntropy` when the `getrandom` feature is enabled, and
+/// `ThreadEntropy` otherwise.
+#[cfg(feature = "getrandom")]
+pub type DefaultEntropy = GetrandomEntropy;
+
+/// Source used by the randomized controllers unless told otherwise. It is
+/// `GetrandomEntropy` when the `getrandom` feature is enabled, and
+/// `ThreadEntropy` otherwise.
+#[cfg(not(feature = "getrandom"))]
+pub type DefaultEntropy = ThreadEntropy;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Always returns the same bits.
+    #[derive(Clone)]
+    struct Constant(u64);
+
+    impl Entropy for Constant {
+        fn next_u64(&mut self) -> u64 {
+            self.0
+        }
+    }
+
+    #[test]
+    fn next_f64_is_open() {
+        assert!(Constant(0).next_f64() > 0.0);
+        assert!(Constant(u64::MAX).next_f64() < 1.0);
+    }
+
+    #[test]
+    fn seeded_entropy_is_reproducible() {
+        let mut first = SeededEntropy::new(7);
+        let mut second = first.clone();
+        for _ in 0..10 {
+            assert_eq!(first.next_u64(), second.next_u64());
+        }
+
+        // Zero is not a valid state, but is a valid seed.
+        assert_ne!(SeededEntropy::new(0).next_u64(), 0);
+    }
+}
@@ -0,0 +1,221 @@
+use map::SkipListMap;
+use node::Node;
+
+use std;
+use std::borrow::Borrow;
+use std::ptr::NonNull;
+
+/// View into a single entry of a `SkipListMap`, looked up by a borrowed key,
+/// which may be vacant or occupied. Returned by `SkipListMap::entry_ref`.
+pub enum EntryRef<'a, 'b, K: 'a, Q: 'b + ?Sized, V: 'a> {
+    Occupied(OccupiedEntryRef<'a, K, V>),
+    Vacant(VacantEntryRef<'a, 'b, K, Q, V>),
+}
+
+/// Entry of an `EntryRef` whose key is in the map.
+pub struct OccupiedEntryRef<'a, K: 'a, V: 'a> {
+    map_: &'a mut SkipListMap<K, V>,
+    node_: NonNull<Node<K, V>>,
+}
+
+/// Entry of an `EntryRef` whose key is not in the map. It holds on to the
+/// borrowed key, which is only turned into a `K` when inserting.
+pub struct VacantEntryRef<'a, 'b, K: 'a, Q: 'b + ?Sized, V: 'a> {
+    map_: &'a mut SkipListMap<K, V>,
+    key_: &'b Q,
+    // Last node before the key at every level, as found by the lookup.
+    updates_: Vec<NonNull<Node<K, V>>>,
+}
+
+impl<K: Ord, V> SkipListMap<K, V> {
+    /// Looks up the entry for `key`, to be read, updated or inserted in
+    /// place with a single search.
+    ///
+    /// Unlike a lookup with an owned key, `key` is only converted into a `K`
+    /// if a vacant entry is inserted into, so e.g. maps with `String` keys
+    /// only allocate when a key is actually added.
+    pub fn entry_ref<'a, 'b, Q>(&'a mut self, key: &'b Q) -> EntryRef<'a, 'b, K, Q, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (lower_bound, updates) = self.find_lower_bound_with_updates(key);
+        match unsafe { lower_bound.as_ref().link(0) } {
+            Some(node) if unsafe { node.as_ref().key::<Q>() } == key => {
+                EntryRef::Occupied(OccupiedEntryRef {
+                    map_: self,
+                    node_: node,
+                })
+            }
+            _ => EntryRef::Vacant(VacantEntryRef {
+                map_: self,
+                key_: key,
+                updates_: updates,
+            }),
+        }
+    }
+
+    /// Returns the value of `key`, inserting `V::default()` first if `key`
+    /// is not in the map, e.g. to bump counters or push into buckets.
+    pub fn get_or_insert_default(&mut self, key: K) -> &mut V
+    where
+        V: Default,
+    {
+        let (lower_bound, updates) = self.find_lower_bound_with_updates(&key);
+        unsafe {
+            if let Some(node) = lower_bound.as_ref().link(0) {
+                if node.as_ref().key::<K>() == &key {
+                    return Node::key_value_mut_ptr(node).1;
+                }
+            }
+
+            let height = self.generate_height(&key);
+            let node = self.link_new_node(&updates, key, V::default(), height);
+            Node::key_value_mut_ptr(node).1
+        }
+    }
+}
+
+impl<'a, 'b, K, Q, V> EntryRef<'a, 'b, K, Q, V>
+where
+    K: Ord + Borrow<Q> + From<&'b Q>,
+    Q: Ord + ?Sized,
+{
+    /// Returns the key of the entry.
+    pub fn key(&self) -> &Q {
+        match *self {
+            EntryRef::Occupied(ref entry) => entry.key().borrow(),
+            EntryRef::Vacant(ref entry) => entry.key(),
+        }
+    }
+
+    /// Returns the value of the entry, inserting `default` first if it is
+    /// vacant.
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        match self {
+            EntryRef::Occupied(entry) => entry.into_mut(),
+            EntryRef::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    /// Returns the value of the entry, inserting the result of `default`
+    /// first if it is vacant.
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            EntryRef::Occupied(entry) => entry.into_mut(),
+            EntryRef::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// Returns the value of the entry, inserting `V::default()` first if it
+    /// is vacant.
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    /// Calls `f` on the value of the entry, if it is occupied.
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self {
+        if let EntryRef::Occupied(ref mut entry) = self {
+            f(entry.get_mut());
+        }
+
+        self
+    }
+}
+
+impl<'a, K: Ord, V> OccupiedEntryRef<'a, K, V> {
+    /// Returns the key stored in the map.
+    pub fn key(&self) -> &K {
+        unsafe { self.node_.as_ref().key() }
+    }
+
+    /// Returns a const reference to the value.
+    pub fn get(&self) -> &V {
+        unsafe { self.node_.as_ref().value() }
+    }
+
+    /// Returns a mutable reference to the value.
+    pub fn get_mut(&mut self) -> &mut V {
+        unsafe { Node::key_value_mut_ptr(self.node_).1 }
+    }
+
+    /// Turns the entry into a mutable reference to the value, that lives as
+    /// long as the borrow of the map.
+    pub fn into_mut(self) -> &'a mut V {
+        unsafe { Node::key_value_mut_ptr(self.node_).1 }
+    }
+
+    /// Replaces the value, returning the previous one.
+    pub fn insert(&mut self, value: V) -> V {
+        unsafe { (*self.node_.as_ptr()).replace_value(value) }
+    }
+
+    /// Removes the entry from the map, returning its key and value.
+    pub fn remove_entry(self) -> (K, V) {
+        let node = self.node_;
+        let before = {
+            let key = unsafe { node.as_ref().key::<K>() };
+            self.map_.find_updates_by(|other| other < key)
+        };
+
+        // The node is the last one detached at every level it is linked at.
+        let mut last = before.clone();
+        let levels = std::cmp::max(unsafe { node.as_ref().height() }, 1);
+        for update in last.iter_mut().take(levels) {
+            *update = node;
+        }
+
+        unsafe {
+            self.map_.detach_between(&before, &last);
+        }
+
+        self.map_.recycle_node(node)
+    }
+
+    /// Removes the entry from the map, returning its value.
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+}
+
+impl<'a, 'b, K, Q, V> VacantEntryRef<'a, 'b, K, Q, V>
+where
+    K: Ord + Borrow<Q> + From<&'b Q>,
+    Q: Ord + ?Sized,
+{
+    /// Returns the borrowed key the entry was looked up with.
+    pub fn key(&self) -> &'b Q {
+        self.key_
+    }
+
+    /// Converts the key into a `K`, and inserts it along with `value`.
+    /// Returns a mutable reference to the inserted value.
+    pub fn insert(self, value: V) -> &'a mut V {
+        let key = K::from(self.key_);
+        let height = self.map_.generate_height(&key);
+        unsafe {
+            let node = self.map_.link_new_node(&self.updates_, key, value, height);
+            Node::key_value_mut_ptr(node).1
+        }
+    }
+}
+
+impl<'a, 'b, K, Q, V> std::fmt::Debug for EntryRef<'a, 'b, K, Q, V>
+where
+    K: std::fmt::Debug,
+    Q: std::fmt::Debug + ?Sized,
+    V: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            EntryRef::Occupied(ref entry) => {
+                let (key, value) = unsafe { entry.node_.as_ref().key_value::<K, V>() };
+                f.debug_tuple("Occupied").field(key).field(value).finish()
+            }
+            EntryRef::Vacant(ref entry) => f.debug_tuple("Vacant").field(&entry.key_).finish(),
+        }
+    }
+}
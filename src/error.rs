@@ -0,0 +1,161 @@
+use map::SkipListMap;
+use node::Node;
+use iter::{DrainRange, Range};
+use memtable::FrozenError;
+use metrics::Operation;
+
+use std;
+use std::borrow::Borrow;
+use std::collections::TryReserveError;
+use std::ops::{Bound, RangeBounds};
+
+/// Errors returned by the `try_*` variants of the mutating APIs, for callers
+/// that must not panic, nor have a request silently ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Error {
+    /// The key to insert is already in the map.
+    KeyExists,
+    /// The key to update or remove is not in the map.
+    KeyMissing,
+    /// The map is full, and the operation would have to evict an entry.
+    CapacityExceeded,
+    /// Memory for the result could not be allocated.
+    AllocationFailure,
+    /// The map is read-only.
+    Frozen,
+    /// The start of a range is after its end, or both are the same excluded
+    /// key.
+    InvalidRange,
+    /// The height asked for a node is above the maximum height of the list.
+    HeightExceeded { requested: usize, max: usize },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Error::KeyExists => f.write_str("the key is already in the map"),
+            Error::KeyMissing => f.write_str("the key is not in the map"),
+            Error::CapacityExceeded => f.write_str("the map is full"),
+            Error::AllocationFailure => f.write_str("memory allocation failed"),
+            Error::Frozen => f.write_str("the map is frozen"),
+            Error::InvalidRange => f.write_str("the range starts after it ends"),
+            Error::HeightExceeded { requested, max } => {
+                write!(f, "height {} is above the maximum height {}", requested, max)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<FrozenError> for Error {
+    fn from(_: FrozenError) -> Error {
+        Error::Frozen
+    }
+}
+
+impl From<TryReserveError> for Error {
+    fn from(_: TryReserveError) -> Error {
+        Error::AllocationFailure
+    }
+}
+
+/// Fails with `Error::InvalidRange` for the ranges `BTreeMap::range` panics
+/// on: those that start after they end, and those that exclude the same key
+/// at both ends.
+fn check_range<T, R>(range: &R) -> Result<(), Error>
+where
+    R: RangeBounds<T>,
+    T: Ord + ?Sized,
+{
+    match (range.start_bound(), range.end_bound()) {
+        (Bound::Excluded(start), Bound::Excluded(end)) if start == end => Err(Error::InvalidRange),
+        (
+            Bound::Included(start) | Bound::Excluded(start),
+            Bound::Included(end) | Bound::Excluded(end),
+        ) if start > end => Err(Error::InvalidRange),
+        _ => Ok(()),
+    }
+}
+
+impl<K: Ord, V> SkipListMap<K, V> {
+    /// Inserts `value` under `key`, and returns a mutable reference to it.
+    /// Fails with `Error::KeyExists`, leaving the map untouched, if `key` is
+    /// already in the map.
+    pub fn try_insert(&mut self, key: K, value: V) -> Result<&mut V, Error> {
+        self.report(|metrics| metrics.operation(Operation::Insert));
+        let (lower_bound, updates) = self.find_lower_bound_with_updates(&key);
+        unsafe {
+            if let Some(node) = lower_bound.as_ref().link(0) {
+                if node.as_ref().key::<K>() == &key {
+                    return Err(Error::KeyExists);
+                }
+            }
+
+            let height = self.generate_height(&key);
+            let node = self.link_new_node(&updates, key, value, height);
+            Ok(Node::key_value_mut_ptr(node).1)
+        }
+    }
+
+    /// Removes the element with key `key`, and returns its value. Fails with
+    /// `Error::KeyMissing` if `key` is not in the map.
+    pub fn try_remove<Q>(&mut self, key: &Q) -> Result<V, Error>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.remove(key).ok_or(Error::KeyMissing)
+    }
+
+    /// Same as `set_height`, but fails with `Error::KeyMissing` if `key` is
+    /// not in the map, and with `Error::HeightExceeded` instead of panicking
+    /// if `height` is above the maximum height of the list.
+    pub fn try_set_height<Q>(&mut self, key: &Q, height: usize) -> Result<usize, Error>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        if height > self.max_height() {
+            return Err(Error::HeightExceeded {
+                requested: height,
+                max: self.max_height(),
+            });
+        }
+
+        self.set_height(key, height).ok_or(Error::KeyMissing)
+    }
+
+    /// Same as `pop_first_n`, but fails with `Error::AllocationFailure`,
+    /// before removing anything, if the returned vector can't be allocated.
+    pub fn try_pop_first_n(&mut self, n: usize) -> Result<Vec<(K, V)>, Error> {
+        let mut entries = Vec::new();
+        entries.try_reserve_exact(std::cmp::min(n, self.len()))?;
+        entries.extend(self.drain_first(n));
+        Ok(entries)
+    }
+
+    /// Same as `range`, but fails with `Error::InvalidRange` if `range`
+    /// starts after it ends, instead of iterating over nothing.
+    pub fn try_range<T, R>(&self, range: R) -> Result<Range<'_, K, V>, Error>
+    where
+        K: Borrow<T>,
+        R: RangeBounds<T>,
+        T: Ord + ?Sized,
+    {
+        check_range(&range)?;
+        Ok(self.range(range))
+    }
+
+    /// Same as `drain_range`, but fails with `Error::InvalidRange`, leaving
+    /// the map untouched, if `range` starts after it ends.
+    pub fn try_drain_range<T, R>(&mut self, range: R) -> Result<DrainRange<'_, K, V>, Error>
+    where
+        K: Borrow<T>,
+        R: RangeBounds<T>,
+        T: Ord + ?Sized,
+    {
+        check_range(&range)?;
+        Ok(self.drain_range(range))
+    }
+}
@@ -0,0 +1,365 @@
+//! Skip List of fixed capacity, which never allocates.
+//!
+//! `FixedSkipList` stores up to `N` entries in arrays that are part of the
+//! structure itself, so it can live on the stack or in a `static`, and every
+//! operation takes bounded time with no call to the allocator. Towers are at
+//! most `H` levels tall, and the controller is held by value rather than
+//! boxed.
+//!
+//! Nodes refer to each other by index. Removed slots are kept in a free list,
+//! and reused by later insertions; once every slot is taken, inserting a new
+//! key fails with `Error::CapacityExceeded`.
+use height_control::{HeightContext, HeightControl};
+use error::Error;
+
+use std;
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::mem::MaybeUninit;
+
+/// Slot used as the null link.
+const NIL: u32 = u32::MAX;
+
+/// Slot standing for the head, whose links live in `head_`.
+const HEAD: u32 = u32::MAX - 1;
+
+/// Ordered map of at most `N` entries, with towers of at most `H` levels. See
+/// the `fixed` module.
+pub struct FixedSkipList<K, V, C, const N: usize, const H: usize = 16> {
+    entries_: [MaybeUninit<(K, V)>; N],
+    // `links_[slot][level]` is only meaningful up to the height of the slot.
+    // Free slots are chained through their level 0 link.
+    links_: [[u32; H]; N],
+    heights_: [u8; N],
+    head_: [u32; H],
+    free_: u32,
+    // Slots from this one on have never been used.
+    unused_: u32,
+    length_: usize,
+    // Highest level reached by any node since the last `clear`.
+    height_: usize,
+    controller_: C,
+}
+
+impl<K, V, C, const N: usize, const H: usize> FixedSkipList<K, V, C, N, H> {
+    /// Builds an empty list.
+    ///
+    /// # Arguments
+    ///
+    ///  * `controller`: generates heights for inserted nodes. A node of height
+    ///    `h` is linked at levels `0..=h`, and heights are capped at `H - 1`.
+    ///
+    /// # Panics
+    ///
+    /// If `H` is 0 or above 256, or `N` does not fit in a `u32`.
+    pub fn new(controller: C) -> FixedSkipList<K, V, C, N, H> {
+        assert!(H > 0 && H <= 256, "towers must have between 1 and 256 levels");
+        assert!(N < HEAD as usize, "capacity does not fit in a u32");
+
+        FixedSkipList {
+            entries_: [const { MaybeUninit::uninit() }; N],
+            links_: [[NIL; H]; N],
+            heights_: [0; N],
+            head_: [NIL; H],
+            free_: NIL,
+            unused_: 0,
+            length_: 0,
+            height_: 0,
+            controller_: controller,
+        }
+    }
+
+    /// Returns the number of elements stored in the structure.
+    pub fn len(&self) -> usize {
+        self.length_
+    }
+
+    /// Returns `true` if there are no elements stored within the structure.
+    pub fn is_empty(&self) -> bool {
+        self.length_ == 0
+    }
+
+    /// Returns the maximum number of elements, `N`.
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns `true` if inserting a new key would fail.
+    pub fn is_full(&self) -> bool {
+        self.length_ == N
+    }
+
+    /// Removes all elements.
+    ///
+    /// # Remarks
+    ///
+    /// The list is emptied before any element is dropped, so if a destructor
+    /// panics, the elements after it are leaked, but the list stays usable.
+    pub fn clear(&mut self) {
+        let mut current = self.head_[0];
+        self.head_ = [NIL; H];
+        self.free_ = NIL;
+        self.unused_ = 0;
+        self.length_ = 0;
+        self.height_ = 0;
+
+        while current != NIL {
+            let slot = current as usize;
+            current = self.links_[slot][0];
+            unsafe {
+                self.entries_[slot].assume_init_drop();
+            }
+        }
+    }
+
+    /// Iterates over the entries, in key order.
+    pub fn iter(&self) -> FixedIter<'_, K, V, C, N, H> {
+        FixedIter {
+            list_: self,
+            current_: self.head_[0],
+        }
+    }
+
+    /// Returns the entry with the smallest key, if any.
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.iter().next()
+    }
+
+    /// Removes the entry with the smallest key, and returns it.
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        let first = self.head_[0];
+        if first == NIL {
+            return None;
+        }
+
+        // The first node is the first one on every level it is linked at.
+        let height = self.heights_[first as usize] as usize;
+        self.head_[..=height].copy_from_slice(&self.links_[first as usize][..=height]);
+        Some(self.release(first))
+    }
+
+    fn link(&self, from: u32, level: usize) -> u32 {
+        if from == HEAD {
+            self.head_[level]
+        } else {
+            self.links_[from as usize][level]
+        }
+    }
+
+    fn link_mut(&mut self, from: u32, level: usize) -> &mut u32 {
+        if from == HEAD {
+            &mut self.head_[level]
+        } else {
+            &mut self.links_[from as usize][level]
+        }
+    }
+
+    fn entry(&self, slot: u32) -> &(K, V) {
+        unsafe { self.entries_[slot as usize].assume_init_ref() }
+    }
+
+    fn key(&self, slot: u32) -> &K {
+        &self.entry(slot).0
+    }
+
+    /// Takes a slot off the free list, or a never used one. The list must not
+    /// be full.
+    fn allocate(&mut self) -> u32 {
+        debug_assert!(!self.is_full());
+        if self.free_ != NIL {
+            let slot = self.free_;
+            self.free_ = self.links_[slot as usize][0];
+            return slot;
+        }
+
+        self.unused_ += 1;
+        self.unused_ - 1
+    }
+
+    /// Moves the entry out of an unlinked slot, and puts the slot back on the
+    /// free list.
+    fn release(&mut self, slot: u32) -> (K, V) {
+        let entry = unsafe { self.entries_[slot as usize].assume_init_read() };
+        self.links_[slot as usize][0] = self.free_;
+        self.free_ = slot;
+        self.length_ -= 1;
+        entry
+    }
+}
+
+impl<K: Ord, V, C, const N: usize, const H: usize> FixedSkipList<K, V, C, N, H> {
+    /// Returns the shape of the list, as seen by its height controller.
+    fn context(&self) -> HeightContext {
+        HeightContext {
+            len: self.length_,
+            height: self.height_,
+        }
+    }
+
+    /// Finds, for every level, the last slot with a key less than `key`.
+    fn find_updates<Q>(&self, key: &Q) -> [u32; H]
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut updates = [HEAD; H];
+        let mut current = HEAD;
+        for level in (0..=self.height_).rev() {
+            loop {
+                let next = self.link(current, level);
+                if next == NIL || self.key(next).borrow() >= key {
+                    break;
+                }
+                current = next;
+            }
+
+            updates[level] = current;
+        }
+
+        updates
+    }
+
+    /// Returns the slot holding `key`, if it exists.
+    fn find<Q>(&self, key: &Q) -> Option<u32>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut current = HEAD;
+        for level in (0..=self.height_).rev() {
+            loop {
+                let next = self.link(current, level);
+                if next == NIL {
+                    break;
+                }
+
+                match self.key(next).borrow().cmp(key) {
+                    Ordering::Less => current = next,
+                    Ordering::Equal => return Some(next),
+                    Ordering::Greater => break,
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Returns a const reference to the element with key `key`, if it exists.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).map(|slot| &self.entry(slot).1)
+    }
+
+    /// Returns a mutable reference to the element with key `key`, if it
+    /// exists.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let slot = self.find(key)?;
+        Some(unsafe { &mut self.entries_[slot as usize].assume_init_mut().1 })
+    }
+
+    /// Returns true if `key` is in the list.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).is_some()
+    }
+
+    /// Removes the element with key `key`, returning its value if it existed.
+    /// Its slot is reused by a later insertion.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let updates = self.find_updates(key);
+        let target = self.link(updates[0], 0);
+        if target == NIL || self.key(target).borrow() != key {
+            return None;
+        }
+
+        for (level, &update) in updates.iter().enumerate().take(self.heights_[target as usize] as usize + 1) {
+            *self.link_mut(update, level) = self.links_[target as usize][level];
+        }
+
+        let (_, value) = self.release(target);
+        Some(value)
+    }
+}
+
+impl<K: Ord, V, C: HeightControl<K>, const N: usize, const H: usize> FixedSkipList<K, V, C, N, H> {
+    /// Inserts `value` under `key`, and returns the value it replaced, if
+    /// any. Fails with `Error::CapacityExceeded`, dropping `key` and `value`,
+    /// if `key` is new and the list is full.
+    pub fn insert(&mut self, key: K, value: V) -> Result<Option<V>, Error> {
+        let updates = self.find_updates(&key);
+        let candidate = self.link(updates[0], 0);
+        if candidate != NIL && *self.key(candidate) == key {
+            let entry = unsafe { self.entries_[candidate as usize].assume_init_mut() };
+            return Ok(Some(std::mem::replace(&mut entry.1, value)));
+        }
+
+        if self.is_full() {
+            return Err(Error::CapacityExceeded);
+        }
+
+        let height = std::cmp::min(self.controller_.get_height_in(&key, &self.context()), H - 1);
+        let slot = self.allocate();
+        self.entries_[slot as usize] = MaybeUninit::new((key, value));
+        self.heights_[slot as usize] = height as u8;
+        for (level, &update) in updates.iter().enumerate().take(height + 1) {
+            self.links_[slot as usize][level] = self.link(update, level);
+            *self.link_mut(update, level) = slot;
+        }
+
+        self.height_ = std::cmp::max(self.height_, height);
+        self.length_ += 1;
+        Ok(None)
+    }
+}
+
+impl<K, V, C, const N: usize, const H: usize> Drop for FixedSkipList<K, V, C, N, H> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<K, V, C, const N: usize, const H: usize> std::fmt::Debug for FixedSkipList<K, V, C, N, H>
+where
+    K: std::fmt::Debug,
+    V: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Iterator over the entries of a `FixedSkipList`, in key order.
+pub struct FixedIter<'a, K: 'a, V: 'a, C: 'a, const N: usize, const H: usize> {
+    list_: &'a FixedSkipList<K, V, C, N, H>,
+    current_: u32,
+}
+
+impl<'a, K, V, C, const N: usize, const H: usize> Iterator for FixedIter<'a, K, V, C, N, H> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.current_ == NIL {
+            return None;
+        }
+
+        let list = self.list_;
+        let slot = self.current_;
+        self.current_ = list.links_[slot as usize][0];
+        let (ref key, ref value) = *list.entry(slot);
+        Some((key, value))
+    }
+}
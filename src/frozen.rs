@@ -0,0 +1,183 @@
+use map::SkipListMap;
+use height_control::HeightControl;
+
+use std;
+use std::borrow::Borrow;
+
+/// Read-only form of a `SkipListMap`, for workloads that build a map once and
+/// then only query it.
+///
+/// Keys and values are kept in two contiguous arrays sorted by key, and
+/// searches are binary searches over the keys. Compared to following the
+/// towers of a list, this touches far fewer cache lines, and the arrays take
+/// no space besides the entries themselves.
+pub struct FrozenSkipListMap<K, V> {
+    keys_: Vec<K>,
+    values_: Vec<V>,
+}
+
+impl<K, V> SkipListMap<K, V> {
+    /// Consumes the list, turning it into a `FrozenSkipListMap` with the same
+    /// entries.
+    pub fn freeze(self) -> FrozenSkipListMap<K, V> {
+        let (keys, values) = self.into_sorted_vec().into_iter().unzip();
+        FrozenSkipListMap {
+            keys_: keys,
+            values_: values,
+        }
+    }
+}
+
+impl<K, V> FrozenSkipListMap<K, V> {
+    /// Turns the map back into a mutable `SkipListMap`, in O(n).
+    ///
+    /// # Arguments
+    ///
+    ///  * `controller`: generates heights for the nodes, both those rebuilt
+    ///    from the frozen entries and those inserted afterwards.
+    pub fn thaw(self, controller: Box<HeightControl<K>>) -> SkipListMap<K, V> {
+        let mut list = SkipListMap::new(controller);
+        let mut fingers = list.empty_fingers();
+
+        // Keys are already sorted and unique, so they can go straight to the
+        // tail.
+        for (key, value) in self.keys_.into_iter().zip(self.values_) {
+            let height = list.generate_height(&key);
+            list.push_back_unchecked(&mut fingers, key, value, height);
+        }
+
+        list
+    }
+
+    /// Returns the number of elements stored in the map.
+    pub fn len(&self) -> usize {
+        self.keys_.len()
+    }
+
+    /// Returns `true` if there are no elements stored in the map.
+    pub fn is_empty(&self) -> bool {
+        self.keys_.is_empty()
+    }
+
+    /// Returns the keys, in order.
+    pub fn keys(&self) -> &[K] {
+        &self.keys_
+    }
+
+    /// Returns the values, in the order of their keys.
+    pub fn values(&self) -> &[V] {
+        &self.values_
+    }
+
+    /// Returns the entry with the smallest key, if any.
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.entry(0)
+    }
+
+    /// Returns the entry with the greatest key, if any.
+    pub fn last(&self) -> Option<(&K, &V)> {
+        self.len().checked_sub(1).and_then(|index| self.entry(index))
+    }
+
+    /// Iterates over the entries, in key order.
+    pub fn iter(&self) -> FrozenIter<'_, K, V> {
+        FrozenIter(self.keys_.iter().zip(self.values_.iter()))
+    }
+
+    fn entry(&self, index: usize) -> Option<(&K, &V)> {
+        Some((self.keys_.get(index)?, &self.values_[index]))
+    }
+}
+
+impl<K: Ord, V> FrozenSkipListMap<K, V> {
+    fn find<Q>(&self, key: &Q) -> Result<usize, usize>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.keys_.binary_search_by(|probe| probe.borrow().cmp(key))
+    }
+
+    /// Returns a const reference to the element with key `key`, if it exists.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).ok().map(|index| &self.values_[index])
+    }
+
+    /// Returns true if `key` is in the map.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).is_ok()
+    }
+
+    /// Returns the first entry with a key greater or equal than `key`, if any.
+    pub fn lower_bound<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let index = match self.find(key) {
+            Ok(index) | Err(index) => index,
+        };
+
+        self.entry(index)
+    }
+}
+
+impl<K, Q, V> std::ops::Index<&Q> for FrozenSkipListMap<K, V>
+where
+    K: Ord + Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    type Output = V;
+
+    fn index(&self, index: &Q) -> &Self::Output {
+        self.get(index).unwrap()
+    }
+}
+
+impl<K: Clone, V: Clone> Clone for FrozenSkipListMap<K, V> {
+    fn clone(&self) -> FrozenSkipListMap<K, V> {
+        FrozenSkipListMap {
+            keys_: self.keys_.clone(),
+            values_: self.values_.clone(),
+        }
+    }
+}
+
+impl<K: std::fmt::Debug, V: std::fmt::Debug> std::fmt::Debug for FrozenSkipListMap<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Iterator over the entries of a `FrozenSkipListMap`, in key order.
+pub struct FrozenIter<'a, K: 'a, V: 'a>(
+    std::iter::Zip<std::slice::Iter<'a, K>, std::slice::Iter<'a, V>>,
+);
+
+impl<'a, K: 'a, V: 'a> Iterator for FrozenIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'a, K: 'a, V: 'a> DoubleEndedIterator for FrozenIter<'a, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back()
+    }
+}
+
+impl<'a, K: 'a, V: 'a> ExactSizeIterator for FrozenIter<'a, K, V> {}
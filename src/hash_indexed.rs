@@ -0,0 +1,168 @@
+use map::SkipListMap;
+use node::Node;
+use height_control::HeightControl;
+use iter::{Iter, Range};
+
+use std;
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::RangeBounds;
+use std::ptr::NonNull;
+
+/// `SkipListMap` with a hash index from every key to its node, so that exact
+/// lookups take O(1) on average, while iteration and range scans still walk
+/// the list in key order.
+///
+/// Keys are stored twice, once in the list and once in the index, so this is
+/// best suited for small or cheaply cloned keys. Inserts and removals pay for
+/// both structures.
+pub struct HashIndexedSkipListMap<K, V> {
+    map_: SkipListMap<K, V>,
+    index_: HashMap<K, NonNull<Node<K, V>>>,
+}
+
+// The index only points to nodes owned by the list.
+unsafe impl<K: Send, V: Send> Send for HashIndexedSkipListMap<K, V> {}
+
+impl<K, V> HashIndexedSkipListMap<K, V> {
+    pub fn new(controller: Box<HeightControl<K>>) -> HashIndexedSkipListMap<K, V> {
+        HashIndexedSkipListMap {
+            map_: SkipListMap::new(controller),
+            index_: HashMap::new(),
+        }
+    }
+
+    /// Removes all elements.
+    pub fn clear(&mut self) {
+        self.index_.clear();
+        self.map_.clear();
+    }
+
+    /// Returns the number of elements stored in the structure.
+    pub fn len(&self) -> usize {
+        self.map_.len()
+    }
+
+    /// Returns `true` if there are no elements stored within the structure.
+    pub fn is_empty(&self) -> bool {
+        self.map_.is_empty()
+    }
+
+    /// Iterates over the entries, in key order.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        self.map_.iter()
+    }
+
+    /// Returns the underlying map.
+    pub fn as_map(&self) -> &SkipListMap<K, V> {
+        &self.map_
+    }
+
+    /// Consumes the map, returning the underlying one.
+    pub fn into_map(self) -> SkipListMap<K, V> {
+        self.map_
+    }
+}
+
+impl<K: Ord + Hash + Clone, V> HashIndexedSkipListMap<K, V> {
+    /// Inserts `value` under `key`, returning the value it replaced, if any.
+    /// Replacing a value only takes a hash lookup.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(&node) = self.index_.get(&key) {
+            return Some(unsafe { (*node.as_ptr()).replace_value(value) });
+        }
+
+        let (_, updates) = self.map_.find_lower_bound_with_updates(&key);
+        let height = self.map_.generate_height(&key);
+        let node = unsafe { self.map_.link_new_node(&updates, key.clone(), value, height) };
+        self.index_.insert(key, node);
+        None
+    }
+
+    /// Removes the element with key `key`, returning its value if it existed.
+    /// The list is only searched if the key is in the index.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + Hash + Eq + ?Sized,
+    {
+        self.index_.remove(key)?;
+        self.map_.remove(key)
+    }
+
+    /// Removes the entry with the smallest key, and returns it.
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        let (key, value) = self.map_.pop_first()?;
+        self.index_.remove(&key);
+        Some((key, value))
+    }
+
+    /// Removes the entry with the largest key, and returns it.
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        let (key, value) = self.map_.pop_last()?;
+        self.index_.remove(&key);
+        Some((key, value))
+    }
+
+    /// Returns a const reference to the element with key `key`, if it exists.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let node = self.index_.get(key)?;
+        Some(unsafe { node.as_ref().value() })
+    }
+
+    /// Returns the stored key and its value, if `key` exists.
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let node = self.index_.get(key)?;
+        Some(unsafe { node.as_ref().key_value::<K, V>() })
+    }
+
+    /// Returns a mutable reference to the element with key `key`, if it
+    /// exists.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let node = *self.index_.get(key)?;
+        Some(unsafe { Node::key_value_mut_ptr(node).1 })
+    }
+
+    /// Returns true if `key` is in the map.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.index_.contains_key(key)
+    }
+
+    /// Returns the entry with the smallest key, if any.
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.map_.first()
+    }
+
+    /// Iterates over the entries within `range`, in key order.
+    pub fn range<T, R>(&self, range: R) -> Range<'_, K, V>
+    where
+        K: Borrow<T>,
+        R: RangeBounds<T>,
+        T: Ord + ?Sized,
+    {
+        self.map_.range(range)
+    }
+}
+
+impl<K: std::fmt::Debug, V: std::fmt::Debug> std::fmt::Debug for HashIndexedSkipListMap<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
@@ -1,10 +1,10 @@
 use map::SkipListMap;
+use entropy::{DefaultEntropy, Entropy};
+use stats::Stats;
 
 use std;
 use std::default::Default;
 
-extern crate rand;
-
 /// This comes from the slightly delicate usage that we have for
 /// `HeightControl<K>`: `SkipList<K>` needs to hold a trait object that
 /// satisfies `HeightControl<K>`; however, there is no way to impose a
@@ -43,7 +43,10 @@ impl<K> Clone for Box<HeightControl<K>> {
 /// Users should avoid implementing this trait unless there are effectively
 /// space or speed concerns and they are certain that a change in the strategy
 /// will fix their problem.
-pub trait HeightControl<K>: HeightControlClone<K> {
+///
+/// Controllers must be `Send`, since they are moved along with the Skip List
+/// that owns them.
+pub trait HeightControl<K>: HeightControlClone<K> + Send {
     /// Returns the maximum height that this controller can generate.
     ///
     /// # Remarks
@@ -85,6 +88,38 @@ pub trait HeightControl<K>: HeightControlClone<K> {
     ///     have to update the internal state whenever doing an insertion; try
     ///     to keep these updates within control.
     fn get_height(&mut self, key: &K) -> usize;
+
+    /// Generates a height for the `key`, given the shape of the list it is
+    /// about to be inserted in, e.g. to cap heights at `log2(len) + 2`. Calls
+    /// `get_height` by default.
+    ///
+    /// # Remarks
+    ///
+    /// Lists that track their length and height call this instead of
+    /// `get_height`. The others, and callers that only have a controller, call
+    /// `get_height`, so controllers overriding this should still give
+    /// sensible heights from it.
+    #[allow(unused_variables)]
+    fn get_height_in(&mut self, key: &K, context: &HeightContext) -> usize {
+        self.get_height(key)
+    }
+
+    /// Receives the current shape of the Skip List, as passed by
+    /// `SkipListMap::tune`, so that the controller can correct the heights it
+    /// generates from then on. Does nothing by default.
+    #[allow(unused_variables)]
+    fn observe(&mut self, stats: &Stats) {}
+}
+
+/// Shape of a list that a node is about to be inserted in, as passed to
+/// `HeightControl::get_height_in`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeightContext {
+    /// Number of elements in the list, not counting the one being inserted.
+    pub len: usize,
+    /// Tallest height given to any node in the list since it was last
+    /// cleared.
+    pub height: usize,
 }
 
 /// Implements height generation through simulation of a capped geometrical
@@ -95,9 +130,12 @@ pub trait HeightControl<K>: HeightControlClone<K> {
 /// * William Pugh. 1990. "Skip lists: a probabilistic alternative to balanced
 ///   trees". Commun. ACM 33, 6 (June 1990), 668-676.
 ///   DOI=http://dx.doi.org/10.1145/78973.78977
-pub struct GeometricalGenerator {
+pub struct GeometricalGenerator<E = DefaultEntropy> {
     upgrade_probability_: f64,
+    // Natural logarithm of `upgrade_probability_`, cached for `get_height`.
+    log_probability_: f64,
     max_height_: usize,
+    entropy_: E,
 }
 
 impl GeometricalGenerator {
@@ -112,43 +150,195 @@ impl GeometricalGenerator {
     ///
     /// # Remarks
     ///
-    /// This generator uses an RNG to simulate up to `max_heights` coin throws
-    /// in every `get_height` call. This is slow, so it should be avoided.
+    /// Every `get_height` call takes a single draw from the RNG, whatever the
+    /// probability, and maps it through the inverse of the distribution. That
+    /// takes a logarithm, so `TwoPowGenerator` is still faster when its
+    /// probability of 1/2 is good enough.
     pub fn new(max_height: usize, upgrade_probability: f64) -> GeometricalGenerator {
+        GeometricalGenerator::with_entropy(max_height, upgrade_probability, Default::default())
+    }
+}
+
+impl<E: Entropy> GeometricalGenerator<E> {
+    /// Builds a new `GeometricalGenerator` that draws its coin throws from
+    /// `entropy`. See `new` for the other arguments.
+    pub fn with_entropy(
+        max_height: usize,
+        upgrade_probability: f64,
+        entropy: E,
+    ) -> GeometricalGenerator<E> {
         GeometricalGenerator {
             upgrade_probability_: upgrade_probability,
+            log_probability_: upgrade_probability.ln(),
             max_height_: max_height,
+            entropy_: entropy,
         }
     }
 }
 
-impl<K: 'static> HeightControl<K> for GeometricalGenerator {
+impl<K: 'static, E: 'static + Entropy> HeightControl<K> for GeometricalGenerator<E> {
     fn max_height(&self) -> usize {
         self.max_height_
     }
 
     #[allow(unused_variables)]
     fn get_height(&mut self, key: &K) -> usize {
-        // Simulates a random variate with geometric distribution. The idea is
-        // that we are modelling number of successes until the first failure.
-        let mut h = 0;
-
-        while h < self.max_height_ {
-            let rand::Open01(throw) = rand::random::<rand::Open01<f64>>();
-            if throw >= self.upgrade_probability_ {
-                return h;
-            }
+        // Simulates a random variate with geometric distribution: the number
+        // of successes until the first failure, so that P(h >= n) = p^n. For
+        // u uniform in (0, 1), P(u <= p^n) = p^n too, and u <= p^n exactly
+        // when n <= ln(u) / ln(p).
+        if self.upgrade_probability_ >= 1.0 {
+            return self.max_height_;
+        }
 
-            h += 1;
+        if self.upgrade_probability_ <= 0.0 {
+            return 0;
         }
 
-        h
+        // The draw is never 0, so the quotient is finite. The cast saturates
+        // anyway.
+        let h = (self.entropy_.next_f64().ln() / self.log_probability_) as usize;
+        std::cmp::min(h, self.max_height_)
     }
 }
 
-impl Clone for GeometricalGenerator {
-    fn clone(&self) -> GeometricalGenerator {
-        GeometricalGenerator::new(self.max_height_, self.upgrade_probability_)
+impl<E: Entropy> Clone for GeometricalGenerator<E> {
+    fn clone(&self) -> GeometricalGenerator<E> {
+        GeometricalGenerator::with_entropy(
+            self.max_height_,
+            self.upgrade_probability_,
+            self.entropy_.clone(),
+        )
+    }
+}
+
+/// `AdaptiveGenerator` simulates a capped geometrical random variable, like
+/// `GeometricalGenerator`, but corrects its promotion probability from the
+/// shape of the list it is given through `SkipListMap::tune`.
+///
+/// Removals never go through the controller, so e.g. a batch of deletes that
+/// happens to hit mostly tall nodes leaves the list flatter than the target
+/// distribution. Once told so, the generator promotes new nodes with a higher
+/// probability until the observed shape is back on target, and with a lower
+/// one when the list has become too tall.
+pub struct AdaptiveGenerator<E = DefaultEntropy> {
+    target_probability_: f64,
+    effective_probability_: f64,
+    // Natural logarithm of `effective_probability_`, cached for `get_height`.
+    log_probability_: f64,
+    max_height_: usize,
+    entropy_: E,
+}
+
+impl AdaptiveGenerator {
+    /// Builds a new `AdaptiveGenerator`
+    ///
+    /// # Arguments
+    ///
+    ///  * `max_height`: maximum height that the generator may give out to any
+    ///    node.
+    ///  * `target_probability`: the promotion probability the shape of the
+    ///    list should follow. Until the first `observe`, it is also the one
+    ///    used to generate heights.
+    pub fn new(max_height: usize, target_probability: f64) -> AdaptiveGenerator {
+        AdaptiveGenerator::with_entropy(max_height, target_probability, Default::default())
+    }
+}
+
+impl<E: Entropy> AdaptiveGenerator<E> {
+    /// Nodes that must be linked above level 0 before the observed shape is
+    /// trusted. Below that, the generator goes back to the target probability.
+    const MIN_SAMPLE: usize = 64;
+
+    /// Builds a new `AdaptiveGenerator` that draws its coin throws from
+    /// `entropy`. See `new` for the other arguments.
+    pub fn with_entropy(
+        max_height: usize,
+        target_probability: f64,
+        entropy: E,
+    ) -> AdaptiveGenerator<E> {
+        AdaptiveGenerator {
+            target_probability_: target_probability,
+            effective_probability_: target_probability,
+            log_probability_: target_probability.ln(),
+            max_height_: max_height,
+            entropy_: entropy,
+        }
+    }
+
+    /// Returns the promotion probability the shape of the list should follow.
+    pub fn target_probability(&self) -> f64 {
+        self.target_probability_
+    }
+
+    /// Returns the promotion probability currently used to generate heights.
+    pub fn effective_probability(&self) -> f64 {
+        self.effective_probability_
+    }
+
+    /// Estimates the promotion probability that produced `stats`, or returns
+    /// `None` if there are too few tall nodes to tell.
+    ///
+    /// Nodes of height 0 and 1 are both linked at level 0 only, so the ratio
+    /// between the first two levels is skewed; only the levels above are used.
+    fn observed_probability(stats: &Stats) -> Option<f64> {
+        let promoted: usize = stats.level_counts.iter().skip(1).sum();
+        if promoted < Self::MIN_SAMPLE {
+            return None;
+        }
+
+        let promoted_again: usize = stats.level_counts.iter().skip(2).sum();
+        Some(promoted_again as f64 / promoted as f64)
+    }
+}
+
+impl<K: 'static, E: 'static + Entropy> HeightControl<K> for AdaptiveGenerator<E> {
+    fn max_height(&self) -> usize {
+        self.max_height_
+    }
+
+    #[allow(unused_variables)]
+    fn get_height(&mut self, key: &K) -> usize {
+        // Same single draw as `GeometricalGenerator::get_height`.
+        if self.effective_probability_ >= 1.0 {
+            return self.max_height_;
+        }
+
+        if self.effective_probability_ <= 0.0 {
+            return 0;
+        }
+
+        let h = (self.entropy_.next_f64().ln() / self.log_probability_) as usize;
+        std::cmp::min(h, self.max_height_)
+    }
+
+    fn observe(&mut self, stats: &Stats) {
+        let target = self.target_probability_;
+        self.effective_probability_ = match Self::observed_probability(stats) {
+            // Overshoot the target by as much as the list drifted from it, so
+            // that new nodes compensate for the old ones. The correction is
+            // bounded so that a single skewed sample can't degenerate the list
+            // into a linked list, or into towers that all reach the top.
+            Some(observed) => {
+                let lowest = target / 2.0;
+                let highest = (1.0 + target) / 2.0;
+                (2.0 * target - observed).max(lowest).min(highest)
+            }
+            None => target,
+        };
+        self.log_probability_ = self.effective_probability_.ln();
+    }
+}
+
+impl<E: Entropy> Clone for AdaptiveGenerator<E> {
+    fn clone(&self) -> AdaptiveGenerator<E> {
+        AdaptiveGenerator {
+            target_probability_: self.target_probability_,
+            effective_probability_: self.effective_probability_,
+            log_probability_: self.log_probability_,
+            max_height_: self.max_height_,
+            entropy_: self.entropy_.clone(),
+        }
     }
 }
 
@@ -161,7 +351,8 @@ impl Clone for GeometricalGenerator {
 pub struct HashCoinGenerator<K, H> {
     max_height_: usize,
     hasher_: H,
-    phantom_: std::marker::PhantomData<K>,
+    // Controllers never own keys, so this should not affect auto traits.
+    phantom_: std::marker::PhantomData<fn(&K)>,
 }
 
 impl<K: std::hash::Hash, H: std::hash::Hasher> HashCoinGenerator<K, H> {
@@ -193,7 +384,7 @@ impl<K: std::hash::Hash, H: std::hash::Hasher> HashCoinGenerator<K, H> {
     }
 }
 
-impl<K: 'static + std::hash::Hash, H: 'static + std::hash::Hasher + Clone> HeightControl<K>
+impl<K: 'static + std::hash::Hash, H: 'static + std::hash::Hasher + Clone + Send> HeightControl<K>
     for HashCoinGenerator<K, H> {
     fn max_height(&self) -> usize {
         self.max_height_
@@ -219,48 +410,103 @@ impl<K: std::hash::Hash, H: std::hash::Hasher + Clone> Clone for HashCoinGenerat
 }
 
 /// `TwoPowGenerator` generates heights by simulating a capped geometrical
-/// random variable, similar to `GeometricalGenerator`. This generator is
-/// restricted to maximum heights that are powers of two and upgrades with
-/// probability 1/2.
+/// random variable, similar to `GeometricalGenerator`. This generator
+/// upgrades with probability 1/2.
 ///
 /// It should be preferred to `GeometricalGenerator` because the simulation is
 /// done using only a single random throw.
-pub struct TwoPowGenerator<K> {
-    max_pow_: usize,
-    phantom_: std::marker::PhantomData<K>,
+pub struct TwoPowGenerator<K, E = DefaultEntropy> {
+    max_height_: usize,
+    entropy_: E,
+    // Controllers never own keys, so this should not affect auto traits.
+    phantom_: std::marker::PhantomData<fn(&K)>,
 }
 
 impl<K> TwoPowGenerator<K> {
+    /// Builds a new `TwoPowGenerator`, that gives out heights up to
+    /// `max_height - 1`. `max_height` must be positive.
     pub fn new(max_height: usize) -> TwoPowGenerator<K> {
-        assert!(max_height.is_power_of_two());
+        TwoPowGenerator::with_entropy(max_height, Default::default())
+    }
+}
+
+impl<K, E: Entropy> TwoPowGenerator<K, E> {
+    /// Builds a new `TwoPowGenerator` that draws its random throws from
+    /// `entropy`.
+    pub fn with_entropy(max_height: usize, entropy: E) -> TwoPowGenerator<K, E> {
+        assert!(max_height > 0, "the maximum height must be positive");
 
         TwoPowGenerator {
-            max_pow_: max_height - 1,
+            max_height_: max_height,
+            entropy_: entropy,
             phantom_: std::marker::PhantomData,
         }
     }
 }
 
-impl<K: 'static> HeightControl<K> for TwoPowGenerator<K> {
+impl<K: 'static, E: 'static + Entropy> HeightControl<K> for TwoPowGenerator<K, E> {
     fn max_height(&self) -> usize {
-        self.max_pow_ + 1
+        self.max_height_
     }
 
     #[allow(unused_variables)]
     fn get_height(&mut self, key: &K) -> usize {
-        // TODO: std::intrinsics::ctlz
         // The probability that a random value has a binary representation that
         // ends with 1 0^k is (1/2)^{k+1}.
-        let height = rand::random::<usize>().trailing_zeros() as usize;
-        // Since we are always doing `% 2^k` here, we are using the simple trick
-        // exposed here: https://stackoverflow.com/q/6670715 .
-        height & self.max_pow_
+        let height = self.entropy_.next_u64().trailing_zeros() as usize;
+        // Clamping, rather than wrapping around, leaves the lower heights
+        // alone and gives the whole tail to the top one, as capping the
+        // geometrical variable should.
+        std::cmp::min(height, self.max_height_ - 1)
+    }
+}
+
+impl<K, E: Entropy> Clone for TwoPowGenerator<K, E> {
+    fn clone(&self) -> TwoPowGenerator<K, E> {
+        TwoPowGenerator::with_entropy(self.max_height_, self.entropy_.clone())
     }
 }
 
-impl<K> Clone for TwoPowGenerator<K> {
-    fn clone(&self) -> TwoPowGenerator<K> {
-        TwoPowGenerator::new(self.max_pow_ + 1)
+/// Generates heights by calling a closure on the key, e.g. to script the
+/// shape of a list in tests, or for policies that don't warrant a type of
+/// their own. Heights are capped to `0..max_height`.
+///
+/// The closure is cloned along with the controller, so it must be `Clone`,
+/// as closures are when everything they capture is.
+#[derive(Clone)]
+pub struct FnHeightControl<F> {
+    max_height_: usize,
+    generate_: F,
+}
+
+impl<F> FnHeightControl<F> {
+    /// Builds a new `FnHeightControl`.
+    ///
+    /// # Arguments
+    ///
+    ///  * `max_height`: number of levels of the list, as returned by
+    ///    `max_height`. Must be positive.
+    ///  * `generate`: returns the height for a key. Heights outside of
+    ///    `0..max_height` are capped to `max_height - 1`.
+    pub fn new(max_height: usize, generate: F) -> FnHeightControl<F> {
+        assert!(max_height > 0, "the maximum height must be positive");
+        FnHeightControl {
+            max_height_: max_height,
+            generate_: generate,
+        }
+    }
+}
+
+impl<K, F> HeightControl<K> for FnHeightControl<F>
+where
+    F: 'static + FnMut(&K) -> usize + Clone + Send,
+{
+    fn max_height(&self) -> usize {
+        self.max_height_
+    }
+
+    fn get_height(&mut self, key: &K) -> usize {
+        std::cmp::min((self.generate_)(key), self.max_height_ - 1)
     }
 }
 
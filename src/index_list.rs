@@ -0,0 +1,306 @@
+//! Skip list kept in an arena, shared by `PrefixSkipListMap`,
+//! `IntervalSkipList` and `AugmentedSkipListMap`.
+//!
+//! Nodes live in a `Vec`, and link to each other by index, so the index of a
+//! node stays the same for as long as it is in the list. Freed nodes are kept
+//! in a free list, and reused by later insertions.
+//!
+//! Every link also carries a span: a summary of the entries from its node,
+//! included, up to the next node at its level, excluded. Lists that keep
+//! summaries rebuild the spans along the search path through `update_spans`,
+//! with a `Spans` hook that defines them. The others use `()`, and never
+//! touch them.
+//!
+//! As in `SkipListMap`, a node of height `h` is linked at levels
+//! `0..max(h, 1)`.
+use std;
+
+/// Marks the end of a level. The head is never the next node of anything, so
+/// its index can be used.
+pub(crate) const NIL: usize = 0;
+
+/// Index of the head, which holds no entry.
+pub(crate) const HEAD: usize = 0;
+
+/// Defines the spans of an `IndexList`. Summaries must form a monoid:
+/// `combine` is associative, and the empty span of the list is its identity.
+pub(crate) trait Spans<E> {
+    type Span: Clone;
+
+    /// Returns the span of a single entry.
+    fn single(entry: &E) -> Self::Span;
+
+    /// Returns the span of the entries in `left`, followed by those in
+    /// `right`.
+    fn combine(left: &Self::Span, right: &Self::Span) -> Self::Span;
+}
+
+/// Number of levels a node of height `height` is linked at.
+fn levels_for(height: usize) -> usize {
+    std::cmp::max(height, 1)
+}
+
+pub(crate) struct IndexNode<E, S> {
+    /// `None` for the head and for freed nodes.
+    entry_: Option<E>,
+    forward_: Vec<usize>,
+    spans_: Vec<S>,
+}
+
+impl<E, S> IndexNode<E, S> {
+    /// Returns the entry of the node, or `None` for the head.
+    pub(crate) fn entry(&self) -> Option<&E> {
+        self.entry_.as_ref()
+    }
+
+    /// Returns the number of levels the node is linked at.
+    pub(crate) fn levels(&self) -> usize {
+        self.forward_.len()
+    }
+
+    /// Returns the node that follows this one at `level`.
+    pub(crate) fn next(&self, level: usize) -> usize {
+        self.forward_[level]
+    }
+
+    /// Returns the span of the link at `level`.
+    pub(crate) fn span(&self, level: usize) -> &S {
+        &self.spans_[level]
+    }
+}
+
+pub(crate) struct IndexList<E, S> {
+    // The head is always at `HEAD`.
+    nodes_: Vec<IndexNode<E, S>>,
+    free_: Vec<usize>,
+    length_: usize,
+    // Span of no entries.
+    empty_: fn() -> S,
+}
+
+impl<E, S> IndexList<E, S> {
+    /// Builds an empty list, for nodes up to `max_height` tall.
+    pub(crate) fn new(max_height: usize, empty: fn() -> S) -> IndexList<E, S> {
+        let levels = levels_for(max_height);
+        let head = IndexNode {
+            entry_: None,
+            forward_: vec![NIL; levels],
+            spans_: (0..levels).map(|_| empty()).collect(),
+        };
+
+        IndexList {
+            nodes_: vec![head],
+            free_: Vec::new(),
+            length_: 0,
+            empty_: empty,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.length_
+    }
+
+    /// Returns the number of levels of the head, which every node fits in.
+    pub(crate) fn levels(&self) -> usize {
+        self.nodes_[HEAD].levels()
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.nodes_.truncate(1);
+        let empty = self.empty_;
+        let head = &mut self.nodes_[HEAD];
+        for (link, span) in head.forward_.iter_mut().zip(&mut head.spans_) {
+            *link = NIL;
+            *span = empty();
+        }
+
+        self.free_.clear();
+        self.length_ = 0;
+    }
+
+    pub(crate) fn node(&self, node: usize) -> &IndexNode<E, S> {
+        &self.nodes_[node]
+    }
+
+    /// Returns the entry of `node`, which must not be the head.
+    pub(crate) fn entry(&self, node: usize) -> &E {
+        self.nodes_[node].entry_.as_ref().unwrap()
+    }
+
+    /// Returns the entry of `node`, which must not be the head. Lists that
+    /// keep summaries must rebuild the spans of the node afterwards.
+    pub(crate) fn entry_mut(&mut self, node: usize) -> &mut E {
+        self.nodes_[node].entry_.as_mut().unwrap()
+    }
+
+    /// Returns the node that follows `node` at `level`.
+    pub(crate) fn next(&self, node: usize, level: usize) -> usize {
+        self.nodes_[node].forward_[level]
+    }
+
+    /// Iterates over the entries, in order.
+    pub(crate) fn entries(&self) -> IndexIter<'_, E, S> {
+        IndexIter {
+            list_: self,
+            current_: self.next(HEAD, 0),
+        }
+    }
+
+    /// Finds the last node for which `before` holds at every level, down to
+    /// `bottom`. The levels below it are filled with the node found at
+    /// `bottom`, for the caller to carry on from. `before` must hold for a
+    /// prefix of the entries.
+    fn descend<F>(&self, bottom: usize, mut before: F) -> Vec<usize>
+    where
+        F: FnMut(&E) -> bool,
+    {
+        let mut updates = vec![HEAD; self.levels()];
+        let mut current = HEAD;
+        for level in (bottom..self.levels()).rev() {
+            loop {
+                let next = self.next(current, level);
+                if next == NIL || !before(self.entry(next)) {
+                    break;
+                }
+                current = next;
+            }
+            updates[level] = current;
+        }
+
+        for update in updates.iter_mut().take(bottom) {
+            *update = current;
+        }
+
+        updates
+    }
+
+    /// Finds the last node for which `before` holds at every level.
+    pub(crate) fn find_updates_by<F>(&self, before: F) -> Vec<usize>
+    where
+        F: FnMut(&E) -> bool,
+    {
+        self.descend(0, before)
+    }
+
+    /// Same as `find_updates_by`, but stops above level 0, and leaves the node
+    /// found at level 1 at level 0 too.
+    pub(crate) fn find_upper_updates_by<F>(&self, before: F) -> Vec<usize>
+    where
+        F: FnMut(&E) -> bool,
+    {
+        self.descend(1, before)
+    }
+
+    /// Links a new node holding `entry` after `updates`, and returns it. Its
+    /// spans are left empty.
+    pub(crate) fn link(&mut self, updates: &[usize], entry: E, height: usize) -> usize {
+        let levels = std::cmp::min(levels_for(height), self.levels());
+        let node = IndexNode {
+            entry_: Some(entry),
+            forward_: vec![NIL; levels],
+            spans_: (0..levels).map(|_| (self.empty_)()).collect(),
+        };
+
+        let node = match self.free_.pop() {
+            Some(index) => {
+                self.nodes_[index] = node;
+                index
+            }
+            None => {
+                self.nodes_.push(node);
+                self.nodes_.len() - 1
+            }
+        };
+
+        for (level, &update) in updates.iter().enumerate().take(levels) {
+            self.nodes_[node].forward_[level] = self.nodes_[update].forward_[level];
+            self.nodes_[update].forward_[level] = node;
+        }
+
+        self.length_ += 1;
+        node
+    }
+
+    /// Unlinks `target`, which follows `updates`, frees it and returns its
+    /// entry.
+    pub(crate) fn unlink(&mut self, updates: &[usize], target: usize) -> E {
+        for (level, &update) in updates.iter().enumerate().take(self.nodes_[target].levels()) {
+            self.nodes_[update].forward_[level] = self.nodes_[target].forward_[level];
+        }
+
+        let node = &mut self.nodes_[target];
+        node.forward_ = Vec::new();
+        node.spans_ = Vec::new();
+        let entry = node.entry_.take().unwrap();
+        self.free_.push(target);
+        self.length_ -= 1;
+        entry
+    }
+}
+
+impl<E, S: Clone> IndexList<E, S> {
+    /// Recomputes the span of `node` at `level`, from the spans one level
+    /// below, which must be up to date.
+    fn update_span<H>(&mut self, node: usize, level: usize)
+    where
+        H: Spans<E, Span = S>,
+    {
+        let span = if level == 0 {
+            match self.nodes_[node].entry_ {
+                Some(ref entry) => H::single(entry),
+                None => (self.empty_)(),
+            }
+        } else {
+            // Spans always hold their first node, and the end may be `NIL`,
+            // which is the head too.
+            let end = self.next(node, level);
+            let mut span = self.nodes_[node].spans_[level - 1].clone();
+            let mut current = self.next(node, level - 1);
+            while current != end {
+                span = H::combine(&span, &self.nodes_[current].spans_[level - 1]);
+                current = self.next(current, level - 1);
+            }
+
+            span
+        };
+
+        self.nodes_[node].spans_[level] = span;
+    }
+
+    /// Rebuilds the spans that cover a change after `updates`: those of
+    /// `node`, if it is still in the list, and those of the nodes in
+    /// `updates` that skip over it. Levels are rebuilt from the bottom up,
+    /// since each is computed from the one below it.
+    pub(crate) fn update_spans<H>(&mut self, updates: &[usize], node: Option<usize>)
+    where
+        H: Spans<E, Span = S>,
+    {
+        let levels = node.map_or(0, |node| self.nodes_[node].levels());
+        for (level, &update) in updates.iter().enumerate() {
+            if let Some(node) = node.filter(|_| level < levels) {
+                self.update_span::<H>(node, level);
+            }
+            self.update_span::<H>(update, level);
+        }
+    }
+}
+
+/// Iterator over the entries of an `IndexList`, in order.
+pub(crate) struct IndexIter<'a, E: 'a, S: 'a> {
+    list_: &'a IndexList<E, S>,
+    current_: usize,
+}
+
+impl<'a, E: 'a, S: 'a> Iterator for IndexIter<'a, E, S> {
+    type Item = &'a E;
+
+    fn next(&mut self) -> Option<&'a E> {
+        if self.current_ == NIL {
+            return None;
+        }
+
+        let entry = self.list_.entry(self.current_);
+        self.current_ = self.list_.next(self.current_, 0);
+        Some(entry)
+    }
+}
@@ -0,0 +1,209 @@
+use height_control::HeightControl;
+use index_list::{IndexIter, IndexList, Spans, HEAD, NIL};
+
+use std;
+use std::cmp::Ordering;
+use std::ops::{Bound, Range};
+
+/// Keeps the largest end among the intervals under every link, or `None` if
+/// there are none, which only happens for spans made of the head alone.
+struct MaxEnd;
+
+impl<T: Ord + Clone, V> Spans<(Range<T>, V)> for MaxEnd {
+    type Span = Option<T>;
+
+    fn single(entry: &(Range<T>, V)) -> Option<T> {
+        Some(entry.0.end.clone())
+    }
+
+    fn combine(left: &Option<T>, right: &Option<T>) -> Option<T> {
+        std::cmp::max(left, right).clone()
+    }
+}
+
+fn compare<T: Ord>(left: &Range<T>, right: &Range<T>) -> Ordering {
+    left.start
+        .cmp(&right.start)
+        .then_with(|| left.end.cmp(&right.end))
+}
+
+/// Set of half-open intervals `[start, end)`, each mapped to a value, that
+/// finds all the intervals overlapping a point or another interval.
+///
+/// Intervals are sorted by their start, then by their end. Every link also
+/// records the largest end among the intervals it skips over, so queries can
+/// step past whole spans that end before the point they are looking for.
+/// Finding the `k` overlapping intervals takes O(log n + k) on average.
+pub struct IntervalSkipList<T, V> {
+    list_: IndexList<(Range<T>, V), Option<T>>,
+    controller_: Box<HeightControl<Range<T>>>,
+}
+
+impl<T, V> IntervalSkipList<T, V> {
+    pub fn new(controller: Box<HeightControl<Range<T>>>) -> IntervalSkipList<T, V> {
+        IntervalSkipList {
+            list_: IndexList::new(controller.max_height(), || None),
+            controller_: controller,
+        }
+    }
+
+    /// Returns the number of intervals stored in the structure.
+    pub fn len(&self) -> usize {
+        self.list_.len()
+    }
+
+    /// Returns `true` if there are no intervals stored within the structure.
+    pub fn is_empty(&self) -> bool {
+        self.list_.len() == 0
+    }
+
+    /// Removes all intervals.
+    pub fn clear(&mut self) {
+        self.list_.clear();
+    }
+
+    /// Iterates over the intervals, sorted by their start and then by their
+    /// end.
+    pub fn iter(&self) -> IntervalIter<'_, T, V> {
+        IntervalIter(self.list_.entries())
+    }
+}
+
+impl<T: Ord + Clone, V> IntervalSkipList<T, V> {
+    /// Finds the last node before `interval` at every level.
+    fn search(&self, interval: &Range<T>) -> Vec<usize> {
+        self.list_.find_updates_by(|entry| compare(&entry.0, interval) == Ordering::Less)
+    }
+
+    /// Returns the node holding `interval`, if it exists, along with the last
+    /// node before it at every level.
+    fn find(&self, interval: &Range<T>) -> (Option<usize>, Vec<usize>) {
+        let updates = self.search(interval);
+        let node = match self.list_.next(updates[0], 0) {
+            NIL => None,
+            next if compare(&self.list_.entry(next).0, interval) == Ordering::Equal => Some(next),
+            _ => None,
+        };
+
+        (node, updates)
+    }
+
+    /// Inserts `value` under `interval`, returning the value it replaced, if
+    /// any.
+    pub fn insert(&mut self, interval: Range<T>, value: V) -> Option<V> {
+        let (node, updates) = self.find(&interval);
+        if let Some(node) = node {
+            let entry = self.list_.entry_mut(node);
+            return Some(std::mem::replace(&mut entry.1, value));
+        }
+
+        let height = self.controller_.get_height(&interval);
+        let node = self.list_.link(&updates, (interval, value), height);
+        self.list_.update_spans::<MaxEnd>(&updates, Some(node));
+        None
+    }
+
+    /// Returns a const reference to the value of `interval`, if it exists.
+    pub fn get(&self, interval: &Range<T>) -> Option<&V> {
+        self.find(interval).0.map(|node| &self.list_.entry(node).1)
+    }
+
+    /// Returns true if `interval` is in the list.
+    pub fn contains(&self, interval: &Range<T>) -> bool {
+        self.find(interval).0.is_some()
+    }
+
+    /// Removes `interval`, returning its value if it existed.
+    pub fn remove(&mut self, interval: &Range<T>) -> Option<V> {
+        let (target, updates) = self.find(interval);
+        let (_, value) = self.list_.unlink(&updates, target?);
+        self.list_.update_spans::<MaxEnd>(&updates, None);
+        Some(value)
+    }
+
+    /// Returns every interval that contains `point`, sorted like `iter`.
+    pub fn stabbing(&self, point: &T) -> Vec<(&Range<T>, &V)> {
+        let mut found = Vec::new();
+        let top = self.list_.levels() - 1;
+        self.collect(HEAD, NIL, top, point, Bound::Included(point), &mut found);
+        found
+    }
+
+    /// Returns every interval that shares at least one point with `range`,
+    /// sorted like `iter`.
+    pub fn find_overlapping(&self, range: &Range<T>) -> Vec<(&Range<T>, &V)> {
+        let mut found = Vec::new();
+        if range.start < range.end {
+            let top = self.list_.levels() - 1;
+            self.collect(HEAD, NIL, top, &range.start, Bound::Excluded(&range.end), &mut found);
+        }
+
+        found
+    }
+
+    /// Walks the nodes at `level` from `node` up to `end`, excluded, and
+    /// collects the intervals that end after `after` and start before
+    /// `before`. Spans that end too early are skipped whole. Returns `false`
+    /// once it finds an interval that starts too late, since all the
+    /// following ones do too.
+    fn collect<'a>(
+        &'a self,
+        node: usize,
+        end: usize,
+        level: usize,
+        after: &T,
+        before: Bound<&T>,
+        found: &mut Vec<(&'a Range<T>, &'a V)>,
+    ) -> bool {
+        // Spans always hold their first node, and `end` may be `NIL`, which
+        // is the head too.
+        let mut current = node;
+        loop {
+            let current_node = self.list_.node(current);
+            if let Some((interval, value)) = current_node.entry() {
+                let starts_before = match before {
+                    Bound::Included(point) => interval.start <= *point,
+                    Bound::Excluded(point) => interval.start < *point,
+                    Bound::Unbounded => true,
+                };
+                if !starts_before {
+                    return false;
+                }
+
+                if level == 0 && interval.end > *after && interval.start < interval.end {
+                    found.push((interval, value));
+                }
+            }
+
+            let ends_after = current_node.span(level).as_ref().is_some_and(|max_end| max_end > after);
+            if level > 0 && ends_after {
+                let next = current_node.next(level);
+                if !self.collect(current, next, level - 1, after, before, found) {
+                    return false;
+                }
+            }
+
+            current = current_node.next(level);
+            if current == end {
+                return true;
+            }
+        }
+    }
+}
+
+impl<T: std::fmt::Debug, V: std::fmt::Debug> std::fmt::Debug for IntervalSkipList<T, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Iterator over the intervals of an `IntervalSkipList`, in order.
+pub struct IntervalIter<'a, T: 'a, V: 'a>(IndexIter<'a, (Range<T>, V), Option<T>>);
+
+impl<'a, T: 'a, V: 'a> Iterator for IntervalIter<'a, T, V> {
+    type Item = (&'a Range<T>, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(interval, value)| (interval, value))
+    }
+}
@@ -1,16 +1,58 @@
-use node::Node;
+use node::{Link, Node};
 use map::SkipListMap;
+use pool::NodePool;
 
 use std;
 use std::borrow::Borrow;
-use std::collections::range::RangeArgument;
-use std::collections::Bound;
+use std::ops::{Bound, RangeBounds};
+use std::ptr::NonNull;
+
+/// Snapshot of the generation of a list, taken when an iterator is created.
+/// In debug builds, iterators compare it with the list's current generation on
+/// every step, and panic if the list was structurally modified in between.
+/// Safe code can't do that, but unsafe code holding raw handles can, and the
+/// iterator would otherwise silently read freed nodes.
+pub(crate) struct GenerationCheck {
+    #[cfg(debug_assertions)]
+    generation_: std::ptr::NonNull<usize>,
+    #[cfg(debug_assertions)]
+    expected_: usize,
+}
+
+// The pointer is only ever read, and the list it points into is borrowed by
+// the iterator for as long as it lives.
+unsafe impl Send for GenerationCheck {}
+unsafe impl Sync for GenerationCheck {}
+
+impl GenerationCheck {
+    #[cfg(debug_assertions)]
+    fn new<K, V>(list: &SkipListMap<K, V>) -> GenerationCheck {
+        GenerationCheck {
+            generation_: std::ptr::NonNull::from(&list.generation_),
+            expected_: list.generation_,
+        }
+    }
+
+    #[cfg(not(debug_assertions))]
+    fn new<K, V>(_list: &SkipListMap<K, V>) -> GenerationCheck {
+        GenerationCheck {}
+    }
+
+    fn check(&self) {
+        #[cfg(debug_assertions)]
+        assert_eq!(
+            unsafe { std::ptr::read_volatile(self.generation_.as_ptr()) },
+            self.expected_,
+            "SkipListMap was modified while being iterated"
+        );
+    }
+}
 
-pub struct Iter<'a, K: 'a, V: 'a>(Option<&'a Node<K, V>>);
+pub struct Iter<'a, K: 'a, V: 'a>(Option<&'a Node<K, V>>, GenerationCheck);
 
 impl<'a, K, V> Iter<'a, K, V> {
     pub fn new(list: &'a SkipListMap<K, V>) -> Iter<'a, K, V> {
-        Iter(unsafe { (*list.head_).next(0) })
+        Iter(list.head().next(0), GenerationCheck::new(list))
     }
 }
 
@@ -19,17 +61,33 @@ impl<'a, K: 'a, V: 'a> Iterator for Iter<'a, K, V> {
 
     fn next(&mut self) -> Option<Self::Item> {
         // TODO: prefetch, likely
+        self.1.check();
         let key_value = self.0.map(|node| node.key_value());
         self.0 = self.0.and_then(|node| node.next(0));
         key_value
     }
 }
 
-pub struct IterMut<'a, K: 'a, V: 'a>(Option<&'a mut Node<K, V>>);
+/// Nodes are kept as raw pointers: each call to `next` hands out a mutable
+/// reference into a different node, which must not be reachable through any
+/// reference still held by the iterator.
+pub struct IterMut<'a, K: 'a, V: 'a> {
+    current_: Link<K, V>,
+    generation_: GenerationCheck,
+    phantom_: std::marker::PhantomData<&'a mut Node<K, V>>,
+}
+
+// The iterator stands for the mutable borrow of the list.
+unsafe impl<'a, K: Send, V: Send> Send for IterMut<'a, K, V> {}
+unsafe impl<'a, K: Sync, V: Sync> Sync for IterMut<'a, K, V> {}
 
 impl<'a, K, V> IterMut<'a, K, V> {
     pub fn new(list: &'a mut SkipListMap<K, V>) -> IterMut<'a, K, V> {
-        IterMut(unsafe { (*list.head_).next_mut(0) })
+        IterMut {
+            current_: list.head().link(0),
+            generation_: GenerationCheck::new(list),
+            phantom_: std::marker::PhantomData,
+        }
     }
 }
 
@@ -37,14 +95,11 @@ impl<'a, K: 'a, V: 'a> Iterator for IterMut<'a, K, V> {
     type Item = (&'a K, &'a mut V);
 
     fn next(&mut self) -> Option<Self::Item> {
-        // TODO: do this the right way...
-        let current = std::mem::replace(&mut self.0, None);
-        if let Some(node) = current {
-            let node_ptr: *mut Node<K, V> = node;
-            std::mem::replace(&mut self.0, node.next_mut(0));
-            Some(unsafe { (*node_ptr).key_value_mut() })
-        } else {
-            None
+        self.generation_.check();
+        let node = self.current_?;
+        unsafe {
+            self.current_ = node.as_ref().link(0);
+            Some(Node::key_value_mut_ptr(node))
         }
     }
 }
@@ -103,33 +158,27 @@ impl<'a, K: 'a, V: 'a> Iterator for ValuesMut<'a, K, V> {
 pub struct Range<'a, K: 'a, V: 'a> {
     /// `current_` is inclusive. We will keep on iterating until `current_` is `None`.
     current_: Option<&'a Node<K, V>>,
-    /// `end_` is inclusive. If `None`, the end is considered to be unbounded. Otherwise, the key
-    /// in the contained `Node<K, V>` is the maximum.
-    end_: Option<&'a Node<K, V>>,
+    /// `last_` is inclusive: it is the last node that will be yielded. If `None`, the end is
+    /// considered to be unbounded.
+    last_: Option<&'a Node<K, V>>,
+    generation_: GenerationCheck,
 }
 
 impl<'a, K: 'a + Ord, V: 'a> Iterator for Range<'a, K, V> {
     type Item = (&'a K, &'a V);
 
     fn next(&mut self) -> Option<Self::Item> {
-        let key_value = self.current_.map(|node| node.key_value());
-
-        self.current_ = self.current_.and_then(
-            |node|
-                node.next(0).and_then(|next|
-                    match self.end_ {
-                        None => Some(next),
-                        Some(end) => {
-                            if likely!(next.key() <= end.key()) {
-                                Some(next)
-                            } else {
-                                None
-                            }
-                        }
-                    }
-                ));
+        self.generation_.check();
+        let node = self.current_?;
+
+        // Both ends are resolved to nodes when building the iterator, so there
+        // is no need to compare keys while iterating.
+        self.current_ = match self.last_ {
+            Some(last) if std::ptr::eq(node, last) => None,
+            _ => node.next(0),
+        };
 
-        key_value
+        Some(node.key_value())
     }
 }
 
@@ -137,10 +186,10 @@ impl<'a, K: 'a + Ord, V: 'a> Range<'a, K, V> {
     pub fn new<T, R>(list: &SkipListMap<K, V>, range: R) -> Range<K, V>
     where
         K: Borrow<T>,
-        R: RangeArgument<T>,
+        R: RangeBounds<T>,
         T: Ord + ?Sized,
     {
-        let lower_bound = match range.start() {
+        let first = match range.start_bound() {
             Bound::Included(key) => list.find_lower_bound(key).next(0),
             Bound::Excluded(key) => {
                 list.find_lower_bound(key).next(0).and_then(
@@ -152,18 +201,126 @@ impl<'a, K: 'a + Ord, V: 'a> Range<'a, K, V> {
                     },
                 )
             }
-            Bound::Unbounded => unsafe { (*list.head_).next(0) },
+            Bound::Unbounded => list.head().next(0),
         };
 
-        let upper_bound = match range.end() {
-            Bound::Included(key) => list.find_lower_bound(key).next(0),
+        // The last node is the greatest one within the bound. It may be the
+        // head, which means that every element is past the end of the range.
+        let last = match range.end_bound() {
+            Bound::Included(key) => {
+                let lower_bound = list.find_lower_bound(key);
+                match lower_bound.next(0) {
+                    Some(next) if next.key() == key => Some(next),
+                    _ => Some(lower_bound),
+                }
+            }
             Bound::Excluded(key) => Some(list.find_lower_bound(key)),
             Bound::Unbounded => None,
         };
 
+        match (first, last) {
+            (first, None) => Range {
+                current_: first,
+                last_: None,
+                generation_: GenerationCheck::new(list),
+            },
+            (Some(first), Some(last))
+                if !std::ptr::eq(last, list.head()) && first.key::<K>() <= last.key::<K>() => Range {
+                current_: Some(first),
+                last_: Some(last),
+                generation_: GenerationCheck::new(list),
+            },
+            _ => Range {
+                current_: None,
+                last_: None,
+                generation_: GenerationCheck::new(list),
+            },
+        }
+    }
+}
+
+impl<'a, K: 'a, V: 'a> Range<'a, K, V> {
+    /// Builds an iterator from `first` up to `last`, both included, as
+    /// resolved by `new`.
+    pub(crate) fn from_bounds(
+        list: &'a SkipListMap<K, V>,
+        first: Option<&'a Node<K, V>>,
+        last: Option<&'a Node<K, V>>,
+    ) -> Range<'a, K, V> {
         Range {
-            current_: lower_bound,
-            end_: upper_bound,
+            current_: first,
+            last_: last,
+            generation_: GenerationCheck::new(list),
+        }
+    }
+
+    /// Returns the next node to yield, or `None` when there is nothing left.
+    pub(crate) fn first_node(&self) -> Option<&'a Node<K, V>> {
+        self.current_
+    }
+
+    /// Returns the last node to yield, or `None` when the range is unbounded
+    /// above.
+    pub(crate) fn last_node(&self) -> Option<&'a Node<K, V>> {
+        self.last_
+    }
+}
+
+/// Moves out entries already unlinked from a list, in key order. Returned by
+/// `SkipListMap::drain_range`.
+pub struct DrainRange<'a, K: 'a, V: 'a> {
+    /// Start of the detached level 0 chain, which ends in `None`.
+    current_: Link<K, V>,
+    remaining_: usize,
+    /// Pool of the list the entries were unlinked from, if any.
+    pool_: Option<NodePool<K, V>>,
+    phantom_: std::marker::PhantomData<&'a mut SkipListMap<K, V>>,
+}
+
+// The detached nodes are uniquely owned by the iterator.
+unsafe impl<'a, K: Send, V: Send> Send for DrainRange<'a, K, V> {}
+unsafe impl<'a, K: Sync, V: Sync> Sync for DrainRange<'a, K, V> {}
+
+impl<'a, K, V> DrainRange<'a, K, V> {
+    pub(crate) fn new(
+        first: Link<K, V>,
+        length: usize,
+        pool: Option<NodePool<K, V>>,
+    ) -> DrainRange<'a, K, V> {
+        DrainRange {
+            current_: first,
+            remaining_: length,
+            pool_: pool,
+            phantom_: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<'a, K: 'a, V: 'a> Iterator for DrainRange<'a, K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.current_?;
+        self.current_ = unsafe { node.as_ref().link(0) };
+        self.remaining_ -= 1;
+        match self.pool_ {
+            Some(ref pool) => Some(pool.recycle(node)),
+            None => Some(SkipListMap::take_node(node)),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining_, Some(self.remaining_))
+    }
+}
+
+impl<'a, K: 'a, V: 'a> ExactSizeIterator for DrainRange<'a, K, V> {}
+
+impl<'a, K, V> Drop for DrainRange<'a, K, V> {
+    fn drop(&mut self) {
+        match self.pool_ {
+            Some(ref pool) => pool.recycle_chain(self.current_.take()),
+            None => SkipListMap::free_chain(self.current_.take()),
         }
     }
 }
@@ -194,7 +351,7 @@ impl<K: Ord, V> SkipListMap<K, V> {
     pub fn range<T, R>(&self, range: R) -> Range<K, V>
     where
         K: Borrow<T>,
-        R: RangeArgument<T>,
+        R: RangeBounds<T>,
         T: Ord + ?Sized,
     {
         Range::new(self, range)
@@ -203,11 +360,52 @@ impl<K: Ord, V> SkipListMap<K, V> {
     pub fn range_mut<T, R>(&mut self, _range: R) -> std::collections::btree_map::RangeMut<K, V>
     where
         K: Borrow<T>,
-        R: RangeArgument<T>,
+        R: RangeBounds<T>,
         T: Ord + ?Sized,
     {
         unimplemented!()
     }
+
+    /// Calls `f` on every entry within `range`, in key order, with a mutable
+    /// reference to its value.
+    pub fn for_each_in_range_mut<T, R, F>(&mut self, range: R, mut f: F)
+    where
+        K: Borrow<T>,
+        R: RangeBounds<T>,
+        T: Ord + ?Sized,
+        F: FnMut(&K, &mut V),
+    {
+        let mut current = match range.start_bound() {
+            Bound::Included(key) => self.find_lower_bound_mut(key).link(0),
+            Bound::Excluded(key) => {
+                match self.find_lower_bound_mut(key).next_mut(0) {
+                    Some(next) if next.key::<T>() == key => next.link(0),
+                    Some(next) => Some(NonNull::from(next)),
+                    None => None,
+                }
+            }
+            Bound::Unbounded => self.head().link(0),
+        };
+
+        // Each node is only reachable through the reference handed to `f`
+        // while it is being visited.
+        while let Some(node) = current {
+            unsafe {
+                let within = match range.end_bound() {
+                    Bound::Included(key) => node.as_ref().key::<T>() <= key,
+                    Bound::Excluded(key) => node.as_ref().key::<T>() < key,
+                    Bound::Unbounded => true,
+                };
+                if !within {
+                    break;
+                }
+
+                current = node.as_ref().link(0);
+                let (key, value) = Node::key_value_mut_ptr(node);
+                f(key, value);
+            }
+        }
+    }
 }
 
 // TODO: size hint
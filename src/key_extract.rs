@@ -0,0 +1,226 @@
+use map::SkipListMap;
+use height_control::HeightControl;
+use iter::{Iter, Range};
+
+use std;
+use std::cmp::Ordering;
+use std::marker::PhantomData;
+use std::ops::{Bound, RangeBounds};
+
+/// Derives the key that entries are sorted by from their full key, e.g. a
+/// timestamp field of a struct. See `KeyExtractMap`.
+pub trait KeyExtract<K: ?Sized> {
+    type SortKey: Ord;
+
+    /// Returns the sort key of `key`. It is called on every comparison, so it
+    /// should be cheap, and it must always return the same for the same key.
+    fn sort_key(key: &K) -> Self::SortKey;
+}
+
+/// Key wrapper that orders `K` by the sort key `E` extracts from it first, and
+/// by `K` itself among equal sort keys.
+#[repr(transparent)]
+pub struct ExtractedKey<K: ?Sized, E> {
+    marker_: PhantomData<fn() -> E>,
+    key_: K,
+}
+
+impl<K, E> ExtractedKey<K, E> {
+    pub fn new(key: K) -> ExtractedKey<K, E> {
+        ExtractedKey {
+            marker_: PhantomData,
+            key_: key,
+        }
+    }
+
+    /// Returns the full key.
+    pub fn key(&self) -> &K {
+        &self.key_
+    }
+
+    /// Returns the full key, consuming the wrapper.
+    pub fn into_key(self) -> K {
+        self.key_
+    }
+}
+
+impl<K: ?Sized, E> ExtractedKey<K, E> {
+    /// Views a reference to a key as a reference to an `ExtractedKey`, so that
+    /// lookups don't need to own the key.
+    pub fn from_ref(key: &K) -> &ExtractedKey<K, E> {
+        // `ExtractedKey` is a transparent wrapper around `K`.
+        unsafe { &*(key as *const K as *const ExtractedKey<K, E>) }
+    }
+}
+
+impl<K: ?Sized + Ord, E: KeyExtract<K>> PartialEq for ExtractedKey<K, E> {
+    fn eq(&self, other: &ExtractedKey<K, E>) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<K: ?Sized + Ord, E: KeyExtract<K>> Eq for ExtractedKey<K, E> {}
+
+impl<K: ?Sized + Ord, E: KeyExtract<K>> PartialOrd for ExtractedKey<K, E> {
+    fn partial_cmp(&self, other: &ExtractedKey<K, E>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K: ?Sized + Ord, E: KeyExtract<K>> Ord for ExtractedKey<K, E> {
+    fn cmp(&self, other: &ExtractedKey<K, E>) -> Ordering {
+        E::sort_key(&self.key_)
+            .cmp(&E::sort_key(&other.key_))
+            .then_with(|| self.key_.cmp(&other.key_))
+    }
+}
+
+impl<K: Clone, E> Clone for ExtractedKey<K, E> {
+    fn clone(&self) -> ExtractedKey<K, E> {
+        ExtractedKey::new(self.key_.clone())
+    }
+}
+
+impl<K: ?Sized + std::hash::Hash, E> std::hash::Hash for ExtractedKey<K, E> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.key_.hash(state)
+    }
+}
+
+impl<K: ?Sized + std::fmt::Debug, E> std::fmt::Debug for ExtractedKey<K, E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.key_.fmt(f)
+    }
+}
+
+fn extracted_bound<K, E>(bound: Bound<&K>) -> Bound<&ExtractedKey<K, E>> {
+    match bound {
+        Bound::Included(key) => Bound::Included(ExtractedKey::from_ref(key)),
+        Bound::Excluded(key) => Bound::Excluded(ExtractedKey::from_ref(key)),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+/// `SkipListMap` whose entries are sorted by a key `E` derives from their full
+/// key, while lookups still take the full key. Entries with the same sort key
+/// are sorted by their full key.
+///
+/// This avoids having to restructure keys into `(sort key, rest)` tuples just
+/// to change their order.
+pub struct KeyExtractMap<K, V, E> {
+    map_: SkipListMap<ExtractedKey<K, E>, V>,
+}
+
+impl<K, V, E> KeyExtractMap<K, V, E> {
+    pub fn new(controller: Box<HeightControl<ExtractedKey<K, E>>>) -> KeyExtractMap<K, V, E> {
+        KeyExtractMap {
+            map_: SkipListMap::new(controller),
+        }
+    }
+
+    /// Removes all elements.
+    pub fn clear(&mut self) {
+        self.map_.clear()
+    }
+
+    /// Returns the number of elements stored in the structure.
+    pub fn len(&self) -> usize {
+        self.map_.len()
+    }
+
+    /// Returns `true` if there are no elements stored within the structure.
+    pub fn is_empty(&self) -> bool {
+        self.map_.is_empty()
+    }
+
+    /// Iterates over the entries, ordered by their sort key.
+    pub fn iter(&self) -> ExtractedIter<Iter<'_, ExtractedKey<K, E>, V>> {
+        ExtractedIter(self.map_.iter())
+    }
+
+    /// Returns the underlying map, keyed by `ExtractedKey<K, E>`.
+    pub fn as_map(&self) -> &SkipListMap<ExtractedKey<K, E>, V> {
+        &self.map_
+    }
+
+    /// Consumes the map, returning the underlying one.
+    pub fn into_map(self) -> SkipListMap<ExtractedKey<K, E>, V> {
+        self.map_
+    }
+}
+
+impl<K: Ord, V, E: KeyExtract<K>> KeyExtractMap<K, V, E> {
+    /// Inserts `value` under `key`, returning the value it replaced, if any.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.map_.insert(ExtractedKey::new(key), value)
+    }
+
+    /// Returns a const reference to the element with key `key`, if it exists.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.map_.get(ExtractedKey::from_ref(key))
+    }
+
+    /// Returns a mutable reference to the element with key `key`, if it
+    /// exists.
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.map_.get_mut(ExtractedKey::from_ref(key))
+    }
+
+    /// Returns true if `key` is in the map.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.map_.contains_key(ExtractedKey::from_ref(key))
+    }
+
+    /// Removes the element with key `key`, returning its value if it existed.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.map_.remove(ExtractedKey::from_ref(key))
+    }
+
+    /// Returns the entry with the smallest sort key, if any.
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.map_.first().map(|(key, value)| (key.key(), value))
+    }
+
+    /// Iterates over the entries between two full keys, ordered by their sort
+    /// key.
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> ExtractedIter<Range<'_, ExtractedKey<K, E>, V>> {
+        let bounds = (
+            extracted_bound(range.start_bound()),
+            extracted_bound(range.end_bound()),
+        );
+
+        ExtractedIter(self.map_.range(bounds))
+    }
+}
+
+impl<K: 'static + Ord + std::hash::Hash, V, E: 'static> Default for KeyExtractMap<K, V, E> {
+    fn default() -> KeyExtractMap<K, V, E> {
+        KeyExtractMap {
+            map_: Default::default(),
+        }
+    }
+}
+
+impl<K: std::fmt::Debug, V: std::fmt::Debug, E> std::fmt::Debug for KeyExtractMap<K, V, E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Iterator over the entries of a `KeyExtractMap`, ordered by their sort key.
+pub struct ExtractedIter<I>(I);
+
+impl<'a, K: 'a, V: 'a, E: 'a, I> Iterator for ExtractedIter<I>
+where
+    I: Iterator<Item = (&'a ExtractedKey<K, E>, &'a V)>,
+{
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(key, value)| (key.key(), value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
@@ -5,23 +5,140 @@
 #![feature(core_intrinsics)]
 #![feature(allow_internal_unsafe)]
 #![feature(stmt_expr_attributes)]
+#![feature(dropck_eyepatch)]
 
-// used for ranges
-#![feature(collections_range)]
-
-// test framework
-#![cfg_attr(test, feature(plugin))]
-#[cfg(test)]
+// test framework, also exposed through the `quickcheck` feature
+#[cfg(any(test, feature = "quickcheck"))]
 extern crate quickcheck;
 
+#[cfg(feature = "python")]
+extern crate pyo3;
+#[cfg(feature = "rkyv")]
+extern crate rkyv;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+#[cfg(feature = "borsh")]
+extern crate borsh;
+#[cfg(feature = "arbitrary")]
+extern crate arbitrary;
+#[cfg(feature = "async")]
+extern crate futures_core;
+// The code generated by pyo3's and rkyv's macros refers to `::core`.
+#[cfg(any(feature = "python", feature = "rkyv"))]
+extern crate core;
+
 #[macro_use]
 mod macros;
 
+mod entropy;
 mod height_control;
 mod node;
 mod map;
 mod iter;
+mod stats;
+mod build;
+mod frozen;
+mod persistent;
+mod descending;
+mod key_extract;
+mod index_list;
+mod prefix;
+mod shared;
+mod boxed;
+mod cow;
+mod bounded;
+mod tombstone;
+mod versioned;
+mod interval;
+mod timer;
+mod dual_index;
+mod transaction;
+mod observed;
+mod metrics;
+mod memtable;
+mod entry;
+mod detached;
+mod pool;
+mod builder;
+mod error;
+mod merge;
+mod changeset;
+mod hash_indexed;
+mod prefixed;
+mod encoding;
+mod snapshot;
+mod thin;
+mod sub_map;
+mod cursor;
+mod warm;
+mod change_capture;
+mod search_by;
+pub mod wal;
+pub mod sorted_run;
+pub mod region;
+pub mod augmented;
+pub mod arena;
+pub mod fixed;
+pub mod const_height;
+#[cfg(any(test, feature = "quickcheck"))]
+mod quickcheck_support;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "rkyv")]
+mod rkyv_support;
+#[cfg(feature = "rayon")]
+mod rayon_support;
+#[cfg(feature = "borsh")]
+mod borsh_support;
+#[cfg(feature = "arbitrary")]
+mod arbitrary_support;
+#[cfg(feature = "async")]
+mod stream;
 
-pub use map::SkipListMap;
-pub use height_control::{HeightControl, HashCoinGenerator, GeometricalGenerator, TwoPowGenerator};
-pub use iter::Iter;
+pub use map::{SkipListMap, RebuildPolicy};
+#[cfg(feature = "getrandom")]
+pub use entropy::GetrandomEntropy;
+pub use entropy::{DefaultEntropy, Entropy, SeededEntropy, ThreadEntropy};
+pub use height_control::{
+    HeightControl, HashCoinGenerator, GeometricalGenerator, TwoPowGenerator, AdaptiveGenerator,
+    FnHeightControl, HeightContext,
+};
+pub use iter::{Iter, Range, DrainRange};
+pub use stats::Stats;
+pub use build::UnsortedError;
+pub use frozen::{FrozenSkipListMap, FrozenIter};
+pub use persistent::{PersistentSkipListMap, PersistentIter};
+pub use descending::{Descending, DescendingSkipListMap, DescendingIter};
+pub use key_extract::{KeyExtract, ExtractedKey, KeyExtractMap, ExtractedIter};
+pub use prefix::{PrefixSkipListMap, PrefixIter};
+pub use boxed::{BoxedSkipListMap, BoxedIter};
+pub use cow::{CowSkipListMap, CowIter};
+pub use bounded::{BoundedSkipListMap, Evict};
+pub use tombstone::{TombstoneSkipListMap, TombstoneIter};
+pub use versioned::{VersionedSkipListMap, VersionedIter};
+pub use interval::{IntervalSkipList, IntervalIter};
+pub use augmented::{Aggregate, AugmentedSkipListMap, AugmentedIter};
+pub use timer::{TimerQueue, TimerId, Expired};
+pub use dual_index::{DualIndexMap, DualIndexIter};
+pub use transaction::Transaction;
+pub use observed::{Observer, ObservedSkipListMap};
+pub use metrics::{Metrics, Operation};
+pub use memtable::{MemTable, ResidentSize, FrozenError};
+pub use entry::{EntryRef, OccupiedEntryRef, VacantEntryRef};
+pub use detached::DetachedNode;
+pub use pool::{NodePool, PoolStats};
+pub use builder::SkipListMapBuilder;
+pub use error::Error;
+pub use merge::{merge_iter, MergeIter, Resolution};
+pub use changeset::Change;
+pub use hash_indexed::HashIndexedSkipListMap;
+pub use prefixed::{KeyPrefix, Prefixed};
+pub use encoding::Encode;
+pub use thin::{ThinBytes, ThinStr};
+pub use sub_map::SubMap;
+pub use cursor::ResumableCursor;
+pub use change_capture::{ChangeCapture, SequencedChange, TruncatedError};
+#[cfg(feature = "rkyv")]
+pub use rkyv_support::{ArchivedSkipListMap, ArchivedIter};
+#[cfg(feature = "async")]
+pub use stream::ChunkedStream;
@@ -0,0 +1,281 @@
+//! Interactive shell over a `SkipListMap<i64, String>`, for exploring the
+//! structure and the shapes produced by the height controllers.
+//!
+//! Commands are read from the standard input, one per line; `help` lists them.
+//! Input that does not come from a terminal is run without prompts, so that
+//! scripts can be piped through.
+extern crate skiplist;
+
+use skiplist::*;
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hasher;
+use std::io::{self, BufRead, IsTerminal, Write};
+
+const USAGE: &str = "\
+usage: skiplist [options]
+
+options:
+  --generator NAME    two-pow (default), geometric, adaptive or hash-coin
+  --max-height N      maximum tower height, 16 by default
+  --probability P     upgrade probability of geometric and adaptive, 0.5 by default
+  --seed N            makes the generated heights reproducible
+  --help              prints this message";
+
+const COMMANDS: &str = "\
+commands:
+  insert KEY VALUE    inserts or replaces VALUE under KEY
+  get KEY             prints the value under KEY
+  remove KEY          removes KEY, and prints its value
+  range START END     prints the entries with START <= key < END
+  len                 prints the number of entries
+  viz                 draws the levels of the list
+  stats               prints the shape of the list
+  clear               removes every entry
+  help                prints this message
+  quit                exits";
+
+struct Options {
+    generator_: String,
+    max_height_: usize,
+    probability_: f64,
+    seed_: Option<u64>,
+}
+
+fn parse_options<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+    let mut options = Options {
+        generator_: "two-pow".to_string(),
+        max_height_: 16,
+        probability_: 0.5,
+        seed_: None,
+    };
+
+    while let Some(arg) = args.next() {
+        let value = args.next().ok_or_else(|| format!("missing value for {}", arg))?;
+        let invalid = || format!("invalid value for {}: {}", arg, value);
+        match arg.as_str() {
+            "--generator" => options.generator_ = value.clone(),
+            "--max-height" => options.max_height_ = value.parse().map_err(|_| invalid())?,
+            "--probability" => options.probability_ = value.parse().map_err(|_| invalid())?,
+            "--seed" => options.seed_ = Some(value.parse().map_err(|_| invalid())?),
+            _ => return Err(format!("unknown option {}\n\n{}", arg, USAGE)),
+        }
+    }
+
+    Ok(options)
+}
+
+fn controller(options: &Options) -> Result<Box<HeightControl<i64>>, String> {
+    match options.seed_ {
+        Some(seed) => controller_with(options, SeededEntropy::new(seed)),
+        None => controller_with(options, DefaultEntropy::default()),
+    }
+}
+
+fn controller_with<E: 'static + Entropy>(
+    options: &Options,
+    entropy: E,
+) -> Result<Box<HeightControl<i64>>, String> {
+    let max_height = options.max_height_;
+    let probability = options.probability_;
+    if max_height == 0 {
+        return Err("the maximum height must be positive".to_string());
+    }
+
+    if options.generator_ != "two-pow" && options.generator_ != "hash-coin"
+        && !(probability > 0.0 && probability < 1.0)
+    {
+        return Err("the probability must be between 0 and 1".to_string());
+    }
+
+    Ok(match options.generator_.as_str() {
+        "two-pow" => Box::new(TwoPowGenerator::with_entropy(max_height, entropy)),
+        "geometric" => Box::new(GeometricalGenerator::with_entropy(max_height, probability, entropy)),
+        "adaptive" => Box::new(AdaptiveGenerator::with_entropy(max_height, probability, entropy)),
+        "hash-coin" => {
+            let mut hasher = DefaultHasher::new();
+            hasher.write_u64(options.seed_.unwrap_or(0));
+            Box::new(HashCoinGenerator::new(max_height, hasher))
+        }
+        other => return Err(format!("unknown generator {}", other)),
+    })
+}
+
+fn parse_key(word: &str) -> Result<i64, String> {
+    word.parse().map_err(|_| format!("keys are integers, not {}", word))
+}
+
+/// Runs a single command, and returns what it prints, or `None` once the shell
+/// should exit.
+fn run_command(map: &mut SkipListMap<i64, String>, words: &[&str]) -> Result<Option<String>, String> {
+    let output = match *words {
+        [] => String::new(),
+        ["insert", key, ref value @ ..] if !value.is_empty() => {
+            match map.insert(parse_key(key)?, value.join(" ")) {
+                Some(old) => format!("replaced {}\n", old),
+                None => "inserted\n".to_string(),
+            }
+        }
+        ["get", key] => match map.get(&parse_key(key)?) {
+            Some(value) => format!("{}\n", value),
+            None => "not found\n".to_string(),
+        },
+        ["remove", key] => match map.remove(&parse_key(key)?) {
+            Some(value) => format!("removed {}\n", value),
+            None => "not found\n".to_string(),
+        },
+        ["range", start, end] => {
+            let (start, end) = (parse_key(start)?, parse_key(end)?);
+            if start > end {
+                return Err("the range starts after it ends".to_string());
+            }
+
+            map.range(start..end).map(|(key, value)| format!("{} = {}\n", key, value)).collect()
+        }
+        ["len"] => format!("{}\n", map.len()),
+        ["viz"] => map.visualize(),
+        ["stats"] => {
+            let stats = map.stats();
+            format!(
+                "level counts: {:?}\naverage height: {:.2}\nmax height: {}\nexpected comparisons: {:.2}\n",
+                stats.level_counts, stats.average_height, stats.max_height,
+                stats.expected_comparisons
+            )
+        }
+        ["clear"] => {
+            map.clear();
+            String::new()
+        }
+        ["help"] => format!("{}\n", COMMANDS),
+        ["quit"] | ["exit"] => return Ok(None),
+        _ => return Err(format!("unknown command: {} (try help)", words.join(" "))),
+    };
+
+    Ok(Some(output))
+}
+
+/// Runs a single command, writing its output or error to `out`. Returns
+/// `Ok(false)` once the shell should exit.
+fn execute<W: Write>(
+    map: &mut SkipListMap<i64, String>,
+    line: &str,
+    out: &mut W,
+) -> io::Result<bool> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    match run_command(map, &words) {
+        Ok(Some(output)) => out.write_all(output.as_bytes())?,
+        Ok(None) => return Ok(false),
+        Err(message) => writeln!(out, "error: {}", message)?,
+    }
+
+    Ok(true)
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--help") {
+        println!("{}", USAGE);
+        return;
+    }
+
+    let options = match parse_options(args.into_iter()) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(2);
+        }
+    };
+
+    let controller = controller(&options).unwrap_or_else(|message| {
+        eprintln!("{}", message);
+        std::process::exit(2);
+    });
+
+    let mut map = SkipListMap::new(controller);
+    let stdin = io::stdin();
+    let interactive = stdin.is_terminal();
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+
+    let mut lines = stdin.lock().lines();
+    loop {
+        if interactive {
+            write!(out, "> ").and_then(|_| out.flush()).expect("failed to write the prompt");
+        }
+
+        let line = match lines.next() {
+            Some(line) => line.expect("failed to read a command"),
+            None => break,
+        };
+
+        if !execute(&mut map, &line, &mut out).expect("failed to write the output") {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(map: &mut SkipListMap<i64, String>, script: &[&str]) -> String {
+        let mut out = Vec::new();
+        for line in script {
+            assert!(execute(map, line, &mut out).unwrap());
+        }
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn commands() {
+        let options = parse_options(vec!["--seed".to_string(), "7".to_string()].into_iter());
+        let mut map = SkipListMap::new(controller(&options.unwrap()).unwrap());
+        let output = run(
+            &mut map,
+            &[
+                "insert 3 three", "insert 1 one", "insert 2 two and a half", "insert 2 two",
+                "get 2", "get 4", "range 1 3", "remove 1", "remove 1", "len",
+            ],
+        );
+        assert_eq!(
+            output,
+            "inserted\ninserted\ninserted\nreplaced two and a half\ntwo\nnot found\n\
+             1 = one\n2 = two\nremoved one\nnot found\n2\n"
+        );
+
+        assert!(run(&mut map, &["viz"]).ends_with("-- 2 -- 3 --> nil\n"));
+        assert!(run(&mut map, &["stats"]).starts_with("level counts: [2"));
+        assert_eq!(run(&mut map, &["clear", "len"]), "0\n");
+        assert!(!execute(&mut map, "quit", &mut Vec::new()).unwrap());
+    }
+
+    #[test]
+    fn errors() {
+        let mut map: SkipListMap<i64, String> = Default::default();
+        let output = run(&mut map, &["get x", "insert 1", "range 3 1", "frobnicate"]);
+        assert_eq!(
+            output,
+            "error: keys are integers, not x\nerror: unknown command: insert 1 (try help)\n\
+             error: the range starts after it ends\nerror: unknown command: frobnicate (try help)\n"
+        );
+    }
+
+    #[test]
+    fn seeded_controllers_repeat_heights() {
+        for generator in &["two-pow", "geometric", "adaptive", "hash-coin"] {
+            let args = vec!["--generator", generator, "--seed", "42", "--max-height", "8"];
+            let options = parse_options(args.into_iter().map(String::from)).unwrap();
+            let mut first = controller(&options).unwrap();
+            let mut second = controller(&options).unwrap();
+            for key in 0..100 {
+                assert_eq!(first.get_height(&key), second.get_height(&key));
+            }
+        }
+
+        let options = parse_options(vec!["--max-height".to_string(), "12".to_string()].into_iter());
+        assert_eq!(controller(&options.unwrap()).unwrap().max_height(), 12);
+        let options = parse_options(vec!["--max-height".to_string(), "0".to_string()].into_iter());
+        assert!(controller(&options.unwrap()).is_err());
+        assert!(parse_options(vec!["--bogus".to_string(), "1".to_string()].into_iter()).is_err());
+    }
+}
@@ -1,9 +1,39 @@
-use node::Node;
-use height_control::HeightControl;
+use node::{Link, Node};
+use height_control::{HeightContext, HeightControl};
+use iter::DrainRange;
+use metrics::{Metrics, Operation};
+use pool::NodePool;
+use stats::Stats;
 
 use std;
 use std::borrow::Borrow;
+use std::ops::{Bound, RangeBounds};
+use std::ptr::NonNull;
+
+/// Node before a key, along with the last node before it at every level, as
+/// found by `find_lower_bound_with_updates`.
+type LowerBound<K, V> = (NonNull<Node<K, V>>, Vec<NonNull<Node<K, V>>>);
+
+/// Decides what happens to the existing towers when the `HeightControl` of a
+/// populated `SkipListMap` is replaced through `set_height_control`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RebuildPolicy {
+    /// Existing nodes keep their heights; only nodes inserted afterwards use
+    /// the new controller. This is O(1), but the list only converges to the
+    /// new distribution as the old nodes are removed.
+    Keep,
+    /// Every node gets a new height from the new controller, and the whole
+    /// structure is relinked in a single O(n) pass.
+    Rebuild,
+}
 
+/// Ordered map, implemented as a Skip List.
+///
+/// Every entry lives in a node of its own, allocated when it is inserted and
+/// freed when it is removed, and the list only ever relinks nodes, so values
+/// never move while they are in the map. Nodes handed over to another map as
+/// a whole, e.g. through `split_off`, `extract_range`, `merge_with` or
+/// `detach` and `attach`, keep their values in place as well. See `get_raw`.
 pub struct SkipListMap<K, V> {
     /// Pointer to the head of the Skip List. The first node is actually a "ghost"
     /// node: it is created within `SkipList::new`, should only be deleted in
@@ -13,59 +43,130 @@ pub struct SkipListMap<K, V> {
     /// The reason we have the ghost node is because it simplifies the algorithms
     /// considerably. Searches for nodes all begin in the ghost node, which has
     /// as `next(0)` the actual first element, if any.
-    pub(crate) head_: *mut Node<K, V>,
+    pub(crate) head_: NonNull<Node<K, V>>,
 
     /// Number of elements in the SkipList
     length_: usize,
 
     /// Maximum reached height
-    height_: usize,
+    pub(crate) height_: usize,
 
     /// Maximum height the `controller_` can generate. This is stored here instead
     /// of calling `controller_` because all calls to `controller_` are virtually
     /// dispatched, which is more expensive than just holding an usize.
+    ///
+    /// After a `set_height_control` with `RebuildPolicy::Keep` this may be
+    /// larger than what the controller generates, since it must also cover the
+    /// nodes that kept their old heights.
     max_height_: usize,
 
     /// Used to generate the height for any given node when inserting data.
     controller_: Box<HeightControl<K>>,
+
+    /// Bumped on every structural change. Iterators check it in debug builds,
+    /// to catch lists that were modified while being iterated through unsafe
+    /// aliasing.
+    pub(crate) generation_: usize,
+
+    /// Hooks set through `set_metrics`, if any.
+    pub(crate) metrics_: Option<Box<Metrics>>,
+
+    /// Pool nodes are taken from and given back to, if any. See `NodePool`.
+    pub(crate) pool_: Option<NodePool<K, V>>,
+
+    /// Last node at every level, to be used as fingers by `link_at_tail`.
+    /// Only valid while `tail_generation_` matches `generation_`, i.e. until
+    /// the structure changes through anything but an append.
+    tail_: Vec<NonNull<Node<K, V>>>,
+    tail_generation_: Option<usize>,
+
+    /// Tells the drop checker that the list owns keys and values, even though
+    /// it only holds pointers to the nodes that contain them.
+    marker_: std::marker::PhantomData<Box<(K, V)>>,
 }
 
 impl<K, V> SkipListMap<K, V> {
-    // TODO: custom allocators??
-    fn allocate_node(key: K, value: V, height: usize) -> *mut Node<K, V> {
+    fn allocate_node(&self, key: K, value: V, height: usize) -> NonNull<Node<K, V>> {
+        if let Some(ref pool) = self.pool_ {
+            return pool.allocate(key, value, height);
+        }
+
         // Generate the node. All memory allocation is done using Box so
         // that we can actually free it using Box later
-        Box::into_raw(Box::new(Node::new(key, value, height)))
+        NonNull::from(Box::leak(Box::new(Node::new(key, value, height))))
+    }
+
+    /// Same as `take_node`, but gives the node back to the pool, if any.
+    pub(crate) fn recycle_node(&self, node: NonNull<Node<K, V>>) -> (K, V) {
+        match self.pool_ {
+            Some(ref pool) => pool.recycle(node),
+            None => Self::take_node(node),
+        }
+    }
+
+    /// Same as `free_chain`, but gives the nodes back to the pool, if any.
+    fn release_chain(&self, first: Link<K, V>) {
+        match self.pool_ {
+            Some(ref pool) => pool.recycle_chain(first),
+            None => Self::free_chain(first),
+        }
     }
 
-    fn free_node(node: *mut Node<K, V>) {
+    fn free_node(node: NonNull<Node<K, V>>) {
         unsafe {
-            Box::from_raw(node);
+            let mut node = Box::from_raw(node.as_ptr());
+            node.drop_key_value();
         }
     }
 
-    fn allocate_dummy_node(max_height: usize) -> *mut Node<K, V> {
-        Self::allocate_node(
-            // We need to produce a key and value that will never be accessed
-            unsafe { std::mem::uninitialized() },
-            unsafe { std::mem::uninitialized() },
-            max_height,
-        )
+    /// Frees `node` and returns its key and value.
+    pub(crate) fn take_node(node: NonNull<Node<K, V>>) -> (K, V) {
+        unsafe { Box::from_raw(node.as_ptr()).into_key_value() }
     }
 
-    /// Releases the memory held by the data structure. Does not initialize it again, so the state
-    /// after usage is invalid. See `clear` function for reference on how to restore.
-    fn dispose(&mut self) {
+    fn allocate_dummy_node(max_height: usize) -> NonNull<Node<K, V>> {
+        NonNull::from(Box::leak(Box::new(Node::new_head(max_height))))
+    }
+
+    pub(crate) fn free_dummy_node(node: NonNull<Node<K, V>>) {
         unsafe {
-            let mut current = self.head_;
+            drop(Box::from_raw(node.as_ptr()));
+        }
+    }
 
-            while let Some(next) = (*current).next_mut(0) {
-                Self::free_node(current);
-                current = next;
-            }
+    /// Returns the ghost node at the head of the list.
+    pub(crate) fn head(&self) -> &Node<K, V> {
+        unsafe { self.head_.as_ref() }
+    }
 
-            Self::free_node(current);
+    /// Frees every node in the level 0 chain that starts at `first`. If the
+    /// destructor of a key or value panics, the rest of the chain is still
+    /// freed while unwinding.
+    pub(crate) fn free_chain(first: Link<K, V>) {
+        struct ChainGuard<K, V>(Link<K, V>);
+
+        impl<K, V> Drop for ChainGuard<K, V> {
+            fn drop(&mut self) {
+                while let Some(node) = self.0 {
+                    self.0 = unsafe { node.as_ref().link(0) };
+
+                    // Only active if freeing `node` panics.
+                    let guard = ChainGuard(self.0);
+                    SkipListMap::free_node(node);
+                    std::mem::forget(guard);
+                }
+            }
         }
+
+        drop(ChainGuard(first));
+    }
+
+    /// Releases the memory held by the data structure. Does not initialize it again, so the state
+    /// after usage is invalid. See `clear` function for reference on how to restore.
+    fn dispose(&mut self) {
+        let first = self.head().link(0);
+        Self::free_dummy_node(self.head_);
+        self.release_chain(first);
     }
 
     pub fn new(controller: Box<HeightControl<K>>) -> SkipListMap<K, V> {
@@ -85,6 +186,12 @@ impl<K, V> SkipListMap<K, V> {
             // The only direct call to controller_ should be done in the
             // `SkipList::insert` function.
             controller_: controller,
+            generation_: 0,
+            metrics_: None,
+            pool_: None,
+            tail_: Vec::new(),
+            tail_generation_: None,
+            marker_: std::marker::PhantomData,
         }
     }
 
@@ -94,10 +201,39 @@ impl<K, V> SkipListMap<K, V> {
 
     /// Removes all elements.
     pub fn clear(&mut self) {
-        self.dispose();
-        self.head_ = Self::allocate_dummy_node(self.max_height());
+        // The list is emptied before releasing anything, so that it stays
+        // valid even if a destructor panics.
+        let first = self.head().link(0);
+        unsafe {
+            (*self.head_.as_ptr()).reset_tower(self.max_height());
+        }
         self.length_ = 0;
         self.height_ = 0;
+        self.bump_generation();
+
+        self.release_chain(first);
+    }
+
+    /// Consumes the list, returning its entries in key order. Keys and values
+    /// are moved out of the nodes as they are freed, into a vector allocated
+    /// once with exactly `len()` elements.
+    pub fn into_sorted_vec(mut self) -> Vec<(K, V)> {
+        let mut entries = Vec::with_capacity(self.len());
+
+        // Nodes are detached first, so that dropping the emptied list only
+        // frees the head.
+        let mut current = self.head().link(0);
+        unsafe {
+            (*self.head_.as_ptr()).reset_tower(self.max_height());
+        }
+        self.length_ = 0;
+
+        while let Some(node) = current {
+            current = unsafe { node.as_ref().link(0) };
+            entries.push(self.recycle_node(node));
+        }
+
+        entries
     }
 
     /// Returns the number of elements stored in the structure.
@@ -110,13 +246,329 @@ impl<K, V> SkipListMap<K, V> {
         self.length_ == 0
     }
 
+    /// Every structural change goes through here, so it is also where the
+    /// new shape is reported to the metrics hooks.
+    fn bump_generation(&mut self) {
+        self.generation_ = self.generation_.wrapping_add(1);
+        self.report(|metrics| metrics.resized(self.length_, self.height_));
+    }
+
     /// Returns the maximum reachable height of the SkipList.
-    fn max_height(&self) -> usize {
+    pub(crate) fn max_height(&self) -> usize {
         self.max_height_
     }
+
+    /// Removes the entry with the smallest key, and returns it.
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        let first = self.unlink_first()?;
+        Some(self.recycle_node(first))
+    }
+
+    /// Unlinks the first node from every level, and returns it. The node is
+    /// still allocated, and its tower keeps its stale links.
+    pub(crate) fn unlink_first(&mut self) -> Link<K, V> {
+        unsafe {
+            let first = self.head().link(0)?;
+            for height in 0..std::cmp::max(first.as_ref().height(), 1) {
+                (*self.head_.as_ptr()).link_to_next(height, first.as_ref());
+            }
+
+            self.length_ -= 1;
+            self.bump_generation();
+            Some(first)
+        }
+    }
+
+    /// Replaces the controller used to generate heights for new nodes.
+    ///
+    /// # Arguments
+    ///
+    ///  * `controller`: the new height generation strategy.
+    ///  * `policy`: whether existing nodes keep their towers, or get new ones
+    ///    generated by `controller`. See `RebuildPolicy` for the trade-offs.
+    pub fn set_height_control(
+        &mut self,
+        mut controller: Box<HeightControl<K>>,
+        policy: RebuildPolicy,
+    ) {
+        let max_height = controller.max_height();
+
+        match policy {
+            RebuildPolicy::Keep => {
+                // Nodes that are already linked may be taller than anything the
+                // new controller generates, so the head can never shrink.
+                self.max_height_ = std::cmp::max(max_height, self.max_height_);
+                unsafe {
+                    (*self.head_.as_ptr()).grow_tower(self.max_height_);
+                }
+            }
+            RebuildPolicy::Rebuild => {
+                // All heights are generated before touching anything, so that
+                // a panicking controller leaves the list as it was.
+                let heights = self.generate_heights(&mut *controller);
+                self.max_height_ = max_height;
+                self.rebuild_towers(heights);
+            }
+        }
+
+        self.controller_ = controller;
+        self.bump_generation();
+    }
+
+    /// Computes the `stats` of the list and hands them to its controller
+    /// through `HeightControl::observe`, so that adaptive controllers such as
+    /// `AdaptiveGenerator` can correct the heights of the nodes inserted from
+    /// then on. Returns the stats.
+    ///
+    /// This is O(n), so it is meant to be called periodically, e.g. after a
+    /// large batch of removals, rather than on every update.
+    pub fn tune(&mut self) -> Stats {
+        let stats = self.stats();
+        self.controller_.observe(&stats);
+        stats
+    }
+
+    /// Generates a height for every node, in order, using `controller`. Each
+    /// node is given the context it would have if the list was built again by
+    /// appending them one by one.
+    fn generate_heights(&self, controller: &mut HeightControl<K>) -> Vec<usize> {
+        let mut heights = Vec::with_capacity(self.len());
+        let mut context = HeightContext { len: 0, height: 0 };
+        let mut current = self.head().next(0);
+        while let Some(node) = current {
+            let height = controller.get_height_in(node.key(), &context);
+            heights.push(height);
+            context.len += 1;
+            context.height = std::cmp::max(context.height, height);
+            current = node.next(0);
+        }
+
+        heights
+    }
+
+    /// Gives every node the corresponding height in `heights` and relinks all
+    /// levels. Since nodes are visited in order, each of them is just appended
+    /// after the last node seen at each of its levels.
+    fn rebuild_towers(&mut self, heights: Vec<usize>) {
+        unsafe {
+            let mut current = self.head().link(0);
+            (*self.head_.as_ptr()).reset_tower(self.max_height());
+
+            let mut fingers = self.empty_fingers();
+            self.height_ = 0;
+
+            for height in heights {
+                // There is exactly one height per node.
+                let node = current.unwrap();
+                current = node.as_ref().link(0);
+                (*node.as_ptr()).reset_tower(height);
+                self.link_at_tail(&mut fingers, node);
+            }
+        }
+    }
+
+    /// Returns the fingers to be used with `link_at_tail` on an empty list:
+    /// the last node at every level is the head.
+    pub(crate) fn empty_fingers(&self) -> Vec<NonNull<Node<K, V>>> {
+        vec![self.head_; self.max_height() + 1]
+    }
+
+    /// Links `node` after every other node, at all of its levels. `fingers`
+    /// holds the last node linked at each level, and is updated. Linking nodes
+    /// in key order this way builds a list in O(n).
+    ///
+    /// Nodes taller than what the controller generates grow the head, as
+    /// `RebuildPolicy::Keep` does. The length is left to the caller.
+    pub(crate) unsafe fn link_at_tail(
+        &mut self,
+        fingers: &mut Vec<NonNull<Node<K, V>>>,
+        node: NonNull<Node<K, V>>,
+    ) {
+        let height = node.as_ref().height();
+        if height > self.max_height() {
+            self.max_height_ = height;
+            (*self.head_.as_ptr()).grow_tower(self.max_height_);
+            fingers.resize(self.max_height_ + 1, self.head_);
+        }
+
+        for (level, finger) in fingers.iter_mut().enumerate().take(std::cmp::max(height, 1)) {
+            (*finger.as_ptr()).link_to(level, Some(node));
+            *finger = node;
+        }
+
+        self.height_ = std::cmp::max(self.height_, height);
+        self.bump_generation();
+    }
+
+    /// Takes the cached fingers to the tail of the list, if they are still
+    /// valid. The cache stays empty until `cache_tail` is called, e.g. if a
+    /// comparison panics in between.
+    fn take_tail(&mut self) -> Option<Vec<NonNull<Node<K, V>>>> {
+        if self.tail_generation_.take() == Some(self.generation_) {
+            Some(std::mem::take(&mut self.tail_))
+        } else {
+            None
+        }
+    }
+
+    /// Caches `fingers` to the tail of the list, valid until the next change.
+    fn cache_tail(&mut self, fingers: Vec<NonNull<Node<K, V>>>) {
+        self.tail_ = fingers;
+        self.tail_generation_ = Some(self.generation_);
+    }
+
+    /// Finds the last node at every level for which `before` holds, starting
+    /// from the head. `before` must hold for a prefix of the keys, e.g. being
+    /// smaller than some bound.
+    pub(crate) fn find_updates_by<F>(&self, mut before: F) -> Vec<NonNull<Node<K, V>>>
+    where
+        F: FnMut(&K) -> bool,
+    {
+        // Levels above the current height are only linked from the head.
+        let mut updates = vec![self.head_; self.max_height()];
+
+        let mut current = self.head();
+        for height in (0..std::cmp::max(self.height_, 1)).rev() {
+            while let Some(next) = current.next(height) {
+                if before(next.key()) {
+                    current = next;
+                } else {
+                    break;
+                }
+            }
+
+            updates[height] = NonNull::from(current);
+        }
+
+        updates
+    }
+
+    /// Unlinks every node after `before` up to `last`, both given per level as
+    /// by `find_updates_by`. Returns the first node of the detached chain,
+    /// which ends at `last`, and its length.
+    ///
+    /// `last` must not come before `before` at any level, and the nodes in
+    /// between must be linked only to each other, or to nodes after `last`.
+    pub(crate) unsafe fn detach_between(
+        &mut self,
+        before: &[NonNull<Node<K, V>>],
+        last: &[NonNull<Node<K, V>>],
+    ) -> (Link<K, V>, usize) {
+        let first = before[0].as_ref().link(0);
+        if std::ptr::eq(before[0].as_ptr(), last[0].as_ptr()) {
+            return (None, 0);
+        }
+
+        for height in 0..std::cmp::max(self.height_, 1) {
+            let next = last[height].as_ref().link(height);
+            (*before[height].as_ptr()).link_to(height, next);
+        }
+        (*last[0].as_ptr()).link_to(0, None);
+
+        let mut detached = 0;
+        let mut current = first;
+        while let Some(node) = current {
+            detached += 1;
+            current = node.as_ref().link(0);
+        }
+
+        self.length_ -= detached;
+        self.bump_generation();
+        (first, detached)
+    }
+
+    /// Finds the last node at every level among the first `count` ones,
+    /// walking them one by one.
+    fn find_updates_at(&self, count: usize) -> Vec<NonNull<Node<K, V>>> {
+        let mut updates = vec![self.head_; self.max_height()];
+
+        let mut current = self.head().link(0);
+        for _ in 0..count {
+            // There are at least `count` nodes.
+            let node = current.unwrap();
+            let levels = unsafe { std::cmp::max(node.as_ref().height(), 1) };
+            for update in updates.iter_mut().take(levels) {
+                *update = node;
+            }
+
+            current = unsafe { node.as_ref().link(0) };
+        }
+
+        updates
+    }
+
+    /// Removes the `n` entries with the smallest keys, or all of them if there
+    /// are fewer, and returns them in key order. The entries are cut off from
+    /// every level at once, instead of being popped one by one.
+    pub fn pop_first_n(&mut self, n: usize) -> Vec<(K, V)> {
+        self.drain_first(n).collect()
+    }
+
+    /// Unlinks the `n` entries with the smallest keys, or all of them if
+    /// there are fewer, and returns an iterator that moves them out.
+    pub(crate) fn drain_first(&mut self, n: usize) -> DrainRange<'_, K, V> {
+        let n = std::cmp::min(n, self.len());
+        let before = vec![self.head_; self.max_height()];
+        let last = self.find_updates_at(n);
+
+        let (first, length) = unsafe { self.detach_between(&before, &last) };
+        DrainRange::new(first, length, self.pool_.clone())
+    }
+
+    /// Removes the `n` entries with the largest keys, or all of them if there
+    /// are fewer, and returns them in key order.
+    ///
+    /// # Remarks
+    ///
+    /// Nodes don't link back, so finding where the last `n` entries start
+    /// walks through all the ones before them.
+    pub fn pop_last_n(&mut self, n: usize) -> Vec<(K, V)> {
+        let n = std::cmp::min(n, self.len());
+        let before = self.find_updates_at(self.len() - n);
+        let last = self.find_updates_by(|_| true);
+
+        let (first, length) = unsafe { self.detach_between(&before, &last) };
+        DrainRange::new(first, length, self.pool_.clone()).collect()
+    }
+
+    /// Generates the tower height for a new node holding `key`.
+    pub(crate) fn generate_height(&mut self, key: &K) -> usize {
+        let context = HeightContext {
+            len: self.length_,
+            height: self.height_,
+        };
+        self.controller_.get_height_in(key, &context)
+    }
+
+    /// Appends a new node with the given tower height after every other node.
+    /// `key` must be greater than all the keys in the list. See
+    /// `link_at_tail`.
+    pub(crate) fn push_back_unchecked(
+        &mut self,
+        fingers: &mut Vec<NonNull<Node<K, V>>>,
+        key: K,
+        value: V,
+        height: usize,
+    ) {
+        let node = self.allocate_node(key, value, height);
+        self.report(|metrics| metrics.allocated(1));
+
+        // Counted before linking, so that the new length is reported.
+        self.length_ += 1;
+        unsafe {
+            self.link_at_tail(fingers, node);
+        }
+    }
 }
 
-impl<K, V> Drop for SkipListMap<K, V> {
+// The list uniquely owns all of its nodes, and the controller is required to
+// be `Send` by `HeightControl`.
+unsafe impl<K: Send, V: Send> Send for SkipListMap<K, V> {}
+
+// Dropping the list only drops keys and values, never looks at them, so they
+// may already be dangling. `marker_` makes sure their own destructors are
+// still taken into account.
+unsafe impl<#[may_dangle] K, #[may_dangle] V> Drop for SkipListMap<K, V> {
     fn drop(&mut self) {
         self.dispose();
     }
@@ -147,6 +599,10 @@ impl<K: std::fmt::Display, V: std::fmt::Display> std::fmt::Display for SkipListM
 impl<K: std::fmt::Debug, V: std::fmt::Debug> std::fmt::Debug for SkipListMap<K, V> {
     // TODO: rewrite
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if f.alternate() {
+            return write!(f, "{}", self.visualize());
+        }
+
         let mut printed = self.len();
 
         write!(f, "[").unwrap();
@@ -166,6 +622,80 @@ impl<K: std::fmt::Debug, V: std::fmt::Debug> std::fmt::Debug for SkipListMap<K,
     }
 }
 
+impl<K: std::fmt::Debug, V> SkipListMap<K, V> {
+    /// Renders the levels of the list as ASCII art, one row per level (highest
+    /// first) and one column per node, e.g.:
+    ///
+    /// ```text
+    /// 1 | head ------- 2 ------------ 5 --> nil
+    /// 0 | head -- 1 -- 2 -- 3 -- 4 -- 5 --> nil
+    /// ```
+    ///
+    /// Each node shows its key on every level it is linked at. This is meant
+    /// for debugging: it is the easiest way to see what heights the
+    /// `HeightControl` actually produced.
+    pub fn visualize(&self) -> String {
+        let mut columns = Vec::with_capacity(self.len());
+        let mut current = self.head().next(0);
+        while let Some(node) = current {
+            let key: &K = node.key();
+            columns.push((format!("{:?}", key), std::cmp::max(node.height(), 1)));
+            current = node.next(0);
+        }
+
+        let levels = std::cmp::max(self.height_, 1);
+        let label_width = (levels - 1).to_string().len();
+        let mut output = String::new();
+
+        for level in (0..levels).rev() {
+            output.push_str(&format!("{:>width$} | head ", level, width = label_width));
+
+            for &(ref key, linked_levels) in &columns {
+                if level < linked_levels {
+                    output.push_str(&format!("-- {} ", key));
+                } else {
+                    output.push_str(&"-".repeat(key.len() + 4));
+                }
+            }
+
+            output.push_str("--> nil\n");
+        }
+
+        output
+    }
+}
+
+/// Whether `insert` and `remove` check the invariants around the nodes they
+/// touch, so that corruption is caught where it happens rather than at a
+/// distant later read.
+const PARANOID: bool = cfg!(any(debug_assertions, feature = "paranoid"));
+
+/// Returns `true` if `key` comes before the range that starts at `bound`.
+fn before_start<K, T>(key: &K, bound: Bound<&T>) -> bool
+where
+    K: Borrow<T>,
+    T: Ord + ?Sized,
+{
+    match bound {
+        Bound::Included(start) => key.borrow() < start,
+        Bound::Excluded(start) => key.borrow() <= start,
+        Bound::Unbounded => false,
+    }
+}
+
+/// Returns `true` if `key` does not go past the range that ends at `bound`.
+fn within_end<K, T>(key: &K, bound: Bound<&T>) -> bool
+where
+    K: Borrow<T>,
+    T: Ord + ?Sized,
+{
+    match bound {
+        Bound::Included(end) => key.borrow() <= end,
+        Bound::Excluded(end) => key.borrow() < end,
+        Bound::Unbounded => true,
+    }
+}
+
 impl<K: Ord, V> SkipListMap<K, V> {
     /// Finds the node previous to the node that would have `key`, if any.
     pub(crate) fn find_lower_bound<Q>(&self, key: &Q) -> &Node<K, V>
@@ -173,19 +703,22 @@ impl<K: Ord, V> SkipListMap<K, V> {
         K: Borrow<Q>,
         Q: Ord + ?Sized,
     {
-        let mut current_ptr: *const Node<K, V> = self.head_;
+        let mut current = self.head();
+        let mut comparisons = 0;
 
         for height in (0..std::cmp::max(self.height_, 1)).rev() {
-            while let Some(next) = unsafe { (*current_ptr).next(height) } {
+            while let Some(next) = current.next(height) {
+                comparisons += 1;
                 if likely!(next.key() < key) {
-                    current_ptr = next;
+                    current = next;
                 } else {
                     break;
                 }
             }
         }
 
-        unsafe { &*current_ptr }
+        self.report(|metrics| metrics.comparisons(comparisons));
+        current
     }
 
     pub(crate) fn find_lower_bound_mut<Q>(&mut self, key: &Q) -> &mut Node<K, V>
@@ -193,11 +726,13 @@ impl<K: Ord, V> SkipListMap<K, V> {
         K: Borrow<Q>,
         Q: Ord + ?Sized,
     {
-        let mut current_ptr: *mut Node<K, V> = self.head_;
+        let mut current_ptr = self.head_;
+        let mut comparisons = 0;
 
         for height in (0..std::cmp::max(self.height_, 1)).rev() {
-            while let Some(next) = unsafe { (*current_ptr).next_mut(height) } {
-                if likely!(next.key() < key) {
+            while let Some(next) = unsafe { current_ptr.as_ref().link(height) } {
+                comparisons += 1;
+                if likely!(unsafe { next.as_ref().key() } < key) {
                     current_ptr = next;
                 } else {
                     break;
@@ -205,61 +740,114 @@ impl<K: Ord, V> SkipListMap<K, V> {
             }
         }
 
-        unsafe { &mut *current_ptr }
+        self.report(|metrics| metrics.comparisons(comparisons));
+        unsafe { &mut *current_ptr.as_ptr() }
+    }
+
+    /// Finds the node with key `key`, if any. Unlike `find_lower_bound`, the
+    /// search stops as soon as it meets the key, at whatever level, so hits
+    /// on tall nodes don't descend all the way to level 0.
+    fn find_node<Q>(&self, key: &Q) -> Link<K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut current = self.head();
+        let mut comparisons = 0;
+
+        for height in (0..std::cmp::max(self.height_, 1)).rev() {
+            while let Some(next) = current.link(height) {
+                comparisons += 1;
+                match unsafe { next.as_ref().key::<Q>() }.cmp(key) {
+                    std::cmp::Ordering::Less => current = unsafe { &*next.as_ptr() },
+                    std::cmp::Ordering::Equal => {
+                        self.report(|metrics| metrics.comparisons(comparisons));
+                        return Some(next);
+                    }
+                    std::cmp::Ordering::Greater => break,
+                }
+            }
+        }
+
+        self.report(|metrics| metrics.comparisons(comparisons));
+        None
     }
 
     /// Finds the node previous to the node that would have `key`, if any. It
     /// also generates an `updates` vector; the vector contains for index i, the
     /// last previous node that had height greater or equal than i.
-    fn find_lower_bound_with_updates<Q>(
-        &mut self,
-        key: &Q,
-    ) -> (&mut Node<K, V>, Vec<&mut Node<K, V>>)
+    ///
+    /// Nodes are returned as raw pointers because the `updates` usually point
+    /// to the same nodes many times over, so handing out references would
+    /// alias.
+    pub(crate) fn find_lower_bound_with_updates<Q>(&mut self, key: &Q) -> LowerBound<K, V>
     where
         K: Borrow<Q>,
         Q: Ord + ?Sized,
     {
-        let max_height = self.max_height();
-        let mut updates = Vec::with_capacity(max_height);
+        // Levels above the current height are only linked from the head.
+        let mut updates = vec![self.head_; self.max_height()];
 
-        // Initialization for the `updates` vector starts from the back and
-        // moves into the front. We set the length of the uninitialized
-        // vector to the actual value we are going to use, so that we can do
-        // this initialization efficiently
-        unsafe {
-            updates.set_len(max_height);
-
-            for update in updates.iter_mut().take(max_height).skip(self.height_) {
-                *update = &mut *self.head_;
+        let mut current_ptr = self.head_;
+        let mut comparisons = 0;
+        for height in (0..std::cmp::max(self.height_, 1)).rev() {
+            while let Some(next) = unsafe { current_ptr.as_ref().link(height) } {
+                comparisons += 1;
+                if likely!(unsafe { next.as_ref().key() } < key) {
+                    current_ptr = next;
+                } else {
+                    break;
+                }
             }
 
-            let mut current_ptr = self.head_;
-            for height in (0..std::cmp::max(self.height_, 1)).rev() {
-                while let Some(next) = (*current_ptr).next_mut(height) {
-                    if likely!(next.key() < key) {
-                        current_ptr = next;
-                    } else {
-                        break;
-                    }
-                }
+            updates[height] = current_ptr;
+        }
 
-                updates[height] = &mut *current_ptr;
-            }
+        self.report(|metrics| metrics.comparisons(comparisons));
 
-            (&mut *current_ptr, updates)
+        (current_ptr, updates)
+    }
+
+    /// Panics if the link that leaves `node` at level `height` is broken: the
+    /// next node must have a greater key, and be tall enough to be linked at
+    /// that level.
+    fn check_link(&self, node: &Node<K, V>, height: usize) {
+        assert!(height < self.max_height(), "link above the maximum height");
+
+        if let Some(next) = node.next(height) {
+            assert!(
+                height < std::cmp::max(next.height(), 1),
+                "node linked above its tower"
+            );
+            assert!(
+                std::ptr::eq(node, self.head()) || node.key::<K>() < next.key::<K>(),
+                "nodes out of order"
+            );
         }
     }
 
     // Insert `key`. Returns false if `key` was already found.
+    //
+    // The height is only generated once the key is known to be missing, right
+    // before allocating its node, so replacing a value costs no entropy.
     pub fn insert(&mut self, key: K, value: V) -> Option<V> {
-        // TODO: initialize this later. This may not ever get used if the key
-        // already exists. Should be done right before allocating the node.
-        let height = self.controller_.get_height(&key);
+        self.report(|metrics| metrics.operation(Operation::Insert));
+
+        if let Some(mut fingers) = self.take_tail() {
+            if unsafe { self.is_after(&fingers, &key) } {
+                let height = self.generate_height(&key);
+                self.push_back_unchecked(&mut fingers, key, value, height);
+                self.cache_tail(fingers);
+                return None;
+            }
 
-        {
-            let (lower_bound, mut updates) = self.find_lower_bound_with_updates(&key);
+            self.cache_tail(fingers);
+        }
 
-            if let Some(next) = lower_bound.next_mut(0) {
+        let (lower_bound, updates) = self.find_lower_bound_with_updates(&key);
+
+        unsafe {
+            if let Some(next) = (*lower_bound.as_ptr()).next_mut(0) {
                 // The lower bound's next node, if present, could be the same
                 // as the key we are looking for, so we could abort early here
                 if unlikely!(next.key() == &key) {
@@ -267,22 +855,225 @@ impl<K: Ord, V> SkipListMap<K, V> {
                 }
             }
 
-            let node = Self::allocate_node(key, value, height);
-            for (height, update) in updates.iter_mut().enumerate().take(
-                std::cmp::max(height, 1),
-            )
-            {
-                unsafe {
-                    (*node).link_to_next(height, update);
-                }
+            let height = self.generate_height(&key);
+            self.link_new_node(&updates, key, value, height);
+        }
+
+        None
+    }
+
+    /// Allocates a node for `key` and `value`, and links it after `updates`,
+    /// which must have been found by `find_lower_bound_with_updates` for a key
+    /// that is not in the list. Returns the new node.
+    pub(crate) unsafe fn link_new_node(
+        &mut self,
+        updates: &[NonNull<Node<K, V>>],
+        key: K,
+        value: V,
+        height: usize,
+    ) -> NonNull<Node<K, V>> {
+        let node = self.allocate_node(key, value, height);
+        self.report(|metrics| metrics.allocated(1));
+        self.link_node(updates, node);
+        node
+    }
 
-                (*update).link_to(height, node);
+    /// Links `node` after `updates`, as `link_new_node` does. Its tower must
+    /// not be taller than the maximum height.
+    pub(crate) unsafe fn link_node(
+        &mut self,
+        updates: &[NonNull<Node<K, V>>],
+        node: NonNull<Node<K, V>>,
+    ) {
+        let height = node.as_ref().height();
+        debug_assert!(height <= self.max_height());
+        for (height, update) in updates.iter().enumerate().take(std::cmp::max(height, 1)) {
+            (*node.as_ptr()).link_to_next(height, update.as_ref());
+            (*update.as_ptr()).link_to(height, Some(node));
+        }
+
+        if PARANOID {
+            for (height, update) in updates.iter().enumerate().take(std::cmp::max(height, 1)) {
+                self.check_link(update.as_ref(), height);
+                self.check_link(node.as_ref(), height);
             }
         }
 
         self.height_ = std::cmp::max(self.height_, height);
         self.length_ += 1;
-        None
+        self.bump_generation();
+
+        // A node appended at the end leaves the updates pointing to the
+        // last node at every level, so later appends can skip the search. The
+        // buffer of the previous tail is reused.
+        if node.as_ref().link(0).is_none() {
+            let mut tail = std::mem::take(&mut self.tail_);
+            tail.clear();
+            tail.extend_from_slice(updates);
+            for update in tail.iter_mut().take(std::cmp::max(height, 1)) {
+                *update = node;
+            }
+            self.cache_tail(tail);
+        }
+    }
+
+    /// Links a node unlinked from this or another list after `updates`,
+    /// dropping the links it kept. If its tower is too tall for this list, it
+    /// gets a new height from the controller.
+    pub(crate) unsafe fn relink_node(
+        &mut self,
+        updates: &[NonNull<Node<K, V>>],
+        node: NonNull<Node<K, V>>,
+    ) {
+        let height = node.as_ref().height();
+        if height <= self.max_height() {
+            for level in 0..=height {
+                (*node.as_ptr()).link_to(level, None);
+            }
+        } else {
+            let height = self.generate_height(node.as_ref().key());
+            (*node.as_ptr()).reset_tower(height);
+        }
+
+        self.link_node(updates, node);
+    }
+
+    /// Appends `value` under `key`, which must be greater than every key in
+    /// the list. Otherwise, the entry is handed back untouched.
+    ///
+    /// # Remarks
+    ///
+    /// The last node at every level is cached between appends, so a sequence
+    /// of appends, e.g. of increasing timestamps, takes O(1) amortized time
+    /// each. Any other change to the structure drops the cache, and the next
+    /// append takes O(log n) to find the tail again. `insert` takes the same
+    /// fast path whenever the cache is valid and `key` goes at the end.
+    pub fn push_back(&mut self, key: K, value: V) -> Result<(), (K, V)> {
+        let mut fingers = match self.take_tail() {
+            Some(fingers) => fingers,
+            None => self.find_updates_by(|_| true),
+        };
+
+        if !unsafe { self.is_after(&fingers, &key) } {
+            self.cache_tail(fingers);
+            return Err((key, value));
+        }
+
+        let height = self.generate_height(&key);
+        self.push_back_unchecked(&mut fingers, key, value, height);
+        self.cache_tail(fingers);
+        Ok(())
+    }
+
+    /// Inserts `value` under `key` without checking whether `key` is already
+    /// in the list, and returns references to the inserted key and value. This
+    /// is meant for bulk loads of data that is known to be unique, but not
+    /// sorted.
+    ///
+    /// The search never compares `key` for equality, and never compares it
+    /// twice against the same node: the node that ends the walk on a level is
+    /// not smaller than `key`, so the walk ends there on every level below it
+    /// too, without comparing. That saves a comparison on most levels.
+    ///
+    /// # Safety
+    ///
+    /// `key` must not be in the list. In debug builds, and with the
+    /// `paranoid` feature, the links around the new node are checked, and a
+    /// duplicate key panics as nodes out of order. Otherwise, it ends up in
+    /// the list twice, and lookups and removals of it may find either of the
+    /// two.
+    pub unsafe fn insert_unique_unchecked(&mut self, key: K, value: V) -> (&K, &mut V) {
+        self.report(|metrics| metrics.operation(Operation::Insert));
+        let updates = self.find_unique_updates(&key);
+        let height = self.generate_height(&key);
+        let node = self.link_new_node(&updates, key, value, height);
+        Node::key_value_mut_ptr(node)
+    }
+
+    /// Same as the `updates` of `find_lower_bound_with_updates`, but skips
+    /// the comparisons against the node that ended the walk on the level
+    /// above. See `insert_unique_unchecked`.
+    fn find_unique_updates(&self, key: &K) -> Vec<NonNull<Node<K, V>>> {
+        let mut updates = vec![self.head_; self.max_height()];
+
+        let mut current_ptr = self.head_;
+        // Node known not to be smaller than `key`, if any.
+        let mut bound: Option<NonNull<Node<K, V>>> = None;
+        let mut comparisons = 0;
+        for height in (0..std::cmp::max(self.height_, 1)).rev() {
+            while let Some(next) = unsafe { current_ptr.as_ref().link(height) } {
+                if bound == Some(next) {
+                    break;
+                }
+
+                comparisons += 1;
+                if likely!(unsafe { next.as_ref().key::<K>() } < key) {
+                    current_ptr = next;
+                } else {
+                    bound = Some(next);
+                    break;
+                }
+            }
+
+            updates[height] = current_ptr;
+        }
+
+        self.report(|metrics| metrics.comparisons(comparisons));
+        updates
+    }
+
+    /// Appends `value` under `key` without comparing it against the last key,
+    /// as `push_back` does. This is meant for bulk loads of data that is
+    /// known to be sorted and unique, e.g. read from a sorted file.
+    ///
+    /// # Safety
+    ///
+    /// `key` must be greater than every key in the list. Otherwise, the list
+    /// ends up out of order, and searches through it give wrong results.
+    pub unsafe fn push_back_unique_unchecked(&mut self, key: K, value: V) {
+        let mut fingers = match self.take_tail() {
+            Some(fingers) => fingers,
+            None => self.find_updates_by(|_| true),
+        };
+
+        let height = self.generate_height(&key);
+        self.push_back_unchecked(&mut fingers, key, value, height);
+        self.cache_tail(fingers);
+    }
+
+    /// Moves `fingers`, the last node before some key at every level, as by
+    /// `find_updates_by`, to the last node before `key`, which must not be
+    /// smaller than that key. Every level is walked from its finger, or from
+    /// the node found one level above, whichever is further, so a sequence of
+    /// increasing keys is found in a single walk through the list.
+    pub(crate) fn advance_fingers(&self, fingers: &mut [NonNull<Node<K, V>>], key: &K) {
+        let mut current = self.head_;
+        for height in (0..std::cmp::max(self.height_, 1)).rev() {
+            unsafe {
+                let finger = fingers[height];
+                if current == self.head_ ||
+                    (finger != self.head_ && current.as_ref().key::<K>() < finger.as_ref().key())
+                {
+                    current = finger;
+                }
+
+                while let Some(next) = current.as_ref().link(height) {
+                    if next.as_ref().key::<K>() < key {
+                        current = next;
+                    } else {
+                        break;
+                    }
+                }
+            }
+
+            fingers[height] = current;
+        }
+    }
+
+    /// Returns `true` if `key` is greater than the last key, as given by the
+    /// `fingers` to the tail.
+    unsafe fn is_after(&self, fingers: &[NonNull<Node<K, V>>], key: &K) -> bool {
+        fingers[0] == self.head_ || fingers[0].as_ref().key::<K>() < key
     }
 
     /// Returns a const reference to the element with key `key`, if it exists.
@@ -291,14 +1082,19 @@ impl<K: Ord, V> SkipListMap<K, V> {
         K: Borrow<Q>,
         Q: Ord + ?Sized,
     {
-        let lower_bound = self.find_lower_bound(key);
-        lower_bound.next(0).and_then(
-            |node| if likely!(node.key() == key) {
-                Some(node.value())
-            } else {
-                None
-            },
-        )
+        self.report(|metrics| metrics.operation(Operation::Get));
+        self.find_node(key).map(|node| unsafe { &*node.as_ptr() }.value())
+    }
+
+    /// Returns the stored key and its value, if `key` exists. The stored key
+    /// may differ from `key`, e.g. when keys are shared through `Arc`.
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.report(|metrics| metrics.operation(Operation::Get));
+        self.find_node(key).map(|node| unsafe { &*node.as_ptr() }.key_value::<K, V>())
     }
 
     /// Returns a mutable reference to the element with key `key`, if it exists.
@@ -307,15 +1103,32 @@ impl<K: Ord, V> SkipListMap<K, V> {
         K: Borrow<Q>,
         Q: Ord + ?Sized,
     {
-        let lower_bound = self.find_lower_bound_mut(key);
-        lower_bound.next_mut(0).and_then(|node| if likely!(
-            node.key() == key
-        )
-        {
-            Some(node.value_mut())
-        } else {
-            None
-        })
+        self.report(|metrics| metrics.operation(Operation::Get));
+        self.find_node(key).map(|node| unsafe { Node::key_value_mut_ptr(node).1 })
+    }
+
+    /// Returns a pointer to the value of `key`, if it exists, for callers that
+    /// hold on to it beyond a borrow of the map, e.g. across FFI or from
+    /// within the value itself.
+    ///
+    /// # Remarks
+    ///
+    /// The pointer stays valid until the entry is removed or the map is
+    /// dropped, since values don't move while in the map. Replacing the value
+    /// through `insert` writes the new one in its place. Reads and writes
+    /// through the pointer must not overlap with references to the value
+    /// handed out by the map, e.g. through `get` or `iter`, same as with any
+    /// other aliased pointer.
+    ///
+    /// There are no `Pin` accessors: `remove` moves the value out instead of
+    /// dropping it in place, which pinning would forbid.
+    pub fn get_raw<Q>(&self, key: &Q) -> Option<NonNull<V>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.report(|metrics| metrics.operation(Operation::Get));
+        self.find_node(key).map(|node| unsafe { Node::value_ptr(node) })
     }
 
     /// Returns true if `key` is in the list.
@@ -334,62 +1147,385 @@ impl<K: Ord, V> SkipListMap<K, V> {
         K: Borrow<Q>,
         Q: Ord + ?Sized,
     {
-        let old_value;
-
-        {
-            let (lower_bound, mut updates) = self.find_lower_bound_with_updates(key);
-
-            match lower_bound.next_mut(0) {
-                // `lower_bound` is the lower bound to the node, so if it doesn't have a
-                // next node at level 0, it means that 'key' is not present. If it
-                // does exist, then there is a possibility that it may be greater
-                // than the actual key we are looking for
-                None => return None,
-                Some(removal) => {
-                    // If the key is not the one that we are looking for, then that
-                    // means we are done
-                    if unlikely!(removal.key() != key) {
-                        return None;
-                    }
+        self.report(|metrics| metrics.operation(Operation::Remove));
+        let removal = self.unlink(key)?;
+        let (_, old_value) = self.recycle_node(removal);
+        Some(old_value)
+    }
 
-                    for (height, update) in updates.iter_mut().enumerate().take(std::cmp::max(
-                        removal.height(),
-                        1,
-                    ))
-                    {
-                        (*update).link_to_next(height, removal);
-                    }
+    /// Unlinks the node with key `key` from every level, and returns it. The
+    /// node is still allocated, and its tower keeps its stale links.
+    pub(crate) fn unlink<Q>(&mut self, key: &Q) -> Link<K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (lower_bound, updates) = self.find_lower_bound_with_updates(key);
+
+        // `lower_bound` is the lower bound to the node, so if it doesn't have a
+        // next node at level 0, it means that 'key' is not present. If it
+        // does exist, then there is a possibility that it may be greater
+        // than the actual key we are looking for
+        let removal = unsafe { lower_bound.as_ref().link(0) }?;
+        if unlikely!(unsafe { removal.as_ref().key() } != key) {
+            return None;
+        }
 
-                    old_value = removal.replace_value(unsafe { std::mem::uninitialized() });
-                    Self::free_node(removal);
-                }
+        unsafe {
+            self.unlink_node(&updates, removal);
+        }
+        Some(removal)
+    }
+
+    /// Unlinks `node` from every level, given the last node before it at
+    /// every level, as found by `find_updates_by`. The node is still
+    /// allocated, and its tower keeps its stale links.
+    pub(crate) unsafe fn unlink_node(
+        &mut self,
+        updates: &[NonNull<Node<K, V>>],
+        node: NonNull<Node<K, V>>,
+    ) {
+        let levels = std::cmp::max(node.as_ref().height(), 1);
+        for (height, update) in updates.iter().enumerate().take(levels) {
+            (*update.as_ptr()).link_to_next(height, node.as_ref());
+        }
+
+        if PARANOID {
+            for (height, update) in updates.iter().enumerate().take(levels) {
+                self.check_link(update.as_ref(), height);
             }
         }
 
         self.length_ -= 1;
-        Some(old_value)
+        self.bump_generation();
+    }
+
+    /// Raises or lowers the tower of the node with key `key` to `height`, in
+    /// place, and returns its previous height, or `None` if `key` is not in
+    /// the list. Only the levels the node joins or leaves are relinked, so
+    /// this takes a single search, e.g. to promote hot keys, or to level out
+    /// a list after a bulk load, without removing and reinserting entries.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `height` is above the maximum height of the list.
+    pub fn set_height<Q>(&mut self, key: &Q, height: usize) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        assert!(height <= self.max_height(), "height above the maximum height");
+
+        let (lower_bound, updates) = self.find_lower_bound_with_updates(key);
+        let node = unsafe { lower_bound.as_ref().link(0) }?;
+        if unsafe { node.as_ref().key() } != key {
+            return None;
+        }
+
+        unsafe {
+            let previous = node.as_ref().height();
+            let levels = std::cmp::max(previous, 1);
+            let new_levels = std::cmp::max(height, 1);
+
+            // Levels the node leaves are unlinked before the tower shrinks,
+            // and the tower grows before the node joins new levels.
+            for (level, update) in updates.iter().enumerate().take(levels).skip(new_levels) {
+                (*update.as_ptr()).link_to_next(level, node.as_ref());
+            }
+            (*node.as_ptr()).resize_tower(height);
+            for (level, update) in updates.iter().enumerate().take(new_levels).skip(levels) {
+                (*node.as_ptr()).link_to_next(level, update.as_ref());
+                (*update.as_ptr()).link_to(level, Some(node));
+            }
+
+            if PARANOID {
+                for (level, update) in updates.iter().enumerate().take(new_levels) {
+                    self.check_link(update.as_ref(), level);
+                    self.check_link(node.as_ref(), level);
+                }
+            }
+
+            self.height_ = std::cmp::max(self.height_, height);
+            self.bump_generation();
+            Some(previous)
+        }
     }
 
     pub fn first(&self) -> Option<(&K, &V)> {
-        unsafe { (*self.head_).next(0).map(|node| node.key_value()) }
+        self.head().next(0).map(|node| node.key_value())
     }
 
     pub fn first_mut(&mut self) -> Option<(&K, &mut V)> {
-        unsafe { (*self.head_).next_mut(0).map(|node| node.key_value_mut()) }
+        unsafe { (*self.head_.as_ptr()).next_mut(0) }.map(|node| node.key_value_mut())
+    }
+
+    /// Removes the entry with the largest key, and returns it.
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        let last = self.find_updates_by(|_| true);
+        if std::ptr::eq(last[0].as_ptr(), self.head_.as_ptr()) {
+            return None;
+        }
+
+        let key = unsafe { last[0].as_ref().key::<K>() };
+        let before = self.find_updates_by(|other| other < key);
+        let (first, length) = unsafe { self.detach_between(&before, &last) };
+        DrainRange::new(first, length, self.pool_.clone()).next()
     }
 
-    // TODO: The following are easier to implement with Drain
-    pub fn split_off<Q>(&mut self, _key: &Q) -> SkipListMap<K, V>
+    /// Splits the list in two at `key`. Returns everything after the given
+    /// key, including the key; `self` keeps everything before it.
+    ///
+    /// # Remarks
+    ///
+    /// The links are cut in O(log n), but counting the elements that are
+    /// moved into the returned list is linear on their number.
+    pub fn split_off<Q>(&mut self, key: &Q) -> SkipListMap<K, V>
     where
         K: Borrow<Q>,
         Q: Ord + ?Sized,
     {
+        let (_, updates) = self.find_lower_bound_with_updates(key);
+        let mut other = self.cut_after(&updates);
+
+        let mut moved = 0;
+        let mut current = other.head().next(0);
+        while let Some(node) = current {
+            moved += 1;
+            current = node.next(0);
+        }
+
+        other.length_ = moved;
+
+        self.length_ -= other.length_;
+        self.bump_generation();
+        other
+    }
+
+    /// Splits the list in two after its first `n` entries. Returns everything
+    /// after them; `self` keeps the first `n`, or all of its entries if there
+    /// are fewer, e.g. to paginate or partition a list by count.
+    ///
+    /// # Remarks
+    ///
+    /// Nodes don't keep their positions, so finding the boundary walks through
+    /// the first `n` entries. The links are then cut in O(log n).
+    pub fn split_at_index(&mut self, n: usize) -> SkipListMap<K, V> {
+        let n = std::cmp::min(n, self.len());
+        let updates = self.find_updates_at(n);
+        let mut other = self.cut_after(&updates);
+
+        other.length_ = self.length_ - n;
+        self.length_ = n;
+        self.bump_generation();
+        other
+    }
+
+    /// Builds an empty list with a copy of the controller, but neither the
+    /// pool nor the metrics.
+    pub(crate) fn new_like(&self) -> SkipListMap<K, V> {
+        SkipListMap::new(self.controller_.clone())
+    }
 
-        unimplemented!()
+    /// Builds an empty list with a copy of the controller, sharing the pool,
+    /// and as tall as `self`, to move nodes of `self` into.
+    fn empty_sharing(&self) -> SkipListMap<K, V> {
+        SkipListMap {
+            head_: Self::allocate_dummy_node(self.max_height()),
+            length_: 0,
+            height_: self.height_,
+            max_height_: self.max_height_,
+            controller_: self.controller_.clone(),
+            generation_: 0,
+            metrics_: None,
+            pool_: self.pool_.clone(),
+            tail_: Vec::new(),
+            tail_generation_: None,
+            marker_: std::marker::PhantomData,
+        }
     }
 
-    pub fn append(&mut self, _other: &mut SkipListMap<K, V>) {
-        unimplemented!()
+    /// Moves every node after `updates`, given per level as by
+    /// `find_updates_by`, into a new list that shares the controller and the
+    /// pool. The lengths of both lists are left to the caller.
+    fn cut_after(&mut self, updates: &[NonNull<Node<K, V>>]) -> SkipListMap<K, V> {
+        let other = self.empty_sharing();
+        unsafe {
+            for (height, update) in updates.iter().enumerate().take(std::cmp::max(self.height_, 1)) {
+                (*other.head_.as_ptr()).link_to(height, update.as_ref().link(height));
+                (*update.as_ptr()).link_to(height, None);
+            }
+        }
+
+        other
+    }
+
+    /// Moves all elements from `other` into `self`, leaving `other` empty. If
+    /// a key is present in both, the value from `other` is kept.
+    pub fn append(&mut self, other: &mut SkipListMap<K, V>) {
+        // Elements are moved one at a time, so both lists stay valid even if a
+        // comparison panics.
+        while let Some((key, value)) = other.pop_first() {
+            self.insert(key, value);
+        }
+    }
+
+    /// Unlinks every entry within `range`, and returns an iterator that moves
+    /// them out in key order. Entries the iterator doesn't reach are dropped
+    /// along with it.
+    ///
+    /// # Remarks
+    ///
+    /// The range is cut out of every level at once, in O(log n), but the
+    /// entries are then counted one by one.
+    pub fn drain_range<T, R>(&mut self, range: R) -> DrainRange<'_, K, V>
+    where
+        K: Borrow<T>,
+        R: RangeBounds<T>,
+        T: Ord + ?Sized,
+    {
+        let before = self.find_updates_by(|key| before_start(key, range.start_bound()));
+
+        // When the first node from the start is within the end, every node
+        // before the start is too, so the end is never behind the start.
+        let first = unsafe { before[0].as_ref().next(0) };
+        let last = match first {
+            Some(first) if within_end(first.key::<K>(), range.end_bound()) => {
+                self.find_updates_by(|key| within_end(key, range.end_bound()))
+            }
+            _ => before.clone(),
+        };
+
+        let (first, length) = unsafe { self.detach_between(&before, &last) };
+        DrainRange::new(first, length, self.pool_.clone())
+    }
+
+    /// Moves every entry within `range` into a new list, which shares the
+    /// controller and the pool of `self`, e.g. to hand a span of keys over to
+    /// another owner.
+    ///
+    /// # Remarks
+    ///
+    /// Nodes are moved as they are, towers included: the range is cut out of
+    /// every level, and spliced into the new list, in O(log n). Counting the
+    /// entries that moved is linear on their number.
+    pub fn extract_range<T, R>(&mut self, range: R) -> SkipListMap<K, V>
+    where
+        K: Borrow<T>,
+        R: RangeBounds<T>,
+        T: Ord + ?Sized,
+    {
+        let before = self.find_updates_by(|key| before_start(key, range.start_bound()));
+        let first = unsafe { before[0].as_ref().next(0) };
+        let last = match first {
+            Some(first) if within_end(first.key::<K>(), range.end_bound()) => {
+                self.find_updates_by(|key| within_end(key, range.end_bound()))
+            }
+            _ => return self.empty_sharing(),
+        };
+
+        let mut other = self.empty_sharing();
+        unsafe {
+            for height in 0..std::cmp::max(self.height_, 1) {
+                // No node within the range reaches this level.
+                if before[height] == last[height] {
+                    continue;
+                }
+
+                (*other.head_.as_ptr()).link_to(height, before[height].as_ref().link(height));
+                (*before[height].as_ptr()).link_to(height, last[height].as_ref().link(height));
+                (*last[height].as_ptr()).link_to(height, None);
+            }
+        }
+
+        let mut moved = 0;
+        let mut current = other.head().next(0);
+        while let Some(node) = current {
+            moved += 1;
+            current = node.next(0);
+        }
+
+        other.length_ = moved;
+        self.length_ -= moved;
+        self.bump_generation();
+        other
+    }
+
+    /// Returns `true` if no key falls within `range`. This takes a single
+    /// descent to the start of the range, and one more comparison against
+    /// its end, e.g. to check a reservation for conflicts.
+    pub fn is_range_empty<T, R>(&self, range: R) -> bool
+    where
+        K: Borrow<T>,
+        R: RangeBounds<T>,
+        T: Ord + ?Sized,
+    {
+        let mut current = self.head();
+        for height in (0..std::cmp::max(self.height_, 1)).rev() {
+            while let Some(next) = current.next(height) {
+                if before_start(next.key::<K>(), range.start_bound()) {
+                    current = next;
+                } else {
+                    break;
+                }
+            }
+        }
+
+        match current.next(0) {
+            Some(first) => !within_end(first.key::<K>(), range.end_bound()),
+            None => true,
+        }
+    }
+
+    /// Keeps only the entries within `range` for which `keep` returns `true`,
+    /// and every entry outside of it. Entries are visited once, in key order.
+    pub fn retain_range<T, R, F>(&mut self, range: R, mut keep: F)
+    where
+        K: Borrow<T>,
+        R: RangeBounds<T>,
+        T: Ord + ?Sized,
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        // The last kept node at every level, which removed nodes are
+        // bypassed from.
+        let mut fingers = self.find_updates_by(|key| before_start(key, range.start_bound()));
+
+        let mut current = unsafe { fingers[0].as_ref().link(0) };
+        while let Some(node) = current {
+            unsafe {
+                if !within_end(node.as_ref().key::<K>(), range.end_bound()) {
+                    break;
+                }
+
+                current = node.as_ref().link(0);
+                let levels = std::cmp::max(node.as_ref().height(), 1);
+                let (key, value) = Node::key_value_mut_ptr(node);
+                if keep(key, value) {
+                    for finger in fingers.iter_mut().take(levels) {
+                        *finger = node;
+                    }
+
+                    continue;
+                }
+
+                for (height, finger) in fingers.iter().enumerate().take(levels) {
+                    (*finger.as_ptr()).link_to_next(height, node.as_ref());
+                }
+            }
+
+            self.length_ -= 1;
+            self.bump_generation();
+            drop(self.recycle_node(node));
+        }
+    }
+
+    /// Keeps only the entries for which `keep` returns `true`. `keep` may
+    /// update the values it is given, e.g. to decay scores and drop the ones
+    /// that reach zero, all in a single pass in key order.
+    pub fn retain_mut<F>(&mut self, keep: F)
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        self.retain_range::<K, _, _>(.., keep)
     }
 }
 
@@ -417,13 +1553,75 @@ where
 
 impl<K: Ord + Clone, V: Clone> Clone for SkipListMap<K, V> {
     fn clone(&self) -> Self {
-        let mut copied: SkipListMap<K, V> = SkipListMap::new(self.controller_.clone());
-        for element in self.iter() {
-            copied.insert(element.0.clone(), element.1.clone());
+        let mut copied = self.new_like();
+        copied.pool_ = self.pool_.clone();
+        let mut fingers = copied.empty_fingers();
+
+        // Entries are visited in order, so they can go straight to the tail.
+        for (key, value) in self.iter() {
+            let height = copied.generate_height(key);
+            copied.push_back_unchecked(&mut fingers, key.clone(), value.clone(), height);
         }
 
         copied
     }
+
+    /// Overwrites the list with a copy of `source`, reusing its nodes: keys
+    /// and values are overwritten through their own `clone_from`, so e.g.
+    /// `String`s keep their buffers, and only the nodes missing are allocated,
+    /// or the extra ones released. Metrics hooks are kept, as they belong to
+    /// the list rather than to its contents.
+    fn clone_from(&mut self, source: &Self) {
+        // Nodes waiting to be reused, which are released along with the guard,
+        // to the pool they came from.
+        struct Spare<K, V> {
+            first_: Link<K, V>,
+            pool_: Option<NodePool<K, V>>,
+        }
+
+        impl<K, V> Drop for Spare<K, V> {
+            fn drop(&mut self) {
+                match self.pool_ {
+                    Some(ref pool) => pool.recycle_chain(self.first_.take()),
+                    None => SkipListMap::free_chain(self.first_.take()),
+                }
+            }
+        }
+
+        // The list is emptied first, so that it stays valid even if a clone
+        // panics.
+        let mut spare = Spare {
+            first_: self.head().link(0),
+            pool_: self.pool_.clone(),
+        };
+        unsafe {
+            (*self.head_.as_ptr()).reset_tower(source.max_height());
+        }
+        self.length_ = 0;
+        self.height_ = 0;
+        self.max_height_ = source.max_height_;
+        self.controller_ = source.controller_.clone();
+        self.pool_ = source.pool_.clone();
+        self.bump_generation();
+
+        let mut fingers = self.empty_fingers();
+        for (key, value) in source.iter() {
+            let height = self.generate_height(key);
+            match spare.first_ {
+                Some(node) => unsafe {
+                    let (reused_key, reused_value) = (*node.as_ptr()).key_value_in_place();
+                    reused_key.clone_from(key);
+                    reused_value.clone_from(value);
+
+                    spare.first_ = node.as_ref().link(0);
+                    (*node.as_ptr()).reset_tower(height);
+                    self.length_ += 1;
+                    self.link_at_tail(&mut fingers, node);
+                },
+                None => self.push_back_unchecked(&mut fingers, key.clone(), value.clone(), height),
+            }
+        }
+    }
 }
 
 // TODO: prefetch, benchmarks
@@ -432,27 +1630,30 @@ mod tests {
     extern crate rand;
 
     use super::*;
-    use quickcheck::{Arbitrary, quickcheck, TestResult, Gen};
-    use height_control::GeometricalGenerator;
-
-    // TODO: when moving into multithreaded support, ensure we protect accordingly.
-    unsafe impl<K, V> Send for SkipListMap<K, V> {}
+    use quickcheck::{quickcheck, TestResult};
 
-    impl<K: Ord + Arbitrary, V: Arbitrary> Arbitrary for SkipListMap<K, V> {
-        fn arbitrary<G: Gen>(gen: &mut G) -> SkipListMap<K, V> {
-            let upgrade_probability = gen.gen_range(0.0, 1.0);
-            let max_height = gen.gen_range(1, 30);
-
-            let controller = Box::new(GeometricalGenerator::new(max_height, upgrade_probability));
-            let mut list = SkipListMap::new(controller);
+    #[test]
+    #[should_panic(expected = "nodes out of order")]
+    fn check_link_detects_disorder() {
+        let list: SkipListMap<i32, i32> = Default::default();
+        let mut next = Node::new(1, 1, 0);
+        let mut node = Node::new(2, 2, 0);
+        node.link_to(0, Some(NonNull::from(&mut next)));
+        list.check_link(&node, 0);
+    }
 
-            let length: usize = Arbitrary::arbitrary(gen);
-            for _i in 0..length {
-                list.insert(Arbitrary::arbitrary(gen), Arbitrary::arbitrary(gen));
-            }
+    #[test]
+    fn tall_appends_keep_the_maximum_height() {
+        use height_control::FnHeightControl;
 
-            list
+        let controller = FnHeightControl::new(4, |_: &i32| 4);
+        let mut list: SkipListMap<i32, i32> = SkipListMap::new(Box::new(controller));
+        for i in 0..10 {
+            assert_eq!(list.push_back(i, i), Ok(()));
         }
+
+        assert_eq!(list.max_height(), 4);
+        assert_eq!(list.head().height(), 4);
     }
 
     #[test]
@@ -495,5 +1696,4 @@ mod tests {
     //
     //        quickcheck(prop as fn(SkipList<i32, i32>) -> TestResult);
     //    }
-    // TODO: memory leak tests
 }
@@ -0,0 +1,239 @@
+use map::SkipListMap;
+use node::{Link, Node};
+use height_control::HeightControl;
+use iter::{Iter, Range};
+
+use std;
+use std::borrow::Borrow;
+use std::ops::RangeBounds;
+
+/// Types whose memory footprint can be estimated by a `MemTable`.
+pub trait ResidentSize {
+    /// Returns the bytes owned outside of the value itself, e.g. the buffer of
+    /// a `String`. The value's own `size_of` is accounted separately.
+    fn heap_bytes(&self) -> usize {
+        0
+    }
+}
+
+macro_rules! inline_resident_size {
+    ($($inline:ty),*) => {
+        $(impl ResidentSize for $inline {})*
+    };
+}
+
+inline_resident_size!(
+    u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, bool, char, f32, f64
+);
+
+impl ResidentSize for String {
+    fn heap_bytes(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl<T: ResidentSize> ResidentSize for Vec<T> {
+    fn heap_bytes(&self) -> usize {
+        self.capacity() * std::mem::size_of::<T>() + self.iter().map(T::heap_bytes).sum::<usize>()
+    }
+}
+
+impl<T: ResidentSize> ResidentSize for Box<T> {
+    fn heap_bytes(&self) -> usize {
+        std::mem::size_of::<T>() + (**self).heap_bytes()
+    }
+}
+
+impl<T: ResidentSize> ResidentSize for Option<T> {
+    fn heap_bytes(&self) -> usize {
+        self.as_ref().map_or(0, T::heap_bytes)
+    }
+}
+
+impl<A: ResidentSize, B: ResidentSize> ResidentSize for (A, B) {
+    fn heap_bytes(&self) -> usize {
+        self.0.heap_bytes() + self.1.heap_bytes()
+    }
+}
+
+/// Error returned when writing to a frozen `MemTable`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrozenError;
+
+impl std::fmt::Display for FrozenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("the memtable is frozen")
+    }
+}
+
+impl std::error::Error for FrozenError {}
+
+/// `SkipListMap` that follows the lifecycle of the memtable of an LSM tree:
+/// writes are accepted until the approximate memory it takes goes over a
+/// budget, then it is frozen, and only read until it is flushed.
+///
+/// The memory taken is estimated as the size of every node, plus the bytes the
+/// keys and values own on the heap. Towers are accounted with their expected
+/// height, rather than their actual one.
+pub struct MemTable<K, V> {
+    map_: SkipListMap<K, V>,
+    budget_: usize,
+    bytes_: usize,
+    frozen_: bool,
+}
+
+impl<K, V> MemTable<K, V> {
+    /// Builds an empty, writable memtable.
+    ///
+    /// # Arguments
+    ///
+    ///  * `budget`: number of bytes after which `is_over_budget` holds.
+    ///  * `controller`: generates heights for the nodes.
+    pub fn new(budget: usize, controller: Box<HeightControl<K>>) -> MemTable<K, V> {
+        MemTable {
+            map_: SkipListMap::new(controller),
+            budget_: budget,
+            bytes_: 0,
+            frozen_: false,
+        }
+    }
+
+    /// Returns the number of bytes after which the memtable should be frozen.
+    pub fn budget(&self) -> usize {
+        self.budget_
+    }
+
+    /// Returns the approximate number of bytes taken by the entries.
+    pub fn resident_bytes(&self) -> usize {
+        self.bytes_
+    }
+
+    /// Returns `true` once the entries take more than the budget.
+    pub fn is_over_budget(&self) -> bool {
+        self.bytes_ > self.budget_
+    }
+
+    /// Makes the memtable read-only: every write fails from now on.
+    pub fn freeze(&mut self) {
+        self.frozen_ = true;
+    }
+
+    /// Returns `true` if the memtable rejects writes.
+    pub fn is_frozen(&self) -> bool {
+        self.frozen_
+    }
+
+    /// Returns the number of elements stored in the structure.
+    pub fn len(&self) -> usize {
+        self.map_.len()
+    }
+
+    /// Returns `true` if there are no elements stored within the structure.
+    pub fn is_empty(&self) -> bool {
+        self.map_.is_empty()
+    }
+
+    /// Iterates over the entries, in key order.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        self.map_.iter()
+    }
+
+    /// Returns the underlying map.
+    pub fn as_map(&self) -> &SkipListMap<K, V> {
+        &self.map_
+    }
+
+    /// Consumes the memtable, returning the underlying map, e.g. to flush it.
+    pub fn into_map(self) -> SkipListMap<K, V> {
+        self.map_
+    }
+
+    fn check_writable(&self) -> Result<(), FrozenError> {
+        if self.frozen_ {
+            Err(FrozenError)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<K: Ord + ResidentSize, V: ResidentSize> MemTable<K, V> {
+    /// Bytes taken by a node besides what its key and value own: the node
+    /// itself, and a tower of the expected height for a coin with p = 1/2.
+    fn node_bytes() -> usize {
+        std::mem::size_of::<Node<K, V>>() + 2 * std::mem::size_of::<Link<K, V>>()
+    }
+
+    /// Inserts `value` under `key`, returning the value it replaced, if any.
+    /// Fails if the memtable is frozen.
+    pub fn insert(&mut self, key: K, value: V) -> Result<Option<V>, FrozenError> {
+        self.check_writable()?;
+
+        let added = value.heap_bytes();
+        let key_bytes = key.heap_bytes();
+        let replaced = self.map_.insert(key, value);
+        match replaced {
+            Some(ref old) => self.bytes_ -= old.heap_bytes(),
+            None => self.bytes_ += Self::node_bytes() + key_bytes,
+        }
+
+        self.bytes_ += added;
+        Ok(replaced)
+    }
+
+    /// Removes the element with key `key`, returning its value if it existed.
+    /// Fails if the memtable is frozen.
+    pub fn remove<Q>(&mut self, key: &Q) -> Result<Option<V>, FrozenError>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.check_writable()?;
+
+        let key_bytes = match self.map_.get_key_value(key) {
+            Some((stored, _)) => stored.heap_bytes(),
+            None => return Ok(None),
+        };
+
+        let value = self.map_.remove(key);
+        if let Some(ref value) = value {
+            self.bytes_ -= Self::node_bytes() + key_bytes + value.heap_bytes();
+        }
+
+        Ok(value)
+    }
+
+    /// Returns a const reference to the element with key `key`, if it exists.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map_.get(key)
+    }
+
+    /// Returns true if `key` is in the memtable.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map_.contains_key(key)
+    }
+
+    /// Iterates over the entries within `range`, in key order.
+    pub fn range<T, R>(&self, range: R) -> Range<'_, K, V>
+    where
+        K: Borrow<T>,
+        R: RangeBounds<T>,
+        T: Ord + ?Sized,
+    {
+        self.map_.range(range)
+    }
+}
+
+impl<K: std::fmt::Debug, V: std::fmt::Debug> std::fmt::Debug for MemTable<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
@@ -0,0 +1,264 @@
+use map::SkipListMap;
+use height_control::HeightControl;
+use build::UnsortedError;
+
+use std;
+use std::collections::BinaryHeap;
+
+/// What `SkipListMap::merge_with` keeps for a key that is in both maps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution<V> {
+    /// Keeps the value already in the map.
+    KeepMine,
+    /// Keeps the value from the merged map.
+    KeepTheirs,
+    /// Replaces both with the given value, e.g. their sum.
+    Combined(V),
+}
+
+impl<K: Ord, V> SkipListMap<K, V> {
+    /// Moves every entry of `other` into `self`. For keys in both maps,
+    /// `resolve` is called with the key, the value in `self` and the value in
+    /// `other`, and picks what to keep.
+    ///
+    /// # Remarks
+    ///
+    /// Both maps are walked once, in key order: each entry of `other` is
+    /// looked up starting from where the previous one was found, and its node
+    /// is moved into `self` as is, so nothing is allocated for new keys. If
+    /// `resolve` panics, the entries not merged yet are left in `other`.
+    pub fn merge_with<F>(&mut self, mut other: SkipListMap<K, V>, mut resolve: F)
+    where
+        F: FnMut(&K, &V, &V) -> Resolution<V>,
+    {
+        let mut fingers = vec![self.head_; self.max_height()];
+        while let Some(theirs) = other.head().link(0) {
+            let key = unsafe { theirs.as_ref().key::<K>() };
+            self.advance_fingers(&mut fingers, key);
+
+            let mine = unsafe { fingers[0].as_ref().link(0) };
+            match mine {
+                Some(mine) if unsafe { mine.as_ref().key::<K>() } == key => {
+                    let resolution =
+                        unsafe { resolve(key, mine.as_ref().value(), theirs.as_ref().value()) };
+
+                    let theirs = other.unlink_first().unwrap();
+                    let (_, value) = other.recycle_node(theirs);
+                    let value = match resolution {
+                        Resolution::KeepMine => continue,
+                        Resolution::KeepTheirs => value,
+                        Resolution::Combined(value) => value,
+                    };
+
+                    drop(unsafe { (*mine.as_ptr()).replace_value(value) });
+                }
+                _ => {
+                    let theirs = other.unlink_first().unwrap();
+                    unsafe {
+                        self.relink_node(&fingers, theirs);
+                        let levels = std::cmp::max(theirs.as_ref().height(), 1);
+                        for finger in fingers.iter_mut().take(levels) {
+                            *finger = theirs;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Next entry of one of the sources being merged. The heap pops the smallest
+/// key first, and among equal keys, the one from the oldest source.
+struct Head<K, V> {
+    key_: K,
+    value_: V,
+    source_: usize,
+}
+
+impl<K: Ord, V> PartialEq for Head<K, V> {
+    fn eq(&self, other: &Head<K, V>) -> bool {
+        self.cmp(other) == std::cmp::Ordering::Equal
+    }
+}
+
+impl<K: Ord, V> Eq for Head<K, V> {}
+
+impl<K: Ord, V> PartialOrd for Head<K, V> {
+    fn partial_cmp(&self, other: &Head<K, V>) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K: Ord, V> Ord for Head<K, V> {
+    fn cmp(&self, other: &Head<K, V>) -> std::cmp::Ordering {
+        other.key_.cmp(&self.key_).then(other.source_.cmp(&self.source_))
+    }
+}
+
+/// K-way merge of sorted sources, shared by `MergeIter` and
+/// `sorted_run::MergedRuns`, which only differ in how they resolve keys
+/// present in several sources.
+///
+/// Sources may fail: the first error they yield is returned in place of the
+/// next entry, and ends the merge.
+pub(crate) struct Merger<K, V, I, E> {
+    sources_: Vec<I>,
+    heap_: BinaryHeap<Head<K, V>>,
+    error_: Option<E>,
+}
+
+impl<K: Ord, V, I: Iterator<Item = Result<(K, V), E>>, E> Merger<K, V, I, E> {
+    /// Starts merging `sources`, given from oldest to newest.
+    pub(crate) fn new(sources: Vec<I>) -> Merger<K, V, I, E> {
+        let mut merger = Merger {
+            heap_: BinaryHeap::with_capacity(sources.len()),
+            sources_: sources,
+            error_: None,
+        };
+
+        for source in 0..merger.sources_.len() {
+            merger.advance(source, None);
+        }
+
+        merger
+    }
+
+    /// Pushes the next entry of `source` into the heap. `previous` is the key
+    /// just taken from it, which must not be repeated.
+    fn advance(&mut self, source: usize, previous: Option<&K>) {
+        match self.sources_[source].next() {
+            Some(Ok((key, value))) => {
+                debug_assert!(
+                    previous.is_none_or(|previous| *previous != key),
+                    "keys must be strictly increasing within every source"
+                );
+                self.heap_.push(Head {
+                    key_: key,
+                    value_: value,
+                    source_: source,
+                });
+            }
+            Some(Err(error)) if self.error_.is_none() => self.error_ = Some(error),
+            Some(Err(_)) => {}
+            None => {}
+        }
+    }
+
+    /// Returns the entry with the smallest key. When the key is present in
+    /// many sources, `resolve` is called with the key, the value merged so far
+    /// from the older sources, and the one from the next source, and picks
+    /// what to keep. The key from the oldest source is kept.
+    pub(crate) fn next<F>(&mut self, mut resolve: F) -> Option<Result<(K, V), E>>
+    where
+        F: FnMut(&K, &V, &V) -> Resolution<V>,
+    {
+        if let Some(error) = self.error_.take() {
+            self.heap_.clear();
+            self.sources_.clear();
+            return Some(Err(error));
+        }
+
+        let head = self.heap_.pop()?;
+        self.advance(head.source_, Some(&head.key_));
+
+        let key = head.key_;
+        let mut value = head.value_;
+        while self.heap_.peek().is_some_and(|next| next.key_ == key) {
+            let theirs = self.heap_.pop().unwrap();
+            self.advance(theirs.source_, Some(&theirs.key_));
+            value = match resolve(&key, &value, &theirs.value_) {
+                Resolution::KeepMine => value,
+                Resolution::KeepTheirs => theirs.value_,
+                Resolution::Combined(combined) => combined,
+            };
+        }
+
+        Some(Ok((key, value)))
+    }
+}
+
+/// Source of a `MergeIter`, which never fails.
+type Infallible<I, K, V> = std::iter::Map<I, fn((K, V)) -> Result<(K, V), std::convert::Infallible>>;
+
+/// Sorted stream over the entries of several sorted sources, as returned by
+/// `merge_iter`.
+pub struct MergeIter<K, V, I, F> {
+    merger_: Merger<K, V, Infallible<I, K, V>, std::convert::Infallible>,
+    resolve_: F,
+}
+
+/// Merges `sources`, each of them sorted by key, into a single stream of
+/// entries in key order, e.g. to combine the inputs of an LSM compaction.
+///
+/// Keys must be strictly increasing within every source. Keys out of order
+/// come out of order from the stream too, but a key repeated within a source
+/// would be resolved as if it came from a newer one, so debug builds panic on
+/// it instead.
+///
+/// Sources must be given from oldest to newest. When a key is present in many
+/// of them, `resolve` is called with the key, the value merged so far from
+/// the older sources, and the one from the next source, and picks what to
+/// keep, as for `SkipListMap::merge_with`. The key from the oldest source is
+/// kept.
+///
+/// # Remarks
+///
+/// Sources are only advanced as entries are taken from the stream, each entry
+/// costing O(log k) for k sources.
+pub fn merge_iter<K, V, I, F>(sources: Vec<I>, resolve: F) -> MergeIter<K, V, I, F>
+where
+    K: Ord,
+    I: Iterator<Item = (K, V)>,
+    F: FnMut(&K, &V, &V) -> Resolution<V>,
+{
+    let sources = sources
+        .into_iter()
+        .map(|source| source.map(Ok as fn((K, V)) -> Result<(K, V), std::convert::Infallible>))
+        .collect();
+
+    MergeIter {
+        merger_: Merger::new(sources),
+        resolve_: resolve,
+    }
+}
+
+impl<K, V, I, F> Iterator for MergeIter<K, V, I, F>
+where
+    K: Ord,
+    I: Iterator<Item = (K, V)>,
+    F: FnMut(&K, &V, &V) -> Resolution<V>,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        match self.merger_.next(&mut self.resolve_)? {
+            Ok(entry) => Some(entry),
+            Err(never) => match never {},
+        }
+    }
+}
+
+impl<K: Ord, V> SkipListMap<K, V> {
+    /// Builds a list from the k-way merge of `sources`, each of them sorted
+    /// by key. See `merge_iter` for how keys present in several sources are
+    /// resolved.
+    ///
+    /// # Remarks
+    ///
+    /// Merged entries are appended as they come, in O(1) each, through
+    /// `build_from_sorted`. Fails with an `UnsortedError` if a source turns
+    /// out not to be sorted, at the position of the first merged entry out of
+    /// order. Keys repeated within a source are not caught in release builds,
+    /// see `merge_iter`.
+    pub fn from_sorted_sources<I, F>(
+        sources: Vec<I>,
+        resolve: F,
+        controller: Box<HeightControl<K>>,
+    ) -> Result<SkipListMap<K, V>, UnsortedError>
+    where
+        I: Iterator<Item = (K, V)>,
+        F: FnMut(&K, &V, &V) -> Resolution<V>,
+    {
+        SkipListMap::build_from_sorted(merge_iter(sources, resolve).map(Ok), controller)
+    }
+}
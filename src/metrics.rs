@@ -0,0 +1,58 @@
+use map::SkipListMap;
+
+/// Kind of lookup or update reported through `Metrics::operation`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+    /// `insert`, whether it added a new key or replaced a value.
+    Insert,
+    /// `get`, `get_key_value` and `get_mut`. `contains_key` is reported as a
+    /// `Get` too.
+    Get,
+    /// `remove`, whether the key was found or not.
+    Remove,
+}
+
+/// Hooks through which a `SkipListMap` reports what it is doing, e.g. to
+/// export counters to a telemetry system. Every method does nothing by
+/// default.
+///
+/// Methods take `&self` because they are also called from lookups, so
+/// implementations keep their counters behind atomics or cells.
+pub trait Metrics: Send {
+    /// `operation` was called on the map.
+    fn operation(&self, _operation: Operation) {}
+
+    /// A search compared `count` keys against the one it was looking for.
+    fn comparisons(&self, _count: usize) {}
+
+    /// `count` nodes were allocated.
+    fn allocated(&self, _count: usize) {}
+
+    /// The structure of the map changed, and it now holds `length` elements
+    /// in towers of up to `height` levels.
+    fn resized(&self, _length: usize, _height: usize) {}
+}
+
+impl<K, V> SkipListMap<K, V> {
+    /// Reports everything the map does from now on to `metrics`, replacing
+    /// the previous hooks, if any. `metrics` is told the current shape of the
+    /// map right away.
+    ///
+    /// Hooks belong to a single map: clones and lists produced by `split_off`
+    /// start without any.
+    pub fn set_metrics(&mut self, metrics: Box<Metrics>) {
+        metrics.resized(self.len(), self.height_);
+        self.metrics_ = Some(metrics);
+    }
+
+    /// Stops reporting, and returns the hooks that were in use, if any.
+    pub fn take_metrics(&mut self) -> Option<Box<Metrics>> {
+        self.metrics_.take()
+    }
+
+    pub(crate) fn report<F: FnOnce(&Metrics)>(&self, f: F) {
+        if let Some(ref metrics) = self.metrics_ {
+            f(&**metrics)
+        }
+    }
+}
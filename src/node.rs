@@ -1,11 +1,26 @@
 use std;
 use std::borrow::{Borrow, BorrowMut};
+use std::mem::MaybeUninit;
+use std::ptr::NonNull;
 
+/// A forward pointer. `None` means there is no next node at that level.
+pub(crate) type Link<K, V> = Option<NonNull<Node<K, V>>>;
+
+/// The key and value are only left uninitialized for the head of the list,
+/// which never exposes them. Since `MaybeUninit` never drops its contents,
+/// whoever frees a regular node is responsible for dropping or moving them out
+/// first (see `drop_key_value` and `into_key_value`).
+///
+/// The level 0 link is stored inline, and only the levels above it live in a
+/// separate vector. Most nodes have height 0, so most nodes take a single
+/// allocation.
 #[derive(Debug)]
 pub(crate) struct Node<K, V> {
-    forward_: std::vec::Vec<*mut Node<K, V>>,
-    key_: K,
-    value_: V,
+    next_: Link<K, V>,
+    // Links of levels `1..=height`.
+    upper_: std::vec::Vec<Link<K, V>>,
+    key_: MaybeUninit<K>,
+    value_: MaybeUninit<V>,
 }
 
 impl<K, V> Node<K, V> {
@@ -14,41 +29,100 @@ impl<K, V> Node<K, V> {
     // height 1 node, and so on and so forth.
     pub fn new(key: K, value: V, height: usize) -> Node<K, V> {
         Node {
-            forward_: vec![std::ptr::null_mut(); height + 1],
-            key_: key,
-            value_: value,
+            next_: None,
+            upper_: vec![None; height],
+            key_: MaybeUninit::new(key),
+            value_: MaybeUninit::new(value),
         }
     }
 
+    // Builds a node without key and value, to be used as the head of a list.
+    // None of the key and value accessors may be called on it.
+    pub fn new_head(height: usize) -> Node<K, V> {
+        Node {
+            next_: None,
+            upper_: vec![None; height],
+            key_: MaybeUninit::uninit(),
+            value_: MaybeUninit::uninit(),
+        }
+    }
+
+    // Drops the key and value in place. Must be called exactly once, and only
+    // on nodes built with `new`.
+    pub unsafe fn drop_key_value(&mut self) {
+        // Dropping them as a pair ensures the value is dropped even if the
+        // key's destructor panics.
+        drop((self.key_.as_ptr().read(), self.value_.as_ptr().read()));
+    }
+
+    // Moves the key and value out of the node. Must only be called on nodes
+    // built with `new`.
+    pub unsafe fn into_key_value(self) -> (K, V) {
+        (self.key_.assume_init(), self.value_.assume_init())
+    }
+
+    // Moves the key and value out, leaving them uninitialized, so that the
+    // node can be filled again through `reuse`. Must only be called on nodes
+    // holding a key and value.
+    pub unsafe fn take_key_value(&mut self) -> (K, V) {
+        (self.key_.as_ptr().read(), self.value_.as_ptr().read())
+    }
+
+    // Fills a node emptied by `take_key_value` with a new key and value, and
+    // an unlinked tower of the given height. The buffer of the old tower is
+    // kept if it is large enough.
+    pub fn reuse(&mut self, key: K, value: V, height: usize) {
+        self.key_ = MaybeUninit::new(key);
+        self.value_ = MaybeUninit::new(value);
+        self.reset_tower(height);
+    }
+
     pub fn height(&self) -> usize {
-        self.forward_.len() - 1
+        self.upper_.len()
+    }
+
+    // Replaces the tower with an unlinked one of the given height, keeping
+    // the buffer of the old one if it is large enough.
+    pub fn reset_tower(&mut self, height: usize) {
+        self.next_ = None;
+        self.upper_.clear();
+        self.upper_.resize(height, None);
+    }
+
+    // Makes the tower at least `height` tall, keeping the existing links.
+    pub fn grow_tower(&mut self, height: usize) {
+        if height > self.height() {
+            self.upper_.resize(height, None);
+        }
+    }
+
+    // Sets the height of the tower, keeping the links of the levels that
+    // remain, and leaving new levels unlinked.
+    pub fn resize_tower(&mut self, height: usize) {
+        self.upper_.resize(height, None);
     }
 
     // Returns a reference to the underlying node at the given height
     pub fn next(&self, height: usize) -> Option<&Node<K, V>> {
-        self.forward_.get(height).and_then(
-            |ptr| if unlikely!(ptr.is_null()) {
-                None
-            } else {
-                Some(unsafe { &**ptr })
-            },
-        )
+        self.link(height).map(|ptr| unsafe { &*ptr.as_ptr() })
+    }
+
+    // Returns the pointer to the next node at the given height, if any.
+    pub fn link(&self, height: usize) -> Link<K, V> {
+        match height {
+            0 => self.next_,
+            _ => self.upper_.get(height - 1).and_then(|link| *link),
+        }
     }
 
     pub fn next_mut(&mut self, height: usize) -> Option<&mut Node<K, V>> {
-        self.forward_.get(height).and_then(
-            |ptr| if unlikely!(ptr.is_null()) {
-                None
-            } else {
-                Some(unsafe { &mut **ptr })
-            },
-        )
+        self.link(height).map(|ptr| unsafe { &mut *ptr.as_ptr() })
     }
 
-    pub fn link_to(&mut self, height: usize, destination: *mut Node<K, V>) {
+    pub fn link_to(&mut self, height: usize, destination: Link<K, V>) {
         debug_assert!(height <= self.height());
         unsafe {
-            *(self.forward_.get_unchecked_mut(height)) = destination;
+            *self.link_unchecked_mut(height) = destination;
         }
     }
 
@@ -56,7 +130,22 @@ impl<K, V> Node<K, V> {
         debug_assert!(height <= self.height());
         debug_assert!(height <= node.height());
         unsafe {
-            *(self.forward_.get_unchecked_mut(height)) = *(node.forward_.get_unchecked(height));
+            *self.link_unchecked_mut(height) = node.link_unchecked(height);
+        }
+    }
+
+    // Same as `link`, without checking that the tower reaches `height`.
+    unsafe fn link_unchecked(&self, height: usize) -> Link<K, V> {
+        match height {
+            0 => self.next_,
+            _ => *self.upper_.get_unchecked(height - 1),
+        }
+    }
+
+    unsafe fn link_unchecked_mut(&mut self, height: usize) -> &mut Link<K, V> {
+        match height {
+            0 => &mut self.next_,
+            _ => self.upper_.get_unchecked_mut(height - 1),
         }
     }
 
@@ -65,7 +154,7 @@ impl<K, V> Node<K, V> {
             K: Borrow<Q>,
             Q: ?Sized,
     {
-        (&self.key_).borrow()
+        unsafe { self.key_.assume_init_ref() }.borrow()
     }
 
     pub fn value<W>(&self) -> &W
@@ -73,15 +162,7 @@ impl<K, V> Node<K, V> {
             V: Borrow<W>,
             W: ?Sized,
     {
-        (&self.value_).borrow()
-    }
-
-    pub fn value_mut<W>(&mut self) -> &mut W
-        where
-            V: BorrowMut<W>,
-            W: ?Sized,
-    {
-        (&mut self.value_).borrow_mut()
+        unsafe { self.value_.assume_init_ref() }.borrow()
     }
 
     pub fn key_value<Q, W>(&self) -> (&Q, &W)
@@ -91,7 +172,7 @@ impl<K, V> Node<K, V> {
             V: Borrow<W>,
             W: ?Sized,
     {
-        (&self.key_.borrow(), &self.value_.borrow())
+        (self.key(), self.value())
     }
 
     pub fn key_value_mut<Q, W>(&mut self) -> (&Q, &mut W)
@@ -101,11 +182,41 @@ impl<K, V> Node<K, V> {
             V: BorrowMut<W>,
             W: ?Sized,
     {
-        (&self.key_.borrow(), (&mut self.value_).borrow_mut())
+        unsafe {
+            (
+                self.key_.assume_init_ref().borrow(),
+                self.value_.assume_init_mut().borrow_mut(),
+            )
+        }
+    }
+
+    // Same as `key_value_mut`, but without going through a mutable reference
+    // to the whole node, so that its links can still be read through other
+    // pointers while the returned references are alive.
+    pub unsafe fn key_value_mut_ptr<'a>(node: NonNull<Node<K, V>>) -> (&'a K, &'a mut V) {
+        let node = node.as_ptr();
+        (
+            &*(*node).key_.as_ptr(),
+            &mut *std::ptr::addr_of_mut!((*node).value_).cast::<V>(),
+        )
+    }
+
+    // Returns a pointer to the value with the provenance of the node itself,
+    // rather than that of a reference to it, so that it stays usable while
+    // the list keeps handing out references to the node.
+    pub unsafe fn value_ptr(node: NonNull<Node<K, V>>) -> NonNull<V> {
+        NonNull::new_unchecked(std::ptr::addr_of_mut!((*node.as_ptr()).value_).cast::<V>())
+    }
+
+    // Exposes the key mutably, so that it can be overwritten in place. The
+    // new key must keep the node in order. Must only be called on nodes
+    // holding a key and value.
+    pub unsafe fn key_value_in_place(&mut self) -> (&mut K, &mut V) {
+        (self.key_.assume_init_mut(), self.value_.assume_init_mut())
     }
 
     pub fn replace_value(&mut self, value: V) -> V {
-        std::mem::replace(&mut self.value_, value)
+        std::mem::replace(unsafe { self.value_.assume_init_mut() }, value)
     }
 }
 
@@ -156,7 +267,7 @@ mod tests {
 
         let mut node = Node::new(key, value, height);
         let next_node = Box::into_raw(Box::new(Node::new(key, value, height)));
-        node.link_to(k_node_set_height, next_node);
+        node.link_to(k_node_set_height, NonNull::new(next_node));
 
         for h in 0..node.height() {
             let next = node.next_mut(h);
@@ -171,7 +282,29 @@ mod tests {
         }
 
         unsafe {
-            Box::from_raw(next_node);
+            drop(Box::from_raw(next_node));
+        }
+    }
+
+    #[test]
+    fn short_towers_are_inline() {
+        let mut node = Node::new(1, 2, 0);
+        assert_eq!(node.height(), 0);
+        assert_eq!(node.upper_.capacity(), 0);
+
+        let next = Box::into_raw(Box::new(Node::new(3, 4, 2)));
+        node.link_to(0, NonNull::new(next));
+        assert_eq!(node.link(0), NonNull::new(next));
+        assert!(node.link(1).is_none());
+
+        node.grow_tower(2);
+        assert_eq!(node.height(), 2);
+        assert_eq!(node.link(0), NonNull::new(next));
+        node.reset_tower(0);
+        assert!(node.link(0).is_none());
+
+        unsafe {
+            drop(Box::from_raw(next));
         }
     }
 }
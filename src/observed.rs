@@ -0,0 +1,178 @@
+use map::SkipListMap;
+use height_control::HeightControl;
+use iter::{Iter, Range};
+
+use std;
+use std::borrow::Borrow;
+use std::ops::RangeBounds;
+
+/// Listener for the changes made to an `ObservedSkipListMap`, e.g. to keep a
+/// secondary index or a cache coherent with it. Every method does nothing by
+/// default.
+///
+/// Observers are called right before each change is applied, so a panicking
+/// observer leaves the map as it was. `clear` is the exception: see there.
+pub trait Observer<K, V>: Send {
+    /// `key` is about to be inserted with `value`.
+    fn inserted(&mut self, _key: &K, _value: &V) {}
+
+    /// The value of `key` is about to be replaced from `old` to `new`.
+    fn updated(&mut self, _key: &K, _old: &V, _new: &V) {}
+
+    /// `key` is about to be removed, along with `value`.
+    fn removed(&mut self, _key: &K, _value: &V) {}
+}
+
+/// `SkipListMap` that reports every change made to it to a set of
+/// `Observer`s.
+///
+/// Values can't be borrowed mutably, since observers couldn't see those
+/// changes; they are replaced through `insert` instead.
+pub struct ObservedSkipListMap<K, V> {
+    map_: SkipListMap<K, V>,
+    observers_: Vec<Box<Observer<K, V>>>,
+}
+
+impl<K, V> ObservedSkipListMap<K, V> {
+    pub fn new(controller: Box<HeightControl<K>>) -> ObservedSkipListMap<K, V> {
+        ObservedSkipListMap {
+            map_: SkipListMap::new(controller),
+            observers_: Vec::new(),
+        }
+    }
+
+    /// Registers `observer`, which will be told about every change from now
+    /// on, after the observers registered before it.
+    pub fn observe(&mut self, observer: Box<Observer<K, V>>) {
+        self.observers_.push(observer);
+    }
+
+    /// Unregisters every observer, and returns them in registration order.
+    pub fn take_observers(&mut self) -> Vec<Box<Observer<K, V>>> {
+        std::mem::take(&mut self.observers_)
+    }
+
+    /// Returns the number of elements stored in the structure.
+    pub fn len(&self) -> usize {
+        self.map_.len()
+    }
+
+    /// Returns `true` if there are no elements stored within the structure.
+    pub fn is_empty(&self) -> bool {
+        self.map_.is_empty()
+    }
+
+    /// Iterates over the entries, in key order.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        self.map_.iter()
+    }
+
+    /// Returns the underlying map.
+    pub fn as_map(&self) -> &SkipListMap<K, V> {
+        &self.map_
+    }
+
+    /// Consumes the map, returning the underlying one. The observers are
+    /// dropped.
+    pub fn into_map(self) -> SkipListMap<K, V> {
+        self.map_
+    }
+}
+
+impl<K: Ord, V> ObservedSkipListMap<K, V> {
+    /// Removes all elements. Observers are told about each of them, in key
+    /// order, once the map is already empty.
+    ///
+    /// # Remarks
+    ///
+    /// If an observer panics, the map stays empty, and the observers are not
+    /// told about the entries after the one it panicked on.
+    pub fn clear(&mut self) {
+        let removed = self.map_.split_at_index(0);
+        for (key, value) in removed.iter() {
+            for observer in &mut self.observers_ {
+                observer.removed(key, value);
+            }
+        }
+    }
+
+    /// Inserts `value` under `key`, returning the value it replaced, if any.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.map_.get(&key) {
+            Some(old) => {
+                for observer in &mut self.observers_ {
+                    observer.updated(&key, old, &value);
+                }
+            }
+            None => {
+                for observer in &mut self.observers_ {
+                    observer.inserted(&key, &value);
+                }
+            }
+        }
+
+        self.map_.insert(key, value)
+    }
+
+    /// Removes the element with key `key`, returning its value if it existed.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (stored, value) = self.map_.get_key_value(key)?;
+        for observer in &mut self.observers_ {
+            observer.removed(stored, value);
+        }
+
+        self.map_.remove(key)
+    }
+
+    /// Returns a const reference to the element with key `key`, if it exists.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map_.get(key)
+    }
+
+    /// Returns true if `key` is in the map.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map_.contains_key(key)
+    }
+
+    /// Returns the entry with the smallest key, if any.
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.map_.first()
+    }
+
+    /// Iterates over the entries within `range`, in key order.
+    pub fn range<T, R>(&self, range: R) -> Range<'_, K, V>
+    where
+        K: Borrow<T>,
+        R: RangeBounds<T>,
+        T: Ord + ?Sized,
+    {
+        self.map_.range(range)
+    }
+}
+
+impl<K: 'static + std::hash::Hash, V> Default for ObservedSkipListMap<K, V> {
+    fn default() -> ObservedSkipListMap<K, V> {
+        ObservedSkipListMap {
+            map_: Default::default(),
+            observers_: Vec::new(),
+        }
+    }
+}
+
+impl<K: std::fmt::Debug, V: std::fmt::Debug> std::fmt::Debug for ObservedSkipListMap<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
@@ -0,0 +1,280 @@
+use height_control::HeightControl;
+
+use std;
+use std::borrow::Borrow;
+use std::sync::{Arc, Mutex, PoisonError};
+
+type Link<K, V> = Option<Arc<PersistentNode<K, V>>>;
+
+/// Nodes before a given key, in order, and the links that follow them.
+type Split<'a, K, V> = (Vec<&'a Arc<PersistentNode<K, V>>>, Vec<Link<K, V>>);
+
+/// Nodes are never modified once built, so that any number of versions can
+/// point to them. A node of height `h` has `h + 1` links.
+struct PersistentNode<K, V> {
+    key_: K,
+    value_: V,
+    forward_: Vec<Link<K, V>>,
+}
+
+/// Immutable Skip List. `insert` and `remove` leave the map untouched, and
+/// return a new version that shares the nodes after the changed key with it.
+/// Every update costs O(k) time and memory, where `k` is the number of keys
+/// smaller than the changed one, so O(n) in the worst case. Cloning a version
+/// is O(1) on the number of entries.
+///
+/// # Remarks
+///
+/// Links only point forward, so every node before the changed key gets
+/// copied, along with its key and value, since one of its links has to
+/// change; unlike persistent trees, which copy a single O(log n) path.
+/// Workloads that mostly change the smallest keys (e.g. queues) are cheap,
+/// and those that append in key order are not.
+///
+/// The controller is shared by all versions derived from the same map.
+pub struct PersistentSkipListMap<K, V> {
+    head_: Vec<Link<K, V>>,
+    length_: usize,
+    controller_: Arc<Mutex<Box<HeightControl<K>>>>,
+}
+
+impl<K, V> PersistentSkipListMap<K, V> {
+    pub fn new(controller: Box<HeightControl<K>>) -> PersistentSkipListMap<K, V> {
+        PersistentSkipListMap {
+            head_: vec![None; controller.max_height() + 1],
+            length_: 0,
+            controller_: Arc::new(Mutex::new(controller)),
+        }
+    }
+
+    /// Returns the number of elements stored in this version.
+    pub fn len(&self) -> usize {
+        self.length_
+    }
+
+    /// Returns `true` if there are no elements stored in this version.
+    pub fn is_empty(&self) -> bool {
+        self.length_ == 0
+    }
+
+    /// Iterates over the entries of this version, in key order.
+    pub fn iter(&self) -> PersistentIter<'_, K, V> {
+        PersistentIter {
+            current_: self.head_[0].as_ref(),
+            remaining_: self.length_,
+        }
+    }
+
+    /// Returns `true` if both versions share all of their nodes, which is the
+    /// case for clones and for versions produced by changes that had no
+    /// effect.
+    pub fn ptr_eq(&self, other: &PersistentSkipListMap<K, V>) -> bool {
+        self.head_.len() == other.head_.len()
+            && self.head_.iter().zip(&other.head_).all(|pair| match pair {
+                (Some(left), Some(right)) => Arc::ptr_eq(left, right),
+                (None, None) => true,
+                _ => false,
+            })
+    }
+}
+
+impl<K: Ord, V> PersistentSkipListMap<K, V> {
+    /// Returns the first node with a key greater or equal than `key`.
+    fn find_lower_bound<Q>(&self, key: &Q) -> Option<&Arc<PersistentNode<K, V>>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut forward = &self.head_;
+        for level in (0..self.head_.len()).rev() {
+            while let Some(next) = forward[level].as_ref() {
+                if next.key_.borrow() >= key {
+                    break;
+                }
+                forward = &next.forward_;
+            }
+        }
+
+        forward[0].as_ref()
+    }
+
+    /// Returns a const reference to the element with key `key`, if it exists.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find_lower_bound(key)
+            .filter(|node| node.key_.borrow() == key)
+            .map(|node| &node.value_)
+    }
+
+    /// Returns true if `key` is in this version.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> PersistentSkipListMap<K, V> {
+    /// Collects the nodes with keys smaller than `key`, in order, along with
+    /// the link that follows them at every level: the first node with a key
+    /// greater or equal than `key` that is tall enough.
+    fn split_at<Q>(&self, key: &Q) -> Split<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut prefix = Vec::new();
+        let mut next = self.head_.clone();
+        let mut current = self.head_[0].as_ref();
+        while let Some(node) = current {
+            if node.key_.borrow() >= key {
+                break;
+            }
+
+            prefix.push(node);
+            next[..node.forward_.len()].clone_from_slice(&node.forward_);
+            current = node.forward_[0].as_ref();
+        }
+
+        (prefix, next)
+    }
+
+    /// Copies the nodes in `prefix`, from last to first, relinking them to
+    /// `next`, and returns the version whose head links to the copies.
+    fn rebuild(
+        &self,
+        prefix: Vec<&Arc<PersistentNode<K, V>>>,
+        mut next: Vec<Link<K, V>>,
+        length: usize,
+    ) -> PersistentSkipListMap<K, V> {
+        for node in prefix.into_iter().rev() {
+            let height = node.forward_.len();
+            let copy = Arc::new(PersistentNode {
+                key_: node.key_.clone(),
+                value_: node.value_.clone(),
+                forward_: next[..height].to_vec(),
+            });
+
+            for link in &mut next[..height] {
+                *link = Some(copy.clone());
+            }
+        }
+
+        PersistentSkipListMap {
+            head_: next,
+            length_: length,
+            controller_: self.controller_.clone(),
+        }
+    }
+
+    /// Returns a new version in which `key` maps to `value`. This version is
+    /// left untouched.
+    pub fn insert(&self, key: K, value: V) -> PersistentSkipListMap<K, V> {
+        let (prefix, mut next) = self.split_at(&key);
+
+        let (forward, length) = match next[0] {
+            // The replaced node keeps its tower, and is skipped over.
+            Some(ref node) if node.key_ == key => (node.forward_.clone(), self.length_),
+            _ => {
+                // Controllers hold no invariants a panic could break.
+                let mut controller = self.controller_.lock().unwrap_or_else(PoisonError::into_inner);
+                let height = controller.get_height(&key);
+                let height = std::cmp::min(height, self.head_.len() - 1);
+                (next[..height + 1].to_vec(), self.length_ + 1)
+            }
+        };
+
+        let height = forward.len();
+        let node = Arc::new(PersistentNode {
+            key_: key,
+            value_: value,
+            forward_: forward,
+        });
+
+        for link in &mut next[..height] {
+            *link = Some(node.clone());
+        }
+
+        self.rebuild(prefix, next, length)
+    }
+
+    /// Returns a new version without `key`. This version is left untouched.
+    pub fn remove<Q>(&self, key: &Q) -> PersistentSkipListMap<K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (prefix, mut next) = self.split_at(key);
+
+        let target = match next[0] {
+            Some(ref node) if node.key_.borrow() == key => node.clone(),
+            _ => return self.clone(),
+        };
+
+        next[..target.forward_.len()].clone_from_slice(&target.forward_);
+        self.rebuild(prefix, next, self.length_ - 1)
+    }
+}
+
+impl<K, V> Clone for PersistentSkipListMap<K, V> {
+    fn clone(&self) -> PersistentSkipListMap<K, V> {
+        PersistentSkipListMap {
+            head_: self.head_.clone(),
+            length_: self.length_,
+            controller_: self.controller_.clone(),
+        }
+    }
+}
+
+// Dropping the nodes recursively would overflow the stack on long lists.
+impl<K, V> Drop for PersistentSkipListMap<K, V> {
+    fn drop(&mut self) {
+        let mut current = self.head_[0].take();
+        self.head_.clear();
+
+        // Every node after `current` is still linked at level 0 by its
+        // predecessor, so only the level 0 link may be the last one to it.
+        while let Some(node) = current {
+            current = match Arc::try_unwrap(node) {
+                Ok(mut node) => node.forward_[0].take(),
+                // Other versions are still using the rest of the list.
+                Err(_) => None,
+            };
+        }
+    }
+}
+
+impl<K: std::fmt::Debug, V: std::fmt::Debug> std::fmt::Debug for PersistentSkipListMap<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Iterator over the entries of a version of a `PersistentSkipListMap`, in
+/// key order.
+pub struct PersistentIter<'a, K: 'a, V: 'a> {
+    current_: Option<&'a Arc<PersistentNode<K, V>>>,
+    remaining_: usize,
+}
+
+impl<'a, K: 'a, V: 'a> Iterator for PersistentIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.current_?;
+        self.current_ = node.forward_[0].as_ref();
+        self.remaining_ -= 1;
+        Some((&node.key_, &node.value_))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining_, Some(self.remaining_))
+    }
+}
+
+impl<'a, K: 'a, V: 'a> ExactSizeIterator for PersistentIter<'a, K, V> {}
@@ -0,0 +1,217 @@
+use map::SkipListMap;
+use node::{Link, Node};
+
+use std;
+use std::ptr::NonNull;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Counters kept by a `NodePool`, as returned by `NodePool::stats`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    /// Nodes the pool had to get from the allocator.
+    pub allocated: usize,
+    /// Nodes handed out again after being given back by a list.
+    pub reused: usize,
+    /// Nodes kept by the pool, waiting to be reused.
+    pub pooled: usize,
+    /// Nodes given back to the allocator, either because the pool was full,
+    /// or through `release`.
+    pub released: usize,
+}
+
+struct PoolState<K, V> {
+    // Nodes without a key or value.
+    free_: Vec<NonNull<Node<K, V>>>,
+    capacity_: usize,
+    stats_: PoolStats,
+}
+
+// Pooled nodes hold no keys or values, only their towers, which are plain
+// allocations.
+unsafe impl<K, V> Send for PoolState<K, V> {}
+
+// Pooled nodes are freed without looking at keys or values, which they don't
+// hold anyway, so lists using the pool can still be dropped after their keys
+// and values.
+unsafe impl<#[may_dangle] K, #[may_dangle] V> Drop for PoolState<K, V> {
+    fn drop(&mut self) {
+        for node in self.free_.drain(..) {
+            SkipListMap::free_dummy_node(node);
+        }
+    }
+}
+
+/// Free list of nodes shared by several `SkipListMap`s, e.g. all the
+/// memtables of a storage engine. Nodes removed from any of the lists are
+/// kept in the pool, up to its capacity, and handed out again to whichever
+/// list inserts next, so that churn across many small lists doesn't go
+/// through the allocator.
+///
+/// Clones of a pool share the same nodes. Lists take nodes from the pool
+/// through `SkipListMap::set_node_pool`, and give them back when their
+/// entries are removed, popped, drained, cleared or dropped. Nodes taken out
+/// through `detach` go back to the pool of their list once they are dropped
+/// or turned into their entry, unless they are attached somewhere else.
+pub struct NodePool<K, V> {
+    state_: Arc<Mutex<PoolState<K, V>>>,
+}
+
+impl<K, V> NodePool<K, V> {
+    /// Builds an empty pool that keeps at most `capacity` free nodes.
+    pub fn new(capacity: usize) -> NodePool<K, V> {
+        NodePool {
+            state_: Arc::new(Mutex::new(PoolState {
+                free_: Vec::new(),
+                capacity_: capacity,
+                stats_: PoolStats::default(),
+            })),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, PoolState<K, V>> {
+        // Nothing panics while the lock is held, except for the allocator.
+        self.state_.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Returns the counters of the pool, shared by all its clones.
+    pub fn stats(&self) -> PoolStats {
+        let state = self.state();
+        PoolStats {
+            pooled: state.free_.len(),
+            ..state.stats_
+        }
+    }
+
+    /// Gives every free node in the pool back to the allocator at once, and
+    /// returns how many there were.
+    pub fn release(&self) -> usize {
+        let free = {
+            let mut state = self.state();
+            state.stats_.released += state.free_.len();
+            std::mem::take(&mut state.free_)
+        };
+
+        let released = free.len();
+        for node in free {
+            SkipListMap::free_dummy_node(node);
+        }
+
+        released
+    }
+
+    /// Allocates up to `additional` free nodes ahead of time, without going
+    /// over the capacity of the pool, and returns how many were added. Their
+    /// towers are sized for the expected height with p = 1/2, and grow when
+    /// they are handed out to taller nodes.
+    pub fn reserve(&self, additional: usize) -> usize {
+        let mut state = self.state();
+        let added = std::cmp::min(additional, state.capacity_.saturating_sub(state.free_.len()));
+        state.free_.reserve(added);
+        for _ in 0..added {
+            state.free_.push(NonNull::from(Box::leak(Box::new(Node::new_head(1)))));
+        }
+
+        state.stats_.allocated += added;
+        added
+    }
+
+    /// Returns a node holding `key` and `value`, with an unlinked tower of
+    /// the given height.
+    pub(crate) fn allocate(&self, key: K, value: V, height: usize) -> NonNull<Node<K, V>> {
+        let reused = {
+            let mut state = self.state();
+            let node = state.free_.pop();
+            if node.is_some() {
+                state.stats_.reused += 1;
+            } else {
+                state.stats_.allocated += 1;
+            }
+
+            node
+        };
+
+        match reused {
+            Some(node) => {
+                unsafe {
+                    (*node.as_ptr()).reuse(key, value, height);
+                }
+                node
+            }
+            None => NonNull::from(Box::leak(Box::new(Node::new(key, value, height)))),
+        }
+    }
+
+    /// Takes the key and value out of `node`, and keeps it for reuse, unless
+    /// the pool is full.
+    pub(crate) fn recycle(&self, node: NonNull<Node<K, V>>) -> (K, V) {
+        let entry = unsafe { (*node.as_ptr()).take_key_value() };
+        let mut state = self.state();
+        if state.free_.len() < state.capacity_ {
+            state.free_.push(node);
+        } else {
+            state.stats_.released += 1;
+            drop(state);
+            SkipListMap::free_dummy_node(node);
+        }
+
+        entry
+    }
+
+    /// Recycles every node in the level 0 chain that starts at `first`. If
+    /// the destructor of a key or value panics, the rest of the chain is
+    /// freed while unwinding.
+    pub(crate) fn recycle_chain(&self, first: Link<K, V>) {
+        struct ChainGuard<K, V>(Link<K, V>);
+
+        impl<K, V> Drop for ChainGuard<K, V> {
+            fn drop(&mut self) {
+                SkipListMap::free_chain(self.0.take());
+            }
+        }
+
+        let mut rest = ChainGuard(first);
+        while let Some(node) = rest.0 {
+            rest.0 = unsafe { node.as_ref().link(0) };
+            drop(self.recycle(node));
+        }
+    }
+}
+
+impl<K, V> Clone for NodePool<K, V> {
+    fn clone(&self) -> NodePool<K, V> {
+        NodePool {
+            state_: self.state_.clone(),
+        }
+    }
+}
+
+impl<K, V> std::fmt::Debug for NodePool<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_tuple("NodePool").field(&self.stats()).finish()
+    }
+}
+
+impl<K, V> SkipListMap<K, V> {
+    /// Makes the list take its nodes from `pool` from now on, and give them
+    /// back to it. Nodes already in the list can be given back as well.
+    pub fn set_node_pool(&mut self, pool: NodePool<K, V>) {
+        self.pool_ = Some(pool);
+    }
+
+    /// Stops using the node pool, if any, and returns it.
+    pub fn take_node_pool(&mut self) -> Option<NodePool<K, V>> {
+        self.pool_.take()
+    }
+
+    /// Allocates nodes for `additional` upcoming insertions ahead of time,
+    /// so that a burst of them doesn't wait on the allocator, and returns how
+    /// many were added. Nodes are kept in the node pool, so this does nothing
+    /// on lists without one, and never goes over the capacity of the pool;
+    /// see `NodePool::reserve`.
+    pub fn reserve(&mut self, additional: usize) -> usize {
+        match self.pool_ {
+            Some(ref pool) => pool.reserve(additional),
+            None => 0,
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use height_control::HeightControl;
+use index_list::{IndexIter, IndexList, HEAD, NIL};
+
+use std;
+use std::cmp::Ordering;
+
+struct PrefixEntry<V> {
+    /// Number of leading bytes shared with the key of the previous node.
+    shared_: usize,
+    /// The rest of the key.
+    suffix_: Box<[u8]>,
+    value_: V,
+}
+
+/// Map from byte strings that stores every key as the length of the prefix it
+/// shares with the previous key, followed by the rest of it. Keys with long
+/// common prefixes, such as paths, URLs, or composite keys, take much less
+/// memory than in a `SkipListMap<Vec<u8>, V>`.
+///
+/// Reconstructing a key needs the key before it, so only nodes linked at level
+/// 0 alone are compressed: the taller ones keep their full key, and act as
+/// restart points. Searches compare against full keys until they reach level
+/// 0, and from there rebuild each key incrementally from the previous one.
+/// As in `SkipListMap`, nodes of heights 0 and 1 are linked at level 0 alone,
+/// so with the default promotion probability of 1/2, about three quarters of
+/// the keys are compressed.
+pub struct PrefixSkipListMap<V> {
+    list_: IndexList<PrefixEntry<V>, ()>,
+    controller_: Box<HeightControl<Vec<u8>>>,
+}
+
+/// Position of a key within the list: the last node at every level with a
+/// smaller key, along with the full key of the one at level 0.
+struct Search {
+    updates_: Vec<usize>,
+    previous_key_: Vec<u8>,
+}
+
+fn common_prefix(left: &[u8], right: &[u8]) -> usize {
+    left.iter().zip(right).take_while(|(l, r)| l == r).count()
+}
+
+impl<V> PrefixSkipListMap<V> {
+    pub fn new(controller: Box<HeightControl<Vec<u8>>>) -> PrefixSkipListMap<V> {
+        PrefixSkipListMap {
+            list_: IndexList::new(controller.max_height(), || ()),
+            controller_: controller,
+        }
+    }
+
+    /// Returns the number of elements stored in the structure.
+    pub fn len(&self) -> usize {
+        self.list_.len()
+    }
+
+    /// Returns `true` if there are no elements stored within the structure.
+    pub fn is_empty(&self) -> bool {
+        self.list_.len() == 0
+    }
+
+    /// Returns the number of key bytes stored, after compression.
+    pub fn key_bytes(&self) -> usize {
+        self.list_.entries().map(|entry| entry.suffix_.len()).sum()
+    }
+
+    /// Removes all elements.
+    pub fn clear(&mut self) {
+        self.list_.clear();
+    }
+
+    /// Iterates over the entries, in key order. Keys are rebuilt as the
+    /// iteration goes, so they are handed out by value.
+    pub fn iter(&self) -> PrefixIter<'_, V> {
+        PrefixIter {
+            entries_: self.list_.entries(),
+            key_: Vec::new(),
+        }
+    }
+
+    /// Replaces the key of `node` by `key`, given the full key of the node
+    /// before it, if it is not the head.
+    fn set_key(&mut self, node: usize, key: &[u8], previous: Option<&[u8]>) {
+        let shared = match previous {
+            // Nodes linked above level 0 are restart points.
+            Some(previous) if self.list_.node(node).levels() == 1 => common_prefix(previous, key),
+            _ => 0,
+        };
+
+        let entry = self.list_.entry_mut(node);
+        entry.shared_ = shared;
+        entry.suffix_ = key[shared..].into();
+    }
+
+    /// Finds the last node before `key` at every level, and compares `key`
+    /// with the node that follows at level 0.
+    fn search(&self, key: &[u8]) -> (Search, Ordering) {
+        // Every node linked above level 0 holds its full key.
+        let mut updates = self.list_.find_upper_updates_by(|entry| &entry.suffix_[..] < key);
+        let mut current = updates[0];
+
+        let mut previous_key = match self.list_.node(current).entry() {
+            Some(entry) => entry.suffix_.to_vec(),
+            None => Vec::new(),
+        };
+        let mut next_key = Vec::new();
+        let ordering = loop {
+            let next = self.list_.next(current, 0);
+            if next == NIL {
+                break Ordering::Greater;
+            }
+
+            next_key.clear();
+            next_key.extend_from_slice(&previous_key);
+            rebuild_key(self.list_.entry(next), &mut next_key);
+            match next_key[..].cmp(key) {
+                Ordering::Less => {
+                    current = next;
+                    std::mem::swap(&mut previous_key, &mut next_key);
+                }
+                ordering => break ordering,
+            }
+        };
+        updates[0] = current;
+
+        let search = Search {
+            updates_: updates,
+            previous_key_: previous_key,
+        };
+
+        (search, ordering)
+    }
+
+    /// Returns the node with key `key`, if it exists.
+    fn find(&self, key: &[u8]) -> Option<usize> {
+        match self.search(key) {
+            (search, Ordering::Equal) => Some(self.list_.next(search.updates_[0], 0)),
+            _ => None,
+        }
+    }
+
+    /// Inserts `value` under `key`, returning the value it replaced, if any.
+    pub fn insert(&mut self, key: Vec<u8>, value: V) -> Option<V> {
+        let (search, ordering) = self.search(&key);
+        let next = self.list_.next(search.updates_[0], 0);
+        if ordering == Ordering::Equal {
+            let entry = self.list_.entry_mut(next);
+            return Some(std::mem::replace(&mut entry.value_, value));
+        }
+
+        let height = self.controller_.get_height(&key);
+        let entry = PrefixEntry {
+            shared_: 0,
+            suffix_: Box::new([]),
+            value_: value,
+        };
+        let node = self.list_.link(&search.updates_, entry, height);
+
+        let previous = match search.updates_[0] {
+            HEAD => None,
+            _ => Some(&search.previous_key_[..]),
+        };
+        self.set_key(node, &key, previous);
+
+        // The next node is now compressed against the new key.
+        if next != NIL && self.list_.node(next).levels() == 1 {
+            let mut next_key = search.previous_key_.clone();
+            rebuild_key(self.list_.entry(next), &mut next_key);
+            self.set_key(next, &next_key, Some(&key));
+        }
+
+        None
+    }
+
+    /// Returns a const reference to the element with key `key`, if it exists.
+    pub fn get(&self, key: &[u8]) -> Option<&V> {
+        self.find(key).map(|node| &self.list_.entry(node).value_)
+    }
+
+    /// Returns a mutable reference to the element with key `key`, if it
+    /// exists.
+    pub fn get_mut(&mut self, key: &[u8]) -> Option<&mut V> {
+        let node = self.find(key)?;
+        Some(&mut self.list_.entry_mut(node).value_)
+    }
+
+    /// Returns true if `key` is in the list.
+    pub fn contains_key(&self, key: &[u8]) -> bool {
+        self.find(key).is_some()
+    }
+
+    /// Removes the element with key `key`, returning its value if it existed.
+    pub fn remove(&mut self, key: &[u8]) -> Option<V> {
+        let (search, ordering) = self.search(key);
+        if ordering != Ordering::Equal {
+            return None;
+        }
+
+        let target = self.list_.next(search.updates_[0], 0);
+        let next = self.list_.next(target, 0);
+
+        // The next node is now compressed against the key before the removed
+        // one, or becomes the first one.
+        if next != NIL && self.list_.node(next).levels() == 1 {
+            let mut next_key = key.to_vec();
+            rebuild_key(self.list_.entry(next), &mut next_key);
+            let previous = match search.updates_[0] {
+                HEAD => None,
+                _ => Some(&search.previous_key_[..]),
+            };
+            self.set_key(next, &next_key, previous);
+        }
+
+        Some(self.list_.unlink(&search.updates_, target).value_)
+    }
+}
+
+/// Rebuilds the key of `entry` in `key`, which holds the full key of the
+/// node before it.
+fn rebuild_key<V>(entry: &PrefixEntry<V>, key: &mut Vec<u8>) {
+    key.truncate(entry.shared_);
+    key.extend_from_slice(&entry.suffix_);
+}
+
+impl<V: std::fmt::Debug> std::fmt::Debug for PrefixSkipListMap<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Iterator over the entries of a `PrefixSkipListMap`, in key order.
+pub struct PrefixIter<'a, V: 'a> {
+    entries_: IndexIter<'a, PrefixEntry<V>, ()>,
+    key_: Vec<u8>,
+}
+
+impl<'a, V: 'a> Iterator for PrefixIter<'a, V> {
+    type Item = (Vec<u8>, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.entries_.next()?;
+        rebuild_key(entry, &mut self.key_);
+        Some((self.key_.clone(), &entry.value_))
+    }
+}
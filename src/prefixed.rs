@@ -0,0 +1,133 @@
+use thin::{ThinBytes, ThinStr};
+
+use std;
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+
+/// Keys that can be summarized by a `u64` which sorts the same way they do:
+/// whenever the prefix of `a` is smaller than the prefix of `b`, `a < b`.
+/// Equal prefixes tell nothing about the keys.
+pub trait KeyPrefix {
+    fn key_prefix(&self) -> u64;
+}
+
+/// First 8 bytes, big-endian so that the `u64` sorts like the bytes, and
+/// padded with zeros. A shorter key only pads to the same prefix as a longer
+/// one that continues with zeros, and then the full keys decide.
+fn bytes_prefix(bytes: &[u8]) -> u64 {
+    let mut prefix = [0u8; 8];
+    let length = std::cmp::min(bytes.len(), prefix.len());
+    prefix[..length].copy_from_slice(&bytes[..length]);
+    u64::from_be_bytes(prefix)
+}
+
+macro_rules! bytes_key_prefix {
+    ($($bytes:ty),*) => {
+        $(impl KeyPrefix for $bytes {
+            fn key_prefix(&self) -> u64 {
+                let bytes: &[u8] = self.as_ref();
+                bytes_prefix(bytes)
+            }
+        })*
+    };
+}
+
+bytes_key_prefix!(str, [u8], String, Vec<u8>, Box<[u8]>, ThinBytes);
+
+impl KeyPrefix for Box<str> {
+    fn key_prefix(&self) -> u64 {
+        bytes_prefix(self.as_bytes())
+    }
+}
+
+impl KeyPrefix for ThinStr {
+    fn key_prefix(&self) -> u64 {
+        bytes_prefix(self.as_bytes())
+    }
+}
+
+/// Key wrapper that keeps the prefix of the key next to it, inline in the
+/// node, so that most comparisons during a search are settled by comparing
+/// two integers, without following the pointer to e.g. the bytes of a
+/// `String`. Only keys sharing their first 8 bytes are compared in full.
+///
+/// Searches only benefit from the prefix when they are given a `Prefixed`
+/// key too. `Prefixed<K>` also borrows as `K`, e.g. to look up a
+/// `SkipListMap<Prefixed<String>, V>` with a `&String`, but those lookups
+/// compare full keys at every step.
+#[derive(Clone)]
+pub struct Prefixed<K> {
+    prefix_: u64,
+    key_: K,
+}
+
+impl<K: KeyPrefix> Prefixed<K> {
+    pub fn new(key: K) -> Prefixed<K> {
+        Prefixed {
+            prefix_: key.key_prefix(),
+            key_: key,
+        }
+    }
+}
+
+impl<K> Prefixed<K> {
+    /// Returns the key.
+    pub fn key(&self) -> &K {
+        &self.key_
+    }
+
+    /// Returns the cached prefix of the key.
+    pub fn prefix(&self) -> u64 {
+        self.prefix_
+    }
+
+    /// Consumes the wrapper, returning the key.
+    pub fn into_inner(self) -> K {
+        self.key_
+    }
+}
+
+impl<K: KeyPrefix> From<K> for Prefixed<K> {
+    fn from(key: K) -> Prefixed<K> {
+        Prefixed::new(key)
+    }
+}
+
+impl<K> Borrow<K> for Prefixed<K> {
+    fn borrow(&self) -> &K {
+        &self.key_
+    }
+}
+
+impl<K: PartialEq> PartialEq for Prefixed<K> {
+    fn eq(&self, other: &Prefixed<K>) -> bool {
+        self.prefix_ == other.prefix_ && self.key_ == other.key_
+    }
+}
+
+impl<K: Eq> Eq for Prefixed<K> {}
+
+impl<K: Ord> PartialOrd for Prefixed<K> {
+    fn partial_cmp(&self, other: &Prefixed<K>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K: Ord> Ord for Prefixed<K> {
+    fn cmp(&self, other: &Prefixed<K>) -> Ordering {
+        self.prefix_.cmp(&other.prefix_).then_with(|| self.key_.cmp(&other.key_))
+    }
+}
+
+// Hashed like the key alone, as required by `Borrow<K>`.
+impl<K: std::hash::Hash> std::hash::Hash for Prefixed<K> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.key_.hash(state)
+    }
+}
+
+impl<K: std::fmt::Debug> std::fmt::Debug for Prefixed<K> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.key_.fmt(f)
+    }
+}
@@ -0,0 +1,163 @@
+//! Python bindings, enabled by the `python` feature.
+//!
+//! The extension module is built with
+//! `cargo rustc --release --features python --crate-type cdylib`, and exposes
+//! `SortedDict`, a sorted dictionary that follows the interface of
+//! `sortedcontainers.SortedDict`.
+
+// Triggered by the code that `#[pymethods]` generates for `PyResult` returns.
+#![allow(clippy::useless_conversion)]
+
+use map::SkipListMap;
+use height_control::TwoPowGenerator;
+
+use std::cmp::Ordering;
+use std::ops;
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+
+use pyo3::exceptions::PyKeyError;
+use pyo3::prelude::*;
+use pyo3::types::{PyIterator, PyList};
+
+/// Python object used as a key. Keys are ordered using Python's comparison
+/// operators.
+struct PyKey(PyObject);
+
+impl PartialEq for PyKey {
+    fn eq(&self, other: &PyKey) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for PyKey {}
+
+impl PartialOrd for PyKey {
+    fn partial_cmp(&self, other: &PyKey) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PyKey {
+    fn cmp(&self, other: &PyKey) -> Ordering {
+        Python::with_gil(|py| match self.0.bind(py).compare(other.0.bind(py)) {
+            Ok(ordering) => ordering,
+            // Comparisons can't fail from the list's point of view, so the
+            // error unwinds out of it, and is turned back into a Python
+            // exception by `guarded`. The list is left untouched.
+            Err(err) => resume_unwind(Box::new(err)),
+        })
+    }
+}
+
+/// Runs `f`, turning any Python exception raised while comparing keys into an
+/// error.
+fn guarded<R, F: FnOnce() -> R>(f: F) -> PyResult<R> {
+    catch_unwind(AssertUnwindSafe(f)).map_err(|payload| match payload.downcast::<PyErr>() {
+        Ok(err) => *err,
+        Err(payload) => resume_unwind(payload),
+    })
+}
+
+fn bound(key: Option<PyObject>, inclusive: bool) -> ops::Bound<PyKey> {
+    match key {
+        None => ops::Bound::Unbounded,
+        Some(key) if inclusive => ops::Bound::Included(PyKey(key)),
+        Some(key) => ops::Bound::Excluded(PyKey(key)),
+    }
+}
+
+/// Sorted dictionary backed by a Skip List.
+#[pyclass(module = "skiplist")]
+struct SortedDict {
+    map_: SkipListMap<PyKey, PyObject>,
+}
+
+#[pymethods]
+impl SortedDict {
+    #[new]
+    fn new() -> SortedDict {
+        SortedDict { map_: SkipListMap::new(Box::new(TwoPowGenerator::new(16))) }
+    }
+
+    fn __len__(&self) -> usize {
+        self.map_.len()
+    }
+
+    fn __contains__(&self, key: PyObject) -> PyResult<bool> {
+        guarded(|| self.map_.contains_key(&PyKey(key)))
+    }
+
+    fn __getitem__(&self, py: Python, key: PyObject) -> PyResult<PyObject> {
+        let key = PyKey(key);
+        match guarded(|| self.map_.get(&key).map(|value| value.clone_ref(py)))? {
+            Some(value) => Ok(value),
+            None => Err(PyKeyError::new_err(key.0)),
+        }
+    }
+
+    fn __setitem__(&mut self, key: PyObject, value: PyObject) -> PyResult<()> {
+        guarded(|| {
+            self.map_.insert(PyKey(key), value);
+        })
+    }
+
+    fn __delitem__(&mut self, key: PyObject) -> PyResult<()> {
+        let key = PyKey(key);
+        match guarded(|| self.map_.remove(&key))? {
+            Some(_) => Ok(()),
+            None => Err(PyKeyError::new_err(key.0)),
+        }
+    }
+
+    fn __iter__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyIterator>> {
+        let keys: Vec<PyObject> = self.map_.keys().map(|key| key.0.clone_ref(py)).collect();
+        PyList::new_bound(py, keys).as_any().iter()
+    }
+
+    /// Returns an iterator over the keys between `minimum` and `maximum`.
+    /// Either end may be `None`, in which case the range is unbounded on that
+    /// side. `inclusive` tells whether each end is part of the range.
+    #[pyo3(signature = (minimum=None, maximum=None, inclusive=(true, true), reverse=false))]
+    fn irange<'py>(
+        &self,
+        py: Python<'py>,
+        minimum: Option<PyObject>,
+        maximum: Option<PyObject>,
+        inclusive: (bool, bool),
+        reverse: bool,
+    ) -> PyResult<Bound<'py, PyIterator>> {
+        let range = (bound(minimum, inclusive.0), bound(maximum, inclusive.1));
+        let mut keys: Vec<PyObject> =
+            guarded(|| self.map_.range(range).map(|(key, _)| key.0.clone_ref(py)).collect())?;
+        if reverse {
+            keys.reverse();
+        }
+
+        PyList::new_bound(py, keys).as_any().iter()
+    }
+
+    /// Returns the index at which `key` would be inserted, before any equal
+    /// key.
+    ///
+    /// This is linear on the returned index.
+    fn bisect_left(&self, key: PyObject) -> PyResult<usize> {
+        guarded(|| self.map_.range(..PyKey(key)).count())
+    }
+
+    /// Returns the index at which `key` would be inserted, after any equal key.
+    ///
+    /// This is linear on the returned index.
+    fn bisect_right(&self, key: PyObject) -> PyResult<usize> {
+        guarded(|| self.map_.range(..=PyKey(key)).count())
+    }
+
+    /// Same as `bisect_right`.
+    fn bisect(&self, key: PyObject) -> PyResult<usize> {
+        self.bisect_right(key)
+    }
+}
+
+#[pymodule]
+fn skiplist(module: &Bound<PyModule>) -> PyResult<()> {
+    module.add_class::<SortedDict>()
+}
@@ -0,0 +1,88 @@
+//! `quickcheck::Arbitrary` implementations, so that property tests (both in
+//! this crate and downstream, through the `quickcheck` feature) can take Skip
+//! Lists and height controllers as inputs.
+use map::SkipListMap;
+use height_control::{GeometricalGenerator, HashCoinGenerator, TwoPowGenerator};
+
+use std;
+use quickcheck::{Arbitrary, Gen};
+
+impl Arbitrary for GeometricalGenerator {
+    fn arbitrary<G: Gen>(gen: &mut G) -> GeometricalGenerator {
+        let upgrade_probability = gen.gen_range(0.0, 1.0);
+        let max_height = gen.gen_range(1, 30);
+        GeometricalGenerator::new(max_height, upgrade_probability)
+    }
+}
+
+impl<K: 'static> Arbitrary for TwoPowGenerator<K> {
+    fn arbitrary<G: Gen>(gen: &mut G) -> TwoPowGenerator<K> {
+        TwoPowGenerator::new(gen.gen_range(1, 33))
+    }
+}
+
+impl<K, H> Arbitrary for HashCoinGenerator<K, H>
+where
+    K: 'static + std::hash::Hash,
+    H: 'static + std::hash::Hasher + Clone + Default + Send,
+{
+    fn arbitrary<G: Gen>(gen: &mut G) -> HashCoinGenerator<K, H> {
+        HashCoinGenerator::new(gen.gen_range(1, 30), H::default())
+    }
+}
+
+impl<K: Ord + Arbitrary, V: Arbitrary> Arbitrary for SkipListMap<K, V> {
+    fn arbitrary<G: Gen>(gen: &mut G) -> SkipListMap<K, V> {
+        let controller: GeometricalGenerator = Arbitrary::arbitrary(gen);
+        let mut list = SkipListMap::new(Box::new(controller));
+
+        let length: usize = Arbitrary::arbitrary(gen);
+        for _i in 0..length {
+            list.insert(Arbitrary::arbitrary(gen), Arbitrary::arbitrary(gen));
+        }
+
+        list
+    }
+
+    /// Shrinks the entries of the list, keeping its height controller.
+    fn shrink(&self) -> Box<Iterator<Item = SkipListMap<K, V>>> {
+        let mut empty = self.clone();
+        empty.clear();
+
+        let entries: Vec<(K, V)> = self.iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+
+        Box::new(entries.shrink().map(move |entries| {
+            let mut list = empty.clone();
+            for (key, value) in entries {
+                list.insert(key, value);
+            }
+
+            list
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck::{quickcheck, TestResult};
+
+    #[test]
+    fn shrink_does_not_grow() {
+        fn prop(list: SkipListMap<u8, u8>) -> TestResult {
+            if list.is_empty() {
+                return TestResult::discard();
+            }
+
+            let lengths: Vec<usize> = list.shrink().take(10).map(|shrunk| shrunk.len()).collect();
+            TestResult::from_bool(
+                lengths.iter().all(|length| *length <= list.len()) &&
+                    lengths.iter().any(|length| *length < list.len()),
+            )
+        }
+
+        quickcheck(prop as fn(SkipListMap<u8, u8>) -> TestResult);
+    }
+}
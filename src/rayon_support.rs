@@ -0,0 +1,85 @@
+//! Parallel bulk loading through `rayon`, enabled by the `rayon` feature.
+//!
+//! `par_extend` sorts the incoming entries in parallel, cuts them into one
+//! shard per thread, and builds the nodes of every shard on its own thread.
+//! Shards cover disjoint key ranges, and are then spliced into the list one
+//! after the other, by moving their nodes over without reallocating them.
+use map::SkipListMap;
+use merge::Resolution;
+
+use std;
+use std::hash::Hash;
+
+use rayon::iter::{
+    FromParallelIterator, IndexedParallelIterator, IntoParallelIterator, ParallelExtend,
+    ParallelIterator,
+};
+use rayon::slice::ParallelSliceMut;
+
+impl<K: Ord + Send, V: Send> ParallelExtend<(K, V)> for SkipListMap<K, V> {
+    /// Inserts every entry, as `extend` does: when a key arrives more than
+    /// once, or is already in the list, the last value wins, and the key
+    /// already stored is kept.
+    ///
+    /// # Remarks
+    ///
+    /// Heights are drawn from the controller of the list, on the calling
+    /// thread and in key order. New nodes are always allocated, even if the
+    /// list has a node pool.
+    fn par_extend<I>(&mut self, entries: I)
+    where
+        I: IntoParallelIterator<Item = (K, V)>,
+    {
+        let mut entries: Vec<(K, V)> = entries.into_par_iter().collect();
+        // Stable, so that equal keys stay in arrival order.
+        entries.par_sort_by(|a, b| a.0.cmp(&b.0));
+        entries.dedup_by(|later, earlier| {
+            let duplicate = later.0 == earlier.0;
+            if duplicate {
+                std::mem::swap(&mut later.1, &mut earlier.1);
+            }
+            duplicate
+        });
+
+        if entries.is_empty() {
+            return;
+        }
+
+        let heights: Vec<usize> = entries.iter().map(|(key, _)| self.generate_height(key)).collect();
+        let shard_len = entries.len().div_ceil(rayon::current_num_threads());
+        let shards: Vec<SkipListMap<K, V>> = (0..entries.len().div_ceil(shard_len))
+            .map(|_| self.new_like())
+            .collect();
+
+        let shards: Vec<SkipListMap<K, V>> = shards
+            .into_par_iter()
+            .zip(entries.into_par_iter().zip(heights).chunks(shard_len))
+            .map(|(mut shard, chunk)| {
+                let mut fingers = shard.empty_fingers();
+                for ((key, value), height) in chunk {
+                    shard.push_back_unchecked(&mut fingers, key, value, height);
+                }
+                shard
+            })
+            .collect();
+
+        for shard in shards {
+            self.merge_with(shard, |_, _, _| Resolution::KeepTheirs);
+        }
+    }
+}
+
+impl<K, V> FromParallelIterator<(K, V)> for SkipListMap<K, V>
+where
+    K: 'static + Ord + Hash + Send,
+    V: Send,
+{
+    fn from_par_iter<I>(entries: I) -> SkipListMap<K, V>
+    where
+        I: IntoParallelIterator<Item = (K, V)>,
+    {
+        let mut list = SkipListMap::default();
+        list.par_extend(entries);
+        list
+    }
+}
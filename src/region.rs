@@ -0,0 +1,683 @@
+//! Skip List stored inside a caller-provided memory region.
+//!
+//! `RegionSkipList` keeps its header and all of its nodes inside a byte slice,
+//! and links nodes through offsets from the start of the region rather than
+//! pointers. The region can therefore be a memory mapped file, which can be
+//! opened again later, or a shared memory segment mapped at different
+//! addresses by several processes.
+//!
+//! Keys and values are stored by copying their bytes, so they must implement
+//! `Pod`. Nodes are allocated from the region itself: a bump allocator hands
+//! out fresh space, and removed nodes are kept in free lists, one per tower
+//! height, to be reused by later insertions.
+//!
+//! Lists can be created with per-node checksums, which cover the key, the
+//! height and the links of every node. They are checked by `verify` and
+//! `get_verified`, so that a torn write or external corruption is reported as
+//! an error instead of silently breaking the order of the list.
+//!
+//! The list does no synchronization of its own. Processes sharing a region
+//! must ensure that no one reads it while it is being modified, e.g. through a
+//! process-shared lock.
+use height_control::HeightControl;
+use encoding::invalid_data;
+
+use std;
+use std::convert::TryFrom;
+use std::io;
+use std::marker::PhantomData;
+use std::ptr::{self, NonNull};
+
+/// Types that can be stored in a region: plain data, without pointers or
+/// destructors, that is valid for any bit pattern written by a value of the
+/// same type.
+///
+/// # Safety
+///
+/// Implementors must not contain references, pointers, or any other data that
+/// is only meaningful within a single process, and reading their bytes back
+/// must produce a valid value. They must not have padding bytes either, since
+/// checksums read every byte of the keys.
+pub unsafe trait Pod: Copy + 'static {}
+
+macro_rules! pod {
+    ($($pod:ty),*) => {
+        $(unsafe impl Pod for $pod {})*
+    };
+}
+
+pod!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+unsafe impl<T: Pod, const N: usize> Pod for [T; N] {}
+
+const MAGIC: &[u8; 4] = b"SKLM";
+const VERSION: u32 = 2;
+
+/// Towers are at most this tall, so that searches can keep their updates on
+/// the stack, and free lists fit in the header.
+const MAX_LEVELS: usize = 32;
+
+/// Offset used as the null link. It is never a valid node, since the header
+/// lives there.
+const NULL: u64 = 0;
+
+#[repr(C)]
+struct Header {
+    magic_: [u8; 4],
+    version_: u32,
+    // Layout of the keys and values, checked when opening a region.
+    key_size_: u32,
+    key_align_: u32,
+    value_size_: u32,
+    value_align_: u32,
+    // Whether nodes carry checksums; 0 or 1.
+    checksums_: u32,
+    reserved_: u32,
+    max_height_: u64,
+    length_: u64,
+    head_: u64,
+    // First byte never handed out by the bump allocator.
+    next_free_: u64,
+    // Removed nodes, by height, linked through their level 0 link.
+    free_: [u64; MAX_LEVELS],
+}
+
+/// A node of height `height_` is followed by `height_ + 1` links.
+#[repr(C)]
+struct Node<K, V> {
+    key_: K,
+    value_: V,
+    height_: u64,
+    // Covers the key, the height and the links, if checksums are enabled.
+    checksum_: u64,
+    forward_: [u64; 0],
+}
+
+/// `SkipListMap` living in a memory region. See the `region` module.
+pub struct RegionSkipList<'a, K, V> {
+    base_: NonNull<u8>,
+    length_: usize,
+    controller_: Box<HeightControl<K>>,
+    marker_: PhantomData<(&'a mut [u8], K, V)>,
+}
+
+fn round_up(value: usize, align: usize) -> usize {
+    value.div_ceil(align) * align
+}
+
+impl<'a, K: Pod, V: Pod> RegionSkipList<'a, K, V> {
+    /// Builds an empty list at the start of `region`, discarding whatever it
+    /// held before.
+    ///
+    /// # Arguments
+    ///
+    ///  * `region`: where the list is stored. It must be aligned for both the
+    ///    keys and values, and to 8 bytes.
+    ///  * `controller`: generates heights for inserted nodes. Heights are
+    ///    capped at 31.
+    ///
+    /// # Remarks
+    ///
+    /// Fails with `io::ErrorKind::InvalidInput` if `region` is misaligned, and
+    /// with `io::ErrorKind::OutOfMemory` if it can't even hold an empty list.
+    pub fn create(
+        region: &'a mut [u8],
+        controller: Box<HeightControl<K>>,
+    ) -> io::Result<RegionSkipList<'a, K, V>> {
+        Self::create_with(region, controller, false)
+    }
+
+    /// Same as `create`, but every node carries a checksum, which is kept up
+    /// to date on every change and checked by `verify` and `get_verified`.
+    ///
+    /// # Remarks
+    ///
+    /// Values are not covered, since they can be changed in place through
+    /// `get_mut`.
+    pub fn create_with_checksums(
+        region: &'a mut [u8],
+        controller: Box<HeightControl<K>>,
+    ) -> io::Result<RegionSkipList<'a, K, V>> {
+        Self::create_with(region, controller, true)
+    }
+
+    fn create_with(
+        region: &'a mut [u8],
+        controller: Box<HeightControl<K>>,
+        checksums: bool,
+    ) -> io::Result<RegionSkipList<'a, K, V>> {
+        let mut list = Self::wrap(region, controller)?;
+        let max_height = std::cmp::min(list.controller_.max_height(), MAX_LEVELS - 1);
+
+        if list.length_ < std::mem::size_of::<Header>() {
+            return Err(io::ErrorKind::OutOfMemory.into());
+        }
+
+        unsafe {
+            ptr::write(
+                list.header_mut(),
+                Header {
+                    magic_: *MAGIC,
+                    version_: VERSION,
+                    key_size_: std::mem::size_of::<K>() as u32,
+                    key_align_: std::mem::align_of::<K>() as u32,
+                    value_size_: std::mem::size_of::<V>() as u32,
+                    value_align_: std::mem::align_of::<V>() as u32,
+                    checksums_: checksums as u32,
+                    reserved_: 0,
+                    max_height_: max_height as u64,
+                    length_: 0,
+                    head_: NULL,
+                    next_free_: std::mem::size_of::<Header>() as u64,
+                    free_: [NULL; MAX_LEVELS],
+                },
+            );
+
+            // The head is never freed, so it always sits right after the
+            // header.
+            let head = list.allocate(max_height)?;
+            list.header_mut().head_ = head;
+            list.seal(head);
+        }
+
+        Ok(list)
+    }
+
+    /// Opens a list previously built with `create` in `region`. Its contents
+    /// are left untouched.
+    ///
+    /// # Arguments
+    ///
+    ///  * `region`: where the list is stored. It must be aligned as required
+    ///    by `create`.
+    ///  * `controller`: generates heights for inserted nodes. Heights are
+    ///    capped at the maximum height the list was created with.
+    ///
+    /// # Safety
+    ///
+    /// Only the header is validated: the nodes are trusted to be those written
+    /// by a `RegionSkipList` with the same key and value types.
+    ///
+    /// # Remarks
+    ///
+    /// Fails with `io::ErrorKind::InvalidData` if the header was not written
+    /// by `create`, or was written for keys or values of another layout.
+    pub unsafe fn open(
+        region: &'a mut [u8],
+        controller: Box<HeightControl<K>>,
+    ) -> io::Result<RegionSkipList<'a, K, V>> {
+        let list = Self::wrap(region, controller)?;
+        if list.length_ < std::mem::size_of::<Header>() {
+            return Err(invalid_data("region is too small to hold a skip list"));
+        }
+
+        let header = list.header();
+        if header.magic_ != *MAGIC {
+            return Err(invalid_data("region does not hold a skip list"));
+        }
+
+        if header.version_ != VERSION {
+            return Err(invalid_data("unsupported region version"));
+        }
+
+        if header.key_size_ as usize != std::mem::size_of::<K>()
+            || header.key_align_ as usize != std::mem::align_of::<K>()
+            || header.value_size_ as usize != std::mem::size_of::<V>()
+            || header.value_align_ as usize != std::mem::align_of::<V>()
+        {
+            return Err(invalid_data("region holds keys or values of another type"));
+        }
+
+        if header.max_height_ as usize >= MAX_LEVELS
+            || header.checksums_ > 1
+            || header.next_free_ > list.length_ as u64
+            || header.head_ != round_up(std::mem::size_of::<Header>(), Self::align()) as u64
+        {
+            return Err(invalid_data("corrupted region header"));
+        }
+
+        Ok(list)
+    }
+
+    fn wrap(
+        region: &'a mut [u8],
+        controller: Box<HeightControl<K>>,
+    ) -> io::Result<RegionSkipList<'a, K, V>> {
+        if !(region.as_ptr() as usize).is_multiple_of(Self::align()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "region is not properly aligned",
+            ));
+        }
+
+        Ok(RegionSkipList {
+            base_: NonNull::new(region.as_mut_ptr()).unwrap(),
+            length_: region.len(),
+            controller_: controller,
+            marker_: PhantomData,
+        })
+    }
+
+    /// Alignment of both the header and the nodes.
+    fn align() -> usize {
+        std::cmp::max(std::mem::align_of::<Header>(), std::mem::align_of::<Node<K, V>>())
+    }
+
+    fn node_size(height: usize) -> usize {
+        let links = std::mem::offset_of!(Node<K, V>, forward_);
+        round_up(links + (height + 1) * std::mem::size_of::<u64>(), Self::align())
+    }
+
+    /// Returns where a node of the given height that starts at `offset` ends,
+    /// or `None` if that overflows, as it may for offsets and heights read
+    /// from corrupted memory.
+    fn checked_node_end(offset: usize, height: usize) -> Option<usize> {
+        let links = height.checked_add(1)?.checked_mul(std::mem::size_of::<u64>())?;
+        let size = std::mem::offset_of!(Node<K, V>, forward_)
+            .checked_add(links)?
+            .checked_next_multiple_of(Self::align())?;
+        offset.checked_add(size)
+    }
+
+    fn header(&self) -> &Header {
+        unsafe { &*(self.base_.as_ptr() as *const Header) }
+    }
+
+    fn header_mut(&mut self) -> &mut Header {
+        unsafe { &mut *(self.base_.as_ptr() as *mut Header) }
+    }
+
+    fn node(&self, offset: u64) -> *mut Node<K, V> {
+        debug_assert!(offset != NULL && (offset as usize) < self.length_);
+        unsafe { self.base_.as_ptr().add(offset as usize) as *mut Node<K, V> }
+    }
+
+    fn height_of(&self, offset: u64) -> usize {
+        unsafe { (*self.node(offset)).height_ as usize }
+    }
+
+    fn key(&self, offset: u64) -> &K {
+        unsafe { &(*self.node(offset)).key_ }
+    }
+
+    fn value(&self, offset: u64) -> &V {
+        unsafe { &(*self.node(offset)).value_ }
+    }
+
+    fn link(&self, offset: u64, level: usize) -> *mut u64 {
+        debug_assert!(level <= self.height_of(offset));
+        unsafe {
+            let forward = ptr::addr_of_mut!((*self.node(offset)).forward_) as *mut u64;
+            forward.add(level)
+        }
+    }
+
+    fn next(&self, offset: u64, level: usize) -> u64 {
+        unsafe { *self.link(offset, level) }
+    }
+
+    /// Returns `true` if the list was created through `create_with_checksums`.
+    pub fn has_checksums(&self) -> bool {
+        self.header().checksums_ != 0
+    }
+
+    /// Computes the FNV-1a hash of the key, the height and the links of the
+    /// node at `offset`.
+    fn checksum(&self, offset: u64) -> u64 {
+        let key = unsafe {
+            std::slice::from_raw_parts(
+                ptr::addr_of!((*self.node(offset)).key_) as *const u8,
+                std::mem::size_of::<K>(),
+            )
+        };
+
+        let height = self.height_of(offset);
+        let links = (0..=height).map(|level| self.next(offset, level));
+        let words = std::iter::once(height as u64).chain(links);
+        key.iter()
+            .cloned()
+            .chain(words.flat_map(u64::to_le_bytes))
+            .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+            })
+    }
+
+    /// Updates the checksum of the node at `offset`, after it was changed.
+    fn seal(&mut self, offset: u64) {
+        if self.has_checksums() {
+            let checksum = self.checksum(offset);
+            unsafe {
+                (*self.node(offset)).checksum_ = checksum;
+            }
+        }
+    }
+
+    /// Checks that `offset` points to a whole node within the region, and
+    /// that its checksum matches, if checksums are enabled.
+    fn check_node(&self, offset: u64) -> io::Result<()> {
+        let out_of_bounds = || invalid_data(&format!("link to {} is out of bounds", offset));
+        let offset = usize::try_from(offset).map_err(|_| out_of_bounds())?;
+        if !offset.is_multiple_of(Self::align())
+            || offset < std::mem::size_of::<Header>()
+            || Self::checked_node_end(offset, 0).is_none_or(|end| end > self.length_)
+        {
+            return Err(out_of_bounds());
+        }
+
+        let height = self.height_of(offset as u64);
+        if height > self.header().max_height_ as usize
+            || Self::checked_node_end(offset, height).is_none_or(|end| end > self.length_)
+        {
+            return Err(invalid_data(&format!("node at {} has a corrupted height", offset)));
+        }
+
+        if self.has_checksums() {
+            let stored = unsafe { (*self.node(offset as u64)).checksum_ };
+            if stored != self.checksum(offset as u64) {
+                return Err(invalid_data(&format!("checksum mismatch in node at {}", offset)));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Hands out an unlinked node of the given height, with its key and value
+    /// left as they were.
+    fn allocate(&mut self, height: usize) -> io::Result<u64> {
+        let free = self.header().free_[height];
+        let offset = if free != NULL {
+            self.header_mut().free_[height] = self.next(free, 0);
+            free
+        } else {
+            let offset = round_up(self.header().next_free_ as usize, Self::align());
+            let end = offset + Self::node_size(height);
+            if end > self.length_ {
+                return Err(io::ErrorKind::OutOfMemory.into());
+            }
+
+            self.header_mut().next_free_ = end as u64;
+            offset as u64
+        };
+
+        unsafe {
+            (*self.node(offset)).height_ = height as u64;
+        }
+        for level in 0..=height {
+            unsafe {
+                *self.link(offset, level) = NULL;
+            }
+        }
+
+        Ok(offset)
+    }
+
+    fn free(&mut self, offset: u64) {
+        let height = self.height_of(offset);
+        let next = self.header().free_[height];
+        unsafe {
+            *self.link(offset, 0) = next;
+        }
+        self.header_mut().free_[height] = offset;
+    }
+
+    /// Returns the number of elements stored in the list.
+    pub fn len(&self) -> usize {
+        self.header().length_ as usize
+    }
+
+    /// Returns `true` if there are no elements stored in the list.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of bytes of the region in use, including removed
+    /// nodes waiting to be reused.
+    pub fn used_bytes(&self) -> usize {
+        self.header().next_free_ as usize
+    }
+
+    /// Removes all elements. Their space is given back to the allocator.
+    pub fn clear(&mut self) {
+        let head = self.header().head_;
+        let max_height = self.header().max_height_ as usize;
+        for level in 0..=max_height {
+            unsafe {
+                *self.link(head, level) = NULL;
+            }
+        }
+        self.seal(head);
+
+        let header = self.header_mut();
+        header.length_ = 0;
+        header.next_free_ = head + Self::node_size(max_height) as u64;
+        header.free_ = [NULL; MAX_LEVELS];
+    }
+
+    /// Iterates over the entries, in key order.
+    pub fn iter(&self) -> RegionIter<'_, K, V> {
+        RegionIter {
+            list_: self,
+            current_: self.next(self.header().head_, 0),
+        }
+    }
+}
+
+impl<'a, K: Pod + Ord, V: Pod> RegionSkipList<'a, K, V> {
+    /// Finds, for every level, the last node with a key less than `key`.
+    fn find_updates(&self, key: &K) -> [u64; MAX_LEVELS] {
+        let mut updates = [NULL; MAX_LEVELS];
+        let mut current = self.header().head_;
+        for level in (0..=self.header().max_height_ as usize).rev() {
+            loop {
+                let next = self.next(current, level);
+                if next == NULL || self.key(next) >= key {
+                    break;
+                }
+                current = next;
+            }
+
+            updates[level] = current;
+        }
+
+        updates
+    }
+
+    /// Returns the node holding `key`, if it exists.
+    fn find(&self, key: &K) -> Option<u64> {
+        let mut current = self.header().head_;
+        for level in (0..=self.header().max_height_ as usize).rev() {
+            loop {
+                let next = self.next(current, level);
+                if next == NULL {
+                    break;
+                }
+
+                match self.key(next).cmp(key) {
+                    std::cmp::Ordering::Less => current = next,
+                    std::cmp::Ordering::Equal => return Some(next),
+                    std::cmp::Ordering::Greater => break,
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Inserts `value` under `key`, returning the value it replaced, if any.
+    ///
+    /// # Remarks
+    ///
+    /// Fails with `io::ErrorKind::OutOfMemory` when the region is full, in
+    /// which case the list is left untouched.
+    pub fn insert(&mut self, key: K, value: V) -> io::Result<Option<V>> {
+        let updates = self.find_updates(&key);
+        let candidate = self.next(updates[0], 0);
+        if candidate != NULL && *self.key(candidate) == key {
+            let previous = std::mem::replace(unsafe { &mut (*self.node(candidate)).value_ }, value);
+            return Ok(Some(previous));
+        }
+
+        let max_height = self.header().max_height_ as usize;
+        let height = std::cmp::min(self.controller_.get_height(&key), max_height);
+        let node = self.allocate(height)?;
+        unsafe {
+            ptr::write(ptr::addr_of_mut!((*self.node(node)).key_), key);
+            ptr::write(ptr::addr_of_mut!((*self.node(node)).value_), value);
+        }
+
+        for (level, &update) in updates.iter().enumerate().take(height + 1) {
+            unsafe {
+                *self.link(node, level) = self.next(update, level);
+                *self.link(update, level) = node;
+            }
+            self.seal(update);
+        }
+
+        self.seal(node);
+        self.header_mut().length_ += 1;
+        Ok(None)
+    }
+
+    /// Returns a const reference to the element with key `key`, if it exists.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.find(key).map(|node| self.value(node))
+    }
+
+    /// Same as `get`, but checks every node the search goes through with
+    /// `check_node` before trusting it.
+    ///
+    /// # Remarks
+    ///
+    /// Fails with `io::ErrorKind::InvalidData` if one of those nodes lies
+    /// outside the region, or its checksum does not match.
+    pub fn get_verified(&self, key: &K) -> io::Result<Option<&V>> {
+        let mut current = self.header().head_;
+        self.check_node(current)?;
+        for level in (0..=self.header().max_height_ as usize).rev() {
+            loop {
+                let next = self.next(current, level);
+                if next == NULL {
+                    break;
+                }
+
+                self.check_node(next)?;
+                match self.key(next).cmp(key) {
+                    std::cmp::Ordering::Less => current = next,
+                    std::cmp::Ordering::Equal => return Ok(Some(self.value(next))),
+                    std::cmp::Ordering::Greater => break,
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Sweeps the whole list, checking that every node lies within the region
+    /// and matches its checksum, that keys are in strictly increasing order
+    /// on every level, and that the length in the header is right.
+    ///
+    /// # Remarks
+    ///
+    /// This is O(n), and only reads the region. Without checksums, corruption
+    /// that keeps the list well formed, e.g. in a single key, goes unnoticed.
+    pub fn verify(&self) -> io::Result<()> {
+        let head = self.header().head_;
+        self.check_node(head)?;
+
+        let mut length = 0;
+        let mut current = self.next(head, 0);
+        while current != NULL {
+            self.check_node(current)?;
+            length += 1;
+            if length > self.len() {
+                return Err(invalid_data("list is longer than its length"));
+            }
+
+            for level in 0..=self.height_of(current) {
+                let next = self.next(current, level);
+                if next == NULL {
+                    continue;
+                }
+
+                self.check_node(next)?;
+                if self.height_of(next) < level || self.key(next) <= self.key(current) {
+                    return Err(invalid_data(&format!("node at {} is out of order", next)));
+                }
+            }
+
+            current = self.next(current, 0);
+        }
+
+        if length != self.len() {
+            return Err(invalid_data("list is shorter than its length"));
+        }
+
+        Ok(())
+    }
+
+    /// Returns a mutable reference to the element with key `key`, if it
+    /// exists.
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.find(key)
+            .map(|node| unsafe { &mut (*self.node(node)).value_ })
+    }
+
+    /// Returns true if `key` is in the list.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.find(key).is_some()
+    }
+
+    /// Removes the element with key `key`, returning its value if it existed.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let updates = self.find_updates(key);
+        let target = self.next(updates[0], 0);
+        if target == NULL || self.key(target) != key {
+            return None;
+        }
+
+        for (level, &update) in updates.iter().enumerate().take(self.height_of(target) + 1) {
+            if self.next(update, level) == target {
+                unsafe {
+                    *self.link(update, level) = self.next(target, level);
+                }
+                self.seal(update);
+            }
+        }
+
+        let value = *self.value(target);
+        self.free(target);
+        self.header_mut().length_ -= 1;
+        Some(value)
+    }
+}
+
+impl<'a, K, V> std::fmt::Debug for RegionSkipList<'a, K, V>
+where
+    K: Pod + std::fmt::Debug,
+    V: Pod + std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Iterator over the entries of a `RegionSkipList`, in key order.
+pub struct RegionIter<'b, K: 'b, V: 'b> {
+    list_: &'b RegionSkipList<'b, K, V>,
+    current_: u64,
+}
+
+impl<'b, K: Pod, V: Pod> Iterator for RegionIter<'b, K, V> {
+    type Item = (&'b K, &'b V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.current_ == NULL {
+            return None;
+        }
+
+        let list = self.list_;
+        let node = self.current_;
+        self.current_ = list.next(node, 0);
+        Some((list.key(node), list.value(node)))
+    }
+}
@@ -0,0 +1,181 @@
+//! Zero-copy archival through `rkyv`, enabled by the `rkyv` feature.
+//!
+//! A `SkipListMap` archives into a flat array of entries sorted by key. The
+//! archived form has no pointers to follow, so it can be used straight from a
+//! memory mapped file: lookups binary search the entries without deserializing
+//! anything.
+use map::SkipListMap;
+use height_control::TwoPowGenerator;
+
+use std::cmp::Ordering;
+
+use rkyv::munge::munge;
+use rkyv::rancor::{Fallible, Source};
+use rkyv::ser::{Allocator, Writer};
+use rkyv::tuple::ArchivedTuple2;
+use rkyv::vec::{ArchivedVec, VecResolver};
+use rkyv::{Archive, Deserialize, Place, Serialize};
+
+/// Archived `SkipListMap`. It is read-only, and searches are O(log n) binary
+/// searches over the entries.
+#[derive(rkyv::Portable, rkyv::bytecheck::CheckBytes)]
+#[bytecheck(crate = rkyv::bytecheck)]
+#[repr(transparent)]
+pub struct ArchivedSkipListMap<K, V> {
+    entries_: ArchivedVec<ArchivedTuple2<K, V>>,
+}
+
+impl<K, V> ArchivedSkipListMap<K, V> {
+    /// Returns the number of elements stored in the archive.
+    pub fn len(&self) -> usize {
+        self.entries_.len()
+    }
+
+    /// Returns `true` if there are no elements stored in the archive.
+    pub fn is_empty(&self) -> bool {
+        self.entries_.is_empty()
+    }
+
+    /// Returns the archived value for `key`, if it exists.
+    ///
+    /// # Arguments
+    ///
+    ///  * `key`: the key to look for. It is compared against the archived
+    ///    keys, so it may be either a native or an archived key.
+    ///
+    /// # Remarks
+    ///
+    /// Archived keys that can't be compared to `key` are treated as greater.
+    pub fn get<Q: ?Sized>(&self, key: &Q) -> Option<&V>
+    where
+        K: PartialOrd<Q>,
+    {
+        self.entries_
+            .binary_search_by(|entry| entry.0.partial_cmp(key).unwrap_or(Ordering::Greater))
+            .ok()
+            .map(|index| &self.entries_[index].1)
+    }
+
+    /// Returns true if `key` is in the archive.
+    pub fn contains_key<Q: ?Sized>(&self, key: &Q) -> bool
+    where
+        K: PartialOrd<Q>,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Iterates over the archived entries, in key order.
+    pub fn iter(&self) -> ArchivedIter<'_, K, V> {
+        ArchivedIter(self.entries_.iter())
+    }
+}
+
+pub struct ArchivedIter<'a, K: 'a, V: 'a>(std::slice::Iter<'a, ArchivedTuple2<K, V>>);
+
+impl<'a, K: 'a, V: 'a> Iterator for ArchivedIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|entry| (&entry.0, &entry.1))
+    }
+}
+
+/// Borrowed entry of the list, archived as a tuple. The list has no contiguous
+/// storage to archive from, so entries are gathered into these first.
+struct EntryRef<'a, K: 'a, V: 'a>(&'a K, &'a V);
+
+impl<'a, K: Archive, V: Archive> Archive for EntryRef<'a, K, V> {
+    type Archived = ArchivedTuple2<K::Archived, V::Archived>;
+    type Resolver = (K::Resolver, V::Resolver);
+
+    fn resolve(&self, resolver: Self::Resolver, out: Place<Self::Archived>) {
+        munge!(let ArchivedTuple2(key, value) = out);
+        self.0.resolve(resolver.0, key);
+        self.1.resolve(resolver.1, value);
+    }
+}
+
+impl<'a, K, V, S> Serialize<S> for EntryRef<'a, K, V>
+where
+    K: Serialize<S>,
+    V: Serialize<S>,
+    S: Fallible + ?Sized,
+{
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        Ok((self.0.serialize(serializer)?, self.1.serialize(serializer)?))
+    }
+}
+
+impl<K: Archive, V: Archive> Archive for SkipListMap<K, V> {
+    type Archived = ArchivedSkipListMap<K::Archived, V::Archived>;
+    type Resolver = VecResolver;
+
+    fn resolve(&self, resolver: Self::Resolver, out: Place<Self::Archived>) {
+        munge!(let ArchivedSkipListMap { entries_ } = out);
+        ArchivedVec::resolve_from_len(self.len(), resolver, entries_);
+    }
+}
+
+impl<K, V, S> Serialize<S> for SkipListMap<K, V>
+where
+    K: Serialize<S>,
+    V: Serialize<S>,
+    S: Fallible + Allocator + Writer + ?Sized,
+{
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        let entries: Vec<EntryRef<K, V>> =
+            self.iter().map(|(key, value)| EntryRef(key, value)).collect();
+        ArchivedVec::serialize_from_slice(&entries, serializer)
+    }
+}
+
+impl<K, V, D> Deserialize<SkipListMap<K, V>, D> for ArchivedSkipListMap<K::Archived, V::Archived>
+where
+    K: 'static + Ord + Archive,
+    V: Archive,
+    K::Archived: Deserialize<K, D>,
+    V::Archived: Deserialize<V, D>,
+    D: Fallible + ?Sized,
+    D::Error: Source,
+{
+    fn deserialize(&self, deserializer: &mut D) -> Result<SkipListMap<K, V>, D::Error> {
+        let mut list = SkipListMap::new(Box::new(TwoPowGenerator::new(16)));
+        for entry in self.entries_.iter() {
+            list.insert(
+                entry.0.deserialize(deserializer)?,
+                entry.1.deserialize(deserializer)?,
+            );
+        }
+
+        Ok(list)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rkyv::rancor::Error;
+
+    #[test]
+    fn archived_lookups() {
+        let mut list: SkipListMap<u32, String> = Default::default();
+        for i in (0..100).rev() {
+            list.insert(i * 2, i.to_string());
+        }
+
+        let bytes = rkyv::to_bytes::<Error>(&list).unwrap();
+        let archived = rkyv::access::<rkyv::Archived<SkipListMap<u32, String>>, Error>(&bytes)
+            .unwrap();
+
+        let key = |key: u32| rkyv::Archived::<u32>::from_native(key);
+        assert_eq!(archived.len(), 100);
+        assert_eq!(archived.get(&key(42)).map(|value| value.as_str()), Some("21"));
+        assert!(!archived.contains_key(&key(43)));
+        assert!(archived.iter().map(|(key, _)| key.to_native()).eq((0..100).map(|i| i * 2)));
+
+        let deserialized: SkipListMap<u32, String> =
+            rkyv::deserialize::<_, Error>(archived).unwrap();
+        assert!(deserialized.iter().eq(list.iter()));
+    }
+}
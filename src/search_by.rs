@@ -0,0 +1,65 @@
+//! Searches driven by comparison closures instead of the ordering of the keys,
+//! e.g. to look up composite keys by one of their parts.
+//!
+//! The closures compare a key with the target of the search, and must be
+//! consistent with the ordering of the map: every key they find `Less` must
+//! come before every key they find `Equal`, and those before every key they
+//! find `Greater`. Otherwise, the results are unspecified, but memory safe.
+use map::SkipListMap;
+use node::Node;
+use iter::Range;
+
+use std;
+use std::cmp::Ordering;
+
+impl<K, V> SkipListMap<K, V> {
+    /// Finds the last node for which `before` holds, or the head if there is
+    /// none. `before` must hold for a prefix of the keys.
+    fn find_last_by<F>(&self, mut before: F) -> &Node<K, V>
+    where
+        F: FnMut(&K) -> bool,
+    {
+        let mut current = self.head();
+        for height in (0..std::cmp::max(self.height_, 1)).rev() {
+            while let Some(next) = current.next(height) {
+                if before(next.key()) {
+                    current = next;
+                } else {
+                    break;
+                }
+            }
+        }
+
+        current
+    }
+
+    /// Returns the entry with the smallest key that `compare` finds `Equal`
+    /// to its target, if any. `compare` returns how a key is ordered relative
+    /// to the target, as for `slice::binary_search_by`.
+    pub fn search_by<F>(&self, mut compare: F) -> Option<(&K, &V)>
+    where
+        F: FnMut(&K) -> Ordering,
+    {
+        let before = self.find_last_by(|key| compare(key) == Ordering::Less);
+        before
+            .next(0)
+            .filter(|node| compare(node.key()) == Ordering::Equal)
+            .map(|node| node.key_value())
+    }
+
+    /// Iterates over the entries from the first key that `start` doesn't find
+    /// `Less` than its target, up to the last key that `end` doesn't find
+    /// `Greater`. Both ends are included; ends that exclude their target can
+    /// be had by returning `Less` from `start`, or `Greater` from `end`, for
+    /// the keys that are `Equal` to it.
+    pub fn range_by<S, E>(&self, mut start: S, mut end: E) -> Range<'_, K, V>
+    where
+        S: FnMut(&K) -> Ordering,
+        E: FnMut(&K) -> Ordering,
+    {
+        let first = self.find_last_by(|key| start(key) == Ordering::Less).next(0);
+        let first = first.filter(|node| end(node.key()) != Ordering::Greater);
+        let last = first.map(|_| self.find_last_by(|key| end(key) != Ordering::Greater));
+        Range::from_bounds(self, first, last)
+    }
+}
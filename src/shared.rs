@@ -0,0 +1,36 @@
+//! Keys shared through `Arc`.
+//!
+//! A `SkipListMap<Arc<T>, V>` can be searched with a plain `&T`, and hands out
+//! its keys as `Arc<T>` handles, so several maps can index the same set of keys
+//! while storing each of them only once. Re-inserting an existing key keeps
+//! the handle already in the map, like `insert` does for any other key type.
+use map::SkipListMap;
+
+use std::sync::Arc;
+
+impl<T: Ord, V> SkipListMap<Arc<T>, V> {
+    /// Inserts `value` under `key`, and returns the handle to the key stored in
+    /// the list, along with the value it replaced, if any.
+    ///
+    /// # Remarks
+    ///
+    /// When `key` is already present, its existing handle is reused, and no
+    /// new `Arc` is allocated.
+    pub fn insert_shared(&mut self, key: T, value: V) -> (Arc<T>, Option<V>) {
+        if let Some(node) = self.find_lower_bound_mut(&key).next_mut(0) {
+            if node.key::<T>() == &key {
+                let shared = node.key::<Arc<T>>().clone();
+                return (shared, Some(node.replace_value(value)));
+            }
+        }
+
+        let shared = Arc::new(key);
+        self.insert(shared.clone(), value);
+        (shared, None)
+    }
+
+    /// Returns the handle to the stored key equal to `key`, if it exists.
+    pub fn shared_key(&self, key: &T) -> Option<Arc<T>> {
+        self.get_key_value(key).map(|(shared, _)| shared.clone())
+    }
+}
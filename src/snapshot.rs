@@ -0,0 +1,96 @@
+use map::SkipListMap;
+use height_control::HeightControl;
+use encoding::{invalid_data, Encode};
+
+use std;
+use std::io::{self, Read, Write};
+
+/// Identifies snapshot files, followed by the format version.
+const MAGIC: &[u8; 4] = b"SKLS";
+const VERSION: u8 = 1;
+
+/// Snapshots are built from untrusted input, so heights above this, and above
+/// the controller's maximum height, are rejected rather than allocated.
+const MAX_HEIGHT: usize = 64;
+
+impl<K: Encode, V: Encode> SkipListMap<K, V> {
+    /// Writes the entries, in order, along with the height of their towers.
+    /// Reading the snapshot back with `read_from` reproduces the exact same
+    /// structure.
+    ///
+    /// # Arguments
+    ///
+    ///  * `writer`: destination of the snapshot. Since the snapshot is written
+    ///    in many small pieces, it should be buffered.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        VERSION.encode(&mut writer)?;
+        self.len().encode(&mut writer)?;
+
+        let mut current = self.head().next(0);
+        while let Some(node) = current {
+            node.height().encode(&mut writer)?;
+            node.key::<K>().encode(&mut writer)?;
+            node.value::<V>().encode(&mut writer)?;
+            current = node.next(0);
+        }
+
+        writer.flush()
+    }
+}
+
+impl<K: Ord + Encode, V: Encode> SkipListMap<K, V> {
+    /// Builds a list from a snapshot written by `write_to`. Every node gets the
+    /// height it had when the snapshot was taken, so the list is rebuilt in
+    /// O(n) without generating any height.
+    ///
+    /// # Arguments
+    ///
+    ///  * `reader`: source of the snapshot. Since the snapshot is read in many
+    ///    small pieces, it should be buffered.
+    ///  * `controller`: generates heights for nodes inserted afterwards.
+    ///
+    /// # Remarks
+    ///
+    /// Fails with `io::ErrorKind::InvalidData` if the snapshot is malformed,
+    /// including when keys are not strictly increasing.
+    pub fn read_from<R: Read>(
+        mut reader: R,
+        controller: Box<HeightControl<K>>,
+    ) -> io::Result<SkipListMap<K, V>> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("not a skip list snapshot"));
+        }
+
+        if u8::decode(&mut reader)? != VERSION {
+            return Err(invalid_data("unsupported snapshot version"));
+        }
+
+        let max_height = std::cmp::max(controller.max_height(), MAX_HEIGHT);
+        let mut list = SkipListMap::new(controller);
+        let mut fingers = list.empty_fingers();
+
+        let length = usize::decode(&mut reader)?;
+        for _ in 0..length {
+            let height = usize::decode(&mut reader)?;
+            if height > max_height {
+                return Err(invalid_data("tower height out of range"));
+            }
+
+            let key = K::decode(&mut reader)?;
+            let value = V::decode(&mut reader)?;
+
+            // The last node linked at level 0 is the greatest one so far.
+            let last = unsafe { fingers[0].as_ref() };
+            if !std::ptr::eq(last, list.head()) && *last.key::<K>() >= key {
+                return Err(invalid_data("keys are not strictly increasing"));
+            }
+
+            list.push_back_unchecked(&mut fingers, key, value, height);
+        }
+
+        Ok(list)
+    }
+}
@@ -0,0 +1,271 @@
+//! Sorted runs: immutable files holding entries in key order, as written by
+//! `SkipListMap::flush_to`.
+//!
+//! This is what an LSM tree flushes its memtable into. `RunReader` streams the
+//! entries of a run back, and `merge` combines several runs into a single
+//! sorted stream. Deletions can be represented by using `Option` values as
+//! tombstones.
+//!
+//! A run is laid out as follows, with numbers encoded as `Encode` does:
+//!
+//!  1. A header: the `SKLR` magic and a version byte.
+//!  2. The entries, each prefixed by the length of its encoded key and value.
+//!  3. A `u64::MAX` length, which marks the end of the entries.
+//!  4. The block index: its length, followed by the first key of each block
+//!     and the offset of its first entry. It is empty if the run was written
+//!     without one.
+//!  5. A footer: the offset of the block index, the number of entries, and the
+//!     magic again.
+use map::SkipListMap;
+use merge::{Merger, Resolution};
+use encoding::{invalid_data, Encode};
+
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+const MAGIC: &[u8; 4] = b"SKLR";
+const VERSION: u8 = 1;
+const HEADER_LENGTH: u64 = 5;
+const FOOTER_LENGTH: i64 = 20;
+const END_OF_ENTRIES: u64 = u64::MAX;
+
+/// Keeps track of the number of bytes written so far.
+struct CountingWriter<W> {
+    inner_: W,
+    written_: u64,
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        let written = self.inner_.write(buffer)?;
+        self.written_ += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner_.flush()
+    }
+}
+
+impl<K: Encode, V: Encode> SkipListMap<K, V> {
+    /// Writes all entries, in key order, as a sorted run without a block
+    /// index. See the `sorted_run` module for the format.
+    ///
+    /// # Arguments
+    ///
+    ///  * `writer`: destination of the run. Since it is written in many small
+    ///    pieces, it should be buffered.
+    pub fn flush_to<W: Write>(&self, writer: W) -> io::Result<()> {
+        self.write_run(writer, None)
+    }
+
+    /// Same as `flush_to`, but also writes a block index, which allows
+    /// `RunReader::seek` to jump close to a key.
+    ///
+    /// # Arguments
+    ///
+    ///  * `writer`: destination of the run.
+    ///  * `block_size`: number of entries per block. The index holds the first
+    ///    key of every block.
+    pub fn flush_to_indexed<W: Write>(&self, writer: W, block_size: usize) -> io::Result<()> {
+        assert!(block_size > 0);
+        self.write_run(writer, Some(block_size))
+    }
+
+    fn write_run<W: Write>(&self, writer: W, block_size: Option<usize>) -> io::Result<()> {
+        let mut writer = CountingWriter {
+            inner_: writer,
+            written_: 0,
+        };
+
+        writer.write_all(MAGIC)?;
+        VERSION.encode(&mut writer)?;
+
+        // Index entries borrow their keys from the list.
+        let mut index: Vec<(&K, u64)> = Vec::new();
+        let mut entry = Vec::new();
+        for (position, (key, value)) in self.iter().enumerate() {
+            if block_size.is_some_and(|size| position % size == 0) {
+                index.push((key, writer.written_));
+            }
+
+            entry.clear();
+            key.encode(&mut entry)?;
+            value.encode(&mut entry)?;
+            (entry.len() as u64).encode(&mut writer)?;
+            writer.write_all(&entry)?;
+        }
+
+        END_OF_ENTRIES.encode(&mut writer)?;
+
+        let index_offset = writer.written_;
+        index.len().encode(&mut writer)?;
+        for &(key, offset) in &index {
+            key.encode(&mut writer)?;
+            offset.encode(&mut writer)?;
+        }
+
+        index_offset.encode(&mut writer)?;
+        self.len().encode(&mut writer)?;
+        writer.write_all(MAGIC)?;
+        writer.flush()
+    }
+}
+
+/// Streams the entries of a sorted run, in key order.
+pub struct RunReader<K, V, R> {
+    reader_: R,
+    done_: bool,
+    peeked_: Option<(K, V)>,
+    index_: Option<Vec<(K, u64)>>,
+}
+
+impl<K: Encode, V: Encode, R: Read> RunReader<K, V, R> {
+    /// Builds a new `RunReader`, positioned at the first entry of the run.
+    ///
+    /// # Arguments
+    ///
+    ///  * `reader`: source of the run, positioned at its start. Since it is
+    ///    read in many small pieces, it should be buffered.
+    pub fn new(mut reader: R) -> io::Result<RunReader<K, V, R>> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("not a sorted run"));
+        }
+
+        if u8::decode(&mut reader)? != VERSION {
+            return Err(invalid_data("unsupported sorted run version"));
+        }
+
+        Ok(RunReader {
+            reader_: reader,
+            done_: false,
+            peeked_: None,
+            index_: None,
+        })
+    }
+
+    fn read_entry(&mut self) -> io::Result<Option<(K, V)>> {
+        if let Some(entry) = self.peeked_.take() {
+            return Ok(Some(entry));
+        }
+
+        if self.done_ {
+            return Ok(None);
+        }
+
+        let length = u64::decode(&mut self.reader_)?;
+        if length == END_OF_ENTRIES {
+            self.done_ = true;
+            return Ok(None);
+        }
+
+        let mut bytes = Vec::new();
+        (&mut self.reader_).take(length).read_to_end(&mut bytes)?;
+        if bytes.len() as u64 != length {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        let mut entry = &bytes[..];
+        let key = K::decode(&mut entry)?;
+        let value = V::decode(&mut entry)?;
+        if !entry.is_empty() {
+            return Err(invalid_data("entry is longer than its contents"));
+        }
+
+        Ok(Some((key, value)))
+    }
+}
+
+impl<K: Ord + Encode, V: Encode, R: Read + Seek> RunReader<K, V, R> {
+    /// Moves the reader to the first entry with a key greater or equal than
+    /// `key`. Uses the block index if the run has one, so that only a single
+    /// block is scanned; otherwise, every entry before `key` is read.
+    pub fn seek(&mut self, key: &K) -> io::Result<()> {
+        if self.index_.is_none() {
+            self.index_ = Some(self.read_index()?);
+        }
+
+        let offset = {
+            let index = self.index_.as_ref().unwrap();
+            // The block to scan is the last one starting at or before `key`.
+            match index.binary_search_by(|(first, _)| first.cmp(key)) {
+                Ok(block) => index[block].1,
+                Err(0) => HEADER_LENGTH,
+                Err(block) => index[block - 1].1,
+            }
+        };
+
+        self.reader_.seek(SeekFrom::Start(offset))?;
+        self.done_ = false;
+        self.peeked_ = None;
+
+        while let Some((current, value)) = self.read_entry()? {
+            if current >= *key {
+                self.peeked_ = Some((current, value));
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_index(&mut self) -> io::Result<Vec<(K, u64)>> {
+        self.reader_.seek(SeekFrom::End(-FOOTER_LENGTH))?;
+        let index_offset = u64::decode(&mut self.reader_)?;
+        let _length = u64::decode(&mut self.reader_)?;
+        let mut magic = [0; 4];
+        self.reader_.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("sorted run footer is missing"));
+        }
+
+        self.reader_.seek(SeekFrom::Start(index_offset))?;
+        Vec::<(K, u64)>::decode(&mut self.reader_)
+    }
+}
+
+impl<K: Encode, V: Encode, R: Read> Iterator for RunReader<K, V, R> {
+    type Item = io::Result<(K, V)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.read_entry() {
+            Ok(entry) => entry.map(Ok),
+            Err(error) => {
+                // There is no way of resynchronizing after an error.
+                self.done_ = true;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+/// Sorted stream over the entries of several runs, as returned by `merge`.
+pub struct MergedRuns<K, V, R> {
+    merger_: Merger<K, V, RunReader<K, V, R>, io::Error>,
+}
+
+/// Merges `runs` into a single stream of entries in key order. Runs must be
+/// given from oldest to newest: when a key is present in many of them, only
+/// the entry from the newest one is returned.
+///
+/// After returning an error, the stream ends.
+pub fn merge<K, V, R>(runs: Vec<RunReader<K, V, R>>) -> MergedRuns<K, V, R>
+where
+    K: Ord + Encode,
+    V: Encode,
+    R: Read,
+{
+    MergedRuns {
+        merger_: Merger::new(runs),
+    }
+}
+
+impl<K: Ord + Encode, V: Encode, R: Read> Iterator for MergedRuns<K, V, R> {
+    type Item = io::Result<(K, V)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Values from newer runs replace those from older ones.
+        self.merger_.next(|_, _, _| Resolution::KeepTheirs)
+    }
+}
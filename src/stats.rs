@@ -0,0 +1,74 @@
+use map::SkipListMap;
+
+use std;
+
+/// Summary of the shape of a `SkipListMap`, as returned by
+/// `SkipListMap::stats`.
+///
+/// The tower height of a node is the number of levels it is linked at, so it
+/// is always at least 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stats {
+    /// Number of nodes linked at each level, starting from level 0. The first
+    /// element is always the number of elements in the list.
+    pub level_counts: Vec<usize>,
+    /// Average tower height over all nodes, or 0 for an empty list.
+    pub average_height: f64,
+    /// Maximum tower height over all nodes, or 0 for an empty list.
+    pub max_height: usize,
+    /// Estimated number of key comparisons performed by a single search.
+    pub expected_comparisons: f64,
+}
+
+impl<K, V> SkipListMap<K, V> {
+    /// Computes per-level node counts and derived metrics for the current
+    /// structure. This is O(n), and is meant to check whether a
+    /// `HeightControl` is producing a balanced list.
+    ///
+    /// # Remarks
+    ///
+    /// The expected number of comparisons assumes that searched keys are
+    /// uniformly spread over the stored ones: on each level, a search walks on
+    /// average over half of the nodes between two consecutive nodes of the
+    /// level above, and does one more comparison to find out it has to go
+    /// down.
+    pub fn stats(&self) -> Stats {
+        let mut level_counts: Vec<usize> = Vec::new();
+        let mut total_height = 0;
+
+        let mut current = self.head().next(0);
+        while let Some(node) = current {
+            let height = std::cmp::max(node.height(), 1);
+            if level_counts.len() < height {
+                level_counts.resize(height, 0);
+            }
+
+            for count in level_counts.iter_mut().take(height) {
+                *count += 1;
+            }
+
+            total_height += height;
+            current = node.next(0);
+        }
+
+        let mut expected_comparisons = 0.0;
+        for (level, count) in level_counts.iter().enumerate() {
+            // The head node splits each level as well, hence the extra segment.
+            let segments = level_counts.get(level + 1).map_or(1, |above| above + 1);
+            expected_comparisons += (*count as f64) / (segments as f64) / 2.0 + 1.0;
+        }
+
+        let average_height = if self.is_empty() {
+            0.0
+        } else {
+            total_height as f64 / self.len() as f64
+        };
+
+        Stats {
+            max_height: level_counts.len(),
+            level_counts,
+            average_height,
+            expected_comparisons,
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! `Stream` adapters for scans, enabled by the `async` feature.
+//!
+//! Walking a large map from an async task holds its executor thread for the
+//! whole traversal. `ChunkedStream` hands the entries out a chunk at a time,
+//! and returns `Pending` once after every chunk, so other tasks get to run.
+use map::SkipListMap;
+use iter::{DrainRange, Iter, Range};
+
+use std::borrow::Borrow;
+use std::ops::RangeBounds;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+
+/// Streams the items of an iterator in chunks of up to `chunk_len` items,
+/// yielding to the executor after every chunk.
+pub struct ChunkedStream<I> {
+    iter_: I,
+    chunk_len_: usize,
+    // Set after handing out a chunk, cleared once the task has yielded.
+    yield_: bool,
+}
+
+impl<I: Iterator> ChunkedStream<I> {
+    /// Builds a new `ChunkedStream` over `iter`, e.g. one of a `SubMap`.
+    /// `chunk_len` must be positive.
+    pub fn new(iter: I, chunk_len: usize) -> ChunkedStream<I> {
+        assert!(chunk_len > 0, "chunks must hold at least one item");
+        ChunkedStream {
+            iter_: iter,
+            chunk_len_: chunk_len,
+            yield_: false,
+        }
+    }
+
+    /// Returns the underlying iterator, positioned after the last chunk handed
+    /// out.
+    pub fn into_inner(self) -> I {
+        self.iter_
+    }
+}
+
+impl<I: Iterator + Unpin> Stream for ChunkedStream<I> {
+    type Item = Vec<I::Item>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Vec<I::Item>>> {
+        let this = self.get_mut();
+        if this.yield_ {
+            this.yield_ = false;
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+
+        let chunk: Vec<I::Item> = this.iter_.by_ref().take(this.chunk_len_).collect();
+        if chunk.is_empty() {
+            return Poll::Ready(None);
+        }
+
+        this.yield_ = true;
+        Poll::Ready(Some(chunk))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.iter_.size_hint();
+        (
+            lower.div_ceil(self.chunk_len_),
+            upper.map(|upper| upper.div_ceil(self.chunk_len_)),
+        )
+    }
+}
+
+impl<K, V> SkipListMap<K, V> {
+    /// Streams the entries in key order, in chunks of up to `chunk_len`. See
+    /// `ChunkedStream`.
+    pub fn stream(&self, chunk_len: usize) -> ChunkedStream<Iter<'_, K, V>> {
+        ChunkedStream::new(self.iter(), chunk_len)
+    }
+}
+
+impl<K: Ord, V> SkipListMap<K, V> {
+    /// Streams the entries within `range` in key order, in chunks of up to
+    /// `chunk_len`. See `ChunkedStream`.
+    pub fn stream_range<T, R>(&self, range: R, chunk_len: usize) -> ChunkedStream<Range<'_, K, V>>
+    where
+        K: Borrow<T>,
+        R: RangeBounds<T>,
+        T: Ord + ?Sized,
+    {
+        ChunkedStream::new(self.range(range), chunk_len)
+    }
+
+    /// Removes the entries within `range`, and streams them out in key order,
+    /// in chunks of up to `chunk_len`. As with `drain_range`, the entries are
+    /// unlinked up front, and the ones left in the stream when it is dropped
+    /// are dropped along with it.
+    pub fn drain_stream<T, R>(
+        &mut self,
+        range: R,
+        chunk_len: usize,
+    ) -> ChunkedStream<DrainRange<'_, K, V>>
+    where
+        K: Borrow<T>,
+        R: RangeBounds<T>,
+        T: Ord + ?Sized,
+    {
+        ChunkedStream::new(self.drain_range(range), chunk_len)
+    }
+}
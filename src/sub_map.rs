@@ -0,0 +1,139 @@
+use map::SkipListMap;
+use node::Node;
+use iter::Range;
+
+use std;
+use std::borrow::Borrow;
+use std::ops::RangeBounds;
+
+/// Read-only view of the entries of a `SkipListMap` within a range of keys,
+/// as returned by `SkipListMap::sub_map`.
+///
+/// The bounds are resolved to the first and last entries within them when the
+/// view is built, so the view copies no keys, and checking whether a key falls
+/// within it takes two comparisons.
+pub struct SubMap<'a, K: 'a, V: 'a> {
+    list_: &'a SkipListMap<K, V>,
+    // `None` when the view is empty.
+    first_: Option<&'a Node<K, V>>,
+    // `None` when the view is unbounded above.
+    last_: Option<&'a Node<K, V>>,
+}
+
+impl<K: Ord, V> SkipListMap<K, V> {
+    /// Returns a view of the entries within `range`. See `SubMap`.
+    pub fn sub_map<T, R>(&self, range: R) -> SubMap<'_, K, V>
+    where
+        K: Borrow<T>,
+        R: RangeBounds<T>,
+        T: Ord + ?Sized,
+    {
+        let range = self.range(range);
+        SubMap {
+            list_: self,
+            first_: range.first_node(),
+            last_: range.last_node(),
+        }
+    }
+}
+
+impl<'a, K: Ord, V> SubMap<'a, K, V> {
+    /// Returns `true` if `key` falls within the view.
+    fn covers<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        match (self.first_, self.last_) {
+            (None, _) => false,
+            (Some(first), None) => first.key::<Q>() <= key,
+            (Some(first), Some(last)) => first.key::<Q>() <= key && key <= last.key::<Q>(),
+        }
+    }
+
+    /// Returns the number of elements within the view.
+    ///
+    /// # Remarks
+    ///
+    /// Elements are counted on every call, in O(k) for a view of k elements.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Returns `true` if there are no elements within the view.
+    pub fn is_empty(&self) -> bool {
+        self.first_.is_none()
+    }
+
+    /// Returns a const reference to the element with key `key`, if it exists
+    /// within the view.
+    pub fn get<Q>(&self, key: &Q) -> Option<&'a V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        if !self.covers(key) {
+            return None;
+        }
+
+        self.list_.get(key)
+    }
+
+    /// Returns true if `key` is in the view.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Iterates over the entries within the view, in key order.
+    pub fn iter(&self) -> Range<'a, K, V> {
+        Range::from_bounds(self.list_, self.first_, self.last_)
+    }
+
+    /// Returns a view of the entries within both this view and `range`.
+    pub fn sub_map<T, R>(&self, range: R) -> SubMap<'a, K, V>
+    where
+        K: Borrow<T>,
+        R: RangeBounds<T>,
+        T: Ord + ?Sized,
+    {
+        let range = self.list_.range(range);
+        let first = match (self.first_, range.first_node()) {
+            (Some(ours), Some(theirs)) => Some(std::cmp::max_by_key(ours, theirs, |node| node.key::<K>())),
+            _ => None,
+        };
+        let last = match (self.last_, range.last_node()) {
+            (Some(ours), Some(theirs)) => Some(std::cmp::min_by_key(ours, theirs, |node| node.key::<K>())),
+            (ours, theirs) => ours.or(theirs),
+        };
+
+        // The narrower end may come before the narrower start.
+        let first = match (first, last) {
+            (Some(first), Some(last)) if first.key::<K>() > last.key::<K>() => None,
+            (first, _) => first,
+        };
+
+        SubMap {
+            list_: self.list_,
+            first_: first,
+            last_: last,
+        }
+    }
+}
+
+impl<'a, K, V> Clone for SubMap<'a, K, V> {
+    fn clone(&self) -> SubMap<'a, K, V> {
+        *self
+    }
+}
+
+impl<'a, K, V> Copy for SubMap<'a, K, V> {}
+
+impl<'a, K: Ord + std::fmt::Debug, V: std::fmt::Debug> std::fmt::Debug for SubMap<'a, K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
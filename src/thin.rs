@@ -0,0 +1,257 @@
+//! Thin owned strings and byte strings, to be used as keys.
+//!
+//! `Box<str>` and `Box<[u8]>` already work as keys, and lookups can be done
+//! with `&str` and `&[u8]` through `Borrow`. Their pointers are fat though: the
+//! length is stored next to the pointer, in every node. `ThinStr` and
+//! `ThinBytes` store the length at the start of their allocation instead, so
+//! they take a single pointer, while still borrowing as `str` and `[u8]`.
+use encoding::{invalid_data, Encode};
+
+use std;
+use std::alloc::{self, Layout};
+use std::borrow::Borrow;
+use std::io::{self, Read, Write};
+use std::ops::Deref;
+use std::ptr::NonNull;
+
+/// Owned byte string behind a single pointer. It compares, hashes, and borrows
+/// as `[u8]`.
+pub struct ThinBytes {
+    // Points to the length, which is followed by the bytes.
+    ptr_: NonNull<usize>,
+}
+
+impl ThinBytes {
+    fn layout(length: usize) -> Layout {
+        let size = std::mem::size_of::<usize>()
+            .checked_add(length)
+            .expect("byte string is too long");
+        Layout::from_size_align(size, std::mem::align_of::<usize>())
+            .expect("byte string is too long")
+    }
+
+    /// Returns the length of the byte string.
+    pub fn len(&self) -> usize {
+        unsafe { *self.ptr_.as_ptr() }
+    }
+
+    /// Returns `true` if the byte string is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        unsafe {
+            let bytes = self.ptr_.as_ptr().add(1) as *const u8;
+            std::slice::from_raw_parts(bytes, self.len())
+        }
+    }
+}
+
+impl From<&[u8]> for ThinBytes {
+    fn from(bytes: &[u8]) -> ThinBytes {
+        let layout = ThinBytes::layout(bytes.len());
+        unsafe {
+            let ptr = alloc::alloc(layout) as *mut usize;
+            let ptr = NonNull::new(ptr).unwrap_or_else(|| alloc::handle_alloc_error(layout));
+            ptr.as_ptr().write(bytes.len());
+            std::ptr::copy_nonoverlapping(
+                bytes.as_ptr(),
+                ptr.as_ptr().add(1) as *mut u8,
+                bytes.len(),
+            );
+            ThinBytes { ptr_: ptr }
+        }
+    }
+}
+
+impl From<Vec<u8>> for ThinBytes {
+    fn from(bytes: Vec<u8>) -> ThinBytes {
+        ThinBytes::from(&bytes[..])
+    }
+}
+
+impl Drop for ThinBytes {
+    fn drop(&mut self) {
+        unsafe {
+            alloc::dealloc(self.ptr_.as_ptr() as *mut u8, ThinBytes::layout(self.len()));
+        }
+    }
+}
+
+// `ThinBytes` uniquely owns its allocation, like `Box<[u8]>`.
+unsafe impl Send for ThinBytes {}
+unsafe impl Sync for ThinBytes {}
+
+impl Clone for ThinBytes {
+    fn clone(&self) -> ThinBytes {
+        ThinBytes::from(self.as_bytes())
+    }
+}
+
+impl Deref for ThinBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl Borrow<[u8]> for ThinBytes {
+    fn borrow(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl AsRef<[u8]> for ThinBytes {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl PartialEq for ThinBytes {
+    fn eq(&self, other: &ThinBytes) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+
+impl Eq for ThinBytes {}
+
+impl PartialOrd for ThinBytes {
+    fn partial_cmp(&self, other: &ThinBytes) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ThinBytes {
+    fn cmp(&self, other: &ThinBytes) -> std::cmp::Ordering {
+        self.as_bytes().cmp(other.as_bytes())
+    }
+}
+
+impl std::hash::Hash for ThinBytes {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.as_bytes().hash(state)
+    }
+}
+
+impl std::fmt::Debug for ThinBytes {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.as_bytes().fmt(f)
+    }
+}
+
+// Encoded as a `Vec<u8>` would be.
+impl Encode for ThinBytes {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.len().encode(writer)?;
+        writer.write_all(self.as_bytes())
+    }
+
+    fn decode<R: Read>(reader: &mut R) -> io::Result<ThinBytes> {
+        let length = usize::decode(reader)?;
+        let mut bytes = Vec::new();
+        reader.take(length as u64).read_to_end(&mut bytes)?;
+        if bytes.len() != length {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        Ok(ThinBytes::from(bytes))
+    }
+}
+
+/// Owned string behind a single pointer. It compares, hashes, and borrows as
+/// `str`.
+///
+/// Strings compare like their bytes, so only hashing needs to go through `str`.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ThinStr {
+    // Always valid UTF-8.
+    bytes_: ThinBytes,
+}
+
+impl ThinStr {
+    /// Returns the length of the string, in bytes.
+    pub fn len(&self) -> usize {
+        self.bytes_.len()
+    }
+
+    /// Returns `true` if the string is empty.
+    pub fn is_empty(&self) -> bool {
+        self.bytes_.is_empty()
+    }
+
+    /// Returns the string.
+    pub fn as_str(&self) -> &str {
+        unsafe { std::str::from_utf8_unchecked(self.bytes_.as_bytes()) }
+    }
+}
+
+impl From<&str> for ThinStr {
+    fn from(string: &str) -> ThinStr {
+        ThinStr {
+            bytes_: ThinBytes::from(string.as_bytes()),
+        }
+    }
+}
+
+impl From<String> for ThinStr {
+    fn from(string: String) -> ThinStr {
+        ThinStr::from(&string[..])
+    }
+}
+
+impl Deref for ThinStr {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Borrow<str> for ThinStr {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for ThinStr {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl std::hash::Hash for ThinStr {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl std::fmt::Debug for ThinStr {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
+impl std::fmt::Display for ThinStr {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
+// Encoded as a `String` would be.
+impl Encode for ThinStr {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.bytes_.encode(writer)
+    }
+
+    fn decode<R: Read>(reader: &mut R) -> io::Result<ThinStr> {
+        let bytes = ThinBytes::decode(reader)?;
+        if std::str::from_utf8(&bytes).is_err() {
+            return Err(invalid_data("invalid UTF-8"));
+        }
+
+        Ok(ThinStr { bytes_: bytes })
+    }
+}
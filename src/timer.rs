@@ -0,0 +1,124 @@
+use map::SkipListMap;
+use height_control::HeightControl;
+use iter::DrainRange;
+
+use std;
+
+/// Handle to an item scheduled in a `TimerQueue`, used to cancel it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TimerId<T> {
+    deadline_: T,
+    sequence_: u64,
+}
+
+impl<T> TimerId<T> {
+    /// Returns the deadline the item was scheduled for.
+    pub fn deadline(&self) -> &T {
+        &self.deadline_
+    }
+}
+
+/// Queue of items ordered by deadline, e.g. the timers of an event loop.
+///
+/// Any number of items can share a deadline, and they expire in the order
+/// they were scheduled in. All the expired items are cut off the front of the
+/// queue at once.
+pub struct TimerQueue<T, I> {
+    map_: SkipListMap<TimerId<T>, I>,
+    sequence_: u64,
+}
+
+impl<T, I> TimerQueue<T, I> {
+    pub fn new(controller: Box<HeightControl<TimerId<T>>>) -> TimerQueue<T, I> {
+        TimerQueue {
+            map_: SkipListMap::new(controller),
+            sequence_: 0,
+        }
+    }
+
+    /// Returns the number of items scheduled.
+    pub fn len(&self) -> usize {
+        self.map_.len()
+    }
+
+    /// Returns `true` if there are no items scheduled.
+    pub fn is_empty(&self) -> bool {
+        self.map_.is_empty()
+    }
+
+    /// Removes all items.
+    pub fn clear(&mut self) {
+        self.map_.clear()
+    }
+}
+
+impl<T: Ord + Clone, I> TimerQueue<T, I> {
+    /// Schedules `item` to expire at `deadline`.
+    pub fn schedule(&mut self, deadline: T, item: I) -> TimerId<T> {
+        let id = TimerId {
+            deadline_: deadline,
+            sequence_: self.sequence_,
+        };
+
+        self.sequence_ += 1;
+        self.map_.insert(id.clone(), item);
+        id
+    }
+
+    /// Unschedules the item `id` refers to, and returns it, if it hasn't
+    /// expired yet.
+    pub fn cancel(&mut self, id: &TimerId<T>) -> Option<I> {
+        self.map_.remove(id)
+    }
+
+    /// Returns the earliest deadline, if any item is scheduled.
+    pub fn next_deadline(&self) -> Option<&T> {
+        self.map_.first().map(|(id, _)| id.deadline())
+    }
+
+    /// Removes every item whose deadline is at or before `now`, and returns
+    /// an iterator over them, in expiration order.
+    pub fn pop_expired(&mut self, now: T) -> Expired<'_, T, I> {
+        let last = TimerId {
+            deadline_: now,
+            sequence_: u64::MAX,
+        };
+
+        Expired(self.map_.drain_range(..=last))
+    }
+}
+
+impl<T: 'static + std::hash::Hash, I> Default for TimerQueue<T, I> {
+    fn default() -> TimerQueue<T, I> {
+        TimerQueue {
+            map_: Default::default(),
+            sequence_: 0,
+        }
+    }
+}
+
+impl<T: std::fmt::Debug, I: std::fmt::Debug> std::fmt::Debug for TimerQueue<T, I> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.map_.iter().map(|(id, item)| (id.deadline(), item)))
+            .finish()
+    }
+}
+
+/// Iterator over the items removed by `TimerQueue::pop_expired`, along with
+/// their deadlines, in expiration order.
+pub struct Expired<'a, T: 'a, I: 'a>(DrainRange<'a, TimerId<T>, I>);
+
+impl<'a, T: 'a, I: 'a> Iterator for Expired<'a, T, I> {
+    type Item = (T, I);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(id, item)| (id.deadline_, item))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'a, T: 'a, I: 'a> ExactSizeIterator for Expired<'a, T, I> {}
@@ -0,0 +1,179 @@
+use map::SkipListMap;
+use height_control::HeightControl;
+use iter::{Iter, Range};
+
+use std;
+use std::borrow::Borrow;
+use std::ops::RangeBounds;
+
+/// `SkipListMap` where `remove` leaves a tombstone behind instead of unlinking
+/// the node, and `compact` unlinks all of them later, in a single pass.
+///
+/// Removing only takes the value out of the node, so it never touches the
+/// towers around it. Tombstones keep their key and node alive until the next
+/// `compact`, and searches still walk through them.
+pub struct TombstoneSkipListMap<K, V> {
+    // Tombstones hold `None`.
+    map_: SkipListMap<K, Option<V>>,
+    tombstones_: usize,
+}
+
+impl<K, V> TombstoneSkipListMap<K, V> {
+    pub fn new(controller: Box<HeightControl<K>>) -> TombstoneSkipListMap<K, V> {
+        TombstoneSkipListMap {
+            map_: SkipListMap::new(controller),
+            tombstones_: 0,
+        }
+    }
+
+    /// Removes all elements, along with the tombstones.
+    pub fn clear(&mut self) {
+        self.map_.clear();
+        self.tombstones_ = 0;
+    }
+
+    /// Returns the number of elements stored in the structure, not counting
+    /// tombstones.
+    pub fn len(&self) -> usize {
+        self.map_.len() - self.tombstones_
+    }
+
+    /// Returns `true` if there are no elements stored within the structure.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of removed entries that haven't been compacted yet.
+    pub fn tombstones(&self) -> usize {
+        self.tombstones_
+    }
+
+    /// Iterates over the entries, in key order, skipping tombstones.
+    pub fn iter(&self) -> TombstoneIter<Iter<'_, K, Option<V>>> {
+        TombstoneIter(self.map_.iter())
+    }
+}
+
+impl<K: Ord, V> TombstoneSkipListMap<K, V> {
+    /// Inserts `value` under `key`, returning the value it replaced, if any.
+    /// Inserting over a tombstone reuses its node.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(slot) = self.map_.get_mut(&key) {
+            let replaced = slot.replace(value);
+            if replaced.is_none() {
+                self.tombstones_ -= 1;
+            }
+
+            return replaced;
+        }
+
+        self.map_.insert(key, Some(value));
+        None
+    }
+
+    /// Returns a const reference to the element with key `key`, if it exists.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map_.get(key).and_then(Option::as_ref)
+    }
+
+    /// Returns a mutable reference to the element with key `key`, if it
+    /// exists.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map_.get_mut(key).and_then(Option::as_mut)
+    }
+
+    /// Returns true if `key` is in the map.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Takes the value of the element with key `key` out, if it exists, and
+    /// leaves a tombstone in its place.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let removed = self.map_.get_mut(key).and_then(Option::take);
+        if removed.is_some() {
+            self.tombstones_ += 1;
+        }
+
+        removed
+    }
+
+    /// Unlinks every tombstone.
+    pub fn compact(&mut self) {
+        if self.tombstones_ > 0 {
+            self.map_.retain_range::<K, _, _>(.., |_, value| value.is_some());
+            self.tombstones_ = 0;
+        }
+    }
+
+    /// Returns the entry with the smallest key, if any.
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.iter().next()
+    }
+
+    /// Iterates over the entries within `range`, in key order, skipping
+    /// tombstones.
+    pub fn range<T, R>(&self, range: R) -> TombstoneIter<Range<'_, K, Option<V>>>
+    where
+        K: Borrow<T>,
+        R: RangeBounds<T>,
+        T: Ord + ?Sized,
+    {
+        TombstoneIter(self.map_.range(range))
+    }
+}
+
+impl<K: 'static + std::hash::Hash, V> Default for TombstoneSkipListMap<K, V> {
+    fn default() -> TombstoneSkipListMap<K, V> {
+        TombstoneSkipListMap {
+            map_: Default::default(),
+            tombstones_: 0,
+        }
+    }
+}
+
+impl<K: std::fmt::Debug, V: std::fmt::Debug> std::fmt::Debug for TombstoneSkipListMap<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Iterator over the entries of a `TombstoneSkipListMap`, in key order.
+pub struct TombstoneIter<I>(I);
+
+impl<'a, K: 'a, V: 'a, I> Iterator for TombstoneIter<I>
+where
+    I: Iterator<Item = (&'a K, &'a Option<V>)>,
+{
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (key, value) in &mut self.0 {
+            if let Some(value) = value {
+                return Some((key, value));
+            }
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.0.size_hint().1)
+    }
+}
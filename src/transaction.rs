@@ -0,0 +1,149 @@
+use map::SkipListMap;
+
+use std::borrow::Borrow;
+use std::collections::BTreeMap;
+
+/// Changes staged against a `SkipListMap` by `SkipListMap::transaction`.
+/// Reads see the staged changes on top of the map.
+pub struct Transaction<'a, K: 'a, V: 'a> {
+    base_: &'a SkipListMap<K, V>,
+    // Removals hold `None`.
+    staged_: BTreeMap<K, Option<V>>,
+}
+
+impl<'a, K: Ord + Clone, V> Transaction<'a, K, V> {
+    /// Returns a const reference to the element with key `key`, if it exists.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        match self.staged_.get(key) {
+            Some(staged) => staged.as_ref(),
+            None => self.base_.get(key),
+        }
+    }
+
+    /// Returns a mutable reference to the element with key `key`, if it
+    /// exists. Values that are only in the map are cloned into the
+    /// transaction first, along with their key.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        V: Clone,
+    {
+        if !self.staged_.contains_key(key) {
+            let (base_key, value) = self.base_.get_key_value(key)?;
+            self.staged_.insert(base_key.clone(), Some(value.clone()));
+        }
+
+        self.staged_.get_mut(key).and_then(Option::as_mut)
+    }
+
+    /// Returns true if `key` is in the map.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Stages the insertion of `value` under `key`.
+    pub fn insert(&mut self, key: K, value: V) {
+        self.staged_.insert(key, Some(value));
+    }
+
+    /// Stages the removal of `key`. Returns `true` if it was in the map.
+    pub fn remove<Q>(&mut self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let existed = self.contains_key(key);
+        match self.base_.get_key_value(key) {
+            Some((base_key, _)) => {
+                self.staged_.insert(base_key.clone(), None);
+            }
+            None => {
+                self.staged_.remove(key);
+            }
+        }
+
+        existed
+    }
+}
+
+/// Changes applied so far by a transaction, which are undone when it is
+/// dropped, unless they are committed.
+struct Applied<'a, K: 'a + Ord, V: 'a> {
+    map_: &'a mut SkipListMap<K, V>,
+    // Every key changed, in order, along with its value before the change.
+    undo_: Vec<(K, Option<V>)>,
+}
+
+impl<'a, K: Ord + Clone, V> Applied<'a, K, V> {
+    fn apply(&mut self, key: K, change: Option<V>) {
+        let previous = match change {
+            Some(value) => self.map_.insert(key.clone(), value),
+            None => self.map_.remove(&key),
+        };
+
+        self.undo_.push((key, previous));
+    }
+
+    fn commit(mut self) {
+        self.undo_.clear();
+    }
+}
+
+impl<'a, K: Ord, V> Drop for Applied<'a, K, V> {
+    fn drop(&mut self) {
+        while let Some((key, previous)) = self.undo_.pop() {
+            match previous {
+                Some(value) => {
+                    self.map_.insert(key, value);
+                }
+                None => {
+                    self.map_.remove(&key);
+                }
+            }
+        }
+    }
+}
+
+impl<K: Ord + Clone, V> SkipListMap<K, V> {
+    /// Runs `f` against a `Transaction` on the map, and applies the changes it
+    /// staged only if it returns `Ok`. If it returns `Err` or panics, the map
+    /// is left untouched.
+    ///
+    /// # Remarks
+    ///
+    /// Applying the changes compares keys. If `Ord` panics midway, the
+    /// changes applied so far are undone before the panic carries on. Undoing
+    /// them compares keys too, and a second panic aborts the process.
+    pub fn transaction<T, E, F>(&mut self, f: F) -> Result<T, E>
+    where
+        F: FnOnce(&mut Transaction<K, V>) -> Result<T, E>,
+    {
+        let mut transaction = Transaction {
+            base_: self,
+            staged_: BTreeMap::new(),
+        };
+
+        let result = f(&mut transaction)?;
+        let staged = transaction.staged_;
+        let mut applied = Applied {
+            map_: self,
+            undo_: Vec::with_capacity(staged.len()),
+        };
+
+        for (key, change) in staged {
+            applied.apply(key, change);
+        }
+
+        applied.commit();
+        Ok(result)
+    }
+}
@@ -0,0 +1,185 @@
+use map::SkipListMap;
+use height_control::HeightControl;
+use iter::Iter;
+
+use std;
+use std::borrow::Borrow;
+
+/// Changes made to a key, in increasing version order. `None` records a
+/// removal.
+type History<V> = Vec<(u64, Option<V>)>;
+
+/// Returns the value in `history` as of `version`, if there was one.
+fn value_at<V>(history: &[(u64, Option<V>)], version: u64) -> Option<&V> {
+    let position = history.partition_point(|&(changed, _)| changed <= version);
+    position
+        .checked_sub(1)
+        .and_then(|position| history[position].1.as_ref())
+}
+
+/// Map that keeps the history of every key: each insert and remove is
+/// recorded under a new version, and reads can ask for the map as it was at
+/// any earlier version.
+///
+/// Versions start at 1 and grow by one on every change, across all keys.
+/// Version 0 is the empty map. Histories are only ever appended to, so memory
+/// grows with the number of changes rather than with the number of keys.
+pub struct VersionedSkipListMap<K, V> {
+    map_: SkipListMap<K, History<V>>,
+    version_: u64,
+    length_: usize,
+}
+
+impl<K, V> VersionedSkipListMap<K, V> {
+    pub fn new(controller: Box<HeightControl<K>>) -> VersionedSkipListMap<K, V> {
+        VersionedSkipListMap {
+            map_: SkipListMap::new(controller),
+            version_: 0,
+            length_: 0,
+        }
+    }
+
+    /// Returns the version of the last change.
+    pub fn version(&self) -> u64 {
+        self.version_
+    }
+
+    /// Returns the number of keys that currently have a value.
+    pub fn len(&self) -> usize {
+        self.length_
+    }
+
+    /// Returns `true` if no key currently has a value.
+    pub fn is_empty(&self) -> bool {
+        self.length_ == 0
+    }
+
+    /// Iterates over the current entries, in key order.
+    pub fn iter(&self) -> VersionedIter<'_, K, V> {
+        self.iter_at(self.version_)
+    }
+
+    /// Iterates over the entries as they were at `version`, in key order.
+    pub fn iter_at(&self, version: u64) -> VersionedIter<'_, K, V> {
+        VersionedIter {
+            iter_: self.map_.iter(),
+            version_: version,
+        }
+    }
+}
+
+impl<K: Ord, V> VersionedSkipListMap<K, V> {
+    /// Records `value` under `key`, and returns the version of the change.
+    pub fn insert(&mut self, key: K, value: V) -> u64 {
+        self.version_ += 1;
+        let change = (self.version_, Some(value));
+
+        match self.map_.get_mut(&key) {
+            Some(history) => {
+                if history.last().is_some_and(|(_, value)| value.is_none()) {
+                    self.length_ += 1;
+                }
+
+                history.push(change);
+            }
+            None => {
+                self.map_.insert(key, vec![change]);
+                self.length_ += 1;
+            }
+        }
+
+        self.version_
+    }
+
+    /// Records the removal of `key`, and returns the version of the change, if
+    /// `key` had a value.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<u64>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let history = self.map_.get_mut(key)?;
+        if history.last().is_some_and(|(_, value)| value.is_none()) {
+            return None;
+        }
+
+        self.version_ += 1;
+        history.push((self.version_, None));
+        self.length_ -= 1;
+        Some(self.version_)
+    }
+
+    /// Returns the current value of `key`, if it has one.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get_at(key, self.version_)
+    }
+
+    /// Returns the value `key` had at `version`, if it had one.
+    pub fn get_at<Q>(&self, key: &Q, version: u64) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map_
+            .get(key)
+            .and_then(|history| value_at(history, version))
+    }
+
+    /// Returns true if `key` currently has a value.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Returns every change made to `key`, in version order. Removals hold
+    /// `None`.
+    pub fn history<Q>(&self, key: &Q) -> &[(u64, Option<V>)]
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map_.get(key).map_or(&[], |history| &history[..])
+    }
+}
+
+impl<K: 'static + std::hash::Hash, V> Default for VersionedSkipListMap<K, V> {
+    fn default() -> VersionedSkipListMap<K, V> {
+        VersionedSkipListMap {
+            map_: Default::default(),
+            version_: 0,
+            length_: 0,
+        }
+    }
+}
+
+impl<K: std::fmt::Debug, V: std::fmt::Debug> std::fmt::Debug for VersionedSkipListMap<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Iterator over the entries of a `VersionedSkipListMap` as they were at some
+/// version, in key order.
+pub struct VersionedIter<'a, K: 'a, V: 'a> {
+    iter_: Iter<'a, K, History<V>>,
+    version_: u64,
+}
+
+impl<'a, K: 'a, V: 'a> Iterator for VersionedIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let version = self.version_;
+        self.iter_
+            .by_ref()
+            .filter_map(|(key, history)| value_at(history, version).map(|value| (key, value)))
+            .next()
+    }
+}
@@ -0,0 +1,172 @@
+//! Write-ahead logging for `SkipListMap`.
+//!
+//! `LoggedMap` wraps a list and appends a record to a log for every change,
+//! before applying it. After a crash, `SkipListMap::recover` replays the log to
+//! rebuild the list. Along with `SkipListMap::write_to`, which can be used to
+//! take checkpoints so that logs can be truncated, this gives a minimal durable
+//! ordered store.
+use map::SkipListMap;
+use height_control::HeightControl;
+use encoding::{invalid_data, Encode};
+
+use std;
+use std::borrow::Borrow;
+use std::io::{self, Read, Write};
+
+/// Tags that start every record in the log.
+const INSERT: u8 = 1;
+const REMOVE: u8 = 2;
+const CLEAR: u8 = 3;
+
+/// `SkipListMap` that logs every change to `log` before applying it.
+///
+/// Records are flushed as soon as they are written, but this only hands them
+/// over to the underlying writer. Making them durable (e.g. by calling
+/// `File::sync_data`) is up to the owner of the writer, which can be reached
+/// through `log_mut`.
+///
+/// When writing a record fails, the list is left untouched. The log may end
+/// with part of that record though, so it should not be appended to anymore:
+/// recover from it instead.
+pub struct LoggedMap<K, V, W: Write> {
+    map_: SkipListMap<K, V>,
+    log_: W,
+}
+
+impl<K: Ord + Encode, V: Encode, W: Write> LoggedMap<K, V, W> {
+    /// Builds a new `LoggedMap`.
+    ///
+    /// # Arguments
+    ///
+    ///  * `map`: the initial contents. They are not logged, so `map` should
+    ///    either be empty, or what `log` replays to (e.g. the output of
+    ///    `SkipListMap::recover`).
+    ///  * `log`: where records are appended.
+    pub fn new(map: SkipListMap<K, V>, log: W) -> LoggedMap<K, V, W> {
+        LoggedMap { map_: map, log_: log }
+    }
+
+    /// Logs and performs `SkipListMap::insert`.
+    pub fn insert(&mut self, key: K, value: V) -> io::Result<Option<V>> {
+        self.log(|log| {
+            INSERT.encode(log)?;
+            key.encode(log)?;
+            value.encode(log)
+        })?;
+
+        Ok(self.map_.insert(key, value))
+    }
+
+    /// Logs and performs `SkipListMap::remove`.
+    ///
+    /// # Remarks
+    ///
+    /// Removals are always logged, even when `key` is not present, so that
+    /// the log does not depend on the contents of the list.
+    pub fn remove(&mut self, key: &K) -> io::Result<Option<V>> {
+        self.log(|log| {
+            REMOVE.encode(log)?;
+            key.encode(log)
+        })?;
+
+        Ok(self.map_.remove(key))
+    }
+
+    /// Logs and performs `SkipListMap::clear`.
+    pub fn clear(&mut self) -> io::Result<()> {
+        self.log(|log| CLEAR.encode(log))?;
+        self.map_.clear();
+        Ok(())
+    }
+
+    /// Returns a const reference to the element with key `key`, if it exists.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map_.get(key)
+    }
+
+    /// Returns the list with the logged changes applied.
+    pub fn map(&self) -> &SkipListMap<K, V> {
+        &self.map_
+    }
+
+    /// Returns the log.
+    pub fn log_mut(&mut self) -> &mut W {
+        &mut self.log_
+    }
+
+    /// Splits the `LoggedMap` into the list and the log.
+    pub fn into_parts(self) -> (SkipListMap<K, V>, W) {
+        (self.map_, self.log_)
+    }
+
+    fn log<F: FnOnce(&mut W) -> io::Result<()>>(&mut self, write: F) -> io::Result<()> {
+        write(&mut self.log_)?;
+        self.log_.flush()
+    }
+}
+
+impl<K: Ord + Encode, V: Encode> SkipListMap<K, V> {
+    /// Replays a log written by `LoggedMap` into a new list.
+    ///
+    /// # Arguments
+    ///
+    ///  * `reader`: source of the log. Since the log is read in many small
+    ///    pieces, it should be buffered.
+    ///  * `controller`: generates heights for the replayed nodes.
+    ///
+    /// # Remarks
+    ///
+    /// A record cut short at the end of the log is what a crash while writing
+    /// it leaves behind. It is ignored, since the change it describes was never
+    /// applied. Any other malformed record fails with
+    /// `io::ErrorKind::InvalidData`.
+    pub fn recover<R: Read>(
+        mut reader: R,
+        controller: Box<HeightControl<K>>,
+    ) -> io::Result<SkipListMap<K, V>> {
+        let mut map = SkipListMap::new(controller);
+
+        loop {
+            match replay(&mut reader, &mut map) {
+                Ok(()) => {}
+                Err(ref error) if error.kind() == io::ErrorKind::UnexpectedEof => return Ok(map),
+                Err(error) => return Err(error),
+            }
+        }
+    }
+}
+
+/// Reads a single record and applies it to `map`.
+fn replay<K: Ord + Encode, V: Encode, R: Read>(
+    reader: &mut R,
+    map: &mut SkipListMap<K, V>,
+) -> io::Result<()> {
+    match u8::decode(reader)? {
+        INSERT => {
+            let key = K::decode(reader)?;
+            let value = V::decode(reader)?;
+            map.insert(key, value);
+        }
+        REMOVE => {
+            map.remove(&K::decode(reader)?);
+        }
+        CLEAR => map.clear(),
+        _ => return Err(invalid_data("unknown log record")),
+    }
+
+    Ok(())
+}
+
+impl<K, V, W: Write> std::fmt::Debug for LoggedMap<K, V, W>
+where
+    K: std::fmt::Debug,
+    V: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("LoggedMap").field("map", &self.map_).finish()
+    }
+}
@@ -0,0 +1,58 @@
+use map::SkipListMap;
+use node::Node;
+
+use std;
+use std::borrow::Borrow;
+use std::ops::RangeBounds;
+
+/// Loads every link in the tower of `node`. That pulls in the node itself,
+/// along with the inline key and value, and the out-of-line upper levels.
+fn touch<K, V>(node: &Node<K, V>) {
+    for level in 0..=node.height() {
+        // Unlike a plain read whose result goes unused, this can't be elided.
+        std::hint::black_box(node.link(level));
+    }
+}
+
+impl<K, V> SkipListMap<K, V> {
+    /// Touches every node along every level, to pull the list into the CPU
+    /// caches, or the page cache for memory that was just mapped in, ahead of
+    /// a latency-critical phase.
+    ///
+    /// # Remarks
+    ///
+    /// Only the memory of the list itself is touched: keys and values that
+    /// own heap memory, e.g. `String`s, are not followed. This takes O(n), and
+    /// is only worth it when the list fits in the cache being warmed.
+    pub fn warm(&self) {
+        touch(self.head());
+        let mut current = self.head().next(0);
+        while let Some(node) = current {
+            touch(node);
+            current = node.next(0);
+        }
+    }
+}
+
+impl<K: Ord, V> SkipListMap<K, V> {
+    /// Touches the search path to `range`, and then every node within it
+    /// along every level. See `warm`.
+    pub fn warm_range<T, R>(&self, range: R)
+    where
+        K: Borrow<T>,
+        R: RangeBounds<T>,
+        T: Ord + ?Sized,
+    {
+        let range = self.range(range);
+        let last = range.last_node();
+        let mut current = range.first_node();
+        while let Some(node) = current {
+            touch(node);
+            if last.is_some_and(|last| std::ptr::eq(last, node)) {
+                break;
+            }
+
+            current = node.next(0);
+        }
+    }
+}
@@ -0,0 +1,48 @@
+#![cfg(feature = "arbitrary")]
+
+extern crate arbitrary;
+extern crate skiplist;
+use skiplist::*;
+
+use arbitrary::{Arbitrary, Unstructured};
+use std::collections::hash_map::DefaultHasher;
+
+/// Odd bytes, so that collections keep going until the input runs out.
+fn input() -> Vec<u8> {
+    let mut state = 0x5eed_u64;
+    (0..4096)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 32) as u8 | 1
+        })
+        .collect()
+}
+
+#[test]
+fn same_input_builds_same_list() {
+    let bytes = input();
+    let first = SkipListMap::<u16, u8>::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+    let second = SkipListMap::<u16, u8>::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+    assert!(first.len() > 1);
+    assert!(first.iter().eq(second.iter()));
+    assert_eq!(first.visualize(), second.visualize());
+}
+
+#[test]
+fn generators_are_valid() {
+    let bytes = input();
+    let mut u = Unstructured::new(&bytes);
+    for key in 0..20u32 {
+        let mut geometric = GeometricalGenerator::arbitrary(&mut u).unwrap();
+        let mut adaptive = AdaptiveGenerator::arbitrary(&mut u).unwrap();
+        let mut two_pow = TwoPowGenerator::<u32>::arbitrary(&mut u).unwrap();
+        let mut hash_coin = HashCoinGenerator::<u32, DefaultHasher>::arbitrary(&mut u).unwrap();
+        let controllers: [&mut HeightControl<u32>; 4] =
+            [&mut geometric, &mut adaptive, &mut two_pow, &mut hash_coin];
+        for controller in controllers {
+            assert!(controller.get_height(&key) <= controller.max_height());
+        }
+    }
+}
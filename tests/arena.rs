@@ -0,0 +1,79 @@
+extern crate skiplist;
+use skiplist::*;
+use skiplist::arena::ArenaSkipList;
+
+use std::collections::BTreeMap;
+
+fn list<K: 'static>() -> ArenaSkipList<K, u32> {
+    ArenaSkipList::new(Box::new(GeometricalGenerator::new(12, 0.5)))
+}
+
+#[test]
+fn insert_get_remove() {
+    let mut list = list();
+    for i in (0..1000u32).rev() {
+        assert_eq!(list.insert(i, i * 2), None);
+    }
+    assert_eq!(list.insert(7, 0), Some(14));
+    assert_eq!(list.len(), 1000);
+
+    for i in 0..500 {
+        assert_eq!(list.remove(&(i * 2)), Some(i * 4));
+    }
+    assert_eq!(list.remove(&0), None);
+    assert_eq!(list.len(), 500);
+    assert_eq!(list.get(&7), Some(&0));
+    assert_eq!(list.get(&9), Some(&18));
+    assert!(!list.contains_key(&10));
+    *list.get_mut(&9).unwrap() = 1;
+    assert_eq!(list.get(&9), Some(&1));
+
+    let keys: Vec<u32> = list.iter().map(|(&key, _)| key).collect();
+    assert_eq!(keys, (0..500).map(|i| i * 2 + 1).collect::<Vec<_>>());
+}
+
+#[test]
+fn removed_slots_are_reused() {
+    let mut list = list();
+    for i in 0..100u32 {
+        list.insert(i, i);
+    }
+    assert_eq!(list.slots(), 101);
+
+    for i in 0..50 {
+        list.remove(&(i * 2));
+    }
+    for i in 100..150 {
+        list.insert(i, i);
+    }
+    assert_eq!(list.slots(), 101);
+    assert!(list.iter().map(|(&key, _)| key).eq((0..50).map(|i| i * 2 + 1).chain(100..150)));
+
+    list.clear();
+    assert!(list.is_empty());
+    assert_eq!(list.slots(), 1);
+    assert_eq!(list.iter().next(), None);
+    list.insert(3, 3);
+    assert_eq!(format!("{:?}", list), "{3: 3}");
+}
+
+#[test]
+fn matches_btree_map() {
+    let mut list = list();
+    let mut model = BTreeMap::new();
+    for i in 0..2000u32 {
+        let key = (i.wrapping_mul(7919) % 300).to_string();
+        if i % 3 == 0 {
+            assert_eq!(list.remove(key.as_str()), model.remove(&key));
+        } else {
+            assert_eq!(list.insert(key.clone(), i), model.insert(key, i));
+        }
+    }
+
+    assert_eq!(list.len(), model.len());
+    assert!(list.iter().eq(model.iter()));
+    for i in 0..300 {
+        let key = i.to_string();
+        assert_eq!(list.get(key.as_str()), model.get(&key));
+    }
+}
@@ -0,0 +1,151 @@
+extern crate skiplist;
+use skiplist::augmented::{Count, Max, Min, Sum, Weight};
+use skiplist::*;
+
+use std::collections::BTreeMap;
+use std::ops::Bound;
+
+fn empty<A: Aggregate<u32, i64>>() -> AugmentedSkipListMap<u32, i64, A> {
+    AugmentedSkipListMap::new(Box::new(GeometricalGenerator::new(8, 0.5)))
+}
+
+fn model() -> BTreeMap<u32, i64> {
+    (0..400u32)
+        .map(|i| ((i * 7919) % 613, (i as i64 * 37) % 101 - 50))
+        .collect()
+}
+
+fn ranges() -> Vec<(Bound<u32>, Bound<u32>)> {
+    let mut ranges = vec![(Bound::Unbounded, Bound::Unbounded)];
+    for start in (0..650).step_by(41) {
+        for length in &[0, 1, 17, 200] {
+            ranges.push((Bound::Included(start), Bound::Excluded(start + length)));
+            ranges.push((Bound::Excluded(start), Bound::Included(start + length)));
+        }
+        ranges.push((Bound::Included(start), Bound::Unbounded));
+        ranges.push((Bound::Unbounded, Bound::Excluded(start)));
+    }
+
+    ranges
+}
+
+#[test]
+fn aggregates_match_btree_map() {
+    let mut model = model();
+    let mut sums = empty::<Sum>();
+    let mut mins = empty::<Min>();
+    let mut maxs = empty::<Max>();
+    let mut counts = empty::<Count>();
+    for (&key, &value) in &model {
+        sums.insert(key, value);
+        mins.insert(key, value);
+        maxs.insert(key, value);
+        counts.insert(key, value);
+    }
+
+    // Removals and replacements must keep every summary up to date.
+    for key in (0..613).step_by(5) {
+        let removed = model.remove(&key);
+        assert_eq!(sums.remove(&key), removed);
+        mins.remove(&key);
+        maxs.remove(&key);
+        counts.remove(&key);
+    }
+    for key in (1..613).step_by(7) {
+        let value = key as i64 % 13;
+        assert_eq!(sums.insert(key, value), model.insert(key, value));
+        mins.insert(key, value);
+        maxs.insert(key, value);
+        counts.insert(key, value);
+    }
+
+    assert_eq!(sums.len(), model.len());
+    assert!(sums.iter().map(|(&key, &value)| (key, value)).eq(model.clone()));
+    for range in ranges() {
+        let values: Vec<i64> = model.range(range).map(|(_, &value)| value).collect();
+        assert_eq!(sums.range_aggregate(range), values.iter().sum::<i64>());
+        assert_eq!(mins.range_aggregate(range), values.iter().cloned().min());
+        assert_eq!(maxs.range_aggregate(range), values.iter().cloned().max());
+        assert_eq!(counts.range_aggregate(range), values.len());
+    }
+}
+
+/// Concatenates the keys, which only works if they are combined in order.
+struct Concat;
+
+impl Aggregate<u32, i64> for Concat {
+    type Summary = Vec<u32>;
+
+    fn empty() -> Vec<u32> {
+        Vec::new()
+    }
+
+    fn single(key: &u32, _value: &i64) -> Vec<u32> {
+        vec![*key]
+    }
+
+    fn combine(left: &Vec<u32>, right: &Vec<u32>) -> Vec<u32> {
+        left.iter().chain(right).cloned().collect()
+    }
+}
+
+#[test]
+fn combines_in_key_order() {
+    let mut list = empty::<Concat>();
+    for key in (0..100).rev() {
+        list.insert(key, 0);
+    }
+
+    assert_eq!(list.range_aggregate(10..20), (10..20).collect::<Vec<_>>());
+    assert_eq!(list.range_aggregate(..), (0..100).collect::<Vec<_>>());
+    list.clear();
+    assert!(list.range_aggregate(..).is_empty());
+}
+
+/// Counts up, so that samples sweep the whole range of weights.
+#[derive(Clone)]
+struct Sweep(u64);
+
+impl Entropy for Sweep {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(1 << 54);
+        self.0
+    }
+}
+
+#[test]
+fn finds_entries_by_cumulative_weight() {
+    let mut list: AugmentedSkipListMap<u32, (u64, u32), Weight> =
+        AugmentedSkipListMap::new(Box::new(GeometricalGenerator::new(8, 0.5)));
+    assert_eq!(list.find_by_cumulative_weight(0), None);
+    assert_eq!(list.sample(&mut Sweep(0)), None);
+
+    let mut model = BTreeMap::new();
+    for i in 0..300u32 {
+        let key = (i * 7919) % 613;
+        let weight = (i as u64 * 37) % 11;
+        list.insert(key, (weight, i));
+        model.insert(key, (weight, i));
+    }
+    for key in (0..613).step_by(5) {
+        list.remove(&key);
+        model.remove(&key);
+    }
+
+    let total: u64 = model.values().map(|&(weight, _)| weight).sum();
+    assert_eq!(list.total_weight(), total);
+
+    let mut expected = model
+        .iter()
+        .flat_map(|(key, value)| std::iter::repeat_n((key, value), value.0 as usize));
+    for weight in 0..total + 3 {
+        assert_eq!(list.find_by_cumulative_weight(weight), expected.next());
+    }
+
+    let mut entropy = Sweep(0);
+    for _ in 0..1024 {
+        let (key, value) = list.sample(&mut entropy).unwrap();
+        assert!(value.0 > 0);
+        assert_eq!(model.get(key), Some(value));
+    }
+}
@@ -0,0 +1,35 @@
+#![cfg(feature = "borsh")]
+
+extern crate borsh;
+extern crate skiplist;
+use skiplist::*;
+
+use std::collections::BTreeMap;
+
+#[test]
+fn encodes_like_btree_map() {
+    let mut list: SkipListMap<u32, String> = Default::default();
+    let mut model = BTreeMap::new();
+    for i in 0..500u32 {
+        let key = i.wrapping_mul(7919) % 613;
+        list.insert(key, i.to_string());
+        model.insert(key, i.to_string());
+    }
+
+    let bytes = borsh::to_vec(&list).unwrap();
+    assert_eq!(bytes, borsh::to_vec(&model).unwrap());
+
+    let decoded: SkipListMap<u32, String> = borsh::from_slice(&bytes).unwrap();
+    assert!(decoded.iter().eq(list.iter()));
+}
+
+#[test]
+fn rejects_unsorted_keys() {
+    let entries: Vec<(u32, u32)> = vec![(1, 10), (3, 30), (2, 20)];
+    let bytes = borsh::to_vec(&entries).unwrap();
+    let error = borsh::from_slice::<SkipListMap<u32, u32>>(&bytes).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+
+    let duplicated = borsh::to_vec(&vec![(1u32, 10u32), (1, 10)]).unwrap();
+    assert!(borsh::from_slice::<SkipListMap<u32, u32>>(&duplicated).is_err());
+}
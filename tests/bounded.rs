@@ -0,0 +1,51 @@
+extern crate skiplist;
+use skiplist::*;
+
+fn bounded(capacity: usize, evict: Evict) -> BoundedSkipListMap<u32, u32> {
+    BoundedSkipListMap::new(capacity, evict, Box::new(GeometricalGenerator::new(8, 0.5)))
+}
+
+#[test]
+fn evicts_smallest() {
+    let mut scores = bounded(3, Evict::Smallest);
+    assert_eq!(scores.insert(10, 0), (None, None));
+    assert_eq!(scores.insert(30, 0), (None, None));
+    assert_eq!(scores.insert(20, 0), (None, None));
+    assert!(scores.is_full());
+
+    assert_eq!(scores.insert(20, 1), (Some(0), None));
+    assert_eq!(scores.insert(40, 0), (None, Some((10, 0))));
+    assert_eq!(scores.insert(5, 0), (None, Some((5, 0))));
+    assert!(scores.iter().map(|(&key, _)| key).eq(vec![20, 30, 40]));
+}
+
+#[test]
+fn evicts_largest() {
+    let mut map = bounded(3, Evict::Largest);
+    for key in (0..10).rev() {
+        map.insert(key, key);
+    }
+
+    assert_eq!(map.len(), 3);
+    assert!(map.iter().map(|(&key, _)| key).eq(0..3));
+    assert_eq!(map.insert(50, 0), (None, Some((50, 0))));
+    assert_eq!(map.insert(1, 7), (Some(1), None));
+    assert_eq!(map.get(&1), Some(&7));
+}
+
+#[test]
+fn set_capacity_evicts_excess() {
+    let mut map = bounded(10, Evict::Smallest);
+    for key in 0..10 {
+        map.insert(key, key);
+    }
+
+    assert_eq!(map.set_capacity(7), vec![(0, 0), (1, 1), (2, 2)]);
+    assert_eq!(map.capacity(), 7);
+    assert!(map.set_capacity(20).is_empty());
+    assert_eq!(map.len(), 7);
+
+    let mut map = bounded(0, Evict::Largest);
+    assert_eq!(map.insert(1, 1), (None, Some((1, 1))));
+    assert!(map.is_empty());
+}
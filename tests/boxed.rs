@@ -0,0 +1,25 @@
+extern crate skiplist;
+use skiplist::*;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Large([u64; 64]);
+
+#[test]
+fn values_are_transparent() {
+    let mut map: BoxedSkipListMap<u32, Large> = Default::default();
+    for i in (0..100).rev() {
+        assert_eq!(map.insert(i, Large([i as u64; 64])), None);
+    }
+
+    assert_eq!(map.len(), 100);
+    assert_eq!(map.insert(5, Large([0; 64])), Some(Large([5; 64])));
+    assert_eq!(map.get(&5), Some(&Large([0; 64])));
+    map.get_mut(&6).unwrap().0[0] = 1000;
+    assert_eq!(map.get(&6).unwrap().0[0], 1000);
+    assert_eq!(map.remove(&7), Some(Large([7; 64])));
+    assert!(!map.contains_key(&7));
+    assert_eq!(map.first().map(|(&key, _)| key), Some(0));
+
+    assert!(map.iter().map(|(&key, _)| key).eq((0..100).filter(|&key| key != 7)));
+    assert!(map.range(10..13).map(|(_, value)| value.0[1]).eq(vec![10, 11, 12]));
+}
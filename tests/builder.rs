@@ -0,0 +1,89 @@
+extern crate skiplist;
+use skiplist::*;
+
+#[test]
+fn builder_defaults() {
+    let mut map: SkipListMap<u32, u32> = SkipListMap::builder().build();
+    for i in 0..100 {
+        map.insert(i, i);
+    }
+    assert_eq!(map.len(), 100);
+    assert!(map.iter().map(|(k, _)| *k).eq(0..100));
+}
+
+#[test]
+fn builder_expected_len_bounds_heights() {
+    let mut map: SkipListMap<u32, u32> = SkipListMap::builder().expected_len(100).build();
+    for i in 0..1000 {
+        map.insert(i, i);
+    }
+
+    // log2(100) rounds up to 7 levels, and then to 8 for the generator.
+    assert!(map.stats().max_height <= 8);
+}
+
+#[test]
+fn builder_height_control() {
+    let mut map: SkipListMap<u32, u32> = SkipListMap::builder()
+        .expected_len(1 << 20)
+        .height_control(Box::new(GeometricalGenerator::new(2, 0.5)))
+        .build();
+    for i in 0..1000 {
+        map.insert(i, i);
+    }
+
+    assert!(map.stats().max_height <= 2);
+}
+
+#[test]
+fn builder_node_pool() {
+    let pool = NodePool::new(10);
+    let mut map: SkipListMap<u32, u32> = SkipListMap::builder().node_pool(pool.clone()).build();
+    map.insert(1, 1);
+    map.remove(&1);
+    assert_eq!(pool.stats().allocated, 1);
+    assert_eq!(pool.stats().pooled, 1);
+}
+
+#[test]
+fn builder_fills_node_pool() {
+    let pool = NodePool::new(10);
+    let mut map: SkipListMap<u32, u32> =
+        SkipListMap::builder().expected_len(100).node_pool(pool.clone()).build();
+    assert_eq!(pool.stats().pooled, 10);
+    assert_eq!(pool.stats().allocated, 10);
+
+    for i in 0..20 {
+        map.insert(i, i);
+    }
+    assert_eq!(pool.stats().reused, 10);
+    assert_eq!(pool.stats().allocated, 20);
+    assert!(map.iter().map(|(k, _)| *k).eq(0..20));
+}
+
+#[test]
+fn with_capacity() {
+    let mut map: SkipListMap<String, u32> = SkipListMap::with_capacity(16);
+    for i in 0..1000 {
+        map.insert(i.to_string(), i);
+    }
+
+    // log2(16) is 4 levels.
+    assert!(map.stats().max_height <= 4);
+    assert_eq!(map.get("500"), Some(&500));
+}
+
+#[test]
+fn with_huge_capacity() {
+    // Lengths past the largest power of two must not overflow into a flat
+    // list.
+    for expected_len in [usize::MAX, (1 << (usize::BITS - 1)) + 1] {
+        let mut map: SkipListMap<u32, u32> = SkipListMap::with_capacity(expected_len);
+        for i in 0..1000 {
+            map.insert(i, i);
+        }
+
+        assert!(map.stats().max_height > 1);
+        assert!(map.iter().map(|(k, _)| *k).eq(0..1000));
+    }
+}
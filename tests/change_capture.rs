@@ -0,0 +1,81 @@
+extern crate skiplist;
+use skiplist::*;
+
+use std::sync::{Arc, Mutex};
+
+fn leader(capture: &ChangeCapture<u32, u32>) -> ObservedSkipListMap<u32, u32> {
+    let mut map: ObservedSkipListMap<u32, u32> = Default::default();
+    map.observe(Box::new(capture.clone()));
+    map
+}
+
+fn apply(follower: &mut SkipListMap<u32, u32>, changes: Vec<SequencedChange<u32, u32>>) {
+    follower.apply_changeset(changes.into_iter().map(|(_, change)| change)).unwrap();
+}
+
+#[test]
+fn sequences_every_change() {
+    let capture = ChangeCapture::new(16);
+    let mut map = leader(&capture);
+    assert_eq!(capture.last_sequence(), 0);
+
+    map.insert(2, 20);
+    map.insert(1, 10);
+    map.insert(2, 21);
+    map.remove(&1);
+    map.remove(&7);
+
+    assert_eq!(capture.last_sequence(), 4);
+    assert_eq!(
+        capture.changes_since(0).unwrap(),
+        vec![
+            (1, Change::Added(2, 20)),
+            (2, Change::Added(1, 10)),
+            (3, Change::Changed(2, 21)),
+            (4, Change::Removed(1)),
+        ]
+    );
+    assert_eq!(capture.changes_since(3).unwrap(), vec![(4, Change::Removed(1))]);
+    assert!(capture.changes_since(4).unwrap().is_empty());
+}
+
+#[test]
+fn followers_catch_up() {
+    let capture = ChangeCapture::new(4);
+    let mut map = leader(&capture);
+    let mut follower: SkipListMap<u32, u32> = Default::default();
+
+    for key in 0..3 {
+        map.insert(key, key);
+    }
+    apply(&mut follower, capture.changes_since(0).unwrap());
+
+    map.insert(1, 100);
+    map.remove(&0);
+    map.insert(5, 5);
+    apply(&mut follower, capture.changes_since(3).unwrap());
+    assert!(follower.iter().eq(map.iter()));
+
+    // Too far behind: changes 1 and 2 were dropped to stay within capacity.
+    assert_eq!(capture.changes_since(1), Err(TruncatedError { oldest: 3 }));
+}
+
+#[test]
+fn sinks_see_every_change() {
+    let capture = ChangeCapture::new(0);
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let sink = seen.clone();
+    capture.subscribe(move |sequence, change: &Change<u32, u32>| {
+        sink.lock().unwrap().push((sequence, change.clone()));
+    });
+
+    let mut map = leader(&capture);
+    map.insert(1, 10);
+    map.clear();
+
+    assert_eq!(*seen.lock().unwrap(), vec![(1, Change::Added(1, 10)), (2, Change::Removed(1))]);
+
+    // Nothing is buffered.
+    assert_eq!(capture.changes_since(0), Err(TruncatedError { oldest: 3 }));
+    assert!(capture.changes_since(2).unwrap().is_empty());
+}
@@ -0,0 +1,68 @@
+extern crate skiplist;
+use skiplist::*;
+
+fn list(entries: &[(u32, u32)]) -> SkipListMap<u32, u32> {
+    let mut list = SkipListMap::new(Box::new(GeometricalGenerator::new(8, 0.5)));
+    for &(key, value) in entries {
+        list.insert(key, value);
+    }
+    list
+}
+
+#[test]
+fn diff_lists_changes_in_order() {
+    let old = list(&[(1, 1), (2, 2), (4, 4), (5, 5)]);
+    let new = list(&[(0, 0), (2, 20), (4, 4), (6, 6)]);
+    assert_eq!(
+        old.diff(&new),
+        vec![
+            Change::Added(0, 0),
+            Change::Removed(1),
+            Change::Changed(2, 20),
+            Change::Removed(5),
+            Change::Added(6, 6),
+        ]
+    );
+    assert!(old.diff(&old).is_empty());
+}
+
+#[test]
+fn replicas_converge() {
+    let mut replica = list(&(0..200).map(|i| (i, i)).collect::<Vec<_>>());
+    let mut primary = replica.clone();
+    for i in (0..300).step_by(3) {
+        primary.insert(i, i * 10);
+    }
+    for i in (0..200).step_by(7) {
+        primary.remove(&i);
+    }
+
+    let changes = replica.diff(&primary);
+    assert_eq!(replica.apply_changeset(changes), Ok(()));
+    assert_eq!(replica.len(), primary.len());
+    assert!(replica.iter().eq(primary.iter()));
+    for i in 0..300 {
+        assert_eq!(replica.get(&i), primary.get(&i));
+    }
+}
+
+#[test]
+fn apply_changeset_out_of_order() {
+    let mut map = list(&[(1, 1), (5, 5), (9, 9)]);
+    let changes = vec![Change::Removed(9), Change::Added(3, 3), Change::Changed(1, 10)];
+    assert_eq!(map.apply_changeset(changes), Ok(()));
+    assert!(map.iter().eq(list(&[(1, 10), (3, 3), (5, 5)]).iter()));
+}
+
+#[test]
+fn apply_changeset_stops_at_mismatches() {
+    let mut map = list(&[(1, 1), (5, 5)]);
+    let changes = vec![Change::Added(2, 2), Change::Added(5, 50), Change::Removed(1)];
+    assert_eq!(map.apply_changeset(changes), Err(Error::KeyExists));
+    assert!(map.keys().cloned().eq(vec![1, 2, 5]));
+    assert_eq!(map.get(&5), Some(&5));
+
+    assert_eq!(map.apply_changeset(vec![Change::Removed(7)]), Err(Error::KeyMissing));
+    assert_eq!(map.apply_changeset(vec![Change::Changed(3, 3)]), Err(Error::KeyMissing));
+    assert_eq!(map.len(), 3);
+}
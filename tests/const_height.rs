@@ -0,0 +1,90 @@
+extern crate skiplist;
+use skiplist::*;
+use skiplist::const_height::ConstHeightSkipListMap;
+
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+#[test]
+fn insert_get_remove() {
+    let mut list: ConstHeightSkipListMap<u32, u32> =
+        ConstHeightSkipListMap::new(Box::new(TwoPowGenerator::new(16)));
+    for i in (0..1000u32).rev() {
+        assert_eq!(list.insert(i, i * 2), None);
+    }
+    assert_eq!(list.insert(7, 0), Some(14));
+    assert_eq!(list.len(), 1000);
+
+    for i in 0..500 {
+        assert_eq!(list.remove(&(i * 2)), Some(i * 4));
+    }
+    assert_eq!(list.remove(&0), None);
+    assert_eq!(list.len(), 500);
+    assert_eq!(list.get(&7), Some(&0));
+    *list.get_mut(&9).unwrap() = 1;
+    assert_eq!(list.get(&9), Some(&1));
+    assert!(!list.contains_key(&10));
+    assert_eq!(list.first(), Some((&1, &2)));
+    assert_eq!(list.pop_first(), Some((1, 2)));
+    assert!(list.iter().map(|(&key, _)| key).eq((1..500).map(|i| i * 2 + 1)));
+}
+
+#[test]
+fn heights_are_capped() {
+    // The controller goes up to 64 levels, the list keeps 3.
+    let mut list: ConstHeightSkipListMap<String, usize, 3> =
+        ConstHeightSkipListMap::new(Box::new(GeometricalGenerator::new(64, 0.9)));
+    let mut model = BTreeMap::new();
+    for i in 0..3000usize {
+        let key = (i.wrapping_mul(7919) % 500).to_string();
+        match i % 4 {
+            0 => assert_eq!(list.remove(key.as_str()), model.remove(&key)),
+            1 => assert_eq!(list.pop_first(), model.pop_first()),
+            _ => assert_eq!(list.insert(key.clone(), i), model.insert(key, i)),
+        }
+    }
+
+    assert_eq!(list.len(), model.len());
+    assert!(list.iter().eq(model.iter()));
+    for i in 0..500 {
+        let key = i.to_string();
+        assert_eq!(list.get(key.as_str()), model.get(&key));
+    }
+}
+
+#[derive(Debug)]
+struct DropCounter(Rc<Cell<usize>>);
+
+impl Drop for DropCounter {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() + 1);
+    }
+}
+
+#[test]
+fn entries_are_dropped_once() {
+    let drops = Rc::new(Cell::new(0));
+    {
+        let mut list: ConstHeightSkipListMap<u32, DropCounter, 4> =
+            ConstHeightSkipListMap::new(Box::new(TwoPowGenerator::new(4)));
+        for i in 0..10 {
+            list.insert(i, DropCounter(drops.clone()));
+        }
+
+        drop(list.insert(3, DropCounter(drops.clone())));
+        drop(list.remove(&4));
+        drop(list.pop_first());
+        assert_eq!(drops.get(), 3);
+
+        list.clear();
+        assert_eq!(drops.get(), 11);
+        assert!(list.is_empty());
+        assert_eq!(format!("{:?}", list), "{}");
+
+        for i in 0..5 {
+            list.insert(i, DropCounter(drops.clone()));
+        }
+    }
+    assert_eq!(drops.get(), 16);
+}
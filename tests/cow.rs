@@ -0,0 +1,29 @@
+extern crate skiplist;
+use skiplist::*;
+
+use std::sync::Arc;
+
+#[test]
+fn clone_shares_values() {
+    let mut map: CowSkipListMap<u32, Vec<u32>> = Default::default();
+    for i in 0..50 {
+        map.insert(i, vec![i; 100]);
+    }
+
+    let mut copy = map.clone();
+    assert!(map.iter().eq(copy.iter()));
+    assert!(Arc::ptr_eq(map.get_shared(&3).unwrap(), copy.get_shared(&3).unwrap()));
+
+    copy.get_mut(&3).unwrap().push(0);
+    assert!(!Arc::ptr_eq(map.get_shared(&3).unwrap(), copy.get_shared(&3).unwrap()));
+    assert!(Arc::ptr_eq(map.get_shared(&4).unwrap(), copy.get_shared(&4).unwrap()));
+    assert_eq!(map.get(&3).unwrap().len(), 100);
+    assert_eq!(copy.get(&3).unwrap().len(), 101);
+
+    assert_eq!(copy.remove(&4), Some(vec![4; 100]));
+    assert_eq!(copy.insert(5, Vec::new()), Some(vec![5; 100]));
+    assert_eq!(map.len(), 50);
+    assert_eq!(copy.len(), 49);
+    assert_eq!(map.get(&5), Some(&vec![5; 100]));
+    assert!(map.contains_key(&4));
+}
@@ -0,0 +1,55 @@
+extern crate skiplist;
+use skiplist::*;
+
+fn filled(keys: &[u32]) -> SkipListMap<u32, u32> {
+    let mut list: SkipListMap<u32, u32> = Default::default();
+    for &key in keys {
+        list.insert(key, key * 10);
+    }
+    list
+}
+
+#[test]
+fn pages_through_the_list() {
+    let list = filled(&[1, 2, 3, 4, 5]);
+    let mut cursor = ResumableCursor::new();
+    assert_eq!(cursor.next_page(&list, 2), vec![(&1, &10), (&2, &20)]);
+    assert_eq!(cursor.next(&list), Some((&3, &30)));
+    assert_eq!(cursor.next_page(&list, 5), vec![(&4, &40), (&5, &50)]);
+    assert_eq!(cursor.position(), Some(&5));
+
+    // Nothing left: the cursor stays at the last key.
+    assert!(cursor.next_page(&list, 5).is_empty());
+    assert_eq!(cursor.next(&list), None);
+    assert_eq!(cursor.position(), Some(&5));
+}
+
+#[test]
+fn resumes_after_mutations() {
+    let mut list = filled(&[10, 20, 30, 40]);
+    let mut cursor = ResumableCursor::new();
+    assert_eq!(cursor.next_page(&list, 2), vec![(&10, &100), (&20, &200)]);
+
+    // Removing the position, and inserting on both sides of it.
+    list.remove(&20);
+    list.insert(15, 150);
+    list.insert(25, 250);
+    list.remove(&30);
+    assert_eq!(cursor.next_page(&list, 2), vec![(&25, &250), (&40, &400)]);
+
+    // Entries appended after the end are picked up on the next call.
+    assert_eq!(cursor.next(&list), None);
+    list.insert(50, 500);
+    assert_eq!(cursor.next(&list), Some((&50, &500)));
+}
+
+#[test]
+fn resumes_from_a_token() {
+    let list = filled(&[1, 2, 3, 4]);
+    let mut cursor = ResumableCursor::new();
+    cursor.next_page(&list, 2);
+
+    let token = cursor.into_position().unwrap();
+    let mut resumed = ResumableCursor::after(token);
+    assert_eq!(resumed.next_page(&list, 10), vec![(&3, &30), (&4, &40)]);
+}
@@ -0,0 +1,49 @@
+extern crate skiplist;
+use skiplist::*;
+
+fn leaderboard() -> DescendingSkipListMap<u32, String> {
+    let mut map = DescendingSkipListMap::default();
+    for score in [40, 10, 30, 50, 20] {
+        map.insert(score, format!("player {}", score));
+    }
+
+    map
+}
+
+#[test]
+fn greatest_first() {
+    let map = leaderboard();
+    assert_eq!(map.len(), 5);
+    assert!(map.iter().map(|(&score, _)| score).eq(vec![50, 40, 30, 20, 10]));
+    assert_eq!(map.first().map(|(&score, _)| score), Some(50));
+}
+
+#[test]
+fn lookups_take_plain_keys() {
+    let mut map = leaderboard();
+    assert_eq!(map.get(&30).map(String::as_str), Some("player 30"));
+    assert!(!map.contains_key(&35));
+    map.get_mut(&30).unwrap().push('!');
+    assert_eq!(map.remove(&30), Some("player 30!".to_string()));
+    assert_eq!(map.len(), 4);
+}
+
+#[test]
+fn range() {
+    let map = leaderboard();
+    assert!(map.range(20..=45).map(|(&k, _)| k).eq(vec![40, 30, 20]));
+    assert!(map.range(20..40).map(|(&k, _)| k).eq(vec![30, 20]));
+    assert!(map.range(..30).map(|(&k, _)| k).eq(vec![20, 10]));
+    assert!(map.range(30..).map(|(&k, _)| k).eq(vec![50, 40, 30]));
+    assert_eq!(map.range(51..).count(), 0);
+}
+
+#[test]
+fn descending_keys_in_a_plain_map() {
+    let mut map: SkipListMap<Descending<&str>, u32> = Default::default();
+    map.insert(Descending("a"), 1);
+    map.insert(Descending("c"), 3);
+    map.insert(Descending("b"), 2);
+    assert!(map.values().cloned().eq(vec![3, 2, 1]));
+    assert_eq!(map.get(Descending::from_ref(&"b")), Some(&2));
+}
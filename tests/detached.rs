@@ -0,0 +1,73 @@
+extern crate skiplist;
+use skiplist::*;
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// Value that counts its drops.
+struct DropCounter(Rc<Cell<usize>>);
+
+impl Drop for DropCounter {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() + 1);
+    }
+}
+
+#[test]
+fn move_entries_between_maps() {
+    let mut left: SkipListMap<u32, String> = Default::default();
+    let mut right: SkipListMap<u32, String> =
+        SkipListMap::new(Box::new(GeometricalGenerator::new(2, 0.5)));
+    for i in 0..100 {
+        left.insert(i, i.to_string());
+    }
+    right.insert(50, "fifty".to_string());
+
+    // Move the upper half of `left` into `right`, which has shorter towers.
+    for i in 40..100 {
+        let mut node = left.detach(&i).unwrap();
+        assert_eq!(*node.key(), i);
+        node.value_mut().push('!');
+        if let Err(node) = right.attach(node) {
+            assert_eq!(*node.key(), 50);
+            assert_eq!(node.into_entry(), (50, "50!".to_string()));
+        }
+    }
+
+    assert!(left.detach(&40).is_none());
+    assert_eq!(left.len(), 40);
+    assert_eq!(right.len(), 60);
+    assert!(left.keys().cloned().eq(0..40));
+    assert!(right.keys().cloned().eq(40..100));
+    assert_eq!(right.get(&50).map(String::as_str), Some("fifty"));
+    assert_eq!(right.get(&99).map(String::as_str), Some("99!"));
+
+    // Nodes can go back to the map they came from.
+    let node = right.detach(&99).unwrap();
+    assert_eq!(node.value(), "99!");
+    left.attach(node).unwrap();
+    assert_eq!(left.get(&99).map(String::as_str), Some("99!"));
+    assert_eq!(left.len(), 41);
+    left.insert(98, "98".to_string());
+    assert!(left.keys().zip(left.keys().skip(1)).all(|(a, b)| a < b));
+}
+
+#[test]
+fn dropping_detached_nodes() {
+    let drops = Rc::new(Cell::new(0));
+    let mut map: SkipListMap<u32, DropCounter> = Default::default();
+    for i in 0..10 {
+        map.insert(i, DropCounter(drops.clone()));
+    }
+
+    drop(map.detach(&3));
+    assert_eq!(drops.get(), 1);
+    let (key, value) = map.detach(&4).unwrap().into_entry();
+    assert_eq!(key, 4);
+    assert_eq!(drops.get(), 1);
+    drop(value);
+    assert_eq!(drops.get(), 2);
+
+    drop(map);
+    assert_eq!(drops.get(), 10);
+}
@@ -0,0 +1,55 @@
+extern crate skiplist;
+use skiplist::*;
+
+fn leaderboard() -> DualIndexMap<&'static str, u32, u32> {
+    DualIndexMap::new(Box::new(GeometricalGenerator::new(8, 0.5)))
+}
+
+#[test]
+fn ranks_follow_scores() {
+    let mut board = leaderboard();
+    assert_eq!(board.insert("carol", 30, 3), None);
+    assert_eq!(board.insert("alice", 10, 1), None);
+    assert_eq!(board.insert("bob", 20, 2), None);
+    assert_eq!(board.insert("dave", 20, 4), None);
+
+    assert_eq!(board.rank_of(&"alice"), Some(0));
+    assert_eq!(board.rank_of(&"bob"), Some(1));
+    assert_eq!(board.rank_of(&"dave"), Some(2));
+    assert_eq!(board.rank_of(&"carol"), Some(3));
+    assert_eq!(board.rank_of(&"erin"), None);
+
+    assert_eq!(board.update_score(&"alice", 40), Some(10));
+    assert_eq!(board.update_score(&"erin", 40), None);
+    assert_eq!(board.rank_of(&"alice"), Some(3));
+    assert_eq!(board.get(&"alice"), Some((&40, &1)));
+    assert_eq!(board.score(&"bob"), Some(&20));
+
+    assert_eq!(board.insert("bob", 50, 5), Some((20, 2)));
+    assert_eq!(board.remove(&"carol"), Some((30, 3)));
+    assert!(!board.contains_id(&"carol"));
+    assert_eq!(board.len(), 3);
+
+    let order: Vec<(&str, u32)> = board.iter().map(|(&id, &score, _)| (id, score)).collect();
+    assert_eq!(order, vec![("dave", 20), ("alice", 40), ("bob", 50)]);
+}
+
+#[test]
+fn get_mut_keeps_the_score() {
+    let mut board = leaderboard();
+    board.insert("alice", 10, 1);
+    board.insert("bob", 20, 2);
+
+    {
+        let (score, value) = board.get_mut(&"alice").unwrap();
+        assert_eq!(*score, 10);
+        *value += 100;
+    }
+    assert_eq!(board.get_mut(&"carol"), None);
+
+    assert_eq!(board.get(&"alice"), Some((&10, &101)));
+    assert_eq!(board.rank_of(&"alice"), Some(0));
+    assert_eq!(board.update_score(&"alice", 30), Some(10));
+    assert_eq!(board.get(&"alice"), Some((&30, &101)));
+    assert_eq!(board.rank_of(&"bob"), Some(0));
+}
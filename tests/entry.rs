@@ -0,0 +1,113 @@
+extern crate skiplist;
+use skiplist::*;
+
+use std::borrow::Borrow;
+use std::cell::Cell;
+
+thread_local! {
+    static CONVERSIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// `String` key that counts how many times it is built from a `&str`.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct CountedKey(String);
+
+impl<'a> From<&'a str> for CountedKey {
+    fn from(key: &'a str) -> CountedKey {
+        CONVERSIONS.with(|conversions| conversions.set(conversions.get() + 1));
+        CountedKey(key.to_string())
+    }
+}
+
+impl Borrow<str> for CountedKey {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+#[test]
+fn entry_ref_only_converts_on_insert() {
+    let mut map: SkipListMap<CountedKey, u32> = Default::default();
+    let words = "the quick brown fox jumps over the lazy dog the end";
+    for word in words.split(' ') {
+        *map.entry_ref(word).or_insert(0) += 1;
+    }
+
+    assert_eq!(map.len(), 9);
+    assert_eq!(map.get("the"), Some(&3));
+    assert_eq!(map.get("fox"), Some(&1));
+    assert_eq!(CONVERSIONS.with(Cell::get), 9);
+}
+
+#[test]
+fn entry_ref_occupied_and_vacant() {
+    let mut map: SkipListMap<String, u32> = Default::default();
+    for i in 0..20 {
+        map.insert(format!("{:02}", i), i);
+    }
+
+    match map.entry_ref("05") {
+        EntryRef::Occupied(mut entry) => {
+            assert_eq!(entry.key(), "05");
+            assert_eq!(*entry.get(), 5);
+            assert_eq!(entry.insert(50), 5);
+            *entry.get_mut() += 1;
+        }
+        EntryRef::Vacant(_) => panic!("05 is in the map"),
+    }
+    assert_eq!(map.get("05"), Some(&51));
+
+    match map.entry_ref("30") {
+        EntryRef::Occupied(_) => panic!("30 is not in the map"),
+        EntryRef::Vacant(entry) => {
+            assert_eq!(entry.key(), "30");
+            assert_eq!(*entry.insert(30), 30);
+        }
+    }
+    assert_eq!(map.get("30"), Some(&30));
+
+    match map.entry_ref("10") {
+        EntryRef::Occupied(entry) => assert_eq!(entry.remove_entry(), ("10".to_string(), 10)),
+        EntryRef::Vacant(_) => panic!("10 is in the map"),
+    }
+    assert!(!map.contains_key("10"));
+
+    assert_eq!(*map.entry_ref("11").and_modify(|value| *value *= 2).or_insert(0), 22);
+    assert_eq!(*map.entry_ref("40").and_modify(|value| *value *= 2).or_insert_with(|| 4), 4);
+    assert_eq!(map.entry_ref("12").key(), "12");
+
+    assert_eq!(map.len(), 21);
+    assert!(map.keys().zip(map.keys().skip(1)).all(|(a, b)| a < b));
+    for (key, value) in map.iter().filter(|&(key, _)| key.as_str() < "20") {
+        let expected = match key.as_str() {
+            "05" => 51,
+            "11" => 22,
+            _ => key.parse().unwrap(),
+        };
+        assert_eq!(*value, expected);
+    }
+}
+
+#[test]
+fn get_or_insert_default() {
+    let mut counts: SkipListMap<u32, usize> = Default::default();
+    for i in 0..100 {
+        *counts.get_or_insert_default(i % 7) += 1;
+    }
+    assert_eq!(counts.len(), 7);
+    assert_eq!(counts.get(&0), Some(&15));
+    assert_eq!(counts.get(&6), Some(&14));
+
+    let mut buckets: SkipListMap<u32, Vec<u32>> = Default::default();
+    for i in 0..10 {
+        buckets.get_or_insert_default(i % 3).push(i);
+    }
+    assert_eq!(buckets.get(&1), Some(&vec![1, 4, 7]));
+
+    let mut words: SkipListMap<String, Vec<usize>> = Default::default();
+    for (position, word) in "a b a c b a".split(' ').enumerate() {
+        words.entry_ref(word).or_default().push(position);
+    }
+    assert_eq!(words.get("a"), Some(&vec![0, 2, 5]));
+    assert_eq!(words.get("c"), Some(&vec![3]));
+}
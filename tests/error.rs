@@ -0,0 +1,92 @@
+extern crate skiplist;
+use skiplist::*;
+
+use std::ops::Bound;
+
+#[test]
+fn try_insert_and_remove() {
+    let mut map: SkipListMap<u32, u32> = Default::default();
+    *map.try_insert(1, 10).unwrap() += 1;
+    assert_eq!(map.try_insert(1, 20), Err(Error::KeyExists));
+    assert_eq!(map.get(&1), Some(&11));
+    assert_eq!(map.len(), 1);
+
+    assert_eq!(map.try_remove(&1), Ok(11));
+    assert_eq!(map.try_remove(&1), Err(Error::KeyMissing));
+    assert!(map.is_empty());
+}
+
+#[test]
+fn try_set_height() {
+    let mut map: SkipListMap<u32, u32> =
+        SkipListMap::new(Box::new(GeometricalGenerator::new(4, 0.5)));
+    map.insert(1, 1);
+    assert_eq!(
+        map.try_set_height(&1, 5),
+        Err(Error::HeightExceeded { requested: 5, max: 4 })
+    );
+    assert_eq!(map.try_set_height(&2, 1), Err(Error::KeyMissing));
+    assert!(map.try_set_height(&1, 4).is_ok());
+    assert_eq!(map.try_set_height(&1, 0), Ok(4));
+    assert_eq!(
+        Error::HeightExceeded { requested: 5, max: 4 }.to_string(),
+        "height 5 is above the maximum height 4"
+    );
+}
+
+#[test]
+fn try_ranges_reject_reversed_bounds() {
+    let mut map: SkipListMap<u32, u32> = Default::default();
+    for i in 0..10 {
+        map.insert(i, i);
+    }
+
+    assert!(map.try_range((Bound::Included(5), Bound::Excluded(3))).is_err());
+    assert_eq!(
+        map.try_range((Bound::Excluded(4), Bound::Excluded(4))).err(),
+        Some(Error::InvalidRange)
+    );
+    assert_eq!(map.try_range(4..4).unwrap().count(), 0);
+    assert_eq!(map.try_range(3..=4).unwrap().count(), 2);
+
+    assert!(map.try_drain_range((Bound::Included(8), Bound::Included(2))).is_err());
+    assert_eq!(map.len(), 10);
+    assert_eq!(map.try_drain_range(..2).unwrap().count(), 2);
+    assert_eq!(map.len(), 8);
+}
+
+#[test]
+fn try_pop_first_n() {
+    let mut map: SkipListMap<u32, u32> = Default::default();
+    for i in 0..10 {
+        map.insert(i, i);
+    }
+
+    let popped = map.try_pop_first_n(3).unwrap();
+    assert_eq!(popped, vec![(0, 0), (1, 1), (2, 2)]);
+    assert_eq!(map.try_pop_first_n(100).unwrap().len(), 7);
+}
+
+#[test]
+fn bounded_try_insert() {
+    let mut map = BoundedSkipListMap::new(2, Evict::Smallest, Box::new(GeometricalGenerator::new(8, 0.5)));
+    assert_eq!(map.try_insert(1, 1), Ok(None));
+    assert_eq!(map.try_insert(2, 2), Ok(None));
+    assert_eq!(map.try_insert(3, 3), Err(Error::CapacityExceeded));
+    assert_eq!(map.try_insert(2, 20), Ok(Some(2)));
+    assert_eq!(map.len(), 2);
+}
+
+#[test]
+fn frozen_errors_convert() {
+    fn write(table: &mut MemTable<u32, u32>) -> Result<(), Error> {
+        table.insert(1, 1)?;
+        Ok(())
+    }
+
+    let mut table = MemTable::new(1024, Box::new(GeometricalGenerator::new(8, 0.5)));
+    assert_eq!(write(&mut table), Ok(()));
+    table.freeze();
+    assert_eq!(write(&mut table), Err(Error::Frozen));
+    assert_eq!(Error::Frozen.to_string(), "the map is frozen");
+}
@@ -0,0 +1,96 @@
+extern crate skiplist;
+use skiplist::*;
+use skiplist::fixed::FixedSkipList;
+
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+type Small<K, V> = FixedSkipList<K, V, TwoPowGenerator<K>, 64, 8>;
+
+#[test]
+fn insert_until_full() {
+    let mut list: Small<u32, u32> = FixedSkipList::new(TwoPowGenerator::new(8));
+    assert_eq!(list.capacity(), 64);
+    for i in (0..64).rev() {
+        assert_eq!(list.insert(i, i * 2), Ok(None));
+    }
+    assert!(list.is_full());
+    assert_eq!(list.insert(64, 0), Err(Error::CapacityExceeded));
+    assert_eq!(list.insert(7, 0), Ok(Some(14)));
+    assert_eq!(list.len(), 64);
+
+    assert_eq!(list.get(&7), Some(&0));
+    *list.get_mut(&9).unwrap() = 1;
+    assert_eq!(list.get(&9), Some(&1));
+    assert!(!list.contains_key(&64));
+    assert!(list.iter().map(|(&key, _)| key).eq(0..64));
+
+    assert_eq!(list.remove(&10), Some(20));
+    assert_eq!(list.remove(&10), None);
+    assert_eq!(list.insert(100, 100), Ok(None));
+    assert_eq!(list.insert(101, 101), Err(Error::CapacityExceeded));
+    assert_eq!(list.pop_first(), Some((0, 0)));
+    assert_eq!(list.first(), Some((&1, &2)));
+    assert_eq!(list.insert(101, 101), Ok(None));
+}
+
+#[test]
+fn matches_btree_map() {
+    let mut list: FixedSkipList<u32, u32, _, 300> = FixedSkipList::new(GeometricalGenerator::new(16, 0.5));
+    let mut model = BTreeMap::new();
+    for i in 0..3000u32 {
+        let key = i.wrapping_mul(7919) % 400;
+        match i % 5 {
+            0 => assert_eq!(list.remove(&key), model.remove(&key)),
+            1 => assert_eq!(list.pop_first(), model.pop_first()),
+            _ if model.len() < 300 || model.contains_key(&key) => {
+                assert_eq!(list.insert(key, i), Ok(model.insert(key, i)))
+            }
+            _ => assert_eq!(list.insert(key, i), Err(Error::CapacityExceeded)),
+        }
+    }
+
+    assert_eq!(list.len(), model.len());
+    assert!(list.iter().eq(model.iter()));
+    for key in 0..400 {
+        assert_eq!(list.get(&key), model.get(&key));
+    }
+}
+
+#[derive(Debug)]
+struct DropCounter(Rc<Cell<usize>>);
+
+impl Drop for DropCounter {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() + 1);
+    }
+}
+
+#[test]
+fn entries_are_dropped_once() {
+    let drops = Rc::new(Cell::new(0));
+    {
+        let mut list: Small<u32, DropCounter> = FixedSkipList::new(TwoPowGenerator::new(8));
+        for i in 0..10 {
+            list.insert(i, DropCounter(drops.clone())).unwrap();
+        }
+        assert_eq!(drops.get(), 0);
+
+        drop(list.insert(3, DropCounter(drops.clone())));
+        drop(list.remove(&4));
+        drop(list.pop_first());
+        assert_eq!(drops.get(), 3);
+
+        list.clear();
+        assert_eq!(drops.get(), 11);
+        assert!(list.is_empty());
+        assert_eq!(list.first().map(|(&key, _)| key), None);
+
+        for i in 0..5 {
+            list.insert(i, DropCounter(drops.clone())).unwrap();
+        }
+        assert_eq!(format!("{:?}", list.iter().next().map(|(key, _)| key)), "Some(0)");
+    }
+    assert_eq!(drops.get(), 16);
+}
@@ -0,0 +1,56 @@
+extern crate skiplist;
+use skiplist::*;
+
+fn frozen() -> FrozenSkipListMap<u32, String> {
+    let mut list: SkipListMap<u32, String> = Default::default();
+    for i in (0..100).rev() {
+        list.insert(i * 2, i.to_string());
+    }
+
+    list.freeze()
+}
+
+#[test]
+fn lookups() {
+    let frozen = frozen();
+    assert_eq!(frozen.len(), 100);
+    assert!(!frozen.is_empty());
+    assert_eq!(frozen.get(&42).map(String::as_str), Some("21"));
+    assert_eq!(frozen[&0], "0");
+    assert!(!frozen.contains_key(&43));
+    assert_eq!(frozen.lower_bound(&43), Some((&44, &"22".to_string())));
+    assert_eq!(frozen.lower_bound(&199), None);
+    assert_eq!(frozen.first(), Some((&0, &"0".to_string())));
+    assert_eq!(frozen.last(), Some((&198, &"99".to_string())));
+}
+
+#[test]
+fn iterates_in_order() {
+    let frozen = frozen();
+    assert!(frozen.iter().map(|(&key, _)| key).eq((0..100).map(|i| i * 2)));
+    assert_eq!(frozen.iter().len(), 100);
+    assert_eq!(frozen.iter().next_back(), frozen.last());
+    assert_eq!(frozen.keys().len(), frozen.values().len());
+}
+
+#[test]
+fn thaw() {
+    let frozen = frozen();
+    let copy = frozen.clone();
+    let mut list = frozen.thaw(Box::new(TwoPowGenerator::new(16)));
+    assert!(list.iter().eq(copy.iter()));
+
+    list.insert(1, "one".to_string());
+    assert_eq!(list.remove(&0), Some("0".to_string()));
+    assert_eq!(list.len(), 100);
+}
+
+#[test]
+fn empty() {
+    let list: SkipListMap<u32, u32> = Default::default();
+    let frozen = list.freeze();
+    assert!(frozen.is_empty());
+    assert_eq!(frozen.first(), None);
+    assert_eq!(frozen.last(), None);
+    assert_eq!(frozen.lower_bound(&0), None);
+}
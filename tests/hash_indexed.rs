@@ -0,0 +1,57 @@
+extern crate skiplist;
+use skiplist::*;
+
+use std::collections::BTreeMap;
+
+fn indexed() -> HashIndexedSkipListMap<String, u32> {
+    HashIndexedSkipListMap::new(Box::new(GeometricalGenerator::new(8, 0.5)))
+}
+
+#[test]
+fn point_lookups_and_ranges() {
+    let mut map = indexed();
+    for i in 0..50 {
+        assert_eq!(map.insert(format!("{:02}", i), i), None);
+    }
+    assert_eq!(map.insert("07".to_string(), 70), Some(7));
+    assert_eq!(map.len(), 50);
+
+    assert_eq!(map.get("07"), Some(&70));
+    assert_eq!(map.get_key_value("08"), Some((&"08".to_string(), &8)));
+    *map.get_mut("09").unwrap() += 1;
+    assert_eq!(map.get("09"), Some(&10));
+    assert!(map.contains_key("49"));
+    assert!(!map.contains_key("50"));
+
+    let keys: Vec<&str> = map.range::<String, _>("10".to_string().."13".to_string()).map(|(k, _)| k.as_str()).collect();
+    assert_eq!(keys, vec!["10", "11", "12"]);
+    assert_eq!(map.first(), Some((&"00".to_string(), &0)));
+}
+
+#[test]
+fn removals_keep_the_index_in_sync() {
+    let mut map = indexed();
+    let mut model = BTreeMap::new();
+    for i in 0..200u32 {
+        let key = (i.wrapping_mul(7919) % 100).to_string();
+        if i % 3 == 0 {
+            assert_eq!(map.remove(key.as_str()), model.remove(&key));
+        } else {
+            assert_eq!(map.insert(key.clone(), i), model.insert(key, i));
+        }
+    }
+
+    assert_eq!(map.pop_first(), model.pop_first());
+    assert_eq!(map.pop_last(), model.pop_last());
+    assert_eq!(map.len(), model.len());
+    assert!(map.iter().eq(model.iter()));
+    for i in 0..100 {
+        let key = i.to_string();
+        assert_eq!(map.get(&key), model.get(&key));
+    }
+
+    map.clear();
+    assert!(map.is_empty());
+    assert_eq!(map.get("1"), None);
+    assert_eq!(map.remove("1"), None);
+}
@@ -0,0 +1,70 @@
+extern crate skiplist;
+use skiplist::*;
+
+use std::ops::Range;
+
+fn empty() -> IntervalSkipList<u32, u32> {
+    IntervalSkipList::new(Box::new(GeometricalGenerator::new(8, 0.5)))
+}
+
+fn interval(i: u32) -> Range<u32> {
+    let start = (i * 7919) % 1000;
+    start..start + (i * 31) % 50
+}
+
+fn naive(intervals: &[(Range<u32>, u32)], query: &Range<u32>) -> Vec<(Range<u32>, u32)> {
+    let mut found: Vec<(Range<u32>, u32)> = intervals
+        .iter()
+        .filter(|(interval, _)| interval.start < query.end && query.start < interval.end)
+        .cloned()
+        .collect();
+    found.sort_by_key(|(interval, _)| (interval.start, interval.end));
+    found
+}
+
+fn owned(found: Vec<(&Range<u32>, &u32)>) -> Vec<(Range<u32>, u32)> {
+    found.into_iter().map(|(interval, &value)| (interval.clone(), value)).collect()
+}
+
+#[test]
+fn finds_overlapping_intervals() {
+    let mut list = empty();
+    let mut intervals = Vec::new();
+    for i in 0..500 {
+        assert_eq!(list.insert(interval(i), i), None);
+        intervals.push((interval(i), i));
+    }
+
+    // Every other interval is removed, to exercise relinking.
+    for i in (0..500).filter(|i| i % 2 == 0) {
+        assert_eq!(list.remove(&interval(i)), Some(i));
+    }
+    intervals.retain(|&(_, i)| i % 2 != 0);
+
+    assert_eq!(list.len(), intervals.len());
+    assert_eq!(list.get(&interval(3)), Some(&3));
+    assert!(!list.contains(&interval(4)));
+
+    for query in (0..1100).step_by(37).map(|start| start..start + start % 23) {
+        assert_eq!(owned(list.find_overlapping(&query)), naive(&intervals, &query));
+    }
+
+    for point in (0..1100).step_by(13) {
+        assert_eq!(owned(list.stabbing(&point)), naive(&intervals, &(point..point + 1)));
+    }
+}
+
+#[test]
+fn replaces_and_clears() {
+    let mut list = empty();
+    assert_eq!(list.insert(1..5, 0), None);
+    assert_eq!(list.insert(1..5, 1), Some(0));
+    assert_eq!(list.insert(1..3, 2), None);
+    assert!(list.iter().map(|(interval, _)| interval.clone()).eq(vec![1..3, 1..5]));
+    assert!(list.stabbing(&5).is_empty());
+    assert_eq!(list.stabbing(&4).len(), 1);
+
+    list.clear();
+    assert!(list.is_empty());
+    assert!(list.stabbing(&2).is_empty());
+}
@@ -70,3 +70,92 @@ fn iter_in_order() {
     }
     assert_eq!(number_of_elements_iterated, 1000);
 }
+
+#[test]
+fn iter_mut_empty() {
+    let mut list: SkipListMap<i32, i32> = Default::default();
+    assert!(list.iter_mut().next().is_none());
+}
+
+#[test]
+fn iter_mut_updates_values() {
+    let mut list: SkipListMap<u32, u32> = Default::default();
+    for i in 0..100 {
+        list.insert(i, i);
+    }
+
+    for (key, value) in list.iter_mut() {
+        *value = key * 2;
+    }
+
+    for (key, value) in list.iter() {
+        assert_eq!(*value, key * 2);
+    }
+}
+
+#[test]
+fn for_each_in_range_mut_updates_range() {
+    use std::ops::Bound;
+
+    let mut list: SkipListMap<u32, u32> = Default::default();
+    for i in (0..100).map(|i| i * 2) {
+        list.insert(i, 0);
+    }
+
+    list.for_each_in_range_mut(10..20, |_, value| *value += 1);
+    list.for_each_in_range_mut((Bound::Excluded(30), Bound::Included(40)), |_, value| *value += 1);
+    list.for_each_in_range_mut(..=3, |_, value| *value += 1);
+    list.for_each_in_range_mut(195.., |_, value| *value += 1);
+    list.for_each_in_range_mut((Bound::Included(60), Bound::Excluded(50)), |_, value| *value += 1);
+
+    let bumped: Vec<u32> = list.iter().filter(|&(_, &value)| value == 1).map(|(&key, _)| key).collect();
+    assert_eq!(bumped, vec![0, 2, 10, 12, 14, 16, 18, 32, 34, 36, 38, 40, 196, 198]);
+}
+
+#[test]
+fn iter_mut_references_coexist() {
+    let mut list: SkipListMap<u32, u32> = Default::default();
+    for i in 0..100 {
+        list.insert(i, 0);
+    }
+
+    // All the mutable references are alive at the same time, and are only
+    // written to after the iteration is over.
+    let mut values: Vec<&mut u32> = list.values_mut().collect();
+    assert_eq!(values.len(), 100);
+    for (i, value) in values.iter_mut().enumerate() {
+        **value = i as u32;
+    }
+
+    assert!(list.values().cloned().eq(0..100));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "modified while being iterated")]
+fn iter_detects_modification() {
+    let mut list: SkipListMap<u32, u32> = Default::default();
+    for i in 0..10 {
+        list.insert(i, i);
+    }
+
+    // Only unsafe code can get here. Inserting past the end does not free
+    // anything, so the iterator can still be stepped safely to notice.
+    let list_ptr: *mut SkipListMap<u32, u32> = &mut list;
+    let mut iter = unsafe { (*list_ptr).iter() };
+    iter.next();
+    unsafe {
+        (*list_ptr).insert(100, 100);
+    }
+    iter.next();
+}
+
+#[test]
+fn mutable_iterators_are_send_and_sync() {
+    fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+    let mut list: SkipListMap<i32, String> = Default::default();
+    list.insert(1, "one".to_string());
+    assert_send_sync(&list.iter_mut());
+    assert_send_sync(&list.drain_range(..));
+}
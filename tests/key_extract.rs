@@ -0,0 +1,61 @@
+extern crate skiplist;
+use skiplist::*;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Event {
+    name: String,
+    timestamp: u64,
+}
+
+fn event(name: &str, timestamp: u64) -> Event {
+    Event {
+        name: name.to_string(),
+        timestamp,
+    }
+}
+
+struct ByTimestamp;
+
+impl KeyExtract<Event> for ByTimestamp {
+    type SortKey = u64;
+
+    fn sort_key(event: &Event) -> u64 {
+        event.timestamp
+    }
+}
+
+fn events() -> KeyExtractMap<Event, u32, ByTimestamp> {
+    let mut map = KeyExtractMap::default();
+    map.insert(event("deploy", 30), 1);
+    map.insert(event("alert", 10), 2);
+    map.insert(event("rollback", 20), 3);
+    // Same timestamp: ordered by the full key.
+    map.insert(event("ack", 10), 4);
+    map
+}
+
+#[test]
+fn ordered_by_sort_key() {
+    let map = events();
+    let names: Vec<&str> = map.iter().map(|(event, _)| event.name.as_str()).collect();
+    assert_eq!(names, vec!["ack", "alert", "rollback", "deploy"]);
+    assert_eq!(map.first().map(|(event, _)| event.timestamp), Some(10));
+}
+
+#[test]
+fn lookups_by_full_key() {
+    let mut map = events();
+    assert_eq!(map.get(&event("rollback", 20)), Some(&3));
+    assert_eq!(map.get(&event("rollback", 21)), None);
+    assert!(!map.contains_key(&event("deploy", 10)));
+    *map.get_mut(&event("alert", 10)).unwrap() += 10;
+    assert_eq!(map.remove(&event("alert", 10)), Some(12));
+    assert_eq!(map.len(), 3);
+}
+
+#[test]
+fn range_between_full_keys() {
+    let map = events();
+    let values: Vec<u32> = map.range(event("alert", 10)..=event("rollback", 20)).map(|(_, &v)| v).collect();
+    assert_eq!(values, vec![2, 3]);
+}
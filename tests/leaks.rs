@@ -0,0 +1,181 @@
+//! Checks that every operation on the list frees exactly what it allocated.
+//!
+//! Two independent mechanisms are used: a global allocator that keeps track of
+//! the number of live allocations, and key/value types that count how many
+//! instances were created and dropped. Counters are thread local, so that tests
+//! running in parallel do not interfere with each other.
+extern crate skiplist;
+use skiplist::*;
+
+extern crate quickcheck;
+use quickcheck::quickcheck;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+thread_local! {
+    static LIVE_ALLOCATIONS: Cell<isize> = const { Cell::new(0) };
+    static LIVE_TRACKED: Cell<isize> = const { Cell::new(0) };
+}
+
+struct CountingAllocator;
+
+impl CountingAllocator {
+    fn record(delta: isize) {
+        // The counter may already be gone while the thread is shutting down.
+        let _ = LIVE_ALLOCATIONS.try_with(|live| live.set(live.get() + delta));
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        CountingAllocator::record(1);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        CountingAllocator::record(-1);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn live_allocations() -> isize {
+    LIVE_ALLOCATIONS.with(|live| live.get())
+}
+
+fn live_tracked() -> isize {
+    LIVE_TRACKED.with(|live| live.get())
+}
+
+/// Counts its live instances. It is used both as key and as value, so that
+/// missing drops on either side are caught.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Tracked(u16);
+
+impl Tracked {
+    fn new(value: u16) -> Tracked {
+        LIVE_TRACKED.with(|live| live.set(live.get() + 1));
+        Tracked(value)
+    }
+}
+
+impl Clone for Tracked {
+    fn clone(&self) -> Tracked {
+        Tracked::new(self.0)
+    }
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        LIVE_TRACKED.with(|live| live.set(live.get() - 1));
+    }
+}
+
+fn tracked_list() -> SkipListMap<Tracked, Tracked> {
+    SkipListMap::new(Box::new(TwoPowGenerator::new(8)))
+}
+
+/// Applies a sequence of operations, encoded as `(operation, key)` pairs, and
+/// then drops the list.
+fn run_operations(operations: &[(u8, u16)]) {
+    let mut list = tracked_list();
+
+    for &(operation, key) in operations {
+        match operation % 5 {
+            0 | 1 => {
+                list.insert(Tracked::new(key), Tracked::new(key));
+            }
+            2 => {
+                list.remove(&Tracked::new(key));
+            }
+            3 => {
+                let _copy = list.clone();
+            }
+            _ => {
+                if key % 8 == 0 {
+                    list.clear();
+                } else {
+                    // Drops most of the drained entries along with the
+                    // iterator.
+                    let start = Tracked::new(key);
+                    list.drain_range(start..).take(1).count();
+                }
+            }
+        }
+    }
+}
+
+fn leaves_nothing_behind(operations: Vec<(u8, u16)>) -> bool {
+    let allocations = live_allocations();
+    let tracked = live_tracked();
+
+    run_operations(&operations);
+
+    live_allocations() == allocations && live_tracked() == tracked
+}
+
+#[test]
+fn operations_leave_nothing_behind() {
+    quickcheck(leaves_nothing_behind as fn(Vec<(u8, u16)>) -> bool);
+}
+
+#[test]
+fn remove_drops_key_and_value() {
+    let mut list = tracked_list();
+    let tracked = live_tracked();
+
+    list.insert(Tracked::new(1), Tracked::new(2));
+    assert_eq!(live_tracked(), tracked + 2);
+
+    // The removed value is handed back, so only the key is gone.
+    let value = list.remove(&Tracked::new(1));
+    assert_eq!(live_tracked(), tracked + 1);
+    drop(value);
+    assert_eq!(live_tracked(), tracked);
+}
+
+#[test]
+fn replaced_values_are_dropped() {
+    let mut list = tracked_list();
+    let tracked = live_tracked();
+
+    list.insert(Tracked::new(1), Tracked::new(2));
+    // The duplicate key is dropped, and the old value is returned.
+    let old = list.insert(Tracked::new(1), Tracked::new(3));
+    assert_eq!(live_tracked(), tracked + 3);
+    drop(old);
+    drop(list);
+    assert_eq!(live_tracked(), tracked);
+}
+
+#[test]
+fn empty_list_frees_head() {
+    let allocations = live_allocations();
+    {
+        let list: SkipListMap<u32, u32> = Default::default();
+        assert!(live_allocations() > allocations);
+        drop(list);
+    }
+    assert_eq!(live_allocations(), allocations);
+}
+
+#[test]
+fn into_sorted_vec_moves_entries_out() {
+    let tracked = live_tracked();
+    {
+        let mut list = tracked_list();
+        for key in [3, 1, 2] {
+            list.insert(Tracked::new(key), Tracked::new(key * 10));
+        }
+
+        let entries = list.into_sorted_vec();
+        assert_eq!(entries.capacity(), 3);
+        assert_eq!(live_tracked(), tracked + 6);
+        let entries: Vec<(u16, u16)> = entries.iter().map(|(key, value)| (key.0, value.0)).collect();
+        assert_eq!(entries, vec![(1, 10), (2, 20), (3, 30)]);
+    }
+    assert_eq!(live_tracked(), tracked);
+}
@@ -286,3 +286,837 @@ fn index_multiple_nonexistant() {
     list.insert(4, 6565);
     list[&23];
 }
+
+#[test]
+fn set_height_control_keep() {
+    let mut list: SkipListMap<u32, u32> = Default::default();
+    for i in 0..100 {
+        list.insert(i, i + 1);
+    }
+
+    list.set_height_control(Box::new(TwoPowGenerator::new(2)), RebuildPolicy::Keep);
+    assert_eq!(list.len(), 100);
+    for i in 0..100 {
+        assert_eq!(list.get(&i), Some(&(i + 1)));
+    }
+
+    for i in 100..200 {
+        assert!(list.insert(i, i + 1).is_none());
+    }
+    for i in 0..200 {
+        assert_eq!(list.remove(&i), Some(i + 1));
+    }
+    assert!(list.is_empty());
+}
+
+#[test]
+fn set_height_control_rebuild() {
+    let mut list: SkipListMap<u32, u32> = Default::default();
+    for i in 0..100 {
+        list.insert(i, i + 1);
+    }
+
+    list.set_height_control(
+        Box::new(GeometricalGenerator::new(32, 0.25)),
+        RebuildPolicy::Rebuild,
+    );
+    assert_eq!(list.len(), 100);
+    assert!(list.iter().map(|(key, _)| *key).eq(0..100));
+
+    for i in (0..100).filter(|i| i % 3 == 0) {
+        assert_eq!(list.remove(&i), Some(i + 1));
+    }
+    for i in 0..100 {
+        assert_eq!(list.contains_key(&i), i % 3 != 0);
+    }
+}
+
+/// Gives every key a fixed height, so that the shape of the list is known.
+#[derive(Clone)]
+struct KeyModHeight;
+
+impl HeightControl<u32> for KeyModHeight {
+    fn max_height(&self) -> usize {
+        3
+    }
+
+    fn get_height(&mut self, key: &u32) -> usize {
+        (*key % 3) as usize
+    }
+}
+
+#[test]
+fn fn_height_control_scripts_heights() {
+    let mut script = vec![0, 2, 1, 7, 0].into_iter().cycle();
+    let controller = FnHeightControl::new(4, move |_: &u32| script.next().unwrap());
+    let mut list: SkipListMap<u32, u32> = SkipListMap::new(Box::new(controller));
+    for key in 0..5 {
+        list.insert(key, key);
+    }
+
+    // Levels follow the script, with 7 capped at 3.
+    assert_eq!(list.stats().level_counts, vec![5, 2, 1]);
+
+    // The clone carries on with the script from the same point.
+    let mut copy = list.clone();
+    list.insert(10, 10);
+    copy.insert(10, 10);
+    assert_eq!(list.stats().level_counts, copy.stats().level_counts);
+}
+
+// Makes every node one level taller than the tallest one so far.
+#[derive(Clone)]
+struct GrowingHeight;
+
+impl HeightControl<u32> for GrowingHeight {
+    fn max_height(&self) -> usize {
+        4
+    }
+
+    fn get_height(&mut self, _: &u32) -> usize {
+        0
+    }
+
+    fn get_height_in(&mut self, _: &u32, context: &HeightContext) -> usize {
+        std::cmp::min(context.height + 1, 3)
+    }
+}
+
+#[test]
+fn height_control_sees_list_shape() {
+    let mut list: SkipListMap<u32, u32> = SkipListMap::new(Box::new(GrowingHeight));
+    for key in 0..5 {
+        list.insert(key, key);
+    }
+
+    // Heights are 1, 2, 3, 3 and 3.
+    assert_eq!(list.stats().level_counts, vec![5, 4, 3]);
+
+    // Rebuilding replays the insertions in key order, even if they happened
+    // in a different one.
+    let mut shuffled: SkipListMap<u32, u32> = SkipListMap::new(Box::new(KeyModHeight));
+    for &key in &[3, 0, 4, 1, 2] {
+        shuffled.insert(key, key);
+    }
+    shuffled.set_height_control(Box::new(GrowingHeight), RebuildPolicy::Rebuild);
+    assert_eq!(shuffled.stats().level_counts, vec![5, 4, 3]);
+}
+
+#[test]
+fn visualize_empty() {
+    let list: SkipListMap<u32, u32> = SkipListMap::new(Box::new(KeyModHeight));
+    assert_eq!(list.visualize(), "0 | head --> nil\n");
+}
+
+#[test]
+fn visualize_levels() {
+    let mut list: SkipListMap<u32, u32> = SkipListMap::new(Box::new(KeyModHeight));
+    for key in 1..6 {
+        list.insert(key, key);
+    }
+
+    assert_eq!(
+        list.visualize(),
+        "1 | head ------- 2 ------------ 5 --> nil\n\
+         0 | head -- 1 -- 2 -- 3 -- 4 -- 5 --> nil\n"
+    );
+    assert_eq!(format!("{:#?}", list), list.visualize());
+}
+
+#[test]
+fn stats_empty() {
+    let list: SkipListMap<u32, u32> = Default::default();
+    let stats = list.stats();
+    assert!(stats.level_counts.is_empty());
+    assert_eq!(stats.max_height, 0);
+    assert_eq!(stats.average_height, 0.0);
+    assert_eq!(stats.expected_comparisons, 0.0);
+}
+
+#[test]
+fn stats_levels() {
+    let mut list: SkipListMap<u32, u32> = SkipListMap::new(Box::new(KeyModHeight));
+    for key in 1..7 {
+        list.insert(key, key);
+    }
+
+    // Keys 2 and 5 are the only ones linked at level 1.
+    let stats = list.stats();
+    assert_eq!(stats.level_counts, vec![6, 2]);
+    assert_eq!(stats.max_height, 2);
+    assert_eq!(stats.average_height, 8.0 / 6.0);
+    assert_eq!(stats.expected_comparisons, (6.0 / 3.0 / 2.0 + 1.0) + (2.0 / 2.0 + 1.0));
+}
+
+#[test]
+fn adaptive_generator_observe() {
+    let stats = |level_counts: Vec<usize>| Stats {
+        max_height: level_counts.len(),
+        level_counts,
+        average_height: 0.0,
+        expected_comparisons: 0.0,
+    };
+
+    let mut generator = AdaptiveGenerator::new(16, 0.5);
+    assert_eq!(generator.effective_probability(), 0.5);
+
+    // Too flat: promotes more, up to the bound.
+    HeightControl::<u32>::observe(&mut generator, &stats(vec![1000, 200, 20, 2]));
+    assert_eq!(generator.effective_probability(), 0.75);
+
+    // Too tall: promotes less.
+    HeightControl::<u32>::observe(&mut generator, &stats(vec![1000, 100, 90, 80]));
+    assert!(generator.effective_probability() < 0.5);
+
+    // Too few tall nodes to tell.
+    HeightControl::<u32>::observe(&mut generator, &stats(vec![100, 10, 1]));
+    assert_eq!(generator.effective_probability(), 0.5);
+    assert_eq!(generator.target_probability(), 0.5);
+}
+
+#[test]
+fn tune_corrects_flat_lists() {
+    let mut list: SkipListMap<u32, u32> =
+        SkipListMap::new(Box::new(GeometricalGenerator::new(16, 0.125)));
+    for key in 0..4000 {
+        list.insert(key, key);
+    }
+
+    list.set_height_control(Box::new(AdaptiveGenerator::new(16, 0.5)), RebuildPolicy::Keep);
+    let before = list.tune();
+    for key in 4000..8000 {
+        list.insert(key, key);
+    }
+
+    let after = list.stats();
+    let ratio = |stats: &Stats| stats.level_counts[2] as f64 / stats.level_counts[1] as f64;
+    assert_eq!(after.level_counts[0], 8000);
+    assert!(ratio(&after) > ratio(&before));
+}
+
+#[test]
+fn set_height_relinks_in_place() {
+    let mut list: SkipListMap<u32, u32> = SkipListMap::new(Box::new(KeyModHeight));
+    for key in 1..7 {
+        list.insert(key, key);
+    }
+    assert_eq!(list.stats().level_counts, vec![6, 2]);
+
+    // Raises a node of height 0, then lowers one of height 2.
+    assert_eq!(list.set_height(&3, 3), Some(0));
+    assert_eq!(list.stats().level_counts, vec![6, 3, 1]);
+    assert_eq!(list.set_height(&5, 1), Some(2));
+    assert_eq!(list.stats().level_counts, vec![6, 2, 1]);
+    assert_eq!(list.set_height(&3, 0), Some(3));
+    assert_eq!(list.stats().level_counts, vec![6, 1]);
+    assert_eq!(list.set_height(&7, 1), None);
+
+    assert!(list.iter().map(|(k, _)| *k).eq(1..7));
+    for key in 1..7 {
+        assert_eq!(list.get(&key), Some(&key));
+    }
+    assert_eq!(list.remove(&2), Some(2));
+    assert_eq!(list.stats().level_counts, vec![5]);
+}
+
+#[test]
+#[should_panic(expected = "height above the maximum height")]
+fn set_height_above_maximum() {
+    let mut list: SkipListMap<u32, u32> = SkipListMap::new(Box::new(KeyModHeight));
+    list.insert(1, 1);
+    list.set_height(&1, 4);
+}
+
+#[test]
+fn split_at_index() {
+    let mut list: SkipListMap<u32, u32> = Default::default();
+    for i in 0..100 {
+        list.insert(i, i);
+    }
+
+    let mut rest = list.split_at_index(40);
+    assert_eq!(list.len(), 40);
+    assert_eq!(rest.len(), 60);
+    assert!(list.keys().cloned().eq(0..40));
+    assert!(rest.keys().cloned().eq(40..100));
+
+    let empty = rest.split_at_index(100);
+    assert!(empty.is_empty());
+    assert_eq!(rest.len(), 60);
+
+    let everything = list.split_at_index(0);
+    assert!(list.is_empty());
+    assert_eq!(everything.len(), 40);
+    list.insert(7, 7);
+    rest.insert(7, 7);
+    assert_eq!(rest.first(), Some((&7, &7)));
+}
+
+#[test]
+fn clone_from_reuses_nodes() {
+    let pool = NodePool::new(1000);
+    let mut list: SkipListMap<u32, String> = Default::default();
+    list.set_node_pool(pool.clone());
+    for i in 0..100 {
+        list.insert(i, i.to_string());
+    }
+
+    let mut copy: SkipListMap<u32, String> = Default::default();
+    copy.set_node_pool(pool.clone());
+    for i in 0..30 {
+        copy.insert(i * 10, String::new());
+    }
+    assert_eq!(pool.stats().allocated, 130);
+
+    // Grows: only the missing nodes are allocated.
+    copy.clone_from(&list);
+    assert_eq!(pool.stats().allocated, 200);
+    assert!(copy.iter().eq(list.iter()));
+
+    // Shrinks: the extra nodes go back to the pool.
+    list.retain_range(.., |key, _| key % 4 == 0);
+    copy.clone_from(&list);
+    assert_eq!(pool.stats().allocated, 200);
+    assert_eq!(pool.stats().pooled, 150);
+    assert_eq!(copy.len(), 25);
+    assert!(copy.iter().eq(list.iter()));
+
+    copy.insert(1, "1".to_string());
+    assert_eq!(copy.remove(&96), Some("96".to_string()));
+    assert_eq!(copy.len(), 25);
+}
+
+#[test]
+fn values_may_borrow_locals_declared_later() {
+    let mut list: SkipListMap<u32, &String> = Default::default();
+    let value = "borrowed".to_string();
+    list.insert(1, &value);
+    assert_eq!(list.get(&1), Some(&&value));
+}
+
+/// Deterministic source, as a platform without `rand` support would plug in.
+#[derive(Clone)]
+struct Counter(u64);
+
+impl Entropy for Counter {
+    fn next_u64(&mut self) -> u64 {
+        self.0 += 1;
+        self.0
+    }
+}
+
+#[test]
+fn custom_entropy() {
+    let mut list: SkipListMap<u32, u32> =
+        SkipListMap::new(Box::new(TwoPowGenerator::with_entropy(8, Counter(0))));
+    for i in 0..100 {
+        list.insert(i, i);
+    }
+
+    assert!(list.keys().cloned().eq(0..100));
+    assert!(list.stats().max_height > 1);
+
+    let mut list: SkipListMap<u32, u32> = SkipListMap::new(Box::new(
+        GeometricalGenerator::with_entropy(8, 0.5, Counter(1 << 62)),
+    ));
+    list.insert(1, 1);
+    assert_eq!(list.get(&1), Some(&1));
+}
+
+#[test]
+fn two_pow_generator_caps_any_height() {
+    // Counting from 1, one in 2^n draws ends with n zeroes.
+    let mut generator = TwoPowGenerator::with_entropy(5, Counter(0));
+    let mut reached = vec![0usize; 5];
+    for _ in 0..1024 {
+        let height = HeightControl::<u32>::get_height(&mut generator, &0);
+        for count in &mut reached[..=height] {
+            *count += 1;
+        }
+    }
+
+    // Taller draws end up at the top height, not back at the bottom.
+    assert_eq!(reached, vec![1024, 512, 256, 128, 64]);
+}
+
+/// Evenly spaced draws, one per `step` of the `u64` range.
+#[derive(Clone)]
+struct Stride(u64, u64);
+
+impl Entropy for Stride {
+    fn next_u64(&mut self) -> u64 {
+        let value = self.0;
+        self.0 = self.0.wrapping_add(self.1);
+        value
+    }
+}
+
+#[test]
+fn geometrical_generator_follows_distribution() {
+    // One height per draw: 4096 of them cover the whole range evenly, so
+    // about 4096 / 4^n reach height n.
+    let mut generator = GeometricalGenerator::with_entropy(8, 0.25, Stride(0, 1 << 52));
+    let mut reached = vec![0usize; 9];
+    for _ in 0..4096 {
+        let height = HeightControl::<u32>::get_height(&mut generator, &0);
+        for count in &mut reached[..=height] {
+            *count += 1;
+        }
+    }
+
+    let expected = [4096, 1024, 256, 64, 16, 4, 1, 1, 1];
+    for (&count, &expected) in reached.iter().zip(expected.iter()) {
+        assert!(count >= expected - 1 && count <= expected + 1, "{:?}", reached);
+    }
+
+    // Promotions that always, or never, happen.
+    let mut always = GeometricalGenerator::with_entropy(8, 1.0, Stride(0, 1));
+    let mut never = GeometricalGenerator::with_entropy(8, 0.0, Stride(0, 1));
+    assert_eq!(HeightControl::<u32>::get_height(&mut always, &0), 8);
+    assert_eq!(HeightControl::<u32>::get_height(&mut never, &0), 0);
+}
+
+#[test]
+fn adaptive_generator_draws_once_per_height() {
+    let stats = Stats {
+        max_height: 4,
+        level_counts: vec![1000, 200, 20, 2],
+        average_height: 0.0,
+        expected_comparisons: 0.0,
+    };
+
+    // Any extra draw would put the two generators out of step.
+    let mut adaptive = AdaptiveGenerator::with_entropy(8, 0.5, Stride(0, 1 << 52));
+    let mut geometrical = GeometricalGenerator::with_entropy(8, 0.5, Stride(0, 1 << 52));
+    for _ in 0..4096 {
+        assert_eq!(
+            HeightControl::<u32>::get_height(&mut adaptive, &0),
+            HeightControl::<u32>::get_height(&mut geometrical, &0)
+        );
+    }
+
+    // The draws have wrapped back to 0, and the probability is now 0.75.
+    HeightControl::<u32>::observe(&mut adaptive, &stats);
+    let mut geometrical = GeometricalGenerator::with_entropy(8, 0.75, Stride(0, 1 << 52));
+    for _ in 0..4096 {
+        assert_eq!(
+            HeightControl::<u32>::get_height(&mut adaptive, &0),
+            HeightControl::<u32>::get_height(&mut geometrical, &0)
+        );
+    }
+}
+
+#[test]
+fn build_from_sorted() {
+    let entries = (0..1000).map(|i| Ok::<_, UnsortedError>((i * 2, i)));
+    let mut list: SkipListMap<i32, i32> =
+        SkipListMap::build_from_sorted(entries, Box::new(TwoPowGenerator::new(16))).unwrap();
+    assert_eq!(list.len(), 1000);
+    assert!(list.iter().map(|(&k, &v)| (k, v)).eq((0..1000).map(|i| (i * 2, i))));
+
+    // The list is a regular one afterwards.
+    list.insert(1, -1);
+    assert_eq!(list.remove(&1998), Some(999));
+    assert_eq!(list.get(&1), Some(&-1));
+    assert_eq!(list.len(), 1000);
+}
+
+#[test]
+fn build_from_sorted_rejects_unsorted() {
+    let entries = vec![(1, 1), (3, 3), (3, 4)].into_iter().map(Ok);
+    let error: UnsortedError =
+        SkipListMap::<i32, i32>::build_from_sorted(entries, Box::new(TwoPowGenerator::new(16)))
+            .unwrap_err();
+    assert_eq!(error, UnsortedError { position: 2 });
+}
+
+#[test]
+fn build_from_sorted_stops_at_errors() {
+    let entries = vec![Ok((1, 1)), Err(std::io::Error::other("disk")), Ok((2, 2))];
+    let error =
+        SkipListMap::<i32, i32>::build_from_sorted(entries, Box::new(TwoPowGenerator::new(16)))
+            .unwrap_err();
+    assert_eq!(error.to_string(), "disk");
+}
+
+#[test]
+fn extend_from_sorted() {
+    let mut list: SkipListMap<i32, i32> = Default::default();
+    list.insert(5, 5);
+    list.extend_from_sorted((10..100).map(|i| (i, i))).unwrap();
+    list.extend_from_sorted(Vec::new()).unwrap();
+    assert_eq!(list.len(), 91);
+
+    let error = list.extend_from_sorted(vec![(100, 0), (102, 0), (101, 0), (103, 0)]);
+    assert_eq!(error, Err(UnsortedError { position: 2 }));
+    assert_eq!(list.extend_from_sorted(vec![(50, 0)]), Err(UnsortedError { position: 0 }));
+    assert_eq!(list.len(), 93);
+    assert_eq!(list.keys().last(), Some(&102));
+    assert!(list.keys().zip(list.keys().skip(1)).all(|(a, b)| a < b));
+    assert_eq!(list.get(&5), Some(&5));
+    assert_eq!(list.get(&50), Some(&50));
+}
+
+#[test]
+fn drain_range_unlinks_entries() {
+    let mut list: SkipListMap<u32, u32> = Default::default();
+    for i in 0..100 {
+        list.insert(i, i * 2);
+    }
+
+    let drained: Vec<(u32, u32)> = list.drain_range(20..30).collect();
+    assert_eq!(drained, (20..30).map(|i| (i, i * 2)).collect::<Vec<_>>());
+    assert_eq!(list.len(), 90);
+    assert!(list.keys().cloned().eq((0..20).chain(30..100)));
+
+    assert_eq!(list.drain_range(20..30).len(), 0);
+    assert_eq!(list.drain_range(95..).len(), 5);
+    assert_eq!(list.drain_range(..=5).next(), Some((0, 0)));
+    assert!(list.keys().cloned().eq((6..20).chain(30..95)));
+
+    list.insert(25, 0);
+    assert_eq!(list.get(&25), Some(&0));
+    assert_eq!(list.range(..26).count(), 15);
+}
+
+#[test]
+fn extract_range_moves_towers() {
+    let mut list: SkipListMap<u32, u32> = Default::default();
+    for i in 0..1000 {
+        list.insert(i, i * 2);
+    }
+
+    let mut extracted = list.extract_range(200..700);
+    assert_eq!(extracted.len(), 500);
+    assert_eq!(list.len(), 500);
+    assert!(extracted.keys().cloned().eq(200..700));
+    assert!(list.keys().cloned().eq((0..200).chain(700..1000)));
+    for i in 0..1000 {
+        let (inside, outside) = if (200..700).contains(&i) { (&extracted, &list) } else { (&list, &extracted) };
+        assert_eq!(inside.get(&i), Some(&(i * 2)));
+        assert_eq!(outside.get(&i), None);
+    }
+
+    assert!(list.extract_range(300..400).is_empty());
+    assert_eq!(list.extract_range(990..).len(), 10);
+    assert_eq!(extracted.extract_range(..=200).len(), 1);
+
+    extracted.insert(5000, 0);
+    assert_eq!(extracted.remove(&450), Some(900));
+    list.insert(450, 1);
+    assert_eq!(extracted.len(), 499);
+    assert_eq!(list.len(), 491);
+    assert_eq!(list.range(100..800).count(), 201);
+}
+
+#[test]
+fn retain_range_filters_within_bounds() {
+    let mut list: SkipListMap<u32, u32> = Default::default();
+    for i in 0..100 {
+        list.insert(i, i);
+    }
+
+    let mut visited = Vec::new();
+    list.retain_range(10..=20, |&key, value| {
+        visited.push(key);
+        *value += 1;
+        key % 2 == 0
+    });
+
+    assert_eq!(visited, (10..=20).collect::<Vec<_>>());
+    assert_eq!(list.len(), 95);
+    assert!(list.keys().cloned().eq((0..100).filter(|&key| !(10..=20).contains(&key) || key % 2 == 0)));
+    assert_eq!(list.get(&12), Some(&13));
+    assert_eq!(list.get(&22), Some(&22));
+
+    list.retain_range(.., |_, _| false);
+    assert!(list.is_empty());
+    list.insert(5, 5);
+    assert_eq!(list.first(), Some((&5, &5)));
+}
+
+#[test]
+fn retain_mut_updates_values() {
+    let mut scores: SkipListMap<u32, u32> = Default::default();
+    for i in 0..20 {
+        scores.insert(i, i % 4);
+    }
+
+    scores.retain_mut(|_, score| {
+        *score = score.saturating_sub(1);
+        *score > 0
+    });
+    assert_eq!(scores.len(), 10);
+    assert!(scores.iter().all(|(key, score)| *score == key % 4 - 1));
+
+    scores.retain_mut(|_, _| false);
+    assert!(scores.is_empty());
+    scores.insert(3, 3);
+    assert_eq!(scores.first(), Some((&3, &3)));
+}
+
+#[test]
+fn pop_first_and_last_n() {
+    let mut list: SkipListMap<u32, u32> = Default::default();
+    for i in 0..100 {
+        list.insert(i, i * 2);
+    }
+
+    assert_eq!(list.pop_first_n(3), vec![(0, 0), (1, 2), (2, 4)]);
+    assert_eq!(list.pop_last_n(2), vec![(98, 196), (99, 198)]);
+    assert_eq!(list.pop_first_n(0), vec![]);
+    assert_eq!(list.len(), 95);
+    assert!(list.keys().cloned().eq(3..98));
+
+    assert_eq!(list.pop_last_n(90).len(), 90);
+    assert!(list.keys().cloned().eq(3..8));
+    assert_eq!(list.pop_first_n(10).len(), 5);
+    assert!(list.is_empty());
+    assert!(list.pop_last_n(1).is_empty());
+
+    list.insert(1, 1);
+    assert_eq!(list.get(&1), Some(&1));
+}
+
+#[test]
+fn pop_first_and_last() {
+    let mut list: SkipListMap<u32, u32> = Default::default();
+    assert_eq!(list.pop_first(), None);
+    assert_eq!(list.pop_last(), None);
+
+    for i in 0..50 {
+        list.insert(i, i);
+    }
+
+    assert_eq!(list.pop_first(), Some((0, 0)));
+    assert_eq!(list.pop_last(), Some((49, 49)));
+    assert_eq!(list.pop_last(), Some((48, 48)));
+    assert_eq!(list.len(), 47);
+    assert!(list.keys().cloned().eq(1..48));
+}
+
+#[test]
+fn push_back_appends_in_order() {
+    let mut list: SkipListMap<u32, u32> = Default::default();
+    for i in 0..100 {
+        assert_eq!(list.push_back(i * 2, i), Ok(()));
+    }
+    assert_eq!(list.push_back(198, 0), Err((198, 0)));
+    assert_eq!(list.push_back(7, 0), Err((7, 0)));
+
+    // Changes in the middle drop the cached tail, which must be found again.
+    list.insert(7, 7);
+    assert_eq!(list.remove(&100), Some(50));
+    assert_eq!(list.push_back(200, 100), Ok(()));
+
+    // Inserts at the end take the same path as appends.
+    for i in 201..300 {
+        assert_eq!(list.insert(i, i), None);
+    }
+    assert_eq!(list.insert(250, 0), Some(250));
+    list.pop_last();
+    assert_eq!(list.push_back(299, 1), Ok(()));
+
+    assert_eq!(list.len(), 200);
+    assert!(list.keys().zip(list.keys().skip(1)).all(|(a, b)| a < b));
+    for i in 0..100 {
+        assert_eq!(list.get(&(i * 2)).is_some(), i != 50);
+    }
+    assert_eq!(list.get(&299), Some(&1));
+    assert_eq!(list.range(250..).count(), 50);
+}
+
+#[test]
+fn unchecked_unique_inserts() {
+    let mut list: SkipListMap<u32, u32> = Default::default();
+    for i in (0..50).rev() {
+        let (key, value) = unsafe { list.insert_unique_unchecked(i * 2, i) };
+        assert_eq!(*key, i * 2);
+        *value += 1;
+    }
+
+    for i in 100..150 {
+        unsafe { list.push_back_unique_unchecked(i, i) };
+    }
+    // Appends and unchecked inserts can be interleaved with checked ones.
+    list.insert(1, 1);
+    unsafe { list.push_back_unique_unchecked(150, 150) };
+    assert_eq!(list.push_back(150, 0), Err((150, 0)));
+
+    assert_eq!(list.len(), 102);
+    assert!(list.keys().zip(list.keys().skip(1)).all(|(a, b)| a < b));
+    assert_eq!(list.get(&98), Some(&50));
+    assert_eq!(list.get(&1), Some(&1));
+    assert_eq!(list.get(&149), Some(&149));
+    assert_eq!(list.range(100..).count(), 51);
+}
+
+thread_local! {
+    static COMPARISONS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// Key that counts every comparison made on it, equality included.
+#[derive(Debug, Clone, Copy)]
+struct Counted(u32);
+
+impl Ord for Counted {
+    fn cmp(&self, other: &Counted) -> std::cmp::Ordering {
+        COMPARISONS.with(|count| count.set(count.get() + 1));
+        self.0.cmp(&other.0)
+    }
+}
+
+impl PartialOrd for Counted {
+    fn partial_cmp(&self, other: &Counted) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Counted {
+    fn eq(&self, other: &Counted) -> bool {
+        self.cmp(other) == std::cmp::Ordering::Equal
+    }
+}
+
+impl Eq for Counted {}
+
+#[test]
+fn unchecked_unique_inserts_compare_less() {
+    let build = || {
+        let generator = GeometricalGenerator::with_entropy(16, 0.5, Stride(1, 0x9e37_79b9_7f4a_7c15));
+        let mut list: SkipListMap<Counted, u32> = SkipListMap::new(Box::new(generator));
+        for i in 0..1000 {
+            list.insert(Counted(i * 2), i);
+        }
+        list
+    };
+
+    let count = |list: &mut SkipListMap<Counted, u32>, unchecked: bool| {
+        COMPARISONS.with(|count| count.set(0));
+        for i in (0..1000).rev().step_by(7) {
+            if unchecked {
+                unsafe { list.insert_unique_unchecked(Counted(i * 2 + 1), i) };
+            } else {
+                list.insert(Counted(i * 2 + 1), i);
+            }
+        }
+        COMPARISONS.with(|count| count.get())
+    };
+
+    let (mut checked, mut unchecked) = (build(), build());
+    let checked_comparisons = count(&mut checked, false);
+    let unchecked_comparisons = count(&mut unchecked, true);
+    assert!(checked.keys().map(|key| key.0).eq(unchecked.keys().map(|key| key.0)));
+    assert!(
+        unchecked_comparisons < checked_comparisons,
+        "{} >= {}",
+        unchecked_comparisons,
+        checked_comparisons
+    );
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "nodes out of order")]
+fn unchecked_duplicates_are_caught_in_debug_builds() {
+    let mut list: SkipListMap<u32, u32> = Default::default();
+    list.insert(1, 1);
+    unsafe { list.insert_unique_unchecked(1, 2) };
+}
+
+#[test]
+fn is_range_empty() {
+    use std::ops::Bound::{Excluded, Included};
+
+    let mut list: SkipListMap<u32, u32> = Default::default();
+    assert!(list.is_range_empty(..));
+    for i in 0..50 {
+        list.insert(i * 10, i);
+    }
+
+    assert!(!list.is_range_empty(..));
+    assert!(!list.is_range_empty(95..=100));
+    assert!(list.is_range_empty(101..110));
+    assert!(!list.is_range_empty(101..=110));
+    assert!(list.is_range_empty((Excluded(100), Excluded(110))));
+    assert!(list.is_range_empty(491..));
+    assert!(!list.is_range_empty(..1));
+    assert!(list.is_range_empty((Included(60), Excluded(50))));
+}
+
+#[test]
+fn warm_leaves_the_list_alone() {
+    let mut list: SkipListMap<u32, String> = Default::default();
+    list.warm();
+    list.warm_range(1..10);
+
+    for key in 0..200 {
+        list.insert(key, key.to_string());
+    }
+    list.warm();
+    list.warm_range(50..150);
+    list.warm_range(150..);
+    list.warm_range(300..400);
+
+    assert_eq!(list.len(), 200);
+    assert!(list.iter().map(|(k, v)| (*k, v.parse().unwrap())).eq((0..200).map(|k| (k, k))));
+}
+
+#[test]
+fn values_never_move() {
+    let mut list: SkipListMap<u32, String> = SkipListMap::new(Box::new(KeyModHeight));
+    for key in 0..100 {
+        list.insert(key, key.to_string());
+    }
+    let pointers: Vec<_> = (0..100).map(|key| list.get_raw(&key).unwrap()).collect();
+    assert_eq!(list.get_raw(&100), None);
+
+    // Other entries come and go, and towers are rebuilt around the values.
+    for key in 100..1000 {
+        list.insert(key, key.to_string());
+    }
+    for key in 500..1000 {
+        list.remove(&key);
+    }
+    list.set_height(&10, 2);
+    list.set_height_control(Box::new(TwoPowGenerator::new(8)), RebuildPolicy::Rebuild);
+
+    // Replacing a value writes the new one in place.
+    list.insert(5, "five".to_string());
+    assert_eq!(unsafe { pointers[5].as_ref() }, "five");
+    unsafe { *pointers[6].as_ptr() = "six".to_string() };
+    assert_eq!(list.get(&6).map(String::as_str), Some("six"));
+
+    // Moving entries to another map keeps them where they are too.
+    let other = list.split_off(&50);
+    for key in 0..100u32 {
+        let map = if key < 50 { &list } else { &other };
+        assert_eq!(map.get_raw(&key), Some(pointers[key as usize]));
+    }
+}
+
+#[test]
+fn search_by_key_projection() {
+    // Keyed by (timestamp, id), looked up by timestamp alone.
+    let mut list: SkipListMap<(u32, u32), &'static str> = Default::default();
+    for &(key, value) in &[((10, 2), "b"), ((10, 1), "a"), ((20, 1), "c"), ((30, 5), "d")] {
+        list.insert(key, value);
+    }
+
+    let at = |timestamp: u32| move |key: &(u32, u32)| key.0.cmp(&timestamp);
+    assert_eq!(list.search_by(at(10)), Some((&(10, 1), &"a")));
+    assert_eq!(list.search_by(at(30)), Some((&(30, 5), &"d")));
+    assert_eq!(list.search_by(at(15)), None);
+    assert_eq!(list.search_by(at(40)), None);
+
+    let values =
+        |range: Range<(u32, u32), &'static str>| range.map(|(_, &v)| v).collect::<Vec<_>>();
+    assert_eq!(values(list.range_by(at(10), at(20))), vec!["a", "b", "c"]);
+    assert_eq!(values(list.range_by(at(11), at(30))), vec!["c", "d"]);
+    assert_eq!(values(list.range_by(at(0), at(5))), Vec::<&str>::new());
+    assert_eq!(values(list.range_by(at(25), at(15))), Vec::<&str>::new());
+
+    // Excluding the end at timestamp 20.
+    let before = |key: &(u32, u32)| key.0.cmp(&20).then(std::cmp::Ordering::Greater);
+    assert_eq!(values(list.range_by(at(0), before)), vec!["a", "b"]);
+}
@@ -0,0 +1,48 @@
+extern crate skiplist;
+use skiplist::*;
+
+#[test]
+fn memtable_tracks_resident_bytes() {
+    let mut table: MemTable<u32, String> = MemTable::new(1024, Box::new(GeometricalGenerator::new(8, 0.5)));
+    assert_eq!(table.resident_bytes(), 0);
+    assert!(!table.is_over_budget());
+
+    table.insert(1, String::with_capacity(100)).unwrap();
+    let one = table.resident_bytes();
+    assert!(one > 100);
+
+    // Replacing a value only changes the heap bytes.
+    table.insert(1, String::with_capacity(200)).unwrap();
+    assert_eq!(table.resident_bytes(), one + 100);
+
+    table.insert(2, String::with_capacity(1000)).unwrap();
+    assert!(table.is_over_budget());
+
+    assert_eq!(table.remove(&2).unwrap().map(|value| value.capacity()), Some(1000));
+    assert_eq!(table.remove(&2), Ok(None));
+    assert_eq!(table.resident_bytes(), one + 100);
+    assert!(!table.is_over_budget());
+
+    table.remove(&1).unwrap();
+    assert_eq!(table.resident_bytes(), 0);
+}
+
+#[test]
+fn frozen_memtable_rejects_writes() {
+    let mut table: MemTable<u32, u32> = MemTable::new(16, Box::new(GeometricalGenerator::new(8, 0.5)));
+    for i in 0..10 {
+        table.insert(i, i).unwrap();
+    }
+    assert!(table.is_over_budget());
+
+    table.freeze();
+    assert!(table.is_frozen());
+    assert_eq!(table.insert(10, 10), Err(FrozenError));
+    assert_eq!(table.remove(&3), Err(FrozenError));
+    assert_eq!(table.len(), 10);
+    assert_eq!(table.get(&3), Some(&3));
+    assert_eq!(table.range(5..).count(), 5);
+
+    let map = table.into_map();
+    assert_eq!(map.len(), 10);
+}
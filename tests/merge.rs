@@ -0,0 +1,142 @@
+extern crate skiplist;
+use skiplist::*;
+
+use std::collections::BTreeMap;
+
+fn list(entries: &[(u32, u32)]) -> SkipListMap<u32, u32> {
+    let mut list = SkipListMap::new(Box::new(GeometricalGenerator::new(8, 0.5)));
+    for &(key, value) in entries {
+        list.insert(key, value);
+    }
+    list
+}
+
+#[test]
+fn merge_with_resolves_conflicts() {
+    let mut mine = list(&[(1, 10), (3, 30), (5, 50), (7, 70)]);
+    let theirs = list(&[(0, 1), (3, 3), (4, 4), (5, 5), (7, 7), (9, 9)]);
+
+    mine.merge_with(theirs, |key, mine, theirs| match *key {
+        3 => Resolution::KeepMine,
+        5 => Resolution::KeepTheirs,
+        _ => Resolution::Combined(mine + theirs),
+    });
+
+    let merged: Vec<(u32, u32)> = mine.iter().map(|(k, v)| (*k, *v)).collect();
+    assert_eq!(merged, vec![(0, 1), (1, 10), (3, 30), (4, 4), (5, 5), (7, 77), (9, 9)]);
+    assert_eq!(mine.len(), 7);
+    assert_eq!(mine.get(&4), Some(&4));
+    mine.insert(8, 8);
+    assert_eq!(mine.remove(&9), Some(9));
+    assert!(mine.keys().cloned().eq(vec![0, 1, 3, 4, 5, 7, 8]));
+}
+
+#[test]
+fn merge_with_matches_btree_map() {
+    let mut mine = list(&[]);
+    let mut theirs = list(&[]);
+    let mut model = BTreeMap::new();
+    for i in 0..500u32 {
+        let key = i.wrapping_mul(2654435761) % 1000;
+        if i % 2 == 0 {
+            mine.insert(key, i);
+            model.insert(key, i);
+        } else {
+            theirs.insert(key, i);
+        }
+    }
+
+    for (key, value) in theirs.iter() {
+        let merged = match model.get(key) {
+            Some(existing) => existing.max(value),
+            None => value,
+        };
+        model.insert(*key, *merged);
+    }
+
+    mine.merge_with(theirs, |_, mine, theirs| {
+        if mine >= theirs {
+            Resolution::KeepMine
+        } else {
+            Resolution::KeepTheirs
+        }
+    });
+
+    assert_eq!(mine.len(), model.len());
+    assert!(mine.iter().eq(model.iter()));
+    for key in model.keys() {
+        assert_eq!(mine.get(key), model.get(key));
+    }
+}
+
+#[test]
+fn merge_with_moves_taller_nodes() {
+    let mut mine: SkipListMap<u32, u32> =
+        SkipListMap::new(Box::new(GeometricalGenerator::new(1, 0.5)));
+    mine.insert(1, 1);
+    let theirs = list(&(0..100).map(|i| (i, i)).collect::<Vec<_>>());
+
+    mine.merge_with(theirs, |_, _, _| Resolution::KeepMine);
+    assert_eq!(mine.len(), 100);
+    assert!(mine.stats().max_height <= 1);
+    assert_eq!(mine.get(&1), Some(&1));
+    assert_eq!(mine.get(&99), Some(&99));
+}
+
+#[test]
+fn merge_iter_resolves_in_source_order() {
+    let sources = vec![
+        vec![(1, "a"), (3, "a"), (5, "a")].into_iter(),
+        vec![(2, "b"), (3, "b")].into_iter(),
+        vec![].into_iter(),
+        vec![(3, "c"), (5, "c"), (6, "c")].into_iter(),
+    ];
+
+    let mut seen = Vec::new();
+    let merged: Vec<(u32, &str)> = merge_iter(sources, |key, mine, theirs| {
+        seen.push((*key, *mine, *theirs));
+        Resolution::KeepTheirs
+    })
+    .collect();
+
+    assert_eq!(merged, vec![(1, "a"), (2, "b"), (3, "c"), (5, "c"), (6, "c")]);
+    assert_eq!(seen, vec![(3, "a", "b"), (3, "b", "c"), (5, "a", "c")]);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "strictly increasing")]
+fn merge_iter_rejects_repeated_keys_within_a_source() {
+    let sources = vec![vec![(1, 1), (1, 2)].into_iter(), vec![(0, 0)].into_iter()];
+    let _ = merge_iter(sources, |_, _, _| Resolution::KeepTheirs).count();
+}
+
+#[test]
+fn from_sorted_sources() {
+    let multiples = |step: u32| (0..100).map(move |key| (key * step, 1));
+    let sources = vec![multiples(2), multiples(3), multiples(5)];
+    let merged: SkipListMap<u32, u32> = SkipListMap::from_sorted_sources(
+        sources,
+        |_, mine, theirs| Resolution::Combined(mine + theirs),
+        Box::new(TwoPowGenerator::new(8)),
+    )
+    .unwrap();
+
+    let mut expected = BTreeMap::new();
+    for &step in &[2, 3, 5] {
+        for key in 0..100 {
+            *expected.entry(key * step).or_insert(0) += 1;
+        }
+    }
+    assert!(merged.iter().eq(expected.iter()));
+    assert_eq!(merged.len(), expected.len());
+
+    let unsorted = vec![vec![(1, 1), (3, 3)].into_iter(), vec![(4, 4), (2, 2)].into_iter()];
+    let error = SkipListMap::<u32, u32>::from_sorted_sources(
+        unsorted,
+        |_, _, _| Resolution::KeepMine,
+        Box::new(TwoPowGenerator::new(8)),
+    )
+    .unwrap_err();
+    assert_eq!(error, UnsortedError { position: 3 });
+}
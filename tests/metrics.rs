@@ -0,0 +1,136 @@
+extern crate skiplist;
+use skiplist::*;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Default)]
+struct Counters {
+    inserts: AtomicUsize,
+    gets: AtomicUsize,
+    removes: AtomicUsize,
+    comparisons: AtomicUsize,
+    allocated: AtomicUsize,
+    length: AtomicUsize,
+    height: AtomicUsize,
+}
+
+struct Telemetry(Arc<Counters>);
+
+impl Metrics for Telemetry {
+    fn operation(&self, operation: Operation) {
+        let counter = match operation {
+            Operation::Insert => &self.0.inserts,
+            Operation::Get => &self.0.gets,
+            Operation::Remove => &self.0.removes,
+        };
+
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn comparisons(&self, count: usize) {
+        self.0.comparisons.fetch_add(count, Ordering::Relaxed);
+    }
+
+    fn allocated(&self, count: usize) {
+        self.0.allocated.fetch_add(count, Ordering::Relaxed);
+    }
+
+    fn resized(&self, length: usize, height: usize) {
+        self.0.length.store(length, Ordering::Relaxed);
+        self.0.height.store(height, Ordering::Relaxed);
+    }
+}
+
+#[test]
+fn metrics_count_operations() {
+    let counters = Arc::new(Counters::default());
+    let mut map: SkipListMap<u32, u32> = Default::default();
+    map.insert(0, 0);
+    map.set_metrics(Box::new(Telemetry(counters.clone())));
+    assert_eq!(counters.length.load(Ordering::Relaxed), 1);
+
+    for i in 1..100 {
+        map.insert(i, i);
+    }
+    map.insert(5, 50);
+    assert_eq!(counters.inserts.load(Ordering::Relaxed), 100);
+    assert_eq!(counters.allocated.load(Ordering::Relaxed), 99);
+    assert_eq!(counters.length.load(Ordering::Relaxed), 100);
+    assert!(counters.height.load(Ordering::Relaxed) >= 1);
+
+    let comparisons = counters.comparisons.load(Ordering::Relaxed);
+    assert_eq!(map.get(&5), Some(&50));
+    assert!(map.contains_key(&7));
+    *map.get_mut(&8).unwrap() += 1;
+    assert_eq!(counters.gets.load(Ordering::Relaxed), 3);
+    assert!(counters.comparisons.load(Ordering::Relaxed) > comparisons);
+
+    assert_eq!(map.remove(&5), Some(50));
+    assert_eq!(map.remove(&500), None);
+    assert_eq!(counters.removes.load(Ordering::Relaxed), 2);
+    assert_eq!(counters.length.load(Ordering::Relaxed), 99);
+
+    map.clear();
+    assert_eq!(counters.length.load(Ordering::Relaxed), 0);
+    assert_eq!(counters.height.load(Ordering::Relaxed), 0);
+
+    assert!(map.take_metrics().is_some());
+    map.insert(1, 1);
+    assert_eq!(counters.inserts.load(Ordering::Relaxed), 100);
+    assert_eq!(counters.length.load(Ordering::Relaxed), 0);
+}
+
+#[test]
+fn metrics_are_not_cloned() {
+    let counters = Arc::new(Counters::default());
+    let mut map: SkipListMap<u32, u32> = Default::default();
+    map.set_metrics(Box::new(Telemetry(counters.clone())));
+    map.insert(1, 1);
+
+    let mut copy = map.clone();
+    let mut rest = map.split_off(&0);
+    copy.insert(2, 2);
+    rest.insert(3, 3);
+    assert_eq!(counters.inserts.load(Ordering::Relaxed), 1);
+}
+
+/// Gives key 50 a tower that reaches the top, and every other key none.
+#[derive(Clone)]
+struct TallFifty;
+
+impl HeightControl<u32> for TallFifty {
+    fn max_height(&self) -> usize {
+        4
+    }
+
+    fn get_height(&mut self, key: &u32) -> usize {
+        if *key == 50 {
+            4
+        } else {
+            0
+        }
+    }
+}
+
+#[test]
+fn lookups_stop_at_the_level_they_hit() {
+    let counters = Arc::new(Counters::default());
+    let mut map: SkipListMap<u32, u32> = SkipListMap::new(Box::new(TallFifty));
+    for i in 0..100 {
+        map.insert(i, i);
+    }
+    map.set_metrics(Box::new(Telemetry(counters.clone())));
+
+    assert_eq!(map.get(&50), Some(&50));
+    assert!(map.contains_key(&50));
+    assert_eq!(map.get_key_value(&50), Some((&50, &50)));
+    *map.get_mut(&50).unwrap() += 1;
+    assert_eq!(counters.comparisons.load(Ordering::Relaxed), 4);
+
+    // Misses and keys on level 0 still go all the way down.
+    assert_eq!(map.get(&49), Some(&49));
+    assert_eq!(map.get(&100), None);
+    assert_eq!(map.get_mut(&0), Some(&mut 0));
+    assert!(counters.comparisons.load(Ordering::Relaxed) > 50);
+}
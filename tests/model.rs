@@ -0,0 +1,261 @@
+//! Differential testing against `BTreeMap`: random sequences of operations are
+//! applied to both a `SkipListMap` and a `BTreeMap`, and every observable
+//! result must be the same.
+extern crate skiplist;
+use skiplist::*;
+
+extern crate rand;
+
+extern crate quickcheck;
+use quickcheck::{quickcheck, Arbitrary, Gen};
+
+use std::collections::BTreeMap;
+use std::ops::{Bound, RangeBounds};
+
+/// Keys are drawn from a small domain, so that operations often hit existing
+/// entries.
+const KEY_DOMAIN: u8 = 64;
+
+#[derive(Debug, Clone)]
+enum Op {
+    Insert(u8, u32),
+    Remove(u8),
+    Get(u8),
+    GetMut(u8, u32),
+    Range(Bound<u8>, Bound<u8>),
+    Iterate,
+    Clear,
+    SplitOff(u8),
+    SplitAtIndex(usize),
+    DrainRange(Bound<u8>, Bound<u8>),
+    RetainRange(Bound<u8>, Bound<u8>),
+    PopFirstN(usize),
+    PopLastN(usize),
+    Append(Vec<(u8, u32)>),
+    PushBack(u8, u32),
+}
+
+fn arbitrary_key<G: Gen>(gen: &mut G) -> u8 {
+    gen.gen_range(0, KEY_DOMAIN)
+}
+
+fn arbitrary_bound<G: Gen>(gen: &mut G, key: u8) -> Bound<u8> {
+    match gen.gen_range(0, 3) {
+        0 => Bound::Included(key),
+        1 => Bound::Excluded(key),
+        _ => Bound::Unbounded,
+    }
+}
+
+fn arbitrary_range<G: Gen>(gen: &mut G) -> (Bound<u8>, Bound<u8>) {
+    let mut start = arbitrary_key(gen);
+    let mut end = arbitrary_key(gen);
+    if start > end {
+        std::mem::swap(&mut start, &mut end);
+    }
+
+    let start = arbitrary_bound(gen, start);
+    let mut end = arbitrary_bound(gen, end);
+    // `BTreeMap::range` panics on empty ranges of this shape.
+    if let (Bound::Excluded(a), Bound::Excluded(b)) = (start, end) {
+        if a == b {
+            end = Bound::Included(b);
+        }
+    }
+
+    (start, end)
+}
+
+impl Arbitrary for Op {
+    fn arbitrary<G: Gen>(gen: &mut G) -> Op {
+        match gen.gen_range(0, 27) {
+            0..=5 => Op::Insert(arbitrary_key(gen), Arbitrary::arbitrary(gen)),
+            6..=8 => Op::Remove(arbitrary_key(gen)),
+            9..=10 => Op::Get(arbitrary_key(gen)),
+            11 => Op::GetMut(arbitrary_key(gen), Arbitrary::arbitrary(gen)),
+            12..=14 => {
+                let (start, end) = arbitrary_range(gen);
+                Op::Range(start, end)
+            }
+            15 => Op::Iterate,
+            16 => Op::Clear,
+            17 => Op::SplitOff(arbitrary_key(gen)),
+            18 => {
+                let (start, end) = arbitrary_range(gen);
+                Op::DrainRange(start, end)
+            }
+            19 => {
+                let (start, end) = arbitrary_range(gen);
+                Op::RetainRange(start, end)
+            }
+            20 => Op::PopFirstN(gen.gen_range(0, 8)),
+            21 => Op::PopLastN(gen.gen_range(0, 8)),
+            22..=23 => Op::PushBack(arbitrary_key(gen), Arbitrary::arbitrary(gen)),
+            24 => Op::SplitAtIndex(gen.gen_range(0, 16)),
+            _ => {
+                let length = gen.gen_range(0, 10);
+                Op::Append(
+                    (0..length)
+                        .map(|_| (arbitrary_key(gen), Arbitrary::arbitrary(gen)))
+                        .collect(),
+                )
+            }
+        }
+    }
+}
+
+fn new_list() -> SkipListMap<u8, u32> {
+    SkipListMap::new(Box::new(GeometricalGenerator::new(8, 0.5)))
+}
+
+fn contents(list: &SkipListMap<u8, u32>) -> Vec<(u8, u32)> {
+    list.iter().map(|(key, value)| (*key, *value)).collect()
+}
+
+fn model_contents(model: &BTreeMap<u8, u32>) -> Vec<(u8, u32)> {
+    model.iter().map(|(key, value)| (*key, *value)).collect()
+}
+
+/// Applies `operations` to both structures, returning a description of the
+/// first divergence, if any.
+fn run(operations: &[Op]) -> Result<(), String> {
+    let mut list = new_list();
+    let mut model = BTreeMap::new();
+
+    for (step, operation) in operations.iter().enumerate() {
+        let same = match *operation {
+            Op::Insert(key, value) => list.insert(key, value) == model.insert(key, value),
+            Op::Remove(key) => list.remove(&key) == model.remove(&key),
+            Op::Get(key) => {
+                list.get(&key) == model.get(&key) &&
+                    list.contains_key(&key) == model.contains_key(&key)
+            }
+            Op::GetMut(key, value) => {
+                let listed = list.get_mut(&key).map(|v| std::mem::replace(v, value));
+                let modeled = model.get_mut(&key).map(|v| std::mem::replace(v, value));
+                listed == modeled
+            }
+            Op::Range(start, end) => {
+                let listed: Vec<(u8, u32)> = list.range((start, end))
+                    .map(|(key, value)| (*key, *value))
+                    .collect();
+                let modeled: Vec<(u8, u32)> = model
+                    .range((start, end))
+                    .map(|(key, value)| (*key, *value))
+                    .collect();
+                listed == modeled && list.is_range_empty((start, end)) == modeled.is_empty()
+            }
+            Op::Iterate => {
+                contents(&list) == model_contents(&model) &&
+                    list.keys().eq(model.keys()) &&
+                    list.values().eq(model.values()) &&
+                    list.first() == model.iter().next()
+            }
+            Op::Clear => {
+                list.clear();
+                model.clear();
+                true
+            }
+            Op::SplitOff(key) => {
+                let listed = list.split_off(&key);
+                let modeled = model.split_off(&key);
+                listed.len() == modeled.len() && contents(&listed) == model_contents(&modeled)
+            }
+            Op::SplitAtIndex(n) => {
+                let listed = list.split_at_index(n);
+                let modeled = match model.keys().nth(n).cloned() {
+                    Some(key) => model.split_off(&key),
+                    None => BTreeMap::new(),
+                };
+                listed.len() == modeled.len() && contents(&listed) == model_contents(&modeled)
+            }
+            Op::DrainRange(start, end) => {
+                let listed: Vec<(u8, u32)> = list.drain_range((start, end)).collect();
+                let keys: Vec<u8> = model.range((start, end)).map(|(key, _)| *key).collect();
+                let modeled: Vec<(u8, u32)> = keys
+                    .into_iter()
+                    .map(|key| (key, model.remove(&key).unwrap()))
+                    .collect();
+                listed == modeled
+            }
+            Op::RetainRange(start, end) => {
+                list.retain_range((start, end), |_, value| *value % 3 != 0);
+                model.retain(|key, value| !(start, end).contains(key) || *value % 3 != 0);
+                true
+            }
+            Op::PopFirstN(n) => {
+                let keys: Vec<u8> = model.keys().take(n).cloned().collect();
+                let modeled: Vec<(u8, u32)> = keys
+                    .into_iter()
+                    .map(|key| (key, model.remove(&key).unwrap()))
+                    .collect();
+                list.pop_first_n(n) == modeled
+            }
+            Op::PopLastN(n) => {
+                let mut keys: Vec<u8> = model.keys().rev().take(n).cloned().collect();
+                keys.reverse();
+                let modeled: Vec<(u8, u32)> = keys
+                    .into_iter()
+                    .map(|key| (key, model.remove(&key).unwrap()))
+                    .collect();
+                list.pop_last_n(n) == modeled
+            }
+            Op::Append(ref entries) => {
+                let mut other = new_list();
+                let mut other_model = BTreeMap::new();
+                for &(key, value) in entries {
+                    other.insert(key, value);
+                    other_model.insert(key, value);
+                }
+
+                list.append(&mut other);
+                model.append(&mut other_model);
+                other.is_empty() && other.iter().next().is_none()
+            }
+            Op::PushBack(key, value) => {
+                let expected = match model.keys().next_back() {
+                    Some(&last) if last >= key => Err((key, value)),
+                    _ => {
+                        model.insert(key, value);
+                        Ok(())
+                    }
+                };
+
+                list.push_back(key, value) == expected
+            }
+        };
+
+        if !same || list.len() != model.len() || list.is_empty() != model.is_empty() {
+            return Err(format!("diverged at step {}: {:?}", step, operation));
+        }
+    }
+
+    if contents(&list) != model_contents(&model) {
+        return Err("final contents diverged".to_string());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn behaves_like_btree_map() {
+    fn prop(operations: Vec<Op>) -> bool {
+        run(&operations).is_ok()
+    }
+
+    quickcheck(prop as fn(Vec<Op>) -> bool);
+}
+
+#[test]
+fn long_sequences_behave_like_btree_map() {
+    use quickcheck::{QuickCheck, StdGen};
+
+    fn prop(operations: Vec<Op>) -> bool {
+        run(&operations).is_ok()
+    }
+
+    QuickCheck::new()
+        .gen(StdGen::new(rand::thread_rng(), 2000))
+        .tests(50)
+        .quickcheck(prop as fn(Vec<Op>) -> bool);
+}
@@ -0,0 +1,100 @@
+extern crate skiplist;
+use skiplist::*;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Secondary index from values to keys.
+struct ReverseIndex(Arc<Mutex<HashMap<u32, u32>>>);
+
+impl Observer<u32, u32> for ReverseIndex {
+    fn inserted(&mut self, key: &u32, value: &u32) {
+        self.0.lock().unwrap().insert(*value, *key);
+    }
+
+    fn updated(&mut self, key: &u32, old: &u32, new: &u32) {
+        let mut index = self.0.lock().unwrap();
+        index.remove(old);
+        index.insert(*new, *key);
+    }
+
+    fn removed(&mut self, _key: &u32, value: &u32) {
+        self.0.lock().unwrap().remove(value);
+    }
+}
+
+struct Counts(Arc<Mutex<(usize, usize, usize)>>);
+
+impl Observer<u32, u32> for Counts {
+    fn inserted(&mut self, _key: &u32, _value: &u32) {
+        self.0.lock().unwrap().0 += 1;
+    }
+
+    fn removed(&mut self, _key: &u32, _value: &u32) {
+        self.0.lock().unwrap().2 += 1;
+    }
+}
+
+#[test]
+fn observers_see_every_change() {
+    let index = Arc::new(Mutex::new(HashMap::new()));
+    let counts = Arc::new(Mutex::new((0, 0, 0)));
+
+    let mut map: ObservedSkipListMap<u32, u32> = Default::default();
+    map.observe(Box::new(ReverseIndex(index.clone())));
+    map.observe(Box::new(Counts(counts.clone())));
+
+    for key in 0..10 {
+        map.insert(key, key * 100);
+    }
+    assert_eq!(map.insert(3, 333), Some(300));
+    assert_eq!(map.remove(&4), Some(400));
+    assert_eq!(map.remove(&4), None);
+
+    {
+        let index = index.lock().unwrap();
+        assert_eq!(index.len(), 9);
+        assert_eq!(index.get(&333), Some(&3));
+        assert_eq!(index.get(&300), None);
+        assert_eq!(index.get(&400), None);
+    }
+    assert_eq!(*counts.lock().unwrap(), (10, 0, 1));
+
+    map.clear();
+    assert!(index.lock().unwrap().is_empty());
+    assert_eq!(*counts.lock().unwrap(), (10, 0, 10));
+
+    assert_eq!(map.take_observers().len(), 2);
+    map.insert(1, 1);
+    assert!(index.lock().unwrap().is_empty());
+}
+
+/// Records the keys it is told were removed, and panics on the second one.
+struct FailsOnSecond(Arc<Mutex<Vec<u32>>>);
+
+impl Observer<u32, u32> for FailsOnSecond {
+    fn removed(&mut self, key: &u32, _value: &u32) {
+        let mut removed = self.0.lock().unwrap();
+        removed.push(*key);
+        if removed.len() == 2 {
+            panic!("observer failed");
+        }
+    }
+}
+
+#[test]
+fn clear_empties_the_map_before_notifying() {
+    let removed = Arc::new(Mutex::new(Vec::new()));
+    let mut map: ObservedSkipListMap<u32, u32> = Default::default();
+    for key in 0..5 {
+        map.insert(key, key);
+    }
+
+    map.observe(Box::new(FailsOnSecond(removed.clone())));
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| map.clear()));
+
+    assert!(result.is_err());
+    assert!(map.is_empty());
+    assert_eq!(map.iter().count(), 0);
+    assert_eq!(*removed.lock().unwrap_or_else(|poisoned| poisoned.into_inner()), vec![0, 1]);
+}
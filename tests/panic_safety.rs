@@ -0,0 +1,277 @@
+extern crate skiplist;
+use skiplist::*;
+
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::rc::Rc;
+
+/// Key whose comparisons panic whenever one of the two sides is poisoned.
+#[derive(Debug, Clone)]
+struct PoisonKey {
+    value: u32,
+    poisoned: bool,
+}
+
+impl PoisonKey {
+    fn new(value: u32) -> PoisonKey {
+        PoisonKey {
+            value,
+            poisoned: false,
+        }
+    }
+
+    fn poisoned(value: u32) -> PoisonKey {
+        PoisonKey {
+            value,
+            poisoned: true,
+        }
+    }
+}
+
+impl PartialEq for PoisonKey {
+    fn eq(&self, other: &PoisonKey) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for PoisonKey {}
+
+impl PartialOrd for PoisonKey {
+    fn partial_cmp(&self, other: &PoisonKey) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PoisonKey {
+    fn cmp(&self, other: &PoisonKey) -> Ordering {
+        if self.poisoned || other.poisoned {
+            panic!("poisoned comparison");
+        }
+
+        self.value.cmp(&other.value)
+    }
+}
+
+/// Value that counts its drops, and panics when dropped if asked to.
+struct DropCounter {
+    drops: Rc<Cell<usize>>,
+    panics: bool,
+}
+
+impl Drop for DropCounter {
+    fn drop(&mut self) {
+        self.drops.set(self.drops.get() + 1);
+        if self.panics {
+            panic!("panicking drop");
+        }
+    }
+}
+
+/// Height controller that panics once it has produced `remaining` heights.
+#[derive(Clone)]
+struct PanickingController {
+    remaining: usize,
+}
+
+impl HeightControl<u32> for PanickingController {
+    fn max_height(&self) -> usize {
+        8
+    }
+
+    fn get_height(&mut self, key: &u32) -> usize {
+        if self.remaining == 0 {
+            panic!("panicking controller");
+        }
+
+        self.remaining -= 1;
+        (*key % 8) as usize
+    }
+}
+
+fn poison_list(length: u32) -> SkipListMap<PoisonKey, u32> {
+    let mut list = SkipListMap::new(Box::new(TwoPowGenerator::new(16)));
+    for i in 0..length {
+        list.insert(PoisonKey::new(i), i);
+    }
+
+    list
+}
+
+fn assert_valid(list: &SkipListMap<PoisonKey, u32>, expected: &[u32]) {
+    assert_eq!(list.len(), expected.len());
+    assert!(list.keys().map(|key| key.value).eq(expected.iter().cloned()));
+    for value in expected {
+        assert_eq!(list.get(&PoisonKey::new(*value)), Some(value));
+    }
+}
+
+#[test]
+fn insert_with_panicking_comparison() {
+    let mut list = poison_list(50);
+
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        list.insert(PoisonKey::poisoned(25), 0);
+    }));
+    assert!(result.is_err());
+
+    let expected: Vec<u32> = (0..50).collect();
+    assert_valid(&list, &expected);
+    assert!(list.insert(PoisonKey::new(50), 50).is_none());
+}
+
+#[test]
+fn remove_with_panicking_comparison() {
+    let mut list = poison_list(50);
+
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        list.remove(&PoisonKey::poisoned(10));
+    }));
+    assert!(result.is_err());
+
+    let expected: Vec<u32> = (0..50).collect();
+    assert_valid(&list, &expected);
+    assert_eq!(list.remove(&PoisonKey::new(10)), Some(10));
+}
+
+#[test]
+fn clear_with_panicking_drop() {
+    let drops = Rc::new(Cell::new(0));
+    let mut list: SkipListMap<u32, DropCounter> = Default::default();
+    for i in 0..20 {
+        list.insert(
+            i,
+            DropCounter {
+                drops: drops.clone(),
+                panics: i == 7,
+            },
+        );
+    }
+
+    let result = catch_unwind(AssertUnwindSafe(|| list.clear()));
+    assert!(result.is_err());
+
+    // Every value was dropped, including the ones after the panicking one.
+    assert_eq!(drops.get(), 20);
+    assert!(list.is_empty());
+    assert!(list.iter().next().is_none());
+
+    list.insert(
+        3,
+        DropCounter {
+            drops: drops.clone(),
+            panics: false,
+        },
+    );
+    assert_eq!(list.len(), 1);
+    drop(list);
+    assert_eq!(drops.get(), 21);
+}
+
+#[test]
+fn drop_with_panicking_drop() {
+    let drops = Rc::new(Cell::new(0));
+    let mut list: SkipListMap<u32, DropCounter> = Default::default();
+    for i in 0..20 {
+        list.insert(
+            i,
+            DropCounter {
+                drops: drops.clone(),
+                panics: i == 0,
+            },
+        );
+    }
+
+    let result = catch_unwind(AssertUnwindSafe(move || drop(list)));
+    assert!(result.is_err());
+    assert_eq!(drops.get(), 20);
+}
+
+#[test]
+fn rebuild_with_panicking_controller() {
+    let mut list: SkipListMap<u32, u32> = Default::default();
+    for i in 0..30 {
+        list.insert(i, i);
+    }
+
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        list.set_height_control(
+            Box::new(PanickingController { remaining: 10 }),
+            RebuildPolicy::Rebuild,
+        );
+    }));
+    assert!(result.is_err());
+
+    assert_eq!(list.len(), 30);
+    assert!(list.keys().cloned().eq(0..30));
+    for i in 0..30 {
+        assert_eq!(list.remove(&i), Some(i));
+    }
+}
+
+#[test]
+fn clone_from_with_panicking_controller() {
+    let mut source = SkipListMap::new(Box::new(PanickingController { remaining: 40 }));
+    for i in 0..30 {
+        source.insert(i, i);
+    }
+
+    let mut list: SkipListMap<u32, u32> = Default::default();
+    for i in 100..150 {
+        list.insert(i, i);
+    }
+
+    // The controller of `source` has 10 heights left, and is cloned along.
+    let result = catch_unwind(AssertUnwindSafe(|| list.clone_from(&source)));
+    assert!(result.is_err());
+
+    assert_eq!(list.len(), 10);
+    assert!(list.keys().cloned().eq(0..10));
+    assert_eq!(list.remove(&5), Some(5));
+}
+
+#[test]
+fn insert_with_panicking_controller() {
+    let mut list = SkipListMap::new(Box::new(PanickingController { remaining: 20 }));
+    for i in (0..20).rev() {
+        list.insert(i * 2, i);
+    }
+
+    // Replacing values, at the tail or not, never asks for a height.
+    for i in 0..20 {
+        assert_eq!(list.insert(i * 2, i + 100), Some(i));
+    }
+    assert_eq!(list.insert(38, 0), Some(119));
+
+    // New keys do, both past the tail and within the list.
+    let result = catch_unwind(AssertUnwindSafe(|| list.insert(40, 40)));
+    assert!(result.is_err());
+    let result = catch_unwind(AssertUnwindSafe(|| list.insert(41, 41)));
+    assert!(result.is_err());
+    let result = catch_unwind(AssertUnwindSafe(|| list.insert(5, 5)));
+    assert!(result.is_err());
+
+    assert_eq!(list.len(), 20);
+    assert!(list.keys().cloned().eq((0..20).map(|i| i * 2)));
+    assert_eq!(list.remove(&38), Some(0));
+}
+
+#[test]
+fn persistent_insert_with_panicking_controller() {
+    let mut version = PersistentSkipListMap::new(Box::new(PanickingController { remaining: 5 }));
+    for i in 0..5 {
+        version = version.insert(i, i);
+    }
+
+    // The controller is shared, and the panic poisons its lock, which later
+    // inserts go through anyway.
+    for _ in 0..2 {
+        let result = catch_unwind(AssertUnwindSafe(|| version.insert(10, 10)));
+        let payload = result.err().unwrap();
+        assert_eq!(payload.downcast_ref::<&str>(), Some(&"panicking controller"));
+    }
+
+    let replaced = version.insert(3, 30);
+    assert_eq!(replaced.get(&3), Some(&30));
+    assert!(version.iter().map(|(&key, &value)| (key, value)).eq((0..5).map(|i| (i, i))));
+}
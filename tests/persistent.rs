@@ -0,0 +1,79 @@
+extern crate skiplist;
+use skiplist::*;
+
+use std::collections::BTreeMap;
+
+fn empty() -> PersistentSkipListMap<u32, String> {
+    PersistentSkipListMap::new(Box::new(TwoPowGenerator::new(16)))
+}
+
+#[test]
+fn versions_are_independent() {
+    let mut versions = vec![empty()];
+    for i in 0..100u32 {
+        let next = versions.last().unwrap().insert((i * 37) % 101, i.to_string());
+        versions.push(next);
+    }
+
+    for (length, version) in versions.iter().enumerate() {
+        assert_eq!(version.len(), length);
+        assert_eq!(version.iter().len(), length);
+        let mut expected: Vec<u32> = (0..length as u32).map(|i| (i * 37) % 101).collect();
+        expected.sort();
+        assert!(version.iter().map(|(&key, _)| key).eq(expected));
+    }
+
+    let last = versions.last().unwrap();
+    let removed = last.remove(&37);
+    assert_eq!(removed.len(), 99);
+    assert!(!removed.contains_key(&37));
+    assert_eq!(last.get(&37).map(String::as_str), Some("1"));
+
+    let replaced = last.insert(37, "new".to_string());
+    assert_eq!(replaced.len(), 100);
+    assert_eq!(replaced.get(&37).map(String::as_str), Some("new"));
+    assert_eq!(last.get(&37).map(String::as_str), Some("1"));
+}
+
+#[test]
+fn matches_btree_map() {
+    let mut map = empty();
+    let mut model = BTreeMap::new();
+    for i in 0..2000u32 {
+        let key = (i * 7919) % 503;
+        if i % 3 == 0 {
+            map = map.remove(&key);
+            model.remove(&key);
+        } else {
+            map = map.insert(key, i.to_string());
+            model.insert(key, i.to_string());
+        }
+    }
+
+    assert_eq!(map.len(), model.len());
+    assert!(map.iter().eq(model.iter()));
+    for key in 0..503 {
+        assert_eq!(map.get(&key), model.get(&key));
+    }
+}
+
+#[test]
+fn removing_missing_key_shares_everything() {
+    let map = empty().insert(1, "one".to_string());
+    assert!(map.remove(&2).ptr_eq(&map));
+    assert!(map.clone().ptr_eq(&map));
+    assert!(!map.insert(2, "two".to_string()).ptr_eq(&map));
+}
+
+#[test]
+fn dropping_long_lists() {
+    let mut map = PersistentSkipListMap::new(Box::new(TwoPowGenerator::new(16)));
+    for i in 0..100_000u32 {
+        // Inserting at the front only copies the new node.
+        map = map.insert(100_000 - i, ());
+    }
+
+    let older = map.remove(&1);
+    drop(map);
+    assert_eq!(older.len(), 99_999);
+}
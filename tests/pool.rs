@@ -0,0 +1,169 @@
+extern crate skiplist;
+use skiplist::*;
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+fn pooled_list(pool: &NodePool<u32, u32>) -> SkipListMap<u32, u32> {
+    let mut list: SkipListMap<u32, u32> = Default::default();
+    list.set_node_pool(pool.clone());
+    list
+}
+
+#[test]
+fn lists_share_nodes() {
+    let pool = NodePool::new(1000);
+    let mut first = pooled_list(&pool);
+    let mut second = pooled_list(&pool);
+
+    for i in 0..100 {
+        first.insert(i, i);
+    }
+    assert_eq!(pool.stats().allocated, 100);
+    assert_eq!(pool.stats().pooled, 0);
+
+    for i in 0..50 {
+        assert_eq!(first.remove(&i), Some(i));
+    }
+    assert_eq!(first.pop_first(), Some((50, 50)));
+    assert_eq!(pool.stats().pooled, 51);
+
+    // The second list takes the nodes the first one gave back.
+    for i in 0..60 {
+        second.insert(i, i * 2);
+    }
+    let stats = pool.stats();
+    assert_eq!(stats.allocated, 109);
+    assert_eq!(stats.reused, 51);
+    assert_eq!(stats.pooled, 0);
+    assert!(second.iter().map(|(&k, &v)| (k, v)).eq((0..60).map(|i| (i, i * 2))));
+
+    first.clear();
+    drop(second);
+    assert_eq!(pool.stats().pooled, 109);
+    assert!(first.is_empty());
+
+    assert_eq!(pool.release(), 109);
+    let stats = pool.stats();
+    assert_eq!(stats.pooled, 0);
+    assert_eq!(stats.released, 109);
+
+    first.insert(1, 1);
+    assert_eq!(pool.stats().allocated, 110);
+}
+
+#[test]
+fn pool_capacity_caps_free_nodes() {
+    let pool = NodePool::new(10);
+    let mut list = pooled_list(&pool);
+    for i in 0..30 {
+        list.insert(i, i);
+    }
+
+    list.retain_range::<u32, _, _>(.., |key, _| key % 2 == 0);
+    assert_eq!(list.len(), 15);
+    let stats = pool.stats();
+    assert_eq!(stats.pooled, 10);
+    assert_eq!(stats.released, 5);
+
+    // Nodes of lists that stop using the pool are not given back.
+    assert!(list.take_node_pool().is_some());
+    list.clear();
+    assert_eq!(pool.stats().pooled, 10);
+}
+
+#[test]
+fn pooled_nodes_drop_values() {
+    struct DropCounter(Rc<Cell<usize>>);
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    let drops = Rc::new(Cell::new(0));
+    let pool = NodePool::new(100);
+    let mut list: SkipListMap<u32, DropCounter> = Default::default();
+    list.set_node_pool(pool.clone());
+    for i in 0..20 {
+        list.insert(i, DropCounter(drops.clone()));
+    }
+
+    list.remove(&3);
+    assert_eq!(drops.get(), 1);
+    let entries = list.into_sorted_vec();
+    assert_eq!(drops.get(), 1);
+    assert_eq!(pool.stats().pooled, 20);
+    drop(entries);
+    assert_eq!(drops.get(), 20);
+}
+
+#[test]
+fn reserve_stays_within_capacity() {
+    let pool: NodePool<u32, u32> = NodePool::new(5);
+    assert_eq!(pool.reserve(3), 3);
+    assert_eq!(pool.reserve(3), 2);
+    assert_eq!(pool.reserve(3), 0);
+    assert_eq!(pool.stats().pooled, 5);
+
+    let mut list = pooled_list(&pool);
+    for i in 0..100 {
+        list.insert(i, i);
+    }
+    assert_eq!(pool.stats().reused, 5);
+    assert_eq!(pool.release(), 0);
+}
+
+#[test]
+fn lists_reserve_through_their_pool() {
+    let mut list: SkipListMap<u32, u32> = Default::default();
+    assert_eq!(list.reserve(10), 0);
+
+    let pool = NodePool::new(8);
+    list.set_node_pool(pool.clone());
+    assert_eq!(list.reserve(10), 8);
+    for i in 0..10 {
+        list.insert(i, i);
+    }
+    let stats = pool.stats();
+    assert_eq!(stats.reused, 8);
+    assert_eq!(stats.allocated, 10);
+}
+
+#[test]
+fn popped_and_drained_nodes_go_back() {
+    let pool = NodePool::new(1000);
+    let mut list = pooled_list(&pool);
+    for i in 0..100 {
+        list.insert(i, i);
+    }
+
+    assert_eq!(list.pop_last(), Some((99, 99)));
+    assert_eq!(list.pop_first_n(10).len(), 10);
+    assert_eq!(list.pop_last_n(10).len(), 10);
+    assert_eq!(pool.stats().pooled, 21);
+
+    // Entries left in the drain are given back when it is dropped.
+    assert_eq!(list.drain_range(20..40).take(5).count(), 5);
+    assert_eq!(pool.stats().pooled, 41);
+
+    match list.entry_ref(&50) {
+        EntryRef::Occupied(entry) => assert_eq!(entry.remove(), 50),
+        EntryRef::Vacant(_) => panic!("50 is in the list"),
+    }
+    assert_eq!(pool.stats().pooled, 42);
+
+    let detached = list.detach(&60).unwrap();
+    drop(detached);
+    assert_eq!(list.detach(&61).unwrap().into_entry(), (61, 61));
+    assert_eq!(pool.stats().pooled, 44);
+
+    // Attached nodes belong to the list they are attached to.
+    let mut other: SkipListMap<u32, u32> = Default::default();
+    assert!(other.attach(list.detach(&62).unwrap()).is_ok());
+    drop(other);
+    let stats = pool.stats();
+    assert_eq!(stats.pooled, 44);
+    assert_eq!(stats.allocated, 100);
+}
@@ -0,0 +1,70 @@
+extern crate skiplist;
+use skiplist::*;
+
+use std::collections::BTreeMap;
+
+fn empty() -> PrefixSkipListMap<u32> {
+    PrefixSkipListMap::new(Box::new(TwoPowGenerator::new(16)))
+}
+
+fn path(i: u32) -> Vec<u8> {
+    format!("/usr/share/doc/packages/{:05}/README", i).into_bytes()
+}
+
+#[test]
+fn matches_btree_map() {
+    let mut map = empty();
+    let mut model = BTreeMap::new();
+    for i in 0..3000u32 {
+        let key = path((i * 7919) % 701);
+        match i % 4 {
+            0 => assert_eq!(map.remove(&key), model.remove(&key)),
+            _ => assert_eq!(map.insert(key.clone(), i), model.insert(key, i)),
+        }
+    }
+
+    assert_eq!(map.len(), model.len());
+    assert!(map.iter().map(|(key, &value)| (key, value)).eq(model.clone()));
+    for i in 0..701 {
+        assert_eq!(map.get(&path(i)), model.get(&path(i)));
+    }
+
+    let keys: Vec<Vec<u8>> = model.keys().cloned().collect();
+    for key in keys {
+        assert_eq!(map.remove(&key), model.remove(&key));
+    }
+    assert!(map.is_empty());
+    assert_eq!(map.key_bytes(), 0);
+}
+
+#[test]
+fn compresses_shared_prefixes() {
+    let mut map = empty();
+    let mut total = 0;
+    for i in 0..1000 {
+        total += path(i).len();
+        map.insert(path(i), i);
+    }
+
+    // Only the keys of restart points are stored in full.
+    assert!(map.key_bytes() < total * 3 / 4);
+    assert!(map.iter().map(|(key, _)| key).eq((0..1000).map(path)));
+}
+
+#[test]
+fn get_mut_and_clear() {
+    let mut map = empty();
+    map.insert(b"abc".to_vec(), 1);
+    map.insert(b"abd".to_vec(), 2);
+    map.insert(b"ab".to_vec(), 3);
+    *map.get_mut(b"abd").unwrap() += 10;
+    assert_eq!(map.get(b"abd"), Some(&12));
+    assert!(map.contains_key(b"ab"));
+    assert!(!map.contains_key(b"a"));
+
+    map.clear();
+    assert!(map.is_empty());
+    assert_eq!(map.get(b"abc"), None);
+    map.insert(b"x".to_vec(), 1);
+    assert_eq!(map.get(b"x"), Some(&1));
+}
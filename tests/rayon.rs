@@ -0,0 +1,39 @@
+#![cfg(feature = "rayon")]
+
+extern crate rayon;
+extern crate skiplist;
+use skiplist::*;
+
+use rayon::prelude::*;
+use std::collections::BTreeMap;
+
+#[test]
+fn par_extend_matches_extend() {
+    let entries: Vec<(u32, u32)> = (0..20_000u32).map(|i| (i.wrapping_mul(7919) % 5000, i)).collect();
+
+    let mut list: SkipListMap<u32, u32> = Default::default();
+    let mut model = BTreeMap::new();
+    for i in (0..6000).step_by(3) {
+        list.insert(i, 0);
+        model.insert(i, 0);
+    }
+
+    list.par_extend(entries.clone());
+    model.extend(entries);
+    assert_eq!(list.len(), model.len());
+    assert!(list.iter().eq(model.iter()));
+    for i in 0..6000 {
+        assert_eq!(list.get(&i), model.get(&i));
+    }
+
+    list.par_extend(Vec::new());
+    assert_eq!(list.len(), model.len());
+}
+
+#[test]
+fn collect_in_parallel() {
+    let mut list: SkipListMap<u32, u64> = (0..10_000u32).into_par_iter().rev().map(|i| (i, i as u64 * i as u64)).collect();
+    assert_eq!(list.len(), 10_000);
+    assert!(list.iter().map(|(&key, &value)| (key, value)).eq((0..10_000).map(|i| (i, i as u64 * i as u64))));
+    assert_eq!(list.remove(&5000), Some(25_000_000));
+}
@@ -0,0 +1,159 @@
+extern crate skiplist;
+use skiplist::*;
+use skiplist::region::RegionSkipList;
+
+use std::io;
+
+/// Regions must be aligned to 8 bytes, which a `Vec<u8>` does not guarantee.
+fn region(words: &mut Vec<u64>) -> &mut [u8] {
+    unsafe { std::slice::from_raw_parts_mut(words.as_mut_ptr() as *mut u8, words.len() * 8) }
+}
+
+fn controller() -> Box<HeightControl<u32>> {
+    Box::new(TwoPowGenerator::new(16))
+}
+
+/// Links every node at level 0 only, so that searches are predictable.
+fn flat() -> Box<HeightControl<u32>> {
+    Box::new(FnHeightControl::new(16, |_: &u32| 0))
+}
+
+#[test]
+fn insert_get_remove() {
+    let mut words = vec![0u64; 1 << 14];
+    let mut list = RegionSkipList::create(region(&mut words), controller()).unwrap();
+    for i in (0..1000u32).rev() {
+        assert_eq!(list.insert(i, i as u64 * 2).unwrap(), None);
+    }
+    assert_eq!(list.insert(7, 0).unwrap(), Some(14));
+    assert_eq!(list.len(), 1000);
+
+    for i in 0..500 {
+        assert_eq!(list.remove(&(i * 2)), Some(i as u64 * 4));
+    }
+    assert_eq!(list.remove(&0), None);
+    assert_eq!(list.len(), 500);
+    assert_eq!(list.get(&7), Some(&0));
+    assert_eq!(list.get(&9), Some(&18));
+    assert!(!list.contains_key(&10));
+    *list.get_mut(&9).unwrap() = 1;
+    assert_eq!(list.get(&9), Some(&1));
+
+    let keys: Vec<u32> = list.iter().map(|(&key, _)| key).collect();
+    assert_eq!(keys, (0..500).map(|i| i * 2 + 1).collect::<Vec<_>>());
+}
+
+#[test]
+fn reopen() {
+    let mut words = vec![0u64; 1 << 12];
+    {
+        let mut list = RegionSkipList::create(region(&mut words), controller()).unwrap();
+        for i in 0..100u32 {
+            list.insert(i, [i; 2]).unwrap();
+        }
+    }
+
+    let list: RegionSkipList<u32, [u32; 2]> =
+        unsafe { RegionSkipList::open(region(&mut words), controller()) }.unwrap();
+    assert_eq!(list.len(), 100);
+    assert!(list.iter().map(|(&key, &value)| (key, value)).eq((0..100).map(|i| (i, [i; 2]))));
+}
+
+#[test]
+fn reopen_with_other_types() {
+    let mut words = vec![0u64; 1 << 10];
+    RegionSkipList::<u32, u32>::create(region(&mut words), controller()).unwrap();
+    let error = unsafe { RegionSkipList::<u32, u64>::open(region(&mut words), controller()) }
+        .unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+    let mut garbage = vec![0u64; 1 << 10];
+    let error = unsafe { RegionSkipList::<u32, u32>::open(region(&mut garbage), controller()) }
+        .unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn misaligned_region() {
+    let mut words = vec![0u64; 1 << 10];
+    let error = RegionSkipList::<u32, u32>::create(&mut region(&mut words)[1..], controller())
+        .unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn out_of_space_reuses_removed_nodes() {
+    // Every node gets height 0, so removed nodes fit any later insertion.
+    let mut words = vec![0u64; 256];
+    let mut list =
+        RegionSkipList::create(region(&mut words), Box::new(GeometricalGenerator::new(4, 0.0)))
+            .unwrap();
+    let mut inserted = 0;
+    loop {
+        match list.insert(inserted, 0u64) {
+            Ok(_) => inserted += 1,
+            Err(error) => {
+                assert_eq!(error.kind(), io::ErrorKind::OutOfMemory);
+                break;
+            }
+        }
+    }
+    assert_eq!(list.len() as u32, inserted);
+    assert!(list.used_bytes() <= 256 * 8);
+
+    for i in 0..inserted {
+        list.remove(&i);
+        list.insert(i + inserted, 0).unwrap();
+    }
+    assert_eq!(list.len() as u32, inserted);
+
+    list.clear();
+    assert!(list.is_empty());
+    list.insert(1, 1).unwrap();
+    assert_eq!(list.get(&1), Some(&1));
+}
+
+#[test]
+fn checksums_detect_corruption() {
+    let mut words = vec![0u64; 1 << 12];
+    {
+        let mut list =
+            RegionSkipList::create_with_checksums(region(&mut words), flat()).unwrap();
+        assert!(list.has_checksums());
+        for i in 0..100u32 {
+            list.insert(i * 2, i as u64).unwrap();
+        }
+        for i in 0..10u32 {
+            list.remove(&(i * 4));
+        }
+        list.verify().unwrap();
+        assert_eq!(list.get_verified(&6).unwrap(), Some(&3));
+        assert_eq!(list.get_verified(&7).unwrap(), None);
+    }
+
+    // Flip a bit of a key stored in the region, e.g. after a torn write.
+    let bytes = region(&mut words);
+    let position = bytes.windows(4).rposition(|window| window == 198u32.to_ne_bytes()).unwrap();
+    bytes[position] ^= 1;
+
+    let list: RegionSkipList<u32, u64> =
+        unsafe { RegionSkipList::open(region(&mut words), flat()) }.unwrap();
+    assert!(list.has_checksums());
+    assert_eq!(list.verify().unwrap_err().kind(), io::ErrorKind::InvalidData);
+    assert_eq!(list.get_verified(&198).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    // Every node is only linked at level 0, so searches for smaller keys
+    // never reach the corrupted one.
+    assert_eq!(list.get_verified(&2).unwrap(), Some(&1));
+}
+
+#[test]
+fn verify_without_checksums() {
+    let mut words = vec![0u64; 1 << 12];
+    let mut list = RegionSkipList::create(region(&mut words), controller()).unwrap();
+    assert!(!list.has_checksums());
+    for i in 0..100u32 {
+        list.insert(i, 0u64).unwrap();
+    }
+    list.verify().unwrap();
+    assert_eq!(list.get_verified(&5).unwrap(), Some(&0));
+}
@@ -0,0 +1,41 @@
+extern crate skiplist;
+use skiplist::*;
+
+use std::sync::Arc;
+
+#[test]
+fn get_key_value_returns_stored_key() {
+    let mut map: SkipListMap<String, u32> = Default::default();
+    map.insert("key".to_string(), 1);
+    let (key, value) = map.get_key_value("key").unwrap();
+    assert_eq!((key.as_str(), *value), ("key", 1));
+    assert_eq!(map.get_key_value("other"), None);
+}
+
+#[test]
+fn reinsertion_reuses_handle() {
+    let mut map: SkipListMap<Arc<String>, u32> = Default::default();
+    let (first, previous) = map.insert_shared("key".to_string(), 1);
+    assert_eq!(previous, None);
+
+    let (second, previous) = map.insert_shared("key".to_string(), 2);
+    assert_eq!(previous, Some(1));
+    assert!(Arc::ptr_eq(&first, &second));
+    assert_eq!(map.get(&"key".to_string()), Some(&2));
+    assert!(Arc::ptr_eq(&map.shared_key(&"key".to_string()).unwrap(), &first));
+}
+
+#[test]
+fn secondary_index_shares_keys() {
+    let mut primary: SkipListMap<Arc<String>, u32> = Default::default();
+    let mut secondary: SkipListMap<u32, Arc<String>> = Default::default();
+    for (name, age) in [("ada", 36), ("alan", 41)] {
+        let (key, _) = primary.insert_shared(name.to_string(), age);
+        secondary.insert(age, key);
+    }
+
+    let key = primary.shared_key(&"ada".to_string()).unwrap();
+    // One reference from each map, and this one.
+    assert_eq!(Arc::strong_count(&key), 3);
+    assert!(Arc::ptr_eq(&secondary[&36], &key));
+}
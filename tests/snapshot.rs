@@ -0,0 +1,89 @@
+extern crate skiplist;
+use skiplist::*;
+
+use std::io;
+
+fn sample(length: u32) -> SkipListMap<u32, String> {
+    let mut list: SkipListMap<u32, String> = Default::default();
+    for i in 0..length {
+        list.insert(i * 3, i.to_string());
+    }
+
+    list
+}
+
+fn snapshot(list: &SkipListMap<u32, String>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    list.write_to(&mut bytes).unwrap();
+    bytes
+}
+
+fn restore(bytes: &[u8]) -> io::Result<SkipListMap<u32, String>> {
+    SkipListMap::read_from(bytes, Box::new(TwoPowGenerator::new(16)))
+}
+
+#[test]
+fn round_trip_empty() {
+    let list = restore(&snapshot(&sample(0))).unwrap();
+    assert!(list.is_empty());
+}
+
+#[test]
+fn round_trip_preserves_structure() {
+    let list = sample(200);
+    let restored = restore(&snapshot(&list)).unwrap();
+
+    assert_eq!(restored.len(), list.len());
+    assert!(restored.iter().eq(list.iter()));
+    assert_eq!(restored.visualize(), list.visualize());
+    assert_eq!(restored.stats(), list.stats());
+}
+
+#[test]
+fn restored_list_is_usable() {
+    let mut restored = restore(&snapshot(&sample(50))).unwrap();
+    restored.insert(1, "one".to_string());
+    assert_eq!(restored.remove(&3), Some("1".to_string()));
+    assert_eq!(restored.get(&1).map(|value| value.as_str()), Some("one"));
+    assert_eq!(restored.len(), 50);
+}
+
+#[test]
+fn taller_towers_than_the_controller() {
+    let list = sample(500);
+    let restored: SkipListMap<u32, String> =
+        SkipListMap::read_from(&snapshot(&list)[..], Box::new(TwoPowGenerator::new(1))).unwrap();
+    assert_eq!(restored.visualize(), list.visualize());
+}
+
+#[test]
+fn rejects_bad_magic() {
+    let mut bytes = snapshot(&sample(3));
+    bytes[0] = b'X';
+    let error = restore(&bytes).err().unwrap();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn rejects_truncated_snapshot() {
+    let bytes = snapshot(&sample(10));
+    let error = restore(&bytes[..bytes.len() - 1]).err().unwrap();
+    assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn rejects_unordered_keys() {
+    let mut first = Vec::new();
+    sample(1).write_to(&mut first).unwrap();
+
+    // Same single entry twice: a header for two entries, then the entry bytes
+    // repeated.
+    let header = 4 + 1 + 8;
+    let mut bytes = first[..header].to_vec();
+    bytes[5] = 2;
+    bytes.extend_from_slice(&first[header..]);
+    bytes.extend_from_slice(&first[header..]);
+
+    let error = restore(&bytes).err().unwrap();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+}
@@ -0,0 +1,99 @@
+extern crate skiplist;
+use skiplist::*;
+use skiplist::sorted_run::{merge, RunReader};
+
+use std::io::{self, Cursor};
+
+fn run(entries: &[(u32, &str)], block_size: Option<usize>) -> Vec<u8> {
+    let mut list: SkipListMap<u32, String> = Default::default();
+    for &(key, value) in entries {
+        list.insert(key, value.to_string());
+    }
+
+    let mut bytes = Vec::new();
+    match block_size {
+        Some(size) => list.flush_to_indexed(&mut bytes, size).unwrap(),
+        None => list.flush_to(&mut bytes).unwrap(),
+    }
+
+    bytes
+}
+
+fn read(bytes: &[u8]) -> Vec<(u32, String)> {
+    RunReader::new(bytes).unwrap().map(|entry| entry.unwrap()).collect()
+}
+
+#[test]
+fn round_trip() {
+    assert!(read(&run(&[], None)).is_empty());
+
+    let entries: Vec<(u32, String)> = (0..100).map(|i| (i, i.to_string())).collect();
+    let borrowed: Vec<(u32, &str)> = entries.iter().map(|&(k, ref v)| (k, v.as_str())).collect();
+    assert_eq!(read(&run(&borrowed, None)), entries);
+    assert_eq!(read(&run(&borrowed, Some(7))), entries);
+}
+
+#[test]
+fn seek() {
+    let entries: Vec<(u32, String)> = (0..100).map(|i| (i * 2, i.to_string())).collect();
+    let borrowed: Vec<(u32, &str)> = entries.iter().map(|&(k, ref v)| (k, v.as_str())).collect();
+
+    for &block_size in &[None, Some(1), Some(8), Some(1000)] {
+        let bytes = run(&borrowed, block_size);
+        let mut reader: RunReader<u32, String, _> = RunReader::new(Cursor::new(&bytes[..])).unwrap();
+
+        for &target in &[0, 1, 50, 51, 198, 199, 500] {
+            reader.seek(&target).unwrap();
+            let keys: Vec<u32> = reader.by_ref().map(|entry| entry.unwrap().0).collect();
+            let expected: Vec<u32> = (0..100).map(|i| i * 2).filter(|&k| k >= target).collect();
+            assert_eq!(keys, expected);
+        }
+    }
+}
+
+#[test]
+fn merge_prefers_newest_run() {
+    let oldest = run(&[(1, "a"), (3, "a"), (5, "a")], None);
+    let middle = run(&[(2, "b"), (3, "b")], Some(1));
+    let newest = run(&[(3, "c"), (6, "c")], None);
+
+    let runs = vec![
+        RunReader::new(&oldest[..]).unwrap(),
+        RunReader::new(&middle[..]).unwrap(),
+        RunReader::new(&newest[..]).unwrap(),
+    ];
+    let merged: Vec<(u32, String)> = merge(runs).map(|entry| entry.unwrap()).collect();
+
+    let expected = vec![(1, "a"), (2, "b"), (3, "c"), (5, "a"), (6, "c")];
+    let expected: Vec<(u32, String)> = expected.into_iter().map(|(k, v)| (k, v.to_string())).collect();
+    assert_eq!(merged, expected);
+}
+
+#[test]
+fn rejects_garbage() {
+    let error = RunReader::<u32, String, _>::new(&b"nope!"[..]).err().unwrap();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn truncated_run() {
+    let bytes = run(&[(1, "one"), (2, "two")], None);
+    let entries: Vec<io::Result<(u32, String)>> = RunReader::new(&bytes[..40]).unwrap().collect();
+    assert_eq!(entries.len(), 2);
+    assert!(entries[0].is_ok());
+    assert_eq!(entries[1].as_ref().err().unwrap().kind(), io::ErrorKind::UnexpectedEof);
+
+    let merged: Vec<io::Result<(u32, String)>> =
+        merge(vec![RunReader::new(&bytes[..40]).unwrap()]).collect();
+    assert_eq!(merged.len(), 2);
+    assert!(merged[1].is_err());
+}
+
+#[test]
+fn build_from_run() {
+    let bytes = run(&[(1, "a"), (4, "b"), (9, "c")], None);
+    let reader: RunReader<u32, String, _> = RunReader::new(&bytes[..]).unwrap();
+    let built = SkipListMap::build_from_sorted(reader, Box::new(TwoPowGenerator::new(16))).unwrap();
+    assert_eq!(built.len(), 3);
+    assert_eq!(built[&4], "b");
+}
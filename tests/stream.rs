@@ -0,0 +1,73 @@
+#![cfg(feature = "async")]
+
+extern crate futures_core;
+extern crate skiplist;
+use skiplist::*;
+
+use std::pin::Pin;
+use std::task::{Context, Poll, Waker};
+
+use futures_core::Stream;
+
+/// Polls `stream` to the end, returning the chunks and the number of times it
+/// yielded.
+fn poll_all<S: Stream + Unpin>(mut stream: S) -> (Vec<S::Item>, usize) {
+    let mut cx = Context::from_waker(Waker::noop());
+    let mut items = Vec::new();
+    let mut yields = 0;
+    loop {
+        match Pin::new(&mut stream).poll_next(&mut cx) {
+            Poll::Ready(Some(item)) => items.push(item),
+            Poll::Ready(None) => return (items, yields),
+            Poll::Pending => yields += 1,
+        }
+    }
+}
+
+fn filled(len: u32) -> SkipListMap<u32, u32> {
+    let mut list: SkipListMap<u32, u32> = Default::default();
+    for key in 0..len {
+        list.insert(key, key * 10);
+    }
+    list
+}
+
+#[test]
+fn stream_yields_between_chunks() {
+    let list = filled(10);
+    let (chunks, yields) = poll_all(list.stream(4));
+    let lens: Vec<usize> = chunks.iter().map(Vec::len).collect();
+    assert_eq!(lens, vec![4, 4, 2]);
+    // One yield after every chunk, since the end only shows up on the next poll.
+    assert_eq!(yields, 3);
+    assert!(chunks.concat().into_iter().eq(list.iter()));
+
+    let empty = filled(0);
+    let (chunks, yields) = poll_all(empty.stream(4));
+    assert!(chunks.is_empty());
+    assert_eq!(yields, 0);
+}
+
+#[test]
+fn stream_range() {
+    let list = filled(10);
+    let (chunks, _) = poll_all(list.stream_range(3..8, 2));
+    let keys: Vec<u32> = chunks.concat().into_iter().map(|(&k, _)| k).collect();
+    assert_eq!(keys, vec![3, 4, 5, 6, 7]);
+}
+
+#[test]
+fn drain_stream() {
+    let mut list = filled(10);
+    let (chunks, _) = poll_all(list.drain_stream(..5, 3));
+    assert_eq!(chunks, vec![vec![(0, 0), (1, 10), (2, 20)], vec![(3, 30), (4, 40)]]);
+    assert_eq!(list.len(), 5);
+    assert_eq!(list.first(), Some((&5, &50)));
+
+    // Entries left in the stream go away with it.
+    let mut stream = list.drain_stream(.., 1);
+    let mut cx = Context::from_waker(Waker::noop());
+    assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Ready(Some(vec![(5, 50)])));
+    drop(stream);
+    assert!(list.is_empty());
+}
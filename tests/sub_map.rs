@@ -0,0 +1,61 @@
+extern crate skiplist;
+use skiplist::*;
+
+use std::collections::BTreeMap;
+use std::ops::Bound;
+
+fn bounds() -> Vec<(Bound<u32>, Bound<u32>)> {
+    let mut bounds = vec![(Bound::Unbounded, Bound::Unbounded)];
+    for start in (0..120).step_by(13) {
+        for length in &[0, 1, 10, 60] {
+            bounds.push((Bound::Included(start), Bound::Excluded(start + length)));
+            bounds.push((Bound::Excluded(start), Bound::Included(start + length)));
+        }
+        bounds.push((Bound::Included(start), Bound::Unbounded));
+        bounds.push((Bound::Unbounded, Bound::Excluded(start)));
+    }
+
+    bounds
+}
+
+#[test]
+fn views_match_btree_map_ranges() {
+    let mut list: SkipListMap<u32, u32> = Default::default();
+    let mut model = BTreeMap::new();
+    for i in 0..50 {
+        list.insert(i * 2 + 1, i);
+        model.insert(i * 2 + 1, i);
+    }
+
+    for &outer in &bounds() {
+        let view = list.sub_map(outer);
+        let expected: Vec<_> = model.range(outer).collect();
+        assert!(view.iter().eq(expected.iter().cloned()));
+        assert_eq!(view.len(), expected.len());
+        assert_eq!(view.is_empty(), expected.is_empty());
+        for key in 0..102 {
+            let inside = expected.iter().any(|&(&k, _)| k == key);
+            assert_eq!(view.get(&key), if inside { model.get(&key) } else { None });
+            assert_eq!(view.contains_key(&key), inside);
+        }
+
+        for &inner in bounds().iter().step_by(5) {
+            let narrowed: Vec<_> = view.sub_map(inner).iter().collect();
+            let expected: Vec<_> = model.range(inner).filter(|entry| expected.contains(entry)).collect();
+            assert_eq!(narrowed, expected, "{:?} within {:?}", inner, outer);
+        }
+    }
+}
+
+#[test]
+fn borrowed_keys() {
+    let mut list: SkipListMap<String, u32> = Default::default();
+    for (i, word) in ["apple", "banana", "cherry", "date"].iter().enumerate() {
+        list.insert(word.to_string(), i as u32);
+    }
+
+    let view = list.sub_map::<str, _>((Bound::Included("b"), Bound::Excluded("d")));
+    assert_eq!(view.get("cherry"), Some(&2));
+    assert_eq!(view.get("date"), None);
+    assert_eq!(format!("{:?}", view), r#"{"banana": 1, "cherry": 2}"#);
+}
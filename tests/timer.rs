@@ -0,0 +1,29 @@
+extern crate skiplist;
+use skiplist::*;
+
+#[test]
+fn pops_expired_in_order() {
+    let mut timers: TimerQueue<u64, &str> = Default::default();
+    assert_eq!(timers.next_deadline(), None);
+
+    timers.schedule(30, "c");
+    timers.schedule(10, "a");
+    let cancelled = timers.schedule(20, "x");
+    timers.schedule(20, "b");
+    timers.schedule(10, "a2");
+    timers.schedule(40, "d");
+
+    assert_eq!(timers.next_deadline(), Some(&10));
+    assert_eq!(*cancelled.deadline(), 20);
+    assert_eq!(timers.cancel(&cancelled), Some("x"));
+    assert_eq!(timers.cancel(&cancelled), None);
+
+    assert_eq!(timers.pop_expired(5).len(), 0);
+    let expired: Vec<(u64, &str)> = timers.pop_expired(20).collect();
+    assert_eq!(expired, vec![(10, "a"), (10, "a2"), (20, "b")]);
+    assert_eq!(timers.next_deadline(), Some(&30));
+    assert_eq!(timers.len(), 2);
+
+    assert_eq!(timers.pop_expired(100).count(), 2);
+    assert!(timers.is_empty());
+}
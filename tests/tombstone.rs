@@ -0,0 +1,34 @@
+extern crate skiplist;
+use skiplist::*;
+
+#[test]
+fn remove_leaves_tombstones() {
+    let mut map: TombstoneSkipListMap<u32, u32> = Default::default();
+    for i in 0..20 {
+        map.insert(i, i);
+    }
+
+    for i in (0..20).filter(|i| i % 3 == 0) {
+        assert_eq!(map.remove(&i), Some(i));
+    }
+    assert_eq!(map.remove(&3), None);
+    assert_eq!(map.remove(&100), None);
+
+    assert_eq!(map.len(), 13);
+    assert_eq!(map.tombstones(), 7);
+    assert_eq!(map.get(&3), None);
+    assert!(!map.contains_key(&6));
+    assert_eq!(map.first(), Some((&1, &1)));
+    assert!(map.iter().map(|(&key, _)| key).eq((0..20).filter(|i| i % 3 != 0)));
+    assert!(map.range(5..10).map(|(&key, _)| key).eq(vec![5, 7, 8]));
+
+    // Inserting over a tombstone revives it.
+    assert_eq!(map.insert(6, 60), None);
+    assert_eq!(map.tombstones(), 6);
+    assert_eq!(map.get(&6), Some(&60));
+
+    map.compact();
+    assert_eq!(map.tombstones(), 0);
+    assert_eq!(map.len(), 14);
+    assert!(map.iter().map(|(&key, _)| key).eq((0..20).filter(|i| i % 3 != 0 || *i == 6)));
+}
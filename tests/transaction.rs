@@ -0,0 +1,116 @@
+extern crate skiplist;
+use skiplist::*;
+
+fn accounts() -> SkipListMap<&'static str, i64> {
+    let mut map: SkipListMap<&'static str, i64> = Default::default();
+    map.insert("alice", 100);
+    map.insert("bob", 50);
+    map
+}
+
+fn transfer(txn: &mut Transaction<&'static str, i64>, from: &'static str, to: &'static str, amount: i64) -> Result<(), String> {
+    *txn.get_mut(&to).ok_or("no such account")? += amount;
+    let balance = txn.get_mut(&from).ok_or("no such account")?;
+    if *balance < amount {
+        return Err("insufficient funds".to_string());
+    }
+
+    *balance -= amount;
+    Ok(())
+}
+
+#[test]
+fn commits_on_success() {
+    let mut map = accounts();
+    let result = map.transaction(|txn| {
+        transfer(txn, "alice", "bob", 30)?;
+        txn.insert("carol", 0);
+        assert!(txn.remove(&"carol"));
+        assert!(!txn.contains_key(&"carol"));
+        txn.insert("dave", 1);
+        Ok::<_, String>(txn.get(&"bob").cloned())
+    });
+
+    assert_eq!(result, Ok(Some(80)));
+    let contents: Vec<(&str, i64)> = map.iter().map(|(&key, &value)| (key, value)).collect();
+    assert_eq!(contents, vec![("alice", 70), ("bob", 80), ("dave", 1)]);
+}
+
+#[test]
+fn rolls_back_on_error() {
+    let mut map = accounts();
+    let result = map.transaction(|txn| {
+        assert!(txn.remove(&"alice"));
+        transfer(txn, "bob", "alice", 30)
+    });
+    assert_eq!(result, Err("no such account".to_string()));
+
+    let result = map.transaction(|txn| transfer(txn, "bob", "alice", 500));
+    assert_eq!(result, Err("insufficient funds".to_string()));
+    assert_eq!(map.get(&"alice"), Some(&100));
+    assert_eq!(map.get(&"bob"), Some(&50));
+}
+
+#[test]
+fn rolls_back_on_panic() {
+    let mut map = accounts();
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        map.transaction(|txn| {
+            txn.remove(&"bob");
+            panic!("aborted");
+            #[allow(unreachable_code)]
+            Ok::<(), ()>(())
+        })
+    }));
+
+    assert!(result.is_err());
+    assert_eq!(map.get(&"bob"), Some(&50));
+    assert_eq!(map.len(), 2);
+}
+
+thread_local! {
+    static ARMED: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+// Key whose comparisons with 13 panic once armed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Fragile(u32);
+
+impl Ord for Fragile {
+    fn cmp(&self, other: &Fragile) -> std::cmp::Ordering {
+        if ARMED.with(|armed| armed.get()) && (self.0 == 13 || other.0 == 13) {
+            panic!("comparison failed");
+        }
+        self.0.cmp(&other.0)
+    }
+}
+
+impl PartialOrd for Fragile {
+    fn partial_cmp(&self, other: &Fragile) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[test]
+fn rolls_back_on_panic_while_applying() {
+    let mut map: SkipListMap<Fragile, u32> = Default::default();
+    for key in 1..4 {
+        map.insert(Fragile(key), key);
+    }
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        map.transaction(|txn| {
+            assert!(txn.remove(&Fragile(2)));
+            txn.insert(Fragile(3), 30);
+            txn.insert(Fragile(5), 5);
+            txn.insert(Fragile(13), 13);
+            ARMED.with(|armed| armed.set(true));
+            Ok::<(), ()>(())
+        })
+    }));
+    ARMED.with(|armed| armed.set(false));
+
+    assert!(result.is_err());
+    let contents: Vec<(u32, u32)> = map.iter().map(|(key, &value)| (key.0, value)).collect();
+    assert_eq!(contents, vec![(1, 1), (2, 2), (3, 3)]);
+}
@@ -0,0 +1,94 @@
+extern crate skiplist;
+use skiplist::*;
+
+use std::ops::Bound;
+
+#[test]
+fn boxed_str_keys() {
+    let mut map: SkipListMap<Box<str>, u32> = Default::default();
+    for (i, word) in ["pear", "apple", "fig"].iter().enumerate() {
+        map.insert(Box::from(*word), i as u32);
+    }
+
+    assert_eq!(map.get("fig"), Some(&2));
+    assert!(!map.contains_key("kiwi"));
+    assert_eq!(map.remove("apple"), Some(1));
+    let range: Vec<&str> = map
+        .range::<str, _>((Bound::Included("f"), Bound::Excluded("p")))
+        .map(|(key, _)| &**key)
+        .collect();
+    assert_eq!(range, vec!["fig"]);
+}
+
+#[test]
+fn thin_keys() {
+    assert_eq!(std::mem::size_of::<ThinStr>(), std::mem::size_of::<usize>());
+    assert_eq!(std::mem::size_of::<Option<ThinBytes>>(), std::mem::size_of::<usize>());
+
+    let mut strings: SkipListMap<ThinStr, u32> = Default::default();
+    strings.insert(ThinStr::from("b"), 2);
+    strings.insert(ThinStr::from(String::from("a")), 1);
+    strings.insert(ThinStr::from(""), 0);
+    assert_eq!(strings.get("a"), Some(&1));
+    assert_eq!(strings.get(""), Some(&0));
+    assert!(strings.keys().map(|key| key.as_str()).eq(vec!["", "a", "b"]));
+
+    let mut bytes: SkipListMap<ThinBytes, u32> = Default::default();
+    bytes.insert(ThinBytes::from(&[1u8, 2][..]), 12);
+    bytes.insert(ThinBytes::from(vec![0u8; 100]), 0);
+    assert_eq!(bytes.get(&[1u8, 2][..]), Some(&12));
+    assert_eq!(bytes.remove(&[0u8; 100][..]), Some(0));
+    assert_eq!(bytes.len(), 1);
+
+    let copy = strings.clone();
+    assert!(copy.iter().eq(strings.iter()));
+}
+
+#[test]
+fn thin_keys_encode_like_owned_ones() {
+    let mut thin = Vec::new();
+    ThinStr::from("skip list").encode(&mut thin).unwrap();
+    let mut owned = Vec::new();
+    "skip list".to_string().encode(&mut owned).unwrap();
+    assert_eq!(thin, owned);
+    assert_eq!(&*ThinStr::decode(&mut &owned[..]).unwrap(), "skip list");
+
+    let mut thin = Vec::new();
+    ThinBytes::from(vec![1u8, 2, 3]).encode(&mut thin).unwrap();
+    let mut owned = Vec::new();
+    vec![1u8, 2, 3].encode(&mut owned).unwrap();
+    assert_eq!(thin, owned);
+}
+
+#[test]
+fn prefixed_keys_sort_like_the_keys() {
+    let words: Vec<&[u8]> = vec![
+        b"", b"\0", b"a", b"a\0", b"a\0\0", b"ab", b"abcdefgh", b"abcdefgh\0", b"abcdefghi",
+        b"abcdefgz", b"b", b"\xff\xff\xff\xff\xff\xff\xff\xff\xff",
+    ];
+    for left in &words {
+        for right in &words {
+            let prefixed = Prefixed::new(left.to_vec()).cmp(&Prefixed::new(right.to_vec()));
+            assert_eq!(prefixed, left.cmp(right), "{:?} vs {:?}", left, right);
+        }
+    }
+
+    assert_eq!(Prefixed::new("ab".to_string()).prefix(), Prefixed::new(ThinStr::from("ab")).prefix());
+    assert_eq!(Prefixed::new(b"abcdefgh".to_vec()).prefix(), u64::from_be_bytes(*b"abcdefgh"));
+}
+
+#[test]
+fn prefixed_keys() {
+    let mut map: SkipListMap<Prefixed<String>, u32> = Default::default();
+    for i in 0..100 {
+        map.insert(Prefixed::new(format!("user/{:03}", i)), i);
+    }
+
+    assert_eq!(map.get(&Prefixed::new("user/042".to_string())), Some(&42));
+    assert_eq!(map.get(&"user/043".to_string()), Some(&43));
+    assert!(!map.contains_key(&Prefixed::new("user/100".to_string())));
+    assert_eq!(map.remove(&Prefixed::new("user/000".to_string())), Some(0));
+    assert_eq!(map.first().map(|(key, _)| key.key().as_str()), Some("user/001"));
+    assert!(map.keys().map(|key| key.key().clone()).eq((1..100).map(|i| format!("user/{:03}", i))));
+    assert_eq!(Prefixed::from("x".to_string()).into_inner(), "x");
+}
@@ -0,0 +1,32 @@
+extern crate skiplist;
+use skiplist::*;
+
+#[test]
+fn reads_at_past_versions() {
+    let mut map: VersionedSkipListMap<&str, u32> = Default::default();
+    assert_eq!(map.insert("a", 1), 1);
+    assert_eq!(map.insert("b", 2), 2);
+    assert_eq!(map.insert("a", 3), 3);
+    assert_eq!(map.remove("b"), Some(4));
+    assert_eq!(map.remove("b"), None);
+    assert_eq!(map.remove("c"), None);
+    assert_eq!(map.insert("b", 5), 5);
+
+    assert_eq!(map.version(), 5);
+    assert_eq!(map.len(), 2);
+    assert_eq!(map.get("a"), Some(&3));
+    assert_eq!(map.get_at("a", 0), None);
+    assert_eq!(map.get_at("a", 2), Some(&1));
+    assert_eq!(map.get_at("b", 3), Some(&2));
+    assert_eq!(map.get_at("b", 4), None);
+    assert_eq!(map.get_at("b", 100), Some(&5));
+
+    assert_eq!(map.history("b"), &[(2, Some(2)), (4, None), (5, Some(5))]);
+    assert!(map.history("c").is_empty());
+
+    let at = |version| map.iter_at(version).map(|(&key, &value)| (key, value)).collect::<Vec<_>>();
+    assert_eq!(at(0), vec![]);
+    assert_eq!(at(2), vec![("a", 1), ("b", 2)]);
+    assert_eq!(at(4), vec![("a", 3)]);
+    assert_eq!(at(5), vec![("a", 3), ("b", 5)]);
+}
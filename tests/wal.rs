@@ -0,0 +1,86 @@
+extern crate skiplist;
+use skiplist::*;
+use skiplist::wal::LoggedMap;
+
+use std::io::{self, Write};
+
+fn recover(log: &[u8]) -> io::Result<SkipListMap<u32, String>> {
+    SkipListMap::recover(log, Box::new(TwoPowGenerator::new(16)))
+}
+
+fn logged() -> LoggedMap<u32, String, Vec<u8>> {
+    LoggedMap::new(Default::default(), Vec::new())
+}
+
+#[test]
+fn recover_empty_log() {
+    assert!(recover(&[]).unwrap().is_empty());
+}
+
+#[test]
+fn recover_replays_changes() {
+    let mut map = logged();
+    for i in 0..100 {
+        map.insert(i, i.to_string()).unwrap();
+    }
+    for i in 0..50 {
+        assert_eq!(map.remove(&(i * 2)).unwrap(), Some((i * 2).to_string()));
+    }
+    assert_eq!(map.insert(1, "one".to_string()).unwrap(), Some("1".to_string()));
+    assert_eq!(map.remove(&1000).unwrap(), None);
+
+    let (list, log) = map.into_parts();
+    let recovered = recover(&log).unwrap();
+    assert_eq!(recovered.len(), 50);
+    assert!(recovered.iter().eq(list.iter()));
+}
+
+#[test]
+fn recover_after_clear() {
+    let mut map = logged();
+    map.insert(1, "1".to_string()).unwrap();
+    map.clear().unwrap();
+    map.insert(2, "2".to_string()).unwrap();
+
+    let recovered = recover(map.log_mut()).unwrap();
+    assert!(recovered.keys().cloned().eq(vec![2]));
+}
+
+#[test]
+fn recover_ignores_torn_record() {
+    let mut map = logged();
+    map.insert(1, "1".to_string()).unwrap();
+    map.insert(2, "two".to_string()).unwrap();
+
+    let (_, log) = map.into_parts();
+    for cut in 1..14 {
+        let recovered = recover(&log[..log.len() - cut]).unwrap();
+        assert!(recovered.keys().cloned().eq(vec![1]));
+    }
+}
+
+#[test]
+fn recover_rejects_unknown_records() {
+    let error = recover(&[42]).err().unwrap();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+}
+
+/// Log that fails every write.
+struct Broken;
+
+impl Write for Broken {
+    fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+        Err(io::Error::other("broken"))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn failed_logging_leaves_list_untouched() {
+    let mut map: LoggedMap<u32, String, Broken> = LoggedMap::new(Default::default(), Broken);
+    assert!(map.insert(1, "1".to_string()).is_err());
+    assert!(map.map().is_empty());
+}